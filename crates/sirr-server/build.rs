@@ -0,0 +1,22 @@
+use std::process::Command;
+
+fn main() {
+    // Embed the commit the server was built from for GET /version.
+    // Falls back to "unknown" for builds outside a git checkout (e.g. crates.io).
+    let sha = std::env::var("SIRR_GIT_SHA").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short=12", "HEAD"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .map(|s| s.trim().to_owned())
+    });
+    println!(
+        "cargo:rustc-env=SIRR_GIT_SHA={}",
+        sha.unwrap_or_else(|| "unknown".into())
+    );
+    println!("cargo:rerun-if-env-changed=SIRR_GIT_SHA");
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/refs/heads");
+}
@@ -5,11 +5,115 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use std::sync::Arc;
+
 use constant_time_eq::constant_time_eq;
 use serde_json::json;
 
+use crate::store::audit::attributed_to;
+use crate::store::model::WritingKey;
 use crate::AppState;
 
+// ── ApiKeys ─────────────────────────────────────────────────────────────────
+
+/// Master API keys: the unlabeled `SIRR_API_KEY` plus any labeled ones from
+/// `SIRR_API_KEYS`, so one pipeline's key can be revoked without rotating
+/// the others. Deliberately not `Debug`.
+#[derive(Clone, Default)]
+pub struct ApiKeys {
+    /// `(key, label)`; `None` for the unlabeled `SIRR_API_KEY`.
+    keys: Arc<Vec<(String, Option<String>)>>,
+}
+
+/// Label attached to requests authenticated by a labeled master key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyLabel(pub String);
+
+impl ApiKeys {
+    /// Build from `SIRR_API_KEY` and `SIRR_API_KEYS`, the latter being
+    /// comma-separated `label:key` pairs. Labels and keys must be unique.
+    pub fn parse(unlabeled: Option<String>, labeled: Option<&str>) -> Result<Self, String> {
+        let mut keys: Vec<(String, Option<String>)> =
+            unlabeled.into_iter().map(|k| (k, None)).collect();
+        for pair in labeled.unwrap_or_default().split(',').map(str::trim) {
+            if pair.is_empty() {
+                continue;
+            }
+            let Some((label, key)) = pair.split_once(':') else {
+                return Err("expected label:key pairs".into());
+            };
+            let (label, key) = (label.trim(), key.trim());
+            if label.is_empty() || key.is_empty() {
+                return Err("label and key must both be non-empty".into());
+            }
+            if keys.iter().any(|(_, l)| l.as_deref() == Some(label)) {
+                return Err(format!("duplicate label {label:?}"));
+            }
+            if keys.iter().any(|(k, _)| k == key) {
+                return Err(format!("key for {label:?} is already configured"));
+            }
+            keys.push((key.to_owned(), Some(label.to_owned())));
+        }
+        Ok(Self {
+            keys: Arc::new(keys),
+        })
+    }
+
+    /// A single unlabeled key, as `SIRR_API_KEY` alone would give.
+    pub fn unlabeled(key: impl Into<String>) -> Self {
+        Self {
+            keys: Arc::new(vec![(key.into(), None)]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Every configured key with its label.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.keys.iter().map(|(k, l)| (k.as_str(), l.as_deref()))
+    }
+
+    /// Label of the master key `headers` carry as a bearer token, for open
+    /// routes that run without [`require_auth`] but still attribute writes.
+    pub fn label_in(&self, headers: &axum::http::HeaderMap) -> Option<&str> {
+        let token = headers
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))?;
+        self.find(token).flatten()
+    }
+
+    /// The matching key's label (`Some(None)` for the unlabeled key), or
+    /// `None`. Every key is compared in constant time, so timing does not
+    /// reveal which entry matched.
+    pub fn find(&self, token: &str) -> Option<Option<&str>> {
+        let mut matched = None;
+        for (key, label) in self.iter() {
+            if constant_time_eq(token.as_bytes(), key.as_bytes()) {
+                matched = Some(label);
+            }
+        }
+        matched
+    }
+}
+
+/// Continue as [`ResolvedAuth::Master`], recording the key's label in the
+/// request extensions and on every audit event the request produces.
+async fn run_as_master(label: Option<&str>, mut request: Request, next: Next) -> Response {
+    request.extensions_mut().insert(ResolvedAuth::Master);
+    match label {
+        Some(label) => {
+            request
+                .extensions_mut()
+                .insert(ApiKeyLabel(label.to_owned()));
+            attributed_to(label.to_owned(), next.run(request)).await
+        }
+        None => next.run(request).await,
+    }
+}
+
 // ── ResolvedAuth ────────────────────────────────────────────────────────────
 
 use crate::store::permissions::{PermBit, Permissions};
@@ -31,6 +135,8 @@ pub enum ResolvedAuth {
         org_id: String,
         key_id: String,
         key_name: String,
+        /// The key's cap on live org secrets it created; `None` = no cap.
+        max_secrets: Option<u32>,
         permissions: Permissions,
     },
 }
@@ -63,6 +169,23 @@ impl ResolvedAuth {
         }
     }
 
+    /// The principal key behind a write, with its secret quota.
+    pub fn writing_key(&self) -> Option<WritingKey<'_>> {
+        match self {
+            Self::Master => None,
+            Self::Principal {
+                key_id,
+                key_name,
+                max_secrets,
+                ..
+            } => Some(WritingKey {
+                id: key_id,
+                name: key_name,
+                max_secrets: *max_secrets,
+            }),
+        }
+    }
+
     // ── permission checks ──
 
     /// Master only has SirrAdmin; principals delegate to their role permissions.
@@ -163,7 +286,7 @@ fn unauthorized() -> Response {
 ///
 /// Auth flow:
 /// 1. Extract Bearer token from Authorization header
-/// 2. Check against SIRR_API_KEY (constant-time) → `ResolvedAuth::Master`
+/// 2. Check against SIRR_API_KEY / SIRR_API_KEYS (constant-time) → `ResolvedAuth::Master`
 /// 3. SHA-256 hash the token, look up in `find_principal_key_by_hash()`
 /// 4. Validate `valid_after` / `valid_before` window
 /// 5. Resolve principal → role → permissions
@@ -174,7 +297,7 @@ pub async fn require_auth(
     mut request: Request,
     next: Next,
 ) -> Response {
-    let has_admin_key = state.api_key.is_some();
+    let has_admin_key = !state.api_keys.is_empty();
 
     // Extract Bearer token.
     let token = request
@@ -193,12 +316,9 @@ pub async fn require_auth(
         return unauthorized();
     };
 
-    // Check against root admin key first (constant-time).
-    if let Some(ref expected) = state.api_key {
-        if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
-            request.extensions_mut().insert(ResolvedAuth::Master);
-            return next.run(request).await;
-        }
+    // Check against root admin keys first (constant-time).
+    if let Some(label) = state.api_keys.find(token) {
+        return run_as_master(label, request, next).await;
     }
 
     // Check against principal keys (SHA-256 hash lookup).
@@ -263,6 +383,7 @@ pub async fn require_auth(
             org_id: key_record.org_id.clone(),
             key_id: key_record.id.clone(),
             key_name: key_record.name.clone(),
+            max_secrets: key_record.max_secrets,
             permissions,
         });
         return next.run(request).await;
@@ -271,7 +392,8 @@ pub async fn require_auth(
     unauthorized()
 }
 
-/// Axum middleware that only accepts the root `SIRR_API_KEY`.
+/// Axum middleware that only accepts a root key (`SIRR_API_KEY` or one of
+/// `SIRR_API_KEYS`).
 ///
 /// Produces `ResolvedAuth::Master`. Keeps open-mode behavior: if no key
 /// is configured, all requests are allowed through as Master.
@@ -281,7 +403,7 @@ pub async fn require_master_key(
     next: Next,
 ) -> Response {
     // Open mode: no admin key configured.
-    if state.api_key.is_none() {
+    if state.api_keys.is_empty() {
         request.extensions_mut().insert(ResolvedAuth::Master);
         return next.run(request).await;
     }
@@ -296,11 +418,8 @@ pub async fn require_master_key(
         return unauthorized();
     };
 
-    if let Some(ref expected) = state.api_key {
-        if constant_time_eq(token.as_bytes(), expected.as_bytes()) {
-            request.extensions_mut().insert(ResolvedAuth::Master);
-            return next.run(request).await;
-        }
+    if let Some(label) = state.api_keys.find(token) {
+        return run_as_master(label, request, next).await;
     }
 
     unauthorized()
@@ -342,6 +461,7 @@ mod tests {
             org_id: "org_1".into(),
             key_id: "pk_1".into(),
             key_name: "default".into(),
+            max_secrets: None,
             permissions: writer_perms,
         };
 
@@ -384,6 +504,7 @@ mod tests {
             org_id: "org_1".into(),
             key_id: "pk_admin".into(),
             key_name: "admin-key".into(),
+            max_secrets: None,
             permissions: admin_perms,
         };
 
@@ -398,4 +519,19 @@ mod tests {
         // Admin does NOT have SirrAdmin.
         assert!(!auth.can_sirr_admin());
     }
+
+    #[test]
+    fn labeled_keys_parse_and_match_by_label() {
+        let keys = ApiKeys::parse(Some("root".into()), Some(" ci:abc:def , deploy:xyz ,")).unwrap();
+        assert_eq!(keys.find("root"), Some(None));
+        assert_eq!(keys.find("abc:def"), Some(Some("ci")));
+        assert_eq!(keys.find("xyz"), Some(Some("deploy")));
+        assert_eq!(keys.find("xy"), None);
+        assert!(ApiKeys::parse(None, Some("")).unwrap().is_empty());
+
+        for bad in ["nolabel", ":key", "label:", "a:k1,a:k2", "a:k,b:k"] {
+            assert!(ApiKeys::parse(None, Some(bad)).is_err(), "{bad}");
+        }
+        assert!(ApiKeys::parse(Some("k".into()), Some("a:k")).is_err());
+    }
 }
@@ -0,0 +1,42 @@
+//! Request extractors that report failures in the crate's JSON error shape.
+
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+/// Drop-in for `axum::Json` in handler arguments. Malformed or mistyped
+/// bodies are rejected with `{"error": ..., "code": ...}` instead of axum's
+/// plain-text message; the status code is unchanged.
+pub struct JsonBody<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(json_rejection(rejection)),
+        }
+    }
+}
+
+fn json_rejection(rejection: JsonRejection) -> Response {
+    let code = match rejection {
+        JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+        JsonRejection::BytesRejection(_) => "invalid_body",
+        _ => "invalid_json",
+    };
+    (
+        rejection.status(),
+        Json(json!({"error": rejection.body_text(), "code": code})),
+    )
+        .into_response()
+}
@@ -1,9 +1,11 @@
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
 
 use axum::{
     extract::{ConnectInfo, Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::{IntoResponse, Response},
+    http::{header, HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse, Response},
     Extension, Json,
 };
 use serde::{Deserialize, Serialize};
@@ -12,14 +14,25 @@ use tracing::info;
 
 use crate::{
     auth::ResolvedAuth,
+    extract::JsonBody,
     license::LicenseStatus,
+    rate_limit,
     store::{
         audit::{
-            AuditEvent, ACTION_SECRET_BURNED, ACTION_SECRET_CREATE, ACTION_SECRET_DELETE,
-            ACTION_SECRET_LIST, ACTION_SECRET_PATCH, ACTION_SECRET_PRUNE, ACTION_SECRET_READ,
-            ACTION_WEBHOOK_CREATE, ACTION_WEBHOOK_DELETE,
+            self, AuditEvent, AuditFailureMode, StagedEvent, ACTION_MAINTENANCE,
+            ACTION_SECRET_ALIAS, ACTION_SECRET_BURNED, ACTION_SECRET_CREATE, ACTION_SECRET_DELETE,
+            ACTION_SECRET_DESCRIBE, ACTION_SECRET_LIST, ACTION_SECRET_PATCH, ACTION_SECRET_PEEK,
+            ACTION_SECRET_PRUNE, ACTION_SECRET_READ, ACTION_SECRET_TOUCH, ACTION_SELF_DESTRUCT,
+            ACTION_WEBHOOK_CREATE, ACTION_WEBHOOK_DELETE, ACTION_WEBHOOK_EXPORT,
+            ACTION_WEBHOOK_IMPORT,
         },
-        AuditQuery, GetResult,
+        db::VALUE_NOT_UTF8,
+        model::{
+            validate_annotations, AnnotationsPatch, ConditionalDelete, DeleteCriteria, NewSecret,
+            Precondition, PruneReason, PruneScope, PutConflict, PutReport, ReadNotifyThrottle,
+            SecretKind, SecretMeta, SecretStatus,
+        },
+        AliasError, AuditQuery, GetResult,
     },
     webhooks::{self, MAX_WEBHOOKS},
     AppState,
@@ -31,6 +44,22 @@ use crate::{
 /// Prevents u64 → i64 overflow in the expiry timestamp calculation.
 const MAX_TTL_SECS: u64 = 315_360_000;
 
+/// How far apart `ttl_seconds` and `expires_at` may land when a create sends
+/// both, to absorb clock skew and request latency.
+const EXPIRY_AGREEMENT_SECS: u64 = 5;
+
+/// Most keys accepted by one `POST /secrets/batch-get`.
+const MAX_BATCH_GET_KEYS: usize = 100;
+
+/// Most secrets accepted by one `POST /secrets/batch`.
+const MAX_BATCH_CREATE_SECRETS: usize = 100;
+
+/// Names under `/secrets/` taken by static routes, so no secret may use them.
+const ROUTE_KEY_NAMES: [&str; 2] = ["batch", "batch-get"];
+
+/// Longest accepted `Idempotency-Key` header value.
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
 // ── Input validation ─────────────────────────────────────────────────────────
 
 /// Validates a secret key name.
@@ -38,18 +67,103 @@ const MAX_TTL_SECS: u64 = 315_360_000;
 /// Allowed: ASCII alphanumerics, `-`, `_`, `.`, 1–256 characters.
 /// Rejects slashes, control characters, and other special characters to keep
 /// audit logs clean and prevent confusion in URL routing or future tooling.
+/// [`ROUTE_KEY_NAMES`] are rejected too: the batch routes shadow them.
 fn validate_key_name(key: &str) -> bool {
     !key.is_empty()
         && key.len() <= 256
+        && !ROUTE_KEY_NAMES.contains(&key)
         && key
             .bytes()
             .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
 }
 
+/// What `POST /secrets` does when the key already holds a live secret.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CreateMode {
+    /// Overwrite it (default).
+    #[default]
+    Upsert,
+    /// Refuse with 409.
+    Reject,
+}
+
+impl std::str::FromStr for CreateMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "upsert" => Ok(Self::Upsert),
+            "reject" => Ok(Self::Reject),
+            other => Err(format!(
+                "unknown create mode {other:?} (expected upsert or reject)"
+            )),
+        }
+    }
+}
+
+/// Whether a create may not overwrite an existing secret. An
+/// `X-Sirr-Create-Mode: upsert|reject` header overrides the instance's
+/// [`CreateMode`] either way, and `If-None-Match: *` always means reject.
+/// `Err` describes a malformed override.
+fn create_only(state: &AppState, headers: &HeaderMap) -> Result<bool, String> {
+    let mode = match headers.get("x-sirr-create-mode") {
+        None => state.create_mode,
+        Some(v) => v
+            .to_str()
+            .map_err(|_| "X-Sirr-Create-Mode must be upsert or reject".to_owned())?
+            .parse()?,
+    };
+    Ok(mode == CreateMode::Reject
+        || headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.trim() == "*"))
+}
+
+/// 409 for a create the store refused, or 507 when it refused because the
+/// store is full; nothing was written.
+fn put_conflict(conflict: PutConflict) -> Response {
+    let status = match conflict {
+        PutConflict::Full { .. } => StatusCode::INSUFFICIENT_STORAGE,
+        _ => StatusCode::CONFLICT,
+    };
+    (status, Json(json!({"error": conflict.to_string()}))).into_response()
+}
+
+/// Random alphanumeric key of `state.generated_key_length` characters that
+/// is not already in use.
+fn generate_unique_key(state: &AppState) -> anyhow::Result<String> {
+    for _ in 0..8 {
+        let key = state.key_generator.generate(state.generated_key_length);
+        if state.secrets.head(&key)?.is_none() {
+            return Ok(key);
+        }
+    }
+    anyhow::bail!("could not generate an unused key")
+}
+
+/// 403 if `key` falls under one of the configured reserved prefixes.
+fn reserved_key(state: &AppState, key: &str) -> Option<Response> {
+    let prefix = state
+        .reserved_key_prefixes
+        .iter()
+        .find(|p| key.starts_with(p.as_str()))?;
+    Some(
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": format!("key prefix {prefix:?} is reserved")})),
+        )
+            .into_response(),
+    )
+}
+
+const KEY_NAME_RULE: &str =
+    "key must be 1–256 characters: alphanumeric, -, _, . only, and not batch or batch-get";
+
 fn bad_key_name() -> Response {
     (
         StatusCode::BAD_REQUEST,
-        Json(json!({"error": "key must be 1–256 characters: alphanumeric, -, _, . only"})),
+        Json(json!({"error": KEY_NAME_RULE})),
     )
         .into_response()
 }
@@ -62,7 +176,11 @@ fn bad_key_name() -> Response {
 /// socket peer matches one of the configured trusted-proxy CIDRs.  An empty
 /// `trusted_proxies` slice means proxy headers are never trusted, so any
 /// client-supplied value is ignored and the real socket IP is used instead.
-fn extract_ip(headers: &HeaderMap, addr: &SocketAddr, trusted_proxies: &[ipnet::IpNet]) -> String {
+pub(crate) fn extract_ip(
+    headers: &HeaderMap,
+    addr: &SocketAddr,
+    trusted_proxies: &[ipnet::IpNet],
+) -> String {
     let peer = addr.ip();
     if !trusted_proxies.is_empty() && trusted_proxies.iter().any(|net| net.contains(&peer)) {
         if let Some(xff) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
@@ -89,6 +207,222 @@ pub async fn health() -> impl IntoResponse {
     Json(json!({"status": "ok"}))
 }
 
+/// Build identity for CI checks; unauthenticated and independent of `/status`.
+pub async fn version() -> impl IntoResponse {
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_sha": env!("SIRR_GIT_SHA"),
+    }))
+}
+
+/// Addresses `run` bound, by listener name (`main`, `admin`). A listener
+/// that fails to bind aborts `run`, so every one listed is serving.
+#[derive(Debug, Default)]
+pub struct ListenerSet {
+    listeners: std::sync::Mutex<BTreeMap<String, SocketAddr>>,
+}
+
+impl ListenerSet {
+    /// Record the address `name` bound.
+    pub fn bound(&self, name: &str, addr: SocketAddr) {
+        self.lock().insert(name.to_owned(), addr);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, SocketAddr>> {
+        self.listeners.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Readiness probe: succeeds only when the store can open a read transaction.
+pub async fn ready(State(state): State<AppState>) -> Response {
+    match state.secrets.ping() {
+        Ok(()) => Json(json!({"status": "ready"})).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "readiness check failed");
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({"status": "unavailable"})),
+            )
+                .into_response()
+        }
+    }
+}
+
+// ── Status ────────────────────────────────────────────────────────────────────
+
+pub async fn status(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let counts = state
+        .store
+        .count_webhooks()
+        .and_then(|webhooks| Ok((webhooks, state.store.count_dead_letters()?)));
+    let (webhook_count, dead_letter_count) = match counts {
+        Ok(counts) => counts,
+        Err(e) => return internal_error(e),
+    };
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "started_at": state.started_at,
+        "uptime_seconds": state.started.elapsed().as_secs(),
+        "webhook_count": webhook_count,
+        "dead_letter_count": dead_letter_count,
+        "maintenance": state.maintenance.load(Ordering::Relaxed),
+        "listeners": *state.listeners.lock(),
+    }))
+    .into_response()
+}
+
+// ── Maintenance ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+}
+
+/// `POST /admin/maintenance` — switch read-only maintenance mode on or off.
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    JsonBody(body): JsonBody<MaintenanceRequest>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let event = AuditEvent::new(
+        ACTION_MAINTENANCE,
+        None,
+        ip,
+        true,
+        Some(format!("enabled={}", body.enabled)),
+        None,
+        None,
+    );
+    // The switch is not a store write, so audit it before flipping it.
+    let pending = PendingAudit(vec![Box::new(move |_| event)]);
+    if let Some(rejected) = audit_mutation(&state, pending, 1) {
+        return rejected;
+    }
+    state.maintenance.store(body.enabled, Ordering::Relaxed);
+    info!(enabled = body.enabled, "audit: instance.maintenance");
+    Json(json!({"maintenance": body.enabled})).into_response()
+}
+
+// ── Self-destruct ────────────────────────────────────────────────────────────
+
+/// Configuration for `POST /admin/self-destruct`.
+pub struct SelfDestruct {
+    /// Value the request's `confirm` field must match.
+    pub token: String,
+    /// `sirr.key` to overwrite and delete; `None` when the key came from
+    /// `SIRR_ENCRYPTION_KEY` and there is no file.
+    pub key_file: Option<std::path::PathBuf>,
+    /// Set to true once the wipe is done; `run` shuts the listeners down on it.
+    pub shutdown: tokio::sync::watch::Sender<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelfDestructRequest {
+    pub confirm: String,
+}
+
+/// Overwrite `path` with zeros, flush it to disk, then delete it. A file
+/// that is already gone counts as shredded.
+fn shred_file(path: &std::path::Path) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = match std::fs::OpenOptions::new().write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let len = file.metadata()?.len() as usize;
+    file.write_all(&vec![0u8; len])?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)
+}
+
+/// `POST /admin/self-destruct` — emergency wipe: delete every secret, shred
+/// `sirr.key` (and `sirr.key.next`), record a final audit event and shut the server down. Needs the
+/// master key and the configured confirmation token.
+pub async fn self_destruct(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    JsonBody(body): JsonBody<SelfDestructRequest>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let Some(config) = state.self_destruct.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "self-destruct is not enabled"})),
+        )
+            .into_response();
+    };
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    if !constant_time_eq::constant_time_eq(body.confirm.as_bytes(), config.token.as_bytes()) {
+        let _ = state.secrets.record_audit(AuditEvent::new(
+            ACTION_SELF_DESTRUCT,
+            None,
+            ip,
+            false,
+            Some("bad confirmation token".into()),
+            None,
+            None,
+        ));
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "confirmation token does not match"})),
+        )
+            .into_response();
+    }
+
+    // `secrets` may be a separate backend from `store`, which holds org
+    // secrets; when they are the same, the second wipe finds nothing.
+    let wiped = match state
+        .secrets
+        .wipe_secrets()
+        .and_then(|n| Ok(n + state.store.wipe_secrets()?))
+    {
+        Ok(n) => n,
+        Err(e) => return internal_error(e),
+    };
+    // The wipe must be on disk before the key goes: a crash in between
+    // must not leave secrets behind under a key that can be recovered.
+    if let Err(e) = state.store.sync() {
+        tracing::error!(error = %e, "self-destruct: could not sync the store");
+    }
+    // An interrupted rotation leaves the next key beside sirr.key.
+    let key_file_removed =
+        match config.key_file.as_deref().map(|path| {
+            shred_file(path).and_then(|()| shred_file(&path.with_extension("key.next")))
+        }) {
+            None => false,
+            Some(Ok(())) => true,
+            Some(Err(e)) => {
+                tracing::error!(error = %e, "self-destruct: could not shred sirr.key");
+                false
+            }
+        };
+    tracing::warn!(wiped, key_file_removed, "audit: instance.self_destruct");
+    let _ = state.secrets.record_audit(AuditEvent::new(
+        ACTION_SELF_DESTRUCT,
+        None,
+        ip,
+        true,
+        Some(format!("wiped={wiped} key_file_removed={key_file_removed}")),
+        None,
+        None,
+    ));
+    let _ = state.store.flush_audit();
+    config.shutdown.send_replace(true);
+    Json(json!({"wiped": wiped, "key_file_removed": key_file_removed})).into_response()
+}
+
 // ── Audit query ──────────────────────────────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
@@ -105,15 +439,19 @@ pub async fn audit_events(
     Query(params): Query<AuditQueryParams>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
+    let mut since = params.since;
+    if let Some(rejected) = bound_audit_range(&state, &mut since, params.until) {
+        return rejected;
+    }
     let limit = params.limit.unwrap_or(100).min(1000);
     let query = AuditQuery {
-        since: params.since,
+        since,
         until: params.until,
         action: params.action,
         limit,
         org_id: None,
     };
-    match state.store.list_audit(&query) {
+    match state.secrets.list_audit(&query) {
         Ok(events) => {
             if state.redact_audit_keys {
                 use sha2::{Digest, Sha256};
@@ -136,20 +474,73 @@ pub async fn audit_events(
     }
 }
 
+/// Bound an audit query to `SIRR_AUDIT_MAX_RANGE_DAYS`. A missing `since` is
+/// clamped to one window before `until` (or now); an explicit range wider
+/// than the window is refused with 400.
+pub(crate) fn bound_audit_range(
+    state: &AppState,
+    since: &mut Option<i64>,
+    until: Option<i64>,
+) -> Option<Response> {
+    let max_range = state.audit_max_range_secs?;
+    let end = until.unwrap_or_else(crate::store::idempotency::now);
+    match *since {
+        None => {
+            *since = Some(end.saturating_sub(max_range));
+            None
+        }
+        Some(start) if end.saturating_sub(start) > max_range => Some(
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!(
+                    "audit query spans more than {} days; narrow since/until",
+                    max_range / 86_400
+                )})),
+            )
+                .into_response(),
+        ),
+        Some(_) => None,
+    }
+}
+
 // ── List ──────────────────────────────────────────────────────────────────────
 
+#[derive(Debug, Deserialize)]
+pub struct ListQueryParams {
+    /// Only return secrets in this state. Without it, expired secrets awaiting
+    /// the sweep are omitted.
+    pub status: Option<SecretStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedSecret {
+    #[serde(flatten)]
+    pub meta: SecretMeta,
+    pub status: SecretStatus,
+}
+
 pub async fn list_secrets(
     State(state): State<AppState>,
     Extension(_auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<ListQueryParams>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
-    match state.store.list() {
+    let listed = state.secrets.list_with_status().map(|all| {
+        all.into_iter()
+            .filter(|(_, status)| match params.status {
+                Some(wanted) => *status == wanted,
+                None => *status != SecretStatus::ExpiredPendingSweep,
+            })
+            .map(|(meta, status)| ListedSecret { meta, status })
+            .collect::<Vec<_>>()
+    });
+    match listed {
         Ok(metas) => {
             info!(count = metas.len(), "audit: secret.list");
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_LIST,
                 None,
                 ip,
@@ -158,107 +549,531 @@ pub async fn list_secrets(
                 None,
                 None,
             ));
-            Json(json!({ "secrets": metas })).into_response()
+            let body = json!({ "secrets": metas }).to_string();
+            let etag = weak_etag(body.as_bytes());
+            if if_none_match_hits(&headers, &etag) {
+                return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+            }
+            (
+                [
+                    (header::CONTENT_TYPE, "application/json".to_owned()),
+                    (header::ETAG, etag),
+                ],
+                body,
+            )
+                .into_response()
         }
         Err(e) => internal_error(e),
     }
 }
 
+/// Weak validator over a response body: any change to the listed metadata
+/// (including read counts) yields a new tag.
+fn weak_etag(body: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("W/\"{}\"", hex::encode(&Sha256::digest(body)[..16]))
+}
+
+/// True if `If-None-Match` lists `etag` (weak comparison) or `*`.
+fn if_none_match_hits(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| {
+            v.split(',')
+                .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+        })
+}
+
 // ── Create ────────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
 pub struct CreateRequest {
+    /// Omit (or send `""`) to have the server generate a random key.
+    #[serde(default)]
     pub key: String,
     pub value: String,
     pub ttl_seconds: Option<u64>,
+    /// Absolute expiry (unix seconds), an alternative to `ttl_seconds`.
+    pub expires_at: Option<i64>,
     pub max_reads: Option<u32>,
     pub delete: Option<bool>,
     pub webhook_url: Option<String>,
+    /// Events delivered to `webhook_url`; omit to receive all of them.
+    pub webhook_events: Option<Vec<String>>,
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+    /// `"structured"` requires `value` to be a JSON object.
+    #[serde(default, rename = "type")]
+    pub kind: SecretKind,
+    /// Samples `secret.read` deliveries to `webhook_url`.
+    #[serde(default)]
+    pub read_notify: ReadNotifyThrottle,
 }
 
 #[derive(Debug, Serialize)]
 pub struct CreateResponse {
     pub key: String,
+    /// Retrieval URL, present when `SIRR_PUBLIC_BASE_URL` is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// A single field-level validation failure, returned in the `errors` list.
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Why `value` is below `SIRR_MIN_VALUE_BYTES`, if it is.
+pub(crate) fn value_too_short(state: &AppState, value: &str) -> Option<String> {
+    (value.len() < state.min_value_bytes).then(|| {
+        format!(
+            "value must be at least {} bytes on this server",
+            state.min_value_bytes
+        )
+    })
+}
+
+/// Run every create-time check and collect all failures, so clients can fix
+/// a request in one round trip instead of one error at a time.
+fn validate_create(body: &CreateRequest, state: &AppState, now: i64) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if !validate_key_name(&body.key) {
+        errors.push(FieldError {
+            field: "key",
+            message: KEY_NAME_RULE.into(),
+        });
+    }
+    if body.value.len() > 1_048_576 {
+        errors.push(FieldError {
+            field: "value",
+            message: "value exceeds 1 MiB limit".into(),
+        });
+    }
+    if let Some(message) = value_too_short(state, &body.value) {
+        errors.push(FieldError {
+            field: "value",
+            message,
+        });
+    }
+    match create_ttl(body, now) {
+        Err(message) => errors.push(FieldError {
+            field: "expires_at",
+            message,
+        }),
+        Ok(Some(ttl)) if ttl > MAX_TTL_SECS => errors.push(FieldError {
+            field: "ttl_seconds",
+            message: format!("ttl_seconds exceeds maximum of {MAX_TTL_SECS} (10 years)"),
+        }),
+        Ok(Some(ttl)) if ttl < state.min_ttl_seconds => errors.push(FieldError {
+            field: "ttl_seconds",
+            message: format!(
+                "ttl_seconds must be at least {} on this server",
+                state.min_ttl_seconds
+            ),
+        }),
+        Ok(None) if state.require_ttl => errors.push(FieldError {
+            field: "ttl_seconds",
+            message: "ttl_seconds is required on this server".into(),
+        }),
+        _ => {}
+    }
+    if body.max_reads == Some(0) {
+        errors.push(FieldError {
+            field: "max_reads",
+            message: "max_reads must be ≥ 1; omit to allow unlimited reads".into(),
+        });
+    }
+    if let Some(ref wurl) = body.webhook_url {
+        if let Err(reason) = webhooks::validate_webhook_url(wurl, &state.webhook_allowed_origins) {
+            errors.push(FieldError {
+                field: "webhook_url",
+                message: format!("webhook_url: {reason}"),
+            });
+        }
+    }
+    if let Some(ref events) = body.webhook_events {
+        if body.webhook_url.is_none() {
+            errors.push(FieldError {
+                field: "webhook_events",
+                message: "webhook_events requires webhook_url".into(),
+            });
+        } else if let Some(bad) = events
+            .iter()
+            .find(|e| *e != "*" && !webhooks::SECRET_WEBHOOK_EVENTS.contains(&e.as_str()))
+        {
+            errors.push(FieldError {
+                field: "webhook_events",
+                message: format!(
+                    "unknown webhook event {bad:?}; expected one of {}",
+                    webhooks::SECRET_WEBHOOK_EVENTS.join(", ")
+                ),
+            });
+        }
+    }
+    if body.kind == SecretKind::Structured
+        && !matches!(
+            serde_json::from_str::<serde_json::Value>(&body.value),
+            Ok(serde_json::Value::Object(_))
+        )
+    {
+        errors.push(FieldError {
+            field: "value",
+            message: "structured secrets require value to be a JSON object".into(),
+        });
+    }
+    if let Err(reason) = validate_annotations(&body.annotations) {
+        errors.push(FieldError {
+            field: "annotations",
+            message: reason,
+        });
+    }
+    if body.read_notify.every == Some(0) {
+        errors.push(FieldError {
+            field: "read_notify",
+            message: "read_notify.every must be ≥ 1".into(),
+        });
+    }
+    errors
+}
+
+/// The create's lifetime in seconds from `now`. An `expires_at` is converted
+/// here; one in the past, beyond the TTL ceiling, or contradicting a
+/// `ttl_seconds` sent alongside it is an error.
+fn create_ttl(body: &CreateRequest, now: i64) -> Result<Option<u64>, String> {
+    let Some(at) = body.expires_at else {
+        return Ok(body.ttl_seconds);
+    };
+    if at <= now {
+        return Err("expires_at must be in the future".into());
+    }
+    let ttl = at.abs_diff(now);
+    if ttl > MAX_TTL_SECS {
+        return Err(format!(
+            "expires_at is more than {MAX_TTL_SECS} seconds (10 years) away"
+        ));
+    }
+    if body
+        .ttl_seconds
+        .is_some_and(|sent| sent.abs_diff(ttl) > EXPIRY_AGREEMENT_SECS)
+    {
+        return Err("ttl_seconds and expires_at disagree; send only one".into());
+    }
+    Ok(Some(ttl))
+}
+
+/// 400 with every validation failure. `error` carries the first message so
+/// clients that only read the single-string shape keep working.
+fn validation_failed(errors: Vec<FieldError>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({"error": errors[0].message, "errors": errors})),
+    )
+        .into_response()
 }
 
 pub async fn create_secret(
     State(state): State<AppState>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Json(body): Json<CreateRequest>,
+    JsonBody(mut body): JsonBody<CreateRequest>,
 ) -> Response {
     // Public bucket: no auth required — the secret key itself is the access token.
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let caller = rate_limit::caller_id(&state.api_keys, &headers, &ip);
 
-    if !validate_key_name(&body.key) {
-        return bad_key_name();
-    }
-    if body.max_reads == Some(0) {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "max_reads must be ≥ 1; omit to allow unlimited reads"})),
-        )
-            .into_response();
-    }
-    if body.value.len() > 1_048_576 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"error": "value exceeds 1 MiB limit"})),
-        )
-            .into_response();
+    // Replay the original response for a retried create. Checked before the
+    // rate limiter so retries don't spend the caller's quota.
+    let idempotency_key = match idempotency_key(&headers, &caller) {
+        Ok(key) => key,
+        Err(rejected) => return rejected,
+    };
+    if let Some(replayed) = idempotency_key
+        .as_deref()
+        .and_then(|idem| replay_idempotent(&state, idem))
+    {
+        return replayed;
     }
-    if let Some(ttl) = body.ttl_seconds {
-        if ttl > MAX_TTL_SECS {
+
+    if let Some(ref limiter) = state.create_limiter {
+        if let Err(retry_after) = limiter.check(&caller) {
             return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("ttl_seconds exceeds maximum of {MAX_TTL_SECS} (10 years)")})),
+                StatusCode::TOO_MANY_REQUESTS,
+                [("retry-after", retry_after.as_secs().max(1).to_string())],
+                Json(json!({"error": "secret creation rate limit exceeded"})),
             )
                 .into_response();
         }
     }
-    if let Some(ref wurl) = body.webhook_url {
-        if let Err(reason) = webhooks::validate_webhook_url(wurl, &state.webhook_allowed_origins) {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("webhook_url: {reason}")})),
-            )
-                .into_response();
-        }
+
+    if body.key.is_empty() {
+        body.key = match generate_unique_key(&state) {
+            Ok(key) => key,
+            Err(e) => return internal_error(e),
+        };
+    }
+
+    let now = crate::store::idempotency::now();
+    let errors = validate_create(&body, &state, now);
+    if !errors.is_empty() {
+        return validation_failed(errors);
+    }
+    // Validated above; `expires_at`, when sent, takes precedence.
+    let ttl_seconds = create_ttl(&body, now).unwrap_or(body.ttl_seconds);
+    if let Some(rejected) = reserved_key(&state, &body.key) {
+        return rejected;
     }
+    let create_only = match create_only(&state, &headers) {
+        Ok(create_only) => create_only,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": error}))).into_response()
+        }
+    };
 
     // Licensing is now enforced at org/principal creation, not per-secret.
 
-    match state.store.put(
-        &body.key,
-        &body.value,
-        body.ttl_seconds,
-        body.max_reads,
-        body.delete.unwrap_or(true),
-        body.webhook_url.clone(),
+    let secret = NewSecret {
+        key: body.key.clone(),
+        value: body.value,
+        ttl_seconds,
+        max_reads: body.max_reads,
+        delete: body.delete.unwrap_or(true),
+        webhook_url: body.webhook_url,
+        webhook_events: body.webhook_events,
+        annotations: body.annotations,
+        kind: body.kind,
+        read_notify: body.read_notify,
+        created_by: state.api_keys.label_in(&headers).map(str::to_owned),
+    };
+    let event = AuditEvent::new(
+        ACTION_SECRET_CREATE,
+        Some(body.key.clone()),
+        ip.clone(),
+        true,
+        None,
+        None,
+        None,
+    );
+    let events = vec![Box::new(move |_| event) as StagedEvent];
+    let (report, pending) = match put_capped(
+        &state,
+        std::slice::from_ref(&secret),
+        create_only,
+        events,
+        &ip,
     ) {
-        Ok(()) => {
-            info!(
-                key = %body.key,
-                ttl_seconds = ?body.ttl_seconds,
-                max_reads = ?body.max_reads,
-                "audit: secret.create"
-            );
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_CREATE,
-                Some(body.key.clone()),
-                ip,
-                true,
-                None,
-                None,
-                None,
-            ));
-            if let Some(ref sender) = state.webhook_sender {
-                sender.fire("secret.created", &body.key, json!({}));
+        Ok(written) => written,
+        Err(rejected) => return rejected,
+    };
+    if let Some(rejected) = audit_mutation(&state, pending, 1) {
+        return rejected;
+    }
+    state.metrics.secrets_created.inc();
+    info!(
+        key = %body.key,
+        ttl_seconds = ?ttl_seconds,
+        max_reads = ?body.max_reads,
+        "audit: secret.create"
+    );
+    if let Some(ref sender) = state.webhook_sender {
+        sender.fire("secret.created", &body.key, json!({}));
+    }
+    let url = state
+        .public_base_url
+        .as_deref()
+        .map(|base| format!("{base}/secrets/{}", body.key));
+    let resp = CreateResponse { key: body.key, url };
+    if let Some(ref idem) = idempotency_key {
+        record_idempotent(&state, idem, StatusCode::CREATED, &resp);
+    }
+    (
+        StatusCode::CREATED,
+        AppendHeaders(quota_headers(&state, report.stored)),
+        Json(resp),
+    )
+        .into_response()
+}
+
+/// The stored form of the request's `Idempotency-Key`, scoped to `scope`
+/// (the caller, plus the endpoint when it is not `POST /secrets`), or the
+/// 400 for a malformed header.
+#[allow(clippy::result_large_err)]
+fn idempotency_key(headers: &HeaderMap, scope: &str) -> Result<Option<String>, Response> {
+    match headers.get("idempotency-key").map(|v| v.to_str()) {
+        None => Ok(None),
+        Some(Ok(k)) if !k.is_empty() && k.len() <= MAX_IDEMPOTENCY_KEY_LEN => {
+            Ok(Some(format!("{scope}:{k}")))
+        }
+        Some(_) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("Idempotency-Key must be 1–{MAX_IDEMPOTENCY_KEY_LEN} visible ASCII characters")})),
+        )
+            .into_response()),
+    }
+}
+
+/// The response first recorded under `idem`, if it is still live.
+fn replay_idempotent(state: &AppState, idem: &str) -> Option<Response> {
+    match state.secrets.get_idempotent(idem) {
+        Ok(Some(prev)) => Some(
+            (
+                StatusCode::from_u16(prev.status).unwrap_or(StatusCode::OK),
+                [
+                    (header::CONTENT_TYPE, "application/json"),
+                    (
+                        header::HeaderName::from_static("idempotent-replayed"),
+                        "true",
+                    ),
+                ],
+                prev.body,
+            )
+                .into_response(),
+        ),
+        Ok(None) => None,
+        Err(e) => Some(internal_error(e)),
+    }
+}
+
+/// Record `body` as the response to replay for `idem`. The write already
+/// happened, so a failure here is only logged.
+fn record_idempotent(state: &AppState, idem: &str, status: StatusCode, body: &impl Serialize) {
+    let recorded = serde_json::to_string(body)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            state
+                .secrets
+                .put_idempotent(idem, status.as_u16(), &json, state.idempotency_ttl_secs)
+        });
+    if let Err(e) = recorded {
+        tracing::warn!(error = %e, "failed to record idempotency key");
+    }
+}
+
+/// `X-Sirr-Quota-Limit` and `X-Sirr-Quota-Remaining` against `max_secrets`,
+/// so clients can back off before creates start failing with 507. Takes the
+/// count the capped write reported; empty when no limit is configured.
+fn quota_headers(state: &AppState, stored_after: Option<usize>) -> Vec<(&'static str, String)> {
+    match (state.max_secrets, stored_after) {
+        (Some(max), Some(stored)) => vec![
+            ("x-sirr-quota-limit", max.to_string()),
+            (
+                "x-sirr-quota-remaining",
+                max.saturating_sub(stored).to_string(),
+            ),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Store `secrets` with `events` as their audit records, enforcing
+/// `max_secrets` inside the write itself; overwrites never count as growth.
+/// With `auto_prune_on_full`, a write refused as full prunes expired and
+/// burned secrets and is retried once; a prune already in flight is not
+/// waited for. Returns the write's report and its unwritten audit events, or
+/// the rejection.
+#[allow(clippy::result_large_err)]
+fn put_capped(
+    state: &AppState,
+    secrets: &[NewSecret],
+    create_only: bool,
+    events: Vec<StagedEvent>,
+    ip: &str,
+) -> Result<(PutReport, PendingAudit), Response> {
+    let put = || {
+        state
+            .secrets
+            .put_many(secrets, create_only, state.max_secrets)
+    };
+    let (mut written, mut pending) = audited_with(state, events, put);
+    if matches!(written, Ok(Err(PutConflict::Full { .. })))
+        && state.auto_prune_on_full
+        && auto_prune(state, ip)? > 0
+    {
+        (written, pending) = audited_with(state, pending.0, put);
+    }
+    match written {
+        Ok(Ok(report)) => Ok((report, pending)),
+        Ok(Err(conflict)) => Err(put_conflict(conflict)),
+        Err(e) => Err(internal_error(e)),
+    }
+}
+
+/// Prune expired and burned secrets to make room in a full store, unless a
+/// prune is already running. Returns how many were removed.
+#[allow(clippy::result_large_err)]
+fn auto_prune(state: &AppState, ip: &str) -> Result<usize, Response> {
+    let Ok(_running) = state.sweep_lock.try_lock() else {
+        return Ok(0);
+    };
+    let ip = ip.to_owned();
+    let event: StagedEvent = Box::new(move |n| {
+        AuditEvent::new(
+            ACTION_SECRET_PRUNE,
+            None,
+            ip,
+            true,
+            Some(format!("pruned={n};auto")),
+            None,
+            None,
+        )
+    });
+    let (pruned, pending) = audited_with(state, vec![event], || state.secrets.prune());
+    let pruned_keys = pruned.map_err(internal_error)?;
+    let n = pruned_keys.len();
+    state.metrics.secrets_pruned.add(n as u64);
+    if n > 0 {
+        if let Some(rejected) = audit_mutation(state, pending, n) {
+            return Err(rejected);
+        }
+        info!(pruned = n, "audit: secret.prune (store full)");
+        if let Some(ref sender) = state.webhook_sender {
+            for key in &pruned_keys {
+                sender.fire("secret.expired", key, json!({"reason": "auto_prune"}));
             }
-            (StatusCode::CREATED, Json(CreateResponse { key: body.key })).into_response()
         }
-        Err(e) => internal_error(e),
     }
+    Ok(n)
+}
+
+// ── Capacity ──────────────────────────────────────────────────────────────────
+
+/// Report the public bucket's secret budget so clients can adapt before a
+/// create is refused with 507. `limit` and `remaining` are null when no
+/// `SIRR_MAX_SECRETS` cap is configured.
+pub async fn capacity(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let stored = match state.secrets.list_with_status() {
+        Ok(stored) => stored,
+        Err(e) => return internal_error(e),
+    };
+    let count = stored.len();
+    let reclaimable = stored
+        .iter()
+        .filter(|(meta, status)| {
+            *status == SecretStatus::ExpiredPendingSweep
+                || (meta.delete && meta.max_reads.is_some_and(|max| meta.read_count >= max))
+        })
+        .count();
+    let remaining = state.max_secrets.map(|max| max.saturating_sub(count));
+    let create_allowed = match state.max_secrets {
+        None => true,
+        Some(max) => count < max || (state.auto_prune_on_full && count - reclaimable < max),
+    };
+    Json(json!({
+        "count": count,
+        "limit": state.max_secrets,
+        "remaining": remaining,
+        "reclaimable": reclaimable,
+        "auto_prune_on_full": state.auto_prune_on_full,
+        "create_allowed": create_allowed,
+    }))
+    .into_response()
 }
 
 // ── Get ───────────────────────────────────────────────────────────────────────
@@ -273,9 +1088,35 @@ pub async fn get_secret(
         return bad_key_name();
     }
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
-    match state.store.get(&key) {
-        Ok(GetResult::Value(value, webhook_url)) => {
-            let _ = state.store.record_audit(AuditEvent::new(
+
+    // Range reads are limited to secrets that cannot burn, so a partial
+    // download never consumes the final read.
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    if range.is_some() {
+        match state.secrets.head(&key) {
+            Ok(Some((meta, _))) if meta.delete && meta.max_reads.is_some() => {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({"error": "range requests are not supported on burn-on-read secrets"})),
+                )
+                    .into_response();
+            }
+            Err(e) => return internal_error(e),
+            _ => {}
+        }
+    }
+
+    let _permit = decrypt_permit(&state, 1).await;
+    match state
+        .secrets
+        .get_from(&key, &ip)
+        .inspect(|read| state.metrics.observe_read(read))
+    {
+        Ok(GetResult::Value(value, webhook_url)) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_READ,
                 Some(key.clone()),
                 ip,
@@ -290,10 +1131,13 @@ pub async fn get_secret(
                     sender.fire_for_url(url, "secret.read", &key, json!({}));
                 }
             }
-            Json(json!({ "key": key, "value": value })).into_response()
+            match range {
+                Some(spec) => range_response(&spec, value.as_bytes()),
+                None => Json(json!({ "key": key, "value": value })).into_response(),
+            }
         }
         Ok(GetResult::Burned(value, webhook_url)) => {
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_BURNED,
                 Some(key.clone()),
                 ip,
@@ -311,7 +1155,7 @@ pub async fn get_secret(
             Json(json!({ "key": key, "value": value })).into_response()
         }
         Ok(GetResult::Sealed) => {
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_READ,
                 Some(key.clone()),
                 ip,
@@ -327,7 +1171,7 @@ pub async fn get_secret(
                 .into_response()
         }
         Ok(GetResult::NotFound) => {
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_READ,
                 Some(key.clone()),
                 ip,
@@ -342,10 +1186,312 @@ pub async fn get_secret(
             )
                 .into_response()
         }
+        Err(e) if e.to_string() == VALUE_NOT_UTF8 => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_READ,
+                Some(key.clone()),
+                ip,
+                false,
+                Some("value is not valid UTF-8".into()),
+                None,
+                None,
+            ));
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"error": "stored value is not valid UTF-8 and cannot be returned"})),
+            )
+                .into_response()
+        }
         Err(e) => internal_error(e),
     }
 }
 
+// ── Batch create ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct BatchCreateRequest {
+    /// Each entry takes the same fields as a `POST /secrets` body.
+    pub secrets: Vec<CreateRequest>,
+}
+
+/// Store up to [`MAX_BATCH_CREATE_SECRETS`] secrets in one transaction.
+/// Every entry goes through the checks a `POST /secrets` would, and any
+/// failure rejects the whole batch, so either all secrets are stored or
+/// none are. Each stored secret gets its own audit event. A retry with the
+/// same `Idempotency-Key` replays the first response.
+pub async fn batch_create_secrets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    JsonBody(mut body): JsonBody<BatchCreateRequest>,
+) -> Response {
+    let n = body.secrets.len();
+    if n == 0 || n > MAX_BATCH_CREATE_SECRETS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(
+                json!({"error": format!("secrets must list 1–{MAX_BATCH_CREATE_SECRETS} entries")}),
+            ),
+        )
+            .into_response();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let caller = rate_limit::caller_id(&state.api_keys, &headers, &ip);
+
+    let idempotency_key = match idempotency_key(&headers, &format!("{caller}:batch")) {
+        Ok(key) => key,
+        Err(rejected) => return rejected,
+    };
+    if let Some(replayed) = idempotency_key
+        .as_deref()
+        .and_then(|idem| replay_idempotent(&state, idem))
+    {
+        return replayed;
+    }
+
+    if let Some(ref limiter) = state.create_limiter {
+        if let Err(retry_after) = limiter.check_n(&caller, n as u32) {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [("retry-after", retry_after.as_secs().max(1).to_string())],
+                Json(json!({"error": "secret creation rate limit exceeded"})),
+            )
+                .into_response();
+        }
+    }
+
+    for entry in body.secrets.iter_mut().filter(|e| e.key.is_empty()) {
+        entry.key = match generate_unique_key(&state) {
+            Ok(key) => key,
+            Err(e) => return internal_error(e),
+        };
+    }
+
+    let now = crate::store::idempotency::now();
+    let mut errors = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for (index, entry) in body.secrets.iter().enumerate() {
+        let mut failed = validate_create(entry, &state, now);
+        if !seen.insert(entry.key.as_str()) {
+            failed.insert(
+                0,
+                FieldError {
+                    field: "key",
+                    message: format!("duplicate key {:?}", entry.key),
+                },
+            );
+        }
+        errors.extend(failed.into_iter().map(|e| (index, &entry.key, e)));
+    }
+    if let Some((index, _, first)) = errors.first() {
+        let error = format!("secrets[{index}]: {}", first.message);
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|(index, key, e)| {
+                json!({"index": index, "key": key, "field": e.field, "message": e.message})
+            })
+            .collect();
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": error, "errors": errors})),
+        )
+            .into_response();
+    }
+    if let Some(rejected) = body
+        .secrets
+        .iter()
+        .find_map(|entry| reserved_key(&state, &entry.key))
+    {
+        return rejected;
+    }
+    let create_only = match create_only(&state, &headers) {
+        Ok(create_only) => create_only,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": error}))).into_response()
+        }
+    };
+    let created_by = state.api_keys.label_in(&headers).map(str::to_owned);
+    let secrets: Vec<NewSecret> = body
+        .secrets
+        .into_iter()
+        .map(|entry| NewSecret {
+            // Validated above; `expires_at`, when sent, takes precedence.
+            ttl_seconds: create_ttl(&entry, now).unwrap_or(entry.ttl_seconds),
+            delete: entry.delete.unwrap_or(true),
+            key: entry.key,
+            value: entry.value,
+            max_reads: entry.max_reads,
+            webhook_url: entry.webhook_url,
+            webhook_events: entry.webhook_events,
+            annotations: entry.annotations,
+            kind: entry.kind,
+            read_notify: entry.read_notify,
+            created_by: created_by.clone(),
+        })
+        .collect();
+    let events = secrets
+        .iter()
+        .map(|secret| {
+            let event = AuditEvent::new(
+                ACTION_SECRET_CREATE,
+                Some(secret.key.clone()),
+                ip.clone(),
+                true,
+                Some("batch".into()),
+                None,
+                None,
+            );
+            Box::new(move |_| event) as StagedEvent
+        })
+        .collect();
+    let (report, pending) = match put_capped(&state, &secrets, create_only, events, &ip) {
+        Ok(written) => written,
+        Err(rejected) => return rejected,
+    };
+    if let Some(rejected) = audit_mutation(&state, pending, n) {
+        return rejected;
+    }
+    state.metrics.secrets_created.add(n as u64);
+    info!(count = n, "audit: secret.create (batch)");
+
+    let mut results = Vec::with_capacity(n);
+    for (secret, &replaced) in secrets.iter().zip(&report.replaced) {
+        if let Some(ref sender) = state.webhook_sender {
+            sender.fire("secret.created", &secret.key, json!({}));
+        }
+        let mut result = json!({
+            "key": secret.key,
+            "status": if replaced { "replaced" } else { "created" },
+        });
+        if let Some(ref base) = state.public_base_url {
+            result["url"] = json!(format!("{base}/secrets/{}", secret.key));
+        }
+        results.push(result);
+    }
+    let replaced = results.iter().filter(|r| r["status"] == "replaced").count();
+    let resp = json!({
+        "stored": n,
+        "created": n - replaced,
+        "replaced": replaced,
+        "secrets": results,
+    });
+    if let Some(ref idem) = idempotency_key {
+        record_idempotent(&state, idem, StatusCode::CREATED, &resp);
+    }
+    (
+        StatusCode::CREATED,
+        AppendHeaders(quota_headers(&state, report.stored)),
+        Json(resp),
+    )
+        .into_response()
+}
+
+// ── Batch get ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct BatchGetRequest {
+    pub keys: Vec<String>,
+}
+
+/// Read up to [`MAX_BATCH_GET_KEYS`] secrets in one request. Each key is
+/// counted, burned or sealed exactly as a `GET /secrets/{key}` would, and
+/// gets its own audit event; results are returned in request order.
+pub async fn batch_get_secrets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    JsonBody(body): JsonBody<BatchGetRequest>,
+) -> Response {
+    if body.keys.is_empty() || body.keys.len() > MAX_BATCH_GET_KEYS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("keys must list 1–{MAX_BATCH_GET_KEYS} secrets")})),
+        )
+            .into_response();
+    }
+    if !body.keys.iter().all(|k| validate_key_name(k)) {
+        return bad_key_name();
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(dup) = body.keys.iter().find(|k| !seen.insert(k.as_str())) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("duplicate key {dup:?}")})),
+        )
+            .into_response();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+
+    let _permit = decrypt_permit(&state, body.keys.len()).await;
+    let results = match state.secrets.get_many(&body.keys, &ip) {
+        Ok(results) => {
+            results
+                .iter()
+                .for_each(|read| state.metrics.observe_read(read));
+            results
+        }
+        Err(e) => return internal_error(e),
+    };
+    let secrets: Vec<_> = body
+        .keys
+        .into_iter()
+        .zip(results)
+        .map(|(key, result)| {
+            let (action, success, detail, event, webhook_url, entry) = match result {
+                GetResult::Value(value, url) => (
+                    ACTION_SECRET_READ,
+                    true,
+                    "batch",
+                    Some("secret.read"),
+                    url,
+                    json!({"key": key, "status": "ok", "value": value}),
+                ),
+                GetResult::Burned(value, url) => (
+                    ACTION_SECRET_BURNED,
+                    true,
+                    "batch",
+                    Some("secret.burned"),
+                    url,
+                    json!({"key": key, "status": "burned", "value": value}),
+                ),
+                GetResult::Sealed => (
+                    ACTION_SECRET_READ,
+                    false,
+                    "batch;sealed",
+                    None,
+                    None,
+                    json!({"key": key, "status": "sealed"}),
+                ),
+                GetResult::NotFound => (
+                    ACTION_SECRET_READ,
+                    false,
+                    "batch;not found or expired",
+                    None,
+                    None,
+                    json!({"key": key, "status": "not_found"}),
+                ),
+            };
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                action,
+                Some(key.clone()),
+                ip.clone(),
+                success,
+                Some(detail.into()),
+                None,
+                None,
+            ));
+            if let (Some(event), Some(sender)) = (event, state.webhook_sender.as_ref()) {
+                sender.fire(event, &key, json!({}));
+                if let Some(ref url) = webhook_url {
+                    sender.fire_for_url(url, event, &key, json!({}));
+                }
+            }
+            entry
+        })
+        .collect();
+    Json(json!({ "secrets": secrets })).into_response()
+}
+
 // ── Head ──────────────────────────────────────────────────────────────────────
 
 pub async fn head_secret(
@@ -358,10 +1504,10 @@ pub async fn head_secret(
         return bad_key_name();
     }
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
-    match state.store.head(&key) {
+    match state.secrets.head(&key) {
         Ok(Some((meta, sealed))) => {
             let detail = if sealed { "head;sealed" } else { "head" };
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_READ,
                 Some(key.clone()),
                 ip,
@@ -385,14 +1531,21 @@ pub async fn head_secret(
             let mut builder = Response::builder()
                 .status(status)
                 .header("X-Sirr-Read-Count", meta.read_count.to_string())
+                .header("X-Sirr-Distinct-Readers", meta.distinct_readers.to_string())
                 .header("X-Sirr-Reads-Remaining", reads_remaining)
                 .header("X-Sirr-Delete", meta.delete.to_string())
-                .header("X-Sirr-Created-At", meta.created_at.to_string());
+                .header("X-Sirr-Created-At", meta.created_at.to_string())
+                .header("X-Sirr-Modified-At", meta.updated_at.to_string());
 
             if let Some(exp) = meta.expires_at {
                 builder = builder.header("X-Sirr-Expires-At", exp.to_string());
             }
 
+            if let Some(ref fp) = meta.value_fingerprint {
+                builder = builder.header("X-Sirr-Value-Fingerprint", fp);
+            }
+            builder = builder.header(header::ETAG, meta.etag());
+
             if sealed {
                 builder = builder.header("X-Sirr-Status", "sealed");
             } else {
@@ -402,7 +1555,7 @@ pub async fn head_secret(
             builder.body(axum::body::Body::empty()).unwrap()
         }
         Ok(None) => {
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_READ,
                 Some(key.clone()),
                 ip,
@@ -421,6 +1574,244 @@ pub async fn head_secret(
     }
 }
 
+/// Read one field of a structured secret. Counts as a full read of the secret.
+pub async fn get_secret_field(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((key, field)): Path<(String, String)>,
+) -> Response {
+    if !validate_key_name(&key) {
+        return bad_key_name();
+    }
+    // Check the type first so reading a text secret here doesn't consume a read.
+    match state.secrets.head(&key) {
+        Ok(Some((meta, _))) if meta.kind != SecretKind::Structured => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "secret is not a structured secret"})),
+            )
+                .into_response();
+        }
+        Err(e) => return internal_error(e),
+        _ => {}
+    }
+
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let _permit = decrypt_permit(&state, 1).await;
+    let (value, webhook_url, event, action) = match state
+        .secrets
+        .get_from(&key, &ip)
+        .inspect(|read| state.metrics.observe_read(read))
+    {
+        Ok(GetResult::Value(v, url)) => (v, url, "secret.read", ACTION_SECRET_READ),
+        Ok(GetResult::Burned(v, url)) => (v, url, "secret.burned", ACTION_SECRET_BURNED),
+        Ok(GetResult::Sealed) => {
+            return (
+                StatusCode::GONE,
+                Json(json!({"error": "secret is sealed — reads exhausted"})),
+            )
+                .into_response();
+        }
+        Ok(GetResult::NotFound) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "not found or expired"})),
+            )
+                .into_response();
+        }
+        Err(e) => return internal_error(e),
+    };
+
+    let _ = state.secrets.record_audit(AuditEvent::new(
+        action,
+        Some(key.clone()),
+        ip,
+        true,
+        Some(format!("field={field}")),
+        None,
+        None,
+    ));
+    if let Some(ref sender) = state.webhook_sender {
+        sender.fire(event, &key, json!({}));
+        if let Some(ref url) = webhook_url {
+            sender.fire_for_url(url, event, &key, json!({}));
+        }
+    }
+
+    let found = serde_json::from_str::<serde_json::Value>(&value)
+        .ok()
+        .and_then(|mut obj| obj.get_mut(&field).map(serde_json::Value::take));
+    match found {
+        Some(v) => Json(json!({ "key": key, "field": field, "value": v })).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": format!("field {field:?} not found")})),
+        )
+            .into_response(),
+    }
+}
+
+/// Parse a single `bytes=` range against a body of `len` bytes, returning
+/// the inclusive `(start, end)`. `None` means the range is unsatisfiable.
+fn parse_byte_range(spec: &str, len: usize) -> Option<(usize, usize)> {
+    let (start, end) = spec.strip_prefix("bytes=")?.trim().split_once('-')?;
+    if len == 0 {
+        return None;
+    }
+    let (start, end) = if start.is_empty() {
+        // Suffix range: the last N bytes.
+        let n: usize = end.parse().ok()?;
+        if n == 0 {
+            return None;
+        }
+        (len.saturating_sub(n), len - 1)
+    } else {
+        let start: usize = start.parse().ok()?;
+        let end = if end.is_empty() {
+            len - 1
+        } else {
+            end.parse::<usize>().ok()?.min(len - 1)
+        };
+        (start, end)
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+/// 206 with the requested slice of `body`, or 416 if the range is unsatisfiable.
+fn range_response(spec: &str, body: &[u8]) -> Response {
+    match parse_byte_range(spec, body.len()) {
+        Some((start, end)) => (
+            StatusCode::PARTIAL_CONTENT,
+            [
+                (header::CONTENT_TYPE, "application/octet-stream".to_owned()),
+                (
+                    header::CONTENT_RANGE,
+                    format!("bytes {start}-{end}/{}", body.len()),
+                ),
+            ],
+            body[start..=end].to_vec(),
+        )
+            .into_response(),
+        None => (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(header::CONTENT_RANGE, format!("bytes */{}", body.len()))],
+            Json(json!({"error": "range not satisfiable"})),
+        )
+            .into_response(),
+    }
+}
+
+// ── Describe ──────────────────────────────────────────────────────────────────
+
+/// JSON counterpart to HEAD: full metadata without decrypting the value or
+/// consuming a read. Sealed secrets return their metadata with 410.
+pub async fn describe_secret(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(key): Path<String>,
+) -> Response {
+    if !validate_key_name(&key) {
+        return bad_key_name();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    match state.secrets.head(&key) {
+        Ok(Some((meta, sealed))) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_DESCRIBE,
+                Some(key.clone()),
+                ip,
+                true,
+                sealed.then(|| "sealed".into()),
+                None,
+                None,
+            ));
+            let status = if sealed {
+                StatusCode::GONE
+            } else {
+                StatusCode::OK
+            };
+            let fingerprint = meta
+                .value_fingerprint
+                .as_deref()
+                .and_then(|fp| header::HeaderValue::from_str(fp).ok());
+            let mut resp = (status, Json(meta)).into_response();
+            if let Some(fp) = fingerprint {
+                resp.headers_mut().insert("x-sirr-value-fingerprint", fp);
+            }
+            resp
+        }
+        Ok(None) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_DESCRIBE,
+                Some(key.clone()),
+                ip,
+                false,
+                Some("not found or expired".into()),
+                None,
+                None,
+            ));
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "not found or expired"})),
+            )
+                .into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+// ── Peek ──────────────────────────────────────────────────────────────────────
+
+/// Admin-only read that bypasses ephemeral semantics: returns the value
+/// without incrementing read_count, burning, or honouring a seal.
+pub async fn peek_secret(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(key): Path<String>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    if !validate_key_name(&key) {
+        return bad_key_name();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let _permit = decrypt_permit(&state, 1).await;
+    match state.secrets.peek(&key) {
+        Ok(Some(value)) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_PEEK,
+                Some(key.clone()),
+                ip,
+                true,
+                None,
+                None,
+                None,
+            ));
+            Json(json!({ "key": key, "value": value })).into_response()
+        }
+        Ok(None) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_PEEK,
+                Some(key.clone()),
+                ip,
+                false,
+                Some("not found or expired".into()),
+                None,
+                None,
+            ));
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "not found or expired"})),
+            )
+                .into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
 // ── Patch ─────────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
@@ -428,6 +1819,17 @@ pub struct PatchRequest {
     pub value: Option<String>,
     pub max_reads: Option<u32>,
     pub ttl_seconds: Option<u64>,
+    /// Replaces the full annotation map when present.
+    pub annotations: Option<BTreeMap<String, String>>,
+    /// Annotations added or overwritten on top of the existing ones.
+    #[serde(default)]
+    pub set_annotations: BTreeMap<String, String>,
+    /// Annotation keys removed; unknown keys are ignored.
+    #[serde(default)]
+    pub remove_annotations: Vec<String>,
+    /// `false` turns an unread burn-on-read secret into a seal-on-read one;
+    /// without it, patching a `delete=true` secret is a 409.
+    pub delete: Option<bool>,
 }
 
 pub async fn patch_secret(
@@ -436,12 +1838,15 @@ pub async fn patch_secret(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(key): Path<String>,
-    Json(body): Json<PatchRequest>,
+    JsonBody(body): JsonBody<PatchRequest>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
     if !validate_key_name(&key) {
         return bad_key_name();
     }
+    if let Some(rejected) = reserved_key(&state, &key) {
+        return rejected;
+    }
     if body.max_reads == Some(0) {
         return (
             StatusCode::BAD_REQUEST,
@@ -459,6 +1864,9 @@ pub async fn patch_secret(
             )
                 .into_response();
         }
+        if let Some(message) = value_too_short(&state, v) {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": message}))).into_response();
+        }
     }
     if let Some(ttl) = body.ttl_seconds {
         if ttl > MAX_TTL_SECS {
@@ -469,110 +1877,408 @@ pub async fn patch_secret(
                 .into_response();
         }
     }
+    if let Some(ref annotations) = body.annotations {
+        if let Err(reason) = validate_annotations(annotations) {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": reason}))).into_response();
+        }
+    }
 
-    match state.store.patch(
-        &key,
-        body.value.as_deref(),
-        body.max_reads,
-        body.ttl_seconds,
-    ) {
+    if let Some(both) = body
+        .remove_annotations
+        .iter()
+        .find(|k| body.set_annotations.contains_key(*k))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("annotation {both:?} is in both set_annotations and remove_annotations")})),
+        )
+            .into_response();
+    }
+
+    let annotations = AnnotationsPatch {
+        replace: body.annotations,
+        set: body.set_annotations,
+        remove: body.remove_annotations,
+    };
+    let event = AuditEvent::new(
+        ACTION_SECRET_PATCH,
+        Some(key.clone()),
+        ip.clone(),
+        true,
+        None,
+        None,
+        None,
+    );
+    let (patched, pending) = audited(&state, event, || {
+        state.secrets.patch(
+            &key,
+            body.value.as_deref(),
+            body.max_reads,
+            body.ttl_seconds,
+            annotations,
+            body.delete,
+        )
+    });
+    match patched {
         Ok(Some(meta)) => {
-            let _ = state.store.record_audit(AuditEvent::new(
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
+            Json(meta).into_response()
+        }
+        Ok(None) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_PATCH,
                 Some(key.clone()),
                 ip,
-                true,
-                None,
+                false,
+                Some("not found or expired".into()),
                 None,
                 None,
             ));
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "not found or expired"})),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            let msg = e.to_string();
+            if msg.contains("cannot patch") {
+                let _ = state.secrets.record_audit(AuditEvent::new(
+                    ACTION_SECRET_PATCH,
+                    Some(key.clone()),
+                    ip,
+                    false,
+                    Some("conflict: delete=true".into()),
+                    None,
+                    None,
+                ));
+                (StatusCode::CONFLICT, Json(json!({"error": msg}))).into_response()
+            } else if let Some(reason) = msg.strip_prefix("invalid annotations: ") {
+                (StatusCode::BAD_REQUEST, Json(json!({"error": reason}))).into_response()
+            } else if msg.starts_with("sealed:") {
+                let _ = state.secrets.record_audit(AuditEvent::new(
+                    ACTION_SECRET_PATCH,
+                    Some(key.clone()),
+                    ip,
+                    false,
+                    Some("gone: secret read limit exhausted".into()),
+                    None,
+                    None,
+                ));
+                (
+                    StatusCode::GONE,
+                    Json(json!({"error": "secret read limit exhausted"})),
+                )
+                    .into_response()
+            } else {
+                internal_error(e)
+            }
+        }
+    }
+}
+
+// ── Touch ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct TouchRequest {
+    pub ttl_seconds: u64,
+}
+
+/// `POST /secrets/{key}/touch` — restart a secret's TTL without resending or
+/// re-encrypting its value. Reads already counted stay counted.
+pub async fn touch_secret(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(key): Path<String>,
+    JsonBody(body): JsonBody<TouchRequest>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    if !validate_key_name(&key) {
+        return bad_key_name();
+    }
+    if body.ttl_seconds > MAX_TTL_SECS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("ttl_seconds exceeds maximum of {MAX_TTL_SECS} (10 years)")})),
+        )
+            .into_response();
+    }
+    if body.ttl_seconds < state.min_ttl_seconds {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!(
+                "ttl_seconds must be at least {} on this server",
+                state.min_ttl_seconds
+            )})),
+        )
+            .into_response();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+
+    let event = AuditEvent::new(
+        ACTION_SECRET_TOUCH,
+        Some(key.clone()),
+        ip.clone(),
+        true,
+        Some(format!("ttl_seconds={}", body.ttl_seconds)),
+        None,
+        None,
+    );
+    let (touched, pending) = audited(&state, event, || {
+        state.secrets.touch(&key, body.ttl_seconds)
+    });
+    match touched {
+        Ok(Some(meta)) => {
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(meta).into_response()
         }
-        Ok(None) => {
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_PATCH,
+        Ok(None) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_TOUCH,
+                Some(key.clone()),
+                ip,
+                false,
+                Some("not found or expired".into()),
+                None,
+                None,
+            ));
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "not found or expired"})),
+            )
+                .into_response()
+        }
+        Err(e) if e.to_string().starts_with("sealed:") => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_TOUCH,
+                Some(key.clone()),
+                ip,
+                false,
+                Some("gone: secret read limit exhausted".into()),
+                None,
+                None,
+            ));
+            (
+                StatusCode::GONE,
+                Json(json!({"error": "secret read limit exhausted"})),
+            )
+                .into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+// ── Aliases ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct AliasRequest {
+    pub alias: String,
+}
+
+/// `POST /secrets/{key}/aliases` — make `alias` another name for `key` in
+/// reads, batch reads, HEAD and describe. Reads through the alias share the
+/// secret's read count and burn budget. An alias of an alias points at the
+/// canonical key.
+pub async fn create_alias(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(key): Path<String>,
+    JsonBody(body): JsonBody<AliasRequest>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    if !validate_key_name(&key) || !validate_key_name(&body.alias) {
+        return bad_key_name();
+    }
+    if let Some(rejected) = reserved_key(&state, &body.alias) {
+        return rejected;
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    // The event names the canonical key, which the store resolves again
+    // inside the transaction.
+    let canonical = match state.secrets.resolve_alias(&key) {
+        Ok(canonical) => canonical.unwrap_or_else(|| key.clone()),
+        Err(e) => return internal_error(e),
+    };
+    let event = AuditEvent::new(
+        ACTION_SECRET_ALIAS,
+        Some(canonical),
+        ip.clone(),
+        true,
+        Some(format!("alias={}", body.alias)),
+        None,
+        None,
+    );
+    let (aliased, pending) = audited(&state, event, || state.secrets.put_alias(&body.alias, &key));
+    match aliased {
+        Ok(Ok(canonical)) => {
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
+            (
+                StatusCode::CREATED,
+                Json(json!({"alias": body.alias, "key": canonical})),
+            )
+                .into_response()
+        }
+        Ok(Err(e)) => {
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_SECRET_ALIAS,
                 Some(key.clone()),
                 ip,
                 false,
-                Some("not found or expired".into()),
+                Some(format!("alias={};{e}", body.alias)),
                 None,
                 None,
             ));
-            (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "not found or expired"})),
-            )
-                .into_response()
+            let status = match e {
+                AliasError::SelfAlias => StatusCode::BAD_REQUEST,
+                AliasError::NotFound => StatusCode::NOT_FOUND,
+                AliasError::KeyExists | AliasError::Taken => StatusCode::CONFLICT,
+            };
+            (status, Json(json!({"error": e.to_string()}))).into_response()
         }
-        Err(e) => {
-            let msg = e.to_string();
-            if msg.contains("cannot patch") {
-                let _ = state.store.record_audit(AuditEvent::new(
-                    ACTION_SECRET_PATCH,
-                    Some(key.clone()),
-                    ip,
-                    false,
-                    Some("conflict: delete=true".into()),
-                    None,
-                    None,
-                ));
-                (StatusCode::CONFLICT, Json(json!({"error": msg}))).into_response()
-            } else if msg.starts_with("sealed:") {
-                let _ = state.store.record_audit(AuditEvent::new(
-                    ACTION_SECRET_PATCH,
-                    Some(key.clone()),
-                    ip,
-                    false,
-                    Some("gone: secret read limit exhausted".into()),
-                    None,
-                    None,
-                ));
-                (
-                    StatusCode::GONE,
-                    Json(json!({"error": "secret read limit exhausted"})),
-                )
-                    .into_response()
-            } else {
-                internal_error(e)
+        Err(e) => internal_error(e),
+    }
+}
+
+/// `DELETE /secrets/{key}/aliases/{alias}` — drop an alias of `key`.
+pub async fn delete_alias(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path((key, alias)): Path<(String, String)>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    if !validate_key_name(&key) || !validate_key_name(&alias) {
+        return bad_key_name();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let event = AuditEvent::new(
+        ACTION_SECRET_ALIAS,
+        Some(key.clone()),
+        ip,
+        true,
+        Some(format!("alias={alias};removed")),
+        None,
+        None,
+    );
+    let (removed, pending) = audited(&state, event, || state.secrets.delete_alias(&alias, &key));
+    match removed {
+        Ok(true) => {
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
             }
+            Json(json!({"deleted": true})).into_response()
         }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "alias not found"})),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
     }
 }
 
 // ── Delete ────────────────────────────────────────────────────────────────────
 
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteQueryParams {
+    /// Include the secret's final metadata in the response as `secret`.
+    #[serde(default)]
+    pub return_meta: bool,
+}
+
 pub async fn delete_secret(
     State(state): State<AppState>,
     Extension(_auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(key): Path<String>,
+    Query(params): Query<DeleteQueryParams>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
     if !validate_key_name(&key) {
         return bad_key_name();
     }
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
-    match state.store.delete(&key) {
-        Ok(true) => {
+    let precondition = delete_precondition(&headers);
+    let event = AuditEvent::new(
+        ACTION_SECRET_DELETE,
+        Some(key.clone()),
+        ip.clone(),
+        true,
+        None,
+        None,
+        None,
+    );
+    let (deleted, pending) = if !precondition.is_empty() {
+        let (outcome, pending) = audited(&state, event, || {
+            state.secrets.delete_if(&key, &precondition)
+        });
+        let deleted = match outcome {
+            Ok(ConditionalDelete::PreconditionFailed) => {
+                let _ = state.secrets.record_audit(AuditEvent::new(
+                    ACTION_SECRET_DELETE,
+                    Some(key.clone()),
+                    ip,
+                    false,
+                    Some("precondition failed".into()),
+                    None,
+                    None,
+                ));
+                return (
+                    StatusCode::PRECONDITION_FAILED,
+                    Json(json!({"error": "secret was modified since the precondition was taken"})),
+                )
+                    .into_response();
+            }
+            Ok(ConditionalDelete::NotFound) => Ok(None),
+            Ok(ConditionalDelete::Deleted(meta)) => Ok(Some(params.return_meta.then_some(*meta))),
+            Err(e) => Err(e),
+        };
+        (deleted, pending)
+    } else if params.return_meta {
+        audited(&state, event, || {
+            state
+                .secrets
+                .delete_with_meta(&key)
+                .map(|meta| meta.map(Some))
+        })
+    } else {
+        audited(&state, event, || {
+            state
+                .secrets
+                .delete(&key)
+                .map(|existed| existed.then_some(None))
+        })
+    };
+    match deleted {
+        Ok(Some(meta)) => {
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
+            state.metrics.secrets_deleted.inc();
             info!(key = %key, "audit: secret.delete");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_DELETE,
-                Some(key.clone()),
-                ip,
-                true,
-                None,
-                None,
-                None,
-            ));
             if let Some(ref sender) = state.webhook_sender {
                 sender.fire("secret.deleted", &key, json!({}));
             }
-            Json(json!({"deleted": true})).into_response()
+            match meta {
+                Some(meta) => Json(json!({"deleted": true, "secret": meta})).into_response(),
+                None => Json(json!({"deleted": true})).into_response(),
+            }
         }
-        Ok(false) => {
+        Ok(None) => {
             info!(key = %key, "audit: secret.delete.not_found");
-            let _ = state.store.record_audit(AuditEvent::new(
+            let _ = state.secrets.record_audit(AuditEvent::new(
                 ACTION_SECRET_DELETE,
                 Some(key.clone()),
                 ip,
@@ -587,35 +2293,152 @@ pub async fn delete_secret(
     }
 }
 
+/// Read `If-Match` (against the `ETag` HEAD sends) and
+/// `If-Unmodified-Since` (against `updated_at`) into a [`Precondition`] the
+/// store checks inside the delete transaction. A header that is absent or
+/// unparseable does not block the delete.
+fn delete_precondition(headers: &HeaderMap) -> Precondition {
+    let if_match = headers
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').map(|tag| tag.trim().to_owned()).collect());
+    let if_unmodified_since = headers
+        .get(header::IF_UNMODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+        .map(|since| {
+            since
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64)
+        });
+    Precondition {
+        if_match,
+        if_unmodified_since,
+    }
+}
+
+/// `DELETE /secrets?expires_before=<unix>&not_accessed_since=<unix>` —
+/// remove every secret matching
+/// the query in one transaction. At least one criterion is required.
+pub async fn delete_matching_secrets(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(criteria): Query<DeleteCriteria>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    if criteria.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "bulk delete needs at least one criterion (expires_before, not_accessed_since)"
+            })),
+        )
+            .into_response();
+    }
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let (expires_before, not_accessed_since) =
+        (criteria.expires_before, criteria.not_accessed_since);
+    let event: StagedEvent = Box::new(move |n| {
+        let mut detail = format!("deleted={n}");
+        if let Some(cutoff) = expires_before {
+            detail.push_str(&format!(" expires_before={cutoff}"));
+        }
+        if let Some(cutoff) = not_accessed_since {
+            detail.push_str(&format!(" not_accessed_since={cutoff}"));
+        }
+        AuditEvent::new(
+            ACTION_SECRET_DELETE,
+            None,
+            ip,
+            true,
+            Some(detail),
+            None,
+            None,
+        )
+    });
+    let (deleted, pending) = audited_with(&state, vec![event], || {
+        state.secrets.delete_matching(&criteria)
+    });
+    match deleted {
+        Ok(deleted) => {
+            let n = deleted.len();
+            if let Some(rejected) = audit_mutation(&state, pending, n) {
+                return rejected;
+            }
+            state.metrics.secrets_deleted.add(n as u64);
+            info!(deleted = n, "audit: secret.delete.bulk");
+            if let Some(ref sender) = state.webhook_sender {
+                for key in &deleted {
+                    sender.fire("secret.deleted", key, json!({}));
+                }
+            }
+            Json(json!({"deleted": n, "keys": deleted})).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
 // ── Prune ─────────────────────────────────────────────────────────────────────
 
+#[derive(Debug, Default, Deserialize)]
+pub struct PruneQueryParams {
+    /// `expired`, `burned`, `tombstoned` or `all`; expired and burned if unset.
+    #[serde(default)]
+    pub reason: PruneScope,
+}
+
 pub async fn prune_secrets(
     State(state): State<AppState>,
     Extension(_auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<PruneQueryParams>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
-    match state.store.prune() {
-        Ok(pruned_keys) => {
-            let n = pruned_keys.len();
-            info!(pruned = n, "audit: secret.prune");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_PRUNE,
-                None,
-                ip,
-                true,
-                Some(format!("pruned={n}")),
-                None,
-                None,
-            ));
+    let Ok(_running) = state.sweep_lock.try_lock() else {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "sweep already running"})),
+        )
+            .into_response();
+    };
+    let event: StagedEvent = Box::new(move |n| {
+        AuditEvent::new(
+            ACTION_SECRET_PRUNE,
+            None,
+            ip,
+            true,
+            Some(format!("pruned={n}")),
+            None,
+            None,
+        )
+    });
+    let (pruned, pending) = audited_with(&state, vec![event], || {
+        state.secrets.prune_by(params.reason)
+    });
+    match pruned {
+        Ok(pruned) => {
+            let n = pruned.len();
+            if let Some(rejected) = audit_mutation(&state, pending, n) {
+                return rejected;
+            }
+            state.metrics.secrets_pruned.add(n as u64);
+            let count = |reason| pruned.iter().filter(|(_, r)| *r == reason).count();
+            let by_reason = json!({
+                "expired": count(PruneReason::Expired),
+                "burned": count(PruneReason::Burned),
+                "tombstoned": count(PruneReason::Tombstoned),
+            });
+            info!(pruned = n, scope = ?params.reason, "audit: secret.prune");
             if let Some(ref sender) = state.webhook_sender {
-                for key in &pruned_keys {
+                for (key, _) in &pruned {
                     sender.fire("secret.expired", key, json!({"reason": "manual_prune"}));
                 }
             }
-            Json(json!({"pruned": n})).into_response()
+            Json(json!({"pruned": n, "by_reason": by_reason})).into_response()
         }
         Err(e) => internal_error(e),
     }
@@ -627,6 +2450,11 @@ pub async fn prune_secrets(
 pub struct CreateWebhookRequest {
     pub url: String,
     pub events: Option<Vec<String>>,
+    /// Payload template; see [`webhooks::render_template`].
+    pub template: Option<String>,
+    /// Per-event receivers overriding `url`.
+    #[serde(default)]
+    pub event_urls: BTreeMap<String, String>,
 }
 
 pub async fn create_webhook(
@@ -634,7 +2462,7 @@ pub async fn create_webhook(
     Extension(_auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Json(body): Json<CreateWebhookRequest>,
+    JsonBody(body): JsonBody<CreateWebhookRequest>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
@@ -642,7 +2470,7 @@ pub async fn create_webhook(
     // License gate: free tier gets 0 webhooks.
     if state.license == LicenseStatus::Free {
         return (
-            StatusCode::PAYMENT_REQUIRED,
+            state.limit_status,
             Json(json!({"error": "webhooks require a SIRR_LICENSE_KEY"})),
         )
             .into_response();
@@ -657,8 +2485,12 @@ pub async fn create_webhook(
             .into_response();
     }
 
+    if let Some(Err(reason)) = body.template.as_deref().map(webhooks::validate_template) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": reason}))).into_response();
+    }
+
     // Check count limit.
-    match state.store.count_webhooks() {
+    match state.secrets.count_webhooks() {
         Ok(count) if count >= MAX_WEBHOOKS => {
             return (
                 StatusCode::CONFLICT,
@@ -671,6 +2503,23 @@ pub async fn create_webhook(
     }
 
     let events = body.events.unwrap_or_else(|| vec!["*".to_string()]);
+    if let Err(reason) = webhooks::validate_event_urls(&body.event_urls, &events) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": reason}))).into_response();
+    }
+    // Registering the same URL twice for overlapping events would deliver
+    // every matching event twice.
+    match state.secrets.list_webhooks() {
+        Ok(regs) => {
+            if let Some(existing) = webhooks::find_duplicate(&regs, &body.url, &events) {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({"error": "webhook URL already registered for these events", "id": existing.id})),
+                )
+                    .into_response();
+            }
+        }
+        Err(e) => return internal_error(e),
+    }
     let id = webhooks::generate_webhook_id();
     let secret = webhooks::generate_signing_secret();
 
@@ -684,19 +2533,25 @@ pub async fn create_webhook(
             .unwrap_or_default()
             .as_secs() as i64,
         org_id: None,
+        template: body.template,
+        event_urls: body.event_urls,
     };
 
-    match state.store.put_webhook(&reg) {
+    let event = AuditEvent::new(
+        ACTION_WEBHOOK_CREATE,
+        None,
+        ip,
+        true,
+        Some(format!("id={id}")),
+        None,
+        None,
+    );
+    let (written, pending) = audited(&state, event, || state.secrets.put_webhook(&reg));
+    match written {
         Ok(()) => {
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_WEBHOOK_CREATE,
-                None,
-                ip,
-                true,
-                Some(format!("id={id}")),
-                None,
-                None,
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             (
                 StatusCode::CREATED,
                 Json(json!({"id": id, "secret": secret})),
@@ -712,7 +2567,7 @@ pub async fn list_webhooks(
     Extension(_auth): Extension<ResolvedAuth>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
-    match state.store.list_webhooks() {
+    match state.secrets.list_webhooks() {
         Ok(regs) => {
             // Redact signing secrets in the response.
             let redacted: Vec<_> = regs
@@ -723,6 +2578,8 @@ pub async fn list_webhooks(
                         "url": r.url,
                         "events": r.events,
                         "created_at": r.created_at,
+                        "template": r.template,
+                        "event_urls": r.event_urls,
                     })
                 })
                 .collect();
@@ -732,26 +2589,207 @@ pub async fn list_webhooks(
     }
 }
 
-pub async fn delete_webhook(
+#[derive(Debug, Deserialize)]
+pub struct WebhookPreviewRequest {
+    /// Unprefixed event name, e.g. `secret.burned`.
+    pub event: String,
+    #[serde(default = "preview_key")]
+    pub key: String,
+    #[serde(default)]
+    pub detail: serde_json::Value,
+    /// Payload template to render instead of the default event JSON.
+    pub template: Option<String>,
+    /// Signing secret; a throwaway one is generated (and returned) if omitted.
+    pub secret: Option<String>,
+}
+
+fn preview_key() -> String {
+    "EXAMPLE_KEY".to_owned()
+}
+
+/// `POST /webhooks/preview` — the body and headers a delivery of the given
+/// event would carry. Nothing is sent or stored.
+pub async fn preview_webhook(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    JsonBody(body): JsonBody<WebhookPreviewRequest>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let Some(sender) = state.webhook_sender.as_ref() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({"error": "webhook delivery is not configured"})),
+        )
+            .into_response();
+    };
+    if body.event.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "event is required"})),
+        )
+            .into_response();
+    }
+    if let Some(Err(reason)) = body.template.as_deref().map(webhooks::validate_template) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": reason}))).into_response();
+    }
+    let secret = body
+        .secret
+        .unwrap_or_else(webhooks::generate_signing_secret);
+    let detail = match body.detail {
+        serde_json::Value::Null => json!({}),
+        detail => detail,
+    };
+    match sender.preview(
+        &body.event,
+        &body.key,
+        detail,
+        body.template.as_deref(),
+        &secret,
+    ) {
+        Ok((payload, signature)) => Json(json!({
+            "body": payload,
+            "signature": signature,
+            "schema_version": webhooks::WEBHOOK_SCHEMA_VERSION,
+            "secret": secret,
+        }))
+        .into_response(),
+        Err(reason) => (StatusCode::BAD_REQUEST, Json(json!({"error": reason}))).into_response(),
+    }
+}
+
+/// `GET /webhooks/export` — every registration, signing secrets included, in
+/// the shape `POST /webhooks/import` accepts.
+pub async fn export_webhooks(
     State(state): State<AppState>,
     Extension(_auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Path(id): Path<String>,
 ) -> Response {
     // Auth is handled by require_master_key middleware.
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
-    match state.store.delete_webhook(&id) {
-        Ok(true) => {
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_WEBHOOK_DELETE,
+    match state.secrets.list_webhooks() {
+        Ok(regs) => {
+            // Handing out signing secrets is worth a trace even on success.
+            let _ = state.secrets.record_audit(AuditEvent::new(
+                ACTION_WEBHOOK_EXPORT,
                 None,
                 ip,
                 true,
-                Some(format!("id={id}")),
+                Some(format!("count={}", regs.len())),
                 None,
                 None,
             ));
+            Json(json!({"webhooks": regs})).into_response()
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportWebhooksRequest {
+    pub webhooks: Vec<webhooks::WebhookRegistration>,
+}
+
+/// `POST /webhooks/import` — restore registrations from an export. Every URL
+/// goes through the SSRF check and the result must fit [`MAX_WEBHOOKS`];
+/// nothing is written unless the whole batch is valid. Registrations whose
+/// ID already exists are overwritten.
+pub async fn import_webhooks(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    JsonBody(body): JsonBody<ImportWebhooksRequest>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+
+    if state.license == LicenseStatus::Free {
+        return (
+            state.limit_status,
+            Json(json!({"error": "webhooks require a SIRR_LICENSE_KEY"})),
+        )
+            .into_response();
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    for reg in &body.webhooks {
+        if reg.id.is_empty() || !ids.insert(reg.id.as_str()) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("webhook id {:?} is empty or repeated", reg.id)})),
+            )
+                .into_response();
+        }
+        if let Err(reason) = webhooks::validate_registration_url(&reg.url)
+            .and_then(|()| {
+                reg.template
+                    .as_deref()
+                    .map_or(Ok(()), webhooks::validate_template)
+            })
+            .and_then(|()| webhooks::validate_event_urls(&reg.event_urls, &reg.events))
+        {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": reason, "id": reg.id})),
+            )
+                .into_response();
+        }
+    }
+
+    let event = AuditEvent::new(
+        ACTION_WEBHOOK_IMPORT,
+        None,
+        ip,
+        true,
+        Some(format!("count={}", body.webhooks.len())),
+        None,
+        None,
+    );
+    let (imported, pending) = audited(&state, event, || {
+        state.secrets.put_webhooks(&body.webhooks, MAX_WEBHOOKS)
+    });
+    match imported {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({"error": format!("import would exceed the maximum of {MAX_WEBHOOKS} webhooks")})),
+            )
+                .into_response();
+        }
+        Err(e) => return internal_error(e),
+    }
+    if let Some(rejected) = audit_mutation(&state, pending, body.webhooks.len()) {
+        return rejected;
+    }
+    Json(json!({"imported": body.webhooks.len()})).into_response()
+}
+
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<ResolvedAuth>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+) -> Response {
+    // Auth is handled by require_master_key middleware.
+    let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
+    let event = AuditEvent::new(
+        ACTION_WEBHOOK_DELETE,
+        None,
+        ip,
+        true,
+        Some(format!("id={id}")),
+        None,
+        None,
+    );
+    let (removed, pending) = audited(&state, event, || state.secrets.delete_webhook(&id));
+    match removed {
+        Ok(true) => {
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(json!({"deleted": true})).into_response()
         }
         Ok(false) => (
@@ -767,6 +2805,80 @@ pub async fn delete_webhook(
 
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
+/// Audit events of a mutation that its own transaction did not write.
+#[must_use]
+pub(crate) struct PendingAudit(Vec<StagedEvent>);
+
+impl From<AuditEvent> for PendingAudit {
+    fn from(event: AuditEvent) -> Self {
+        Self(vec![Box::new(move |_| event)])
+    }
+}
+
+/// Run `mutate` with `event` as its audit record; see [`audited_with`].
+pub(crate) fn audited<T>(
+    state: &AppState,
+    event: AuditEvent,
+    mutate: impl FnOnce() -> T,
+) -> (T, PendingAudit) {
+    audited_with(state, vec![Box::new(move |_| event)], mutate)
+}
+
+/// Run `mutate` with `events` as its audit records. In `fail` mode they are
+/// staged, so the store writes them in the mutation's own transaction and a
+/// failed audit write aborts the mutation; otherwise they are left for
+/// [`audit_mutation`] to write once the mutation has committed.
+pub(crate) fn audited_with<T>(
+    state: &AppState,
+    events: Vec<StagedEvent>,
+    mutate: impl FnOnce() -> T,
+) -> (T, PendingAudit) {
+    if state.audit_failure_mode == AuditFailureMode::Fail {
+        let (result, left) = audit::with_staged(events, mutate);
+        (result, PendingAudit(left))
+    } else {
+        (mutate(), PendingAudit(events))
+    }
+}
+
+/// Write the audit events of a mutation that changed `changed` records and
+/// whose transaction did not write them, applying `SIRR_AUDIT_FAILURE_MODE`
+/// to a failed write. Returns the 500 response to send in `fail` mode.
+#[must_use]
+pub(crate) fn audit_mutation(
+    state: &AppState,
+    pending: PendingAudit,
+    changed: usize,
+) -> Option<Response> {
+    for event in pending.0 {
+        let Err(e) = state.secrets.record_audit(event(changed)) else {
+            continue;
+        };
+        match state.audit_failure_mode {
+            AuditFailureMode::Ignore => {}
+            AuditFailureMode::Warn => {
+                tracing::error!(error = %e, "audit write failed; mutation was not audited");
+            }
+            AuditFailureMode::Fail => {
+                return Some(internal_error(e.context("audit write failed")));
+            }
+        }
+    }
+    None
+}
+
+/// Wait for one `SIRR_MAX_CONCURRENT_DECRYPTS` permit per value a read
+/// decrypts. Hold the guard until the store call returns.
+pub(crate) async fn decrypt_permit(
+    state: &AppState,
+    decrypts: usize,
+) -> Option<crate::store::DecryptPermit> {
+    match state.secrets.decrypt_limiter() {
+        Some(limiter) => Some(limiter.acquire_many(decrypts).await),
+        None => None,
+    }
+}
+
 fn internal_error(e: anyhow::Error) -> Response {
     tracing::error!(error = %e, "internal error");
     (
@@ -39,7 +39,7 @@ pub fn instance_id_from_key(key_bytes: &[u8]) -> String {
 ///
 /// The first heartbeat fires immediately. Failures are logged at `warn`
 /// level and never retried — the next interval tick will try again.
-pub fn spawn_heartbeat(config: HeartbeatConfig) {
+pub fn spawn_heartbeat(config: HeartbeatConfig) -> tokio::task::JoinHandle<()> {
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(10))
         .build()
@@ -80,7 +80,7 @@ pub fn spawn_heartbeat(config: HeartbeatConfig) {
                 }
             }
         }
-    });
+    })
 }
 
 #[cfg(test)]
@@ -0,0 +1,236 @@
+//! Scheduled online key rotation (`SIRR_ROTATE_INTERVAL_DAYS`).
+//!
+//! The schedule is anchored on the modification time of `sirr.key`, which
+//! every rotation rewrites, so a restart neither skips nor repeats one.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use tracing::{error, info};
+
+use crate::store::audit::{AuditEvent, ACTION_KEY_ROTATE};
+use crate::store::{crypto, Store};
+use crate::webhooks::WebhookSender;
+
+/// Wait before trying again after a failed rotation.
+const RETRY_AFTER_FAILURE: Duration = Duration::from_secs(3600);
+
+/// When automatic rotations run.
+#[derive(Debug, Clone, Copy)]
+pub struct RotationSchedule {
+    pub interval: Duration,
+    /// UTC hour (0-23) a due rotation is held back to, if any.
+    pub hour: Option<u32>,
+}
+
+impl RotationSchedule {
+    /// First moment at least `interval` after `last` that falls in the
+    /// configured hour.
+    pub fn next_run(&self, last: SystemTime) -> SystemTime {
+        let due = last + self.interval;
+        let Some(hour) = self.hour else {
+            return due;
+        };
+        let secs = due.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut window = secs - secs % 86400 + u64::from(hour) * 3600;
+        if secs >= window + 3600 {
+            window += 86400;
+        }
+        UNIX_EPOCH + Duration::from_secs(window.max(secs))
+    }
+}
+
+/// Generate a new key, re-encrypt every live secret under it and replace
+/// `key_path`. Returns the new key version and the number of secrets rotated.
+pub fn rotate_now(store: &Store, key_path: &Path) -> Result<(u8, usize)> {
+    let version = store.next_key_version()?;
+    let new_key = crypto::generate_key();
+
+    // Written and synced before the store commits, so a crash in between
+    // still leaves the key the secrets are now encrypted under on disk.
+    let pending = key_path.with_extension("key.next");
+    let mut file =
+        File::create(&pending).with_context(|| format!("write {}", pending.display()))?;
+    file.write_all(new_key.as_bytes())
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("write {}", pending.display()))?;
+    let rotated = store.rotate(&new_key, version)?;
+    // The old key must not go before the rotated records are on disk.
+    store.sync()?;
+    std::fs::rename(&pending, key_path).with_context(|| {
+        format!(
+            "replace {} (the current key is in {})",
+            key_path.display(),
+            pending.display()
+        )
+    })?;
+    Ok((version, rotated))
+}
+
+/// Spawn a background task that rotates the key whenever `schedule` says
+/// one is due, firing a `key.rotated` webhook after each rotation.
+pub fn spawn_key_rotation(
+    store: Store,
+    key_path: PathBuf,
+    schedule: RotationSchedule,
+    webhooks: Option<WebhookSender>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let last = std::fs::metadata(&key_path)
+                .and_then(|m| m.modified())
+                .unwrap_or_else(|_| SystemTime::now());
+            let next = schedule.next_run(last);
+            let wait = next.duration_since(SystemTime::now()).unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            let (task_store, task_path) = (store.clone(), key_path.clone());
+            let outcome = tokio::task::spawn_blocking(move || rotate_now(&task_store, &task_path))
+                .await
+                .unwrap_or_else(|e| Err(anyhow::anyhow!("rotation task failed: {e}")));
+            match outcome {
+                Ok((version, rotated)) => {
+                    info!(
+                        key_version = version,
+                        rotated, "scheduled key rotation complete"
+                    );
+                    if let Some(webhooks) = &webhooks {
+                        webhooks.fire(
+                            "key.rotated",
+                            "",
+                            serde_json::json!({"key_version": version, "rotated": rotated}),
+                        );
+                    }
+                }
+                Err(e) => {
+                    error!(error = %format!("{e:#}"), "scheduled key rotation failed");
+                    let _ = store.record_audit(AuditEvent::new(
+                        ACTION_KEY_ROTATE,
+                        None,
+                        "local".into(),
+                        false,
+                        Some(format!("scheduled;error={e:#}")),
+                        None,
+                        None,
+                    ));
+                    tokio::time::sleep(RETRY_AFTER_FAILURE).await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::model::NewSecret;
+    use crate::store::GetResult;
+
+    #[test]
+    fn due_rotation_waits_for_its_hour() {
+        let day = |d: u64, h: u64| UNIX_EPOCH + Duration::from_secs(d * 86400 + h * 3600);
+        let schedule = RotationSchedule {
+            interval: Duration::from_secs(86400),
+            hour: Some(3),
+        };
+        assert_eq!(schedule.next_run(day(10, 1)), day(11, 3));
+        assert_eq!(schedule.next_run(day(10, 5)), day(12, 3));
+        // Already inside the window: run at once.
+        let inside = day(10, 3) + Duration::from_secs(600);
+        assert_eq!(
+            schedule.next_run(inside),
+            inside + Duration::from_secs(86400)
+        );
+
+        let anytime = RotationSchedule {
+            hour: None,
+            ..schedule
+        };
+        assert_eq!(anytime.next_run(day(10, 5)), day(11, 5));
+    }
+
+    #[test]
+    fn key_versions_wrap_past_255_to_unused_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("sirr.key");
+        let key = crypto::generate_key();
+        std::fs::write(&key_path, key.as_bytes()).unwrap();
+        let store = Store::open_versioned(&dir.path().join("sirr.db"), key, 255).unwrap();
+        store
+            .put(&NewSecret {
+                key: "A".into(),
+                value: "hunter2".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+
+        assert_eq!(rotate_now(&store, &key_path).unwrap(), (1, 1));
+        assert_eq!(rotate_now(&store, &key_path).unwrap(), (2, 1));
+        assert!(matches!(store.get("A").unwrap(), GetResult::Value(v, _) if v == "hunter2"));
+    }
+
+    #[tokio::test]
+    async fn scheduled_rotation_bumps_version_and_keeps_secrets_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("sirr.key");
+        let key = crypto::generate_key();
+        std::fs::write(&key_path, key.as_bytes()).unwrap();
+        let store = Store::open(&dir.path().join("sirr.db"), key).unwrap();
+        for k in ["A", "B"] {
+            store
+                .put(&NewSecret {
+                    key: k.to_owned(),
+                    value: "hunter2".into(),
+                    ..NewSecret::default()
+                })
+                .unwrap();
+        }
+        let old_key = std::fs::read(&key_path).unwrap();
+
+        let task = spawn_key_rotation(
+            store.clone(),
+            key_path.clone(),
+            RotationSchedule {
+                interval: Duration::from_millis(100),
+                hour: None,
+            },
+            None,
+        );
+        for _ in 0..50 {
+            if store.max_key_version().unwrap() > 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(store.max_key_version().unwrap() > 1);
+
+        // The running store already uses the new key...
+        assert!(matches!(store.get("A").unwrap(), GetResult::Value(v, _) if v == "hunter2"));
+        store
+            .put(&NewSecret {
+                key: "C".into(),
+                value: "after".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+
+        // ...and so does a restart from the rewritten key file.
+        let new_key = std::fs::read(&key_path).unwrap();
+        assert_ne!(new_key, old_key);
+        assert!(!key_path.with_extension("key.next").exists());
+        task.abort();
+        let _ = task.await;
+        drop(store);
+        let reopened = Store::open(
+            &dir.path().join("sirr.db"),
+            crypto::load_key(&new_key).unwrap(),
+        )
+        .unwrap();
+        let report = reopened.check().unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert_eq!(report.ok, 3);
+    }
+}
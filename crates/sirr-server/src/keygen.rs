@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, rngs::StdRng, CryptoRng, Rng, RngCore, SeedableRng};
+use sha2::Sha256;
+
+/// Source of server-generated secret keys. Each key is drawn from an RNG
+/// seeded with HMAC(instance seed, counter ‖ OS randomness), so keys stay
+/// unique per instance and differ across instances even if the OS RNG
+/// were to repeat itself.
+pub struct KeyGenerator {
+    /// Random per-process secret; never leaves memory.
+    instance_seed: [u8; 32],
+    /// Bumped for every key, so no two draws on this instance share a seed.
+    counter: AtomicU64,
+}
+
+impl Default for KeyGenerator {
+    fn default() -> Self {
+        let mut instance_seed = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut instance_seed);
+        Self {
+            instance_seed,
+            counter: AtomicU64::new(0),
+        }
+    }
+}
+
+impl KeyGenerator {
+    /// Alphanumeric key of `len` characters.
+    pub fn generate(&self, len: usize) -> String {
+        self.generate_with(&mut rand::rngs::OsRng, len)
+    }
+
+    /// [`generate`](Self::generate) with the OS randomness drawn from `rng`.
+    pub fn generate_with(&self, rng: &mut (impl RngCore + CryptoRng), len: usize) -> String {
+        let mut entropy = [0u8; 32];
+        rng.fill_bytes(&mut entropy);
+        let count = self.counter.fetch_add(1, Ordering::Relaxed);
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.instance_seed)
+            .expect("HMAC accepts any key length");
+        mac.update(&count.to_le_bytes());
+        mac.update(&entropy);
+        let seed: [u8; 32] = mac.finalize().into_bytes().into();
+
+        StdRng::from_seed(seed)
+            .sample_iter(&Alphanumeric)
+            .take(len)
+            .map(char::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// An "RNG" that returns the same bytes forever.
+    struct Stuck;
+
+    impl RngCore for Stuck {
+        fn next_u32(&mut self) -> u32 {
+            0
+        }
+        fn next_u64(&mut self) -> u64 {
+            0
+        }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(0);
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            dest.fill(0);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for Stuck {}
+
+    #[test]
+    fn generated_keys_do_not_collide() {
+        let keygen = KeyGenerator::default();
+        let keys: HashSet<String> = (0..10_000).map(|_| keygen.generate(24)).collect();
+        assert_eq!(keys.len(), 10_000);
+        assert!(keys
+            .iter()
+            .all(|k| k.len() == 24 && k.bytes().all(|b| b.is_ascii_alphanumeric())));
+    }
+
+    #[test]
+    fn stuck_rng_still_yields_unique_per_instance_keys() {
+        let a = KeyGenerator::default();
+        let b = KeyGenerator::default();
+        let from_a: HashSet<String> = (0..1_000)
+            .map(|_| a.generate_with(&mut Stuck, 24))
+            .collect();
+        assert_eq!(from_a.len(), 1_000, "counter keeps one instance unique");
+        // Same counter position, same OS bytes: the instance seed differs.
+        assert!(!from_a.contains(&b.generate_with(&mut Stuck, 24)));
+    }
+}
@@ -1,11 +1,17 @@
 pub mod auth;
 pub mod dirs;
+pub mod extract;
 pub mod handlers;
 pub mod heartbeat;
+pub mod key_rotation;
+pub mod keygen;
 pub mod license;
+pub mod metrics;
 pub mod org_handlers;
+pub mod rate_limit;
 pub mod server;
 pub mod store;
+pub mod tls;
 pub mod validator;
 pub mod webhooks;
 
@@ -13,8 +19,12 @@ pub mod webhooks;
 #[derive(Clone)]
 pub struct AppState {
     pub store: store::Store,
-    /// Optional API key for write-protecting mutations.
-    pub api_key: Option<String>,
+    /// Backend for the public `/secrets` bucket, its audit log and webhook
+    /// registrations. `run` points this at `store`; tests may substitute
+    /// [`store::MemoryStore`].
+    pub secrets: std::sync::Arc<dyn store::SecretStore>,
+    /// Master API keys for write-protecting mutations; empty runs open.
+    pub api_keys: auth::ApiKeys,
     /// Validated license status (set at startup).
     pub license: license::LicenseStatus,
     /// Online license validator (present only when a license key is configured).
@@ -33,6 +43,57 @@ pub struct AppState {
     /// When true (default), the public /secrets bucket routes are enabled.
     /// Set `ENABLE_PUBLIC_BUCKET=false` to disable the legacy single-tenant routes.
     pub enable_public_bucket: bool,
+    /// Unix timestamp at which `run` started serving.
+    pub started_at: i64,
+    /// Monotonic start instant used to compute uptime.
+    pub started: std::time::Instant,
+    /// Per-caller limiter for `POST /secrets` (None = unlimited).
+    pub create_limiter: Option<rate_limit::CreateLimiter>,
+    /// Held while a prune runs so on-demand and scheduled sweeps never overlap.
+    pub sweep_lock: std::sync::Arc<tokio::sync::Mutex<()>>,
+    /// Base URL used to build the shareable `url` in create responses,
+    /// without a trailing slash. None = the field is omitted.
+    pub public_base_url: Option<String>,
+    /// Cap on stored public-bucket secrets, counting expired ones not yet
+    /// swept (None = unlimited).
+    pub max_secrets: Option<usize>,
+    /// When the cap is hit, prune expired/burned secrets and re-check
+    /// before rejecting the create.
+    pub auto_prune_on_full: bool,
+    /// Shortest `ttl_seconds` accepted on create (0 = no minimum).
+    pub min_ttl_seconds: u64,
+    /// Reject creates that omit `ttl_seconds`.
+    pub require_ttl: bool,
+    /// Status for license tier limit responses (402, 403 or 429).
+    pub limit_status: axum::http::StatusCode,
+    /// Key prefixes clients may not create or patch under.
+    pub reserved_key_prefixes: std::sync::Arc<Vec<String>>,
+    /// Length of keys generated for creates that omit `key`.
+    pub generated_key_length: usize,
+    /// Source of those generated keys.
+    pub key_generator: std::sync::Arc<keygen::KeyGenerator>,
+    /// Record refused CORS preflights as `cors.rejected` audit events.
+    pub audit_cors_rejections: bool,
+    /// Response to a failed audit write on a mutation.
+    pub audit_failure_mode: store::AuditFailureMode,
+    /// Widest `since`..`until` span an audit query may cover; `None` = no limit.
+    pub audit_max_range_secs: Option<i64>,
+    /// Whether `POST /secrets` may overwrite an existing key.
+    pub create_mode: handlers::CreateMode,
+    /// How long an `Idempotency-Key` create response is replayed.
+    pub idempotency_ttl_secs: u64,
+    /// Read-only maintenance mode, toggled at runtime and reset on restart.
+    pub maintenance: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// `POST /admin/self-destruct` settings; `None` disables the endpoint.
+    pub self_destruct: Option<std::sync::Arc<handlers::SelfDestruct>>,
+    /// Values shorter than this many bytes are rejected with 400.
+    pub min_value_bytes: usize,
+    /// Addresses `run` bound, reported by `GET /status`.
+    pub listeners: std::sync::Arc<handlers::ListenerSet>,
+    /// Counters and latency histogram served by `GET /metrics`.
+    pub metrics: std::sync::Arc<metrics::Metrics>,
+    /// Serve `GET /metrics` without the master key.
+    pub metrics_public: bool,
 }
 
-pub use server::{read_key_file, resolve_data_dir, run, ServerConfig};
+pub use server::{read_key_file, resolve_data_dir, run, run_until, ServerConfig};
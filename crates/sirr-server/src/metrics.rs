@@ -0,0 +1,251 @@
+//! Prometheus text exposition for `GET /metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::store::GetResult;
+use crate::AppState;
+
+/// Content type of the Prometheus text format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds, in seconds, of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Monotonic counter.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; `+Inf` is `count`.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|&le| secs <= le) {
+            self.buckets[i] += 1;
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Process-wide counters behind `GET /metrics`, shared by the handlers, the
+/// background sweep and the webhook sender. Totals reset on restart.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub secrets_created: Counter,
+    /// Reads that returned a value, burning reads included.
+    pub secret_reads: Counter,
+    pub secrets_burned: Counter,
+    /// Reads refused because the secret is sealed.
+    pub sealed_reads: Counter,
+    pub secrets_deleted: Counter,
+    /// Secrets removed by `POST /prune`, the background sweep or a
+    /// prune-on-full create.
+    pub secrets_pruned: Counter,
+    pub webhook_deliveries_ok: Counter,
+    pub webhook_deliveries_failed: Counter,
+    /// Handler latency per matched route.
+    latency: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl Metrics {
+    /// Count the outcome of a value read.
+    pub fn observe_read(&self, result: &GetResult) {
+        match result {
+            GetResult::Value(..) => self.secret_reads.inc(),
+            GetResult::Burned(..) => {
+                self.secret_reads.inc();
+                self.secrets_burned.inc();
+            }
+            GetResult::Sealed => self.sealed_reads.inc(),
+            GetResult::NotFound => {}
+        }
+    }
+
+    pub fn observe_latency(&self, route: &str, elapsed: Duration) {
+        self.latency
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(route.to_owned())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Render every counter and the latency histogram.
+    pub fn render(&self, out: &mut String) {
+        for (name, help, counter) in [
+            (
+                "sirr_secrets_created_total",
+                "Secrets created.",
+                &self.secrets_created,
+            ),
+            (
+                "sirr_secret_reads_total",
+                "Reads that returned a secret value.",
+                &self.secret_reads,
+            ),
+            (
+                "sirr_secrets_burned_total",
+                "Secrets deleted by their final read.",
+                &self.secrets_burned,
+            ),
+            (
+                "sirr_sealed_reads_total",
+                "Reads refused because the secret is sealed.",
+                &self.sealed_reads,
+            ),
+            (
+                "sirr_secrets_deleted_total",
+                "Secrets deleted by request.",
+                &self.secrets_deleted,
+            ),
+            (
+                "sirr_secrets_pruned_total",
+                "Expired or burned secrets removed by prune or the sweep.",
+                &self.secrets_pruned,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {}", counter.get());
+        }
+
+        out.push_str("# HELP sirr_webhook_deliveries_total Webhook delivery attempts.\n");
+        out.push_str("# TYPE sirr_webhook_deliveries_total counter\n");
+        for (result, counter) in [
+            ("success", &self.webhook_deliveries_ok),
+            ("failure", &self.webhook_deliveries_failed),
+        ] {
+            let _ = writeln!(
+                out,
+                "sirr_webhook_deliveries_total{{result=\"{result}\"}} {}",
+                counter.get()
+            );
+        }
+
+        let latency = self
+            .latency
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        out.push_str(
+            "# HELP sirr_http_request_duration_seconds Handler latency per matched route.\n",
+        );
+        out.push_str("# TYPE sirr_http_request_duration_seconds histogram\n");
+        for (route, h) in &latency {
+            let route = escape_label(route);
+            let mut cumulative = 0;
+            for (le, n) in LATENCY_BUCKETS.iter().zip(h.buckets) {
+                cumulative += n;
+                let _ = writeln!(
+                    out,
+                    "sirr_http_request_duration_seconds_bucket{{route=\"{route}\",le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "sirr_http_request_duration_seconds_bucket{{route=\"{route}\",le=\"+Inf\"}} {}",
+                h.count
+            );
+            let _ = writeln!(
+                out,
+                "sirr_http_request_duration_seconds_sum{{route=\"{route}\"}} {}",
+                h.sum
+            );
+            let _ = writeln!(
+                out,
+                "sirr_http_request_duration_seconds_count{{route=\"{route}\"}} {}",
+                h.count
+            );
+        }
+    }
+}
+
+/// Render `sirr_secrets_active`, the secrets stored in the public bucket.
+fn render_active_secrets(out: &mut String, active: usize) {
+    out.push_str(
+        "# HELP sirr_secrets_active Secrets in the public bucket, including expired ones not yet swept.\n",
+    );
+    out.push_str("# TYPE sirr_secrets_active gauge\n");
+    let _ = writeln!(out, "sirr_secrets_active {active}");
+}
+
+/// Render `sirr_audit_total` from `(action, success)` totals.
+pub fn render_audit_counts(out: &mut String, counts: &BTreeMap<(String, bool), u64>) {
+    out.push_str("# HELP sirr_audit_total Audit events recorded since startup.\n");
+    out.push_str("# TYPE sirr_audit_total counter\n");
+    for ((action, success), n) in counts {
+        let _ = writeln!(
+            out,
+            "sirr_audit_total{{action=\"{}\",success=\"{success}\"}} {n}",
+            escape_label(action)
+        );
+    }
+}
+
+/// Escape a label value per the exposition format.
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    // Auth is handled by require_master_key middleware unless
+    // SIRR_METRICS_PUBLIC is set.
+    let mut out = String::new();
+    render_audit_counts(&mut out, &state.secrets.audit_counts());
+    state.metrics.render(&mut out);
+    match state.secrets.count_public() {
+        Ok(count) => render_active_secrets(&mut out, count),
+        Err(e) => tracing::warn!(error = %e, "failed to count secrets for metrics"),
+    }
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], out).into_response()
+}
+
+/// Route layer timing each matched request into
+/// `sirr_http_request_duration_seconds`.
+pub async fn track_latency(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_owned());
+    let started = Instant::now();
+    let resp = next.run(req).await;
+    if let Some(route) = route {
+        state.metrics.observe_latency(&route, started.elapsed());
+    }
+    resp
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::SocketAddr;
 
 use axum::{
@@ -14,15 +14,18 @@ use tracing::info;
 
 use crate::{
     auth::ResolvedAuth,
+    extract::JsonBody,
+    handlers::{self, audit_mutation, audited, audited_with, value_too_short},
     license,
     store::{
         audit::{
-            AuditEvent, ACTION_KEY_CREATE, ACTION_KEY_DELETE, ACTION_ORG_CREATE, ACTION_ORG_DELETE,
-            ACTION_PRINCIPAL_CREATE, ACTION_PRINCIPAL_DELETE, ACTION_ROLE_CREATE,
-            ACTION_ROLE_DELETE, ACTION_SECRET_BURNED, ACTION_SECRET_CREATE, ACTION_SECRET_DELETE,
-            ACTION_SECRET_LIST, ACTION_SECRET_PATCH, ACTION_SECRET_PRUNE, ACTION_SECRET_READ,
-            ACTION_WEBHOOK_CREATE, ACTION_WEBHOOK_DELETE,
+            AuditEvent, StagedEvent, ACTION_KEY_CREATE, ACTION_KEY_DELETE, ACTION_ORG_CREATE,
+            ACTION_ORG_DELETE, ACTION_PRINCIPAL_CREATE, ACTION_PRINCIPAL_DELETE,
+            ACTION_ROLE_CREATE, ACTION_ROLE_DELETE, ACTION_SECRET_BURNED, ACTION_SECRET_CREATE,
+            ACTION_SECRET_DELETE, ACTION_SECRET_LIST, ACTION_SECRET_PATCH, ACTION_SECRET_PRUNE,
+            ACTION_SECRET_READ, ACTION_WEBHOOK_CREATE, ACTION_WEBHOOK_DELETE,
         },
+        model::expiry_after,
         org::{validate_metadata, OrgRecord, PrincipalKeyRecord, PrincipalRecord, RoleRecord},
         permissions::{PermBit, Permissions},
         AuditQuery, GetResult,
@@ -120,7 +123,7 @@ pub async fn create_org(
     Extension(auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Json(body): Json<CreateOrgRequest>,
+    JsonBody(body): JsonBody<CreateOrgRequest>,
 ) -> Response {
     if !auth.is_master() && !auth.can_sirr_admin() {
         return forbidden();
@@ -140,7 +143,7 @@ pub async fn create_org(
         match state.store.list_orgs() {
             Ok(orgs) if orgs.len() >= max => {
                 return (
-                    StatusCode::PAYMENT_REQUIRED,
+                    state.limit_status,
                     Json(json!({
                         "error": format!(
                             "tier limit: max {max} org(s) — upgrade at https://sirrlock.com/pricing"
@@ -164,18 +167,22 @@ pub async fn create_org(
         created_at: now_epoch(),
     };
 
-    match state.store.put_org(&org) {
+    let event = AuditEvent::new(
+        ACTION_ORG_CREATE,
+        None,
+        ip,
+        true,
+        Some(format!("org_id={id}")),
+        Some(id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (written, pending) = audited(&state, event, || state.store.put_org(&org));
+    match written {
         Ok(()) => {
             info!(org_id = %id, name = %body.name, "audit: org.create");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_ORG_CREATE,
-                None,
-                ip,
-                true,
-                Some(format!("org_id={id}")),
-                Some(id.clone()),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             (
                 StatusCode::CREATED,
                 Json(json!({"id": id, "name": org.name})),
@@ -213,18 +220,22 @@ pub async fn delete_org(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.delete_org(&org_id) {
+    let event = AuditEvent::new(
+        ACTION_ORG_DELETE,
+        None,
+        ip,
+        true,
+        Some(format!("org_id={org_id}")),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (deleted, pending) = audited(&state, event, || state.store.delete_org(&org_id));
+    match deleted {
         Ok(true) => {
             info!(org_id = %org_id, "audit: org.delete");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_ORG_DELETE,
-                None,
-                ip,
-                true,
-                Some(format!("org_id={org_id}")),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(json!({"deleted": true})).into_response()
         }
         Ok(false) => not_found("org not found"),
@@ -255,7 +266,7 @@ pub async fn create_principal(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(org_id): Path<String>,
-    Json(body): Json<CreatePrincipalRequest>,
+    JsonBody(body): JsonBody<CreatePrincipalRequest>,
 ) -> Response {
     if !(auth.is_master() || auth.can_manage_org() && auth.org_id() == Some(&org_id)) {
         return forbidden();
@@ -293,7 +304,7 @@ pub async fn create_principal(
         match state.store.list_principals(&org_id) {
             Ok(principals) if principals.len() >= max => {
                 return (
-                    StatusCode::PAYMENT_REQUIRED,
+                    state.limit_status,
                     Json(json!({
                         "error": format!(
                             "tier limit: max {max} principal(s) per org — upgrade at https://sirrlock.com/pricing"
@@ -319,18 +330,22 @@ pub async fn create_principal(
         created_at: now_epoch(),
     };
 
-    match state.store.put_principal(&principal) {
+    let event = AuditEvent::new(
+        ACTION_PRINCIPAL_CREATE,
+        None,
+        ip,
+        true,
+        Some(format!("principal_id={id}")),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (written, pending) = audited(&state, event, || state.store.put_principal(&principal));
+    match written {
         Ok(()) => {
             info!(principal_id = %id, org_id = %org_id, "audit: principal.create");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_PRINCIPAL_CREATE,
-                None,
-                ip,
-                true,
-                Some(format!("principal_id={id}")),
-                Some(org_id.clone()),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             (
                 StatusCode::CREATED,
                 Json(json!({
@@ -389,18 +404,22 @@ pub async fn delete_principal(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.delete_principal(&org_id, &id) {
+    let event = AuditEvent::new(
+        ACTION_PRINCIPAL_DELETE,
+        None,
+        ip,
+        true,
+        Some(format!("principal_id={id}")),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (deleted, pending) = audited(&state, event, || state.store.delete_principal(&org_id, &id));
+    match deleted {
         Ok(true) => {
             info!(principal_id = %id, org_id = %org_id, "audit: principal.delete");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_PRINCIPAL_DELETE,
-                None,
-                ip,
-                true,
-                Some(format!("principal_id={id}")),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(json!({"deleted": true})).into_response()
         }
         Ok(false) => not_found("principal not found"),
@@ -429,7 +448,7 @@ pub async fn create_role(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(org_id): Path<String>,
-    Json(body): Json<CreateRoleRequest>,
+    JsonBody(body): JsonBody<CreateRoleRequest>,
 ) -> Response {
     if !(auth.is_master() || auth.can_manage_org() && auth.org_id() == Some(&org_id)) {
         return forbidden();
@@ -460,18 +479,22 @@ pub async fn create_role(
         created_at: now_epoch(),
     };
 
-    match state.store.put_role(&role) {
+    let event = AuditEvent::new(
+        ACTION_ROLE_CREATE,
+        None,
+        ip,
+        true,
+        Some(format!("role={}", body.name)),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (written, pending) = audited(&state, event, || state.store.put_role(&role));
+    match written {
         Ok(()) => {
             info!(role = %body.name, org_id = %org_id, "audit: role.create");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_ROLE_CREATE,
-                None,
-                ip,
-                true,
-                Some(format!("role={}", body.name)),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             (
                 StatusCode::CREATED,
                 Json(json!({
@@ -528,18 +551,24 @@ pub async fn delete_role(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.delete_role(Some(&org_id), &name) {
+    let event = AuditEvent::new(
+        ACTION_ROLE_DELETE,
+        None,
+        ip,
+        true,
+        Some(format!("role={name}")),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (deleted, pending) = audited(&state, event, || {
+        state.store.delete_role(Some(&org_id), &name)
+    });
+    match deleted {
         Ok(true) => {
             info!(role = %name, org_id = %org_id, "audit: role.delete");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_ROLE_DELETE,
-                None,
-                ip,
-                true,
-                Some(format!("role={name}")),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(json!({"deleted": true})).into_response()
         }
         Ok(false) => not_found("role not found"),
@@ -593,6 +622,7 @@ pub async fn get_me(
                 "valid_after": k.valid_after,
                 "valid_before": k.valid_before,
                 "created_at": k.created_at,
+                "max_secrets": k.max_secrets,
             })
         })
         .collect();
@@ -618,7 +648,7 @@ pub struct PatchMeRequest {
 pub async fn patch_me(
     State(state): State<AppState>,
     Extension(auth): Extension<ResolvedAuth>,
-    Json(body): Json<PatchMeRequest>,
+    JsonBody(body): JsonBody<PatchMeRequest>,
 ) -> Response {
     if !auth.can_account_manage() {
         return forbidden();
@@ -665,6 +695,8 @@ pub struct CreateKeyRequest {
     pub name: String,
     pub valid_for_seconds: Option<i64>,
     pub valid_before: Option<i64>,
+    /// Maximum number of live secrets this key may create. Omit for unlimited.
+    pub max_secrets: Option<u32>,
 }
 
 pub async fn create_key(
@@ -672,7 +704,7 @@ pub async fn create_key(
     Extension(auth): Extension<ResolvedAuth>,
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Json(body): Json<CreateKeyRequest>,
+    JsonBody(body): JsonBody<CreateKeyRequest>,
 ) -> Response {
     if !auth.can_account_manage() {
         return forbidden();
@@ -690,6 +722,9 @@ pub async fn create_key(
     if body.name.is_empty() || body.name.len() > 128 {
         return bad_request("key name must be 1-128 characters");
     }
+    if body.max_secrets == Some(0) {
+        return bad_request("max_secrets must be >= 1; omit to allow unlimited secrets");
+    }
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
     let now = now_epoch();
@@ -724,20 +759,25 @@ pub async fn create_key(
         valid_after,
         valid_before,
         created_at: now,
+        max_secrets: body.max_secrets,
     };
 
-    match state.store.put_principal_key(&key_record) {
+    let event = AuditEvent::new(
+        ACTION_KEY_CREATE,
+        None,
+        ip,
+        true,
+        Some(format!("key_id={id}")),
+        Some(org_id.clone()),
+        Some(principal_id.clone()),
+    );
+    let (written, pending) = audited(&state, event, || state.store.put_principal_key(&key_record));
+    match written {
         Ok(()) => {
             info!(key_id = %id, principal_id = %principal_id, "audit: key.create");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_KEY_CREATE,
-                None,
-                ip,
-                true,
-                Some(format!("key_id={id}")),
-                Some(org_id),
-                Some(principal_id),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             (
                 StatusCode::CREATED,
                 Json(json!({
@@ -746,6 +786,7 @@ pub async fn create_key(
                     "key": raw_key,
                     "valid_after": valid_after,
                     "valid_before": valid_before,
+                    "max_secrets": body.max_secrets,
                 })),
             )
                 .into_response()
@@ -776,18 +817,24 @@ pub async fn delete_key(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.delete_principal_key(&principal_id, &key_id) {
+    let event = AuditEvent::new(
+        ACTION_KEY_DELETE,
+        None,
+        ip,
+        true,
+        Some(format!("key_id={key_id}")),
+        Some(org_id.clone()),
+        Some(principal_id.clone()),
+    );
+    let (deleted, pending) = audited(&state, event, || {
+        state.store.delete_principal_key(&principal_id, &key_id)
+    });
+    match deleted {
         Ok(true) => {
             info!(key_id = %key_id, principal_id = %principal_id, "audit: key.delete");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_KEY_DELETE,
-                None,
-                ip,
-                true,
-                Some(format!("key_id={key_id}")),
-                Some(org_id),
-                Some(principal_id),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(json!({"deleted": true})).into_response()
         }
         Ok(false) => not_found("key not found"),
@@ -814,7 +861,7 @@ pub async fn create_org_secret(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(org_id): Path<String>,
-    Json(body): Json<CreateOrgSecretRequest>,
+    JsonBody(body): JsonBody<CreateOrgSecretRequest>,
 ) -> Response {
     if !auth.can_create() || auth.org_id() != Some(&org_id) {
         return forbidden();
@@ -829,6 +876,9 @@ pub async fn create_org_secret(
     if body.value.len() > 1_048_576 {
         return bad_request("value exceeds 1 MiB limit");
     }
+    if let Some(message) = value_too_short(&state, &body.value) {
+        return bad_request(&message);
+    }
     if let Some(ttl) = body.ttl_seconds {
         if ttl > MAX_TTL_SECS {
             return bad_request(&format!(
@@ -844,35 +894,49 @@ pub async fn create_org_secret(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    let expires_at = body.ttl_seconds.map(|ttl| now_epoch() + ttl as i64);
-
-    match state.store.put_org_secret(
-        &org_id,
-        &body.key,
-        &body.value,
-        expires_at,
-        body.max_reads,
-        body.delete.unwrap_or(true),
-        body.webhook_url.clone(),
-        auth.principal_id(),
-        body.allowed_keys.clone(),
-    ) {
-        Ok(()) => {
+    let expires_at = body.ttl_seconds.map(|ttl| expiry_after(now_epoch(), ttl));
+
+    let event = AuditEvent::new(
+        ACTION_SECRET_CREATE,
+        Some(body.key.clone()),
+        ip,
+        true,
+        None,
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (written, pending) = audited(&state, event, || {
+        state.store.put_org_secret(
+            &org_id,
+            &body.key,
+            &body.value,
+            expires_at,
+            body.max_reads,
+            body.delete.unwrap_or(true),
+            body.webhook_url.clone(),
+            auth.principal_id(),
+            body.allowed_keys.clone(),
+            auth.writing_key(),
+        )
+    });
+    match written {
+        Ok(Ok(())) => {
+            state.metrics.secrets_created.inc();
             info!(key = %body.key, org_id = %org_id, "audit: secret.create");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_CREATE,
-                Some(body.key.clone()),
-                ip,
-                true,
-                None,
-                Some(org_id.clone()),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             if let Some(ref sender) = state.webhook_sender {
                 sender.fire("secret.created", &body.key, json!({}));
             }
             (StatusCode::CREATED, Json(json!({"key": body.key}))).into_response()
         }
+        // The per-key `max_secrets` quota is checked in the write itself.
+        Ok(Err(refused)) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": refused.to_string()})),
+        )
+            .into_response(),
         Err(e) => internal_error(e),
     }
 }
@@ -965,7 +1029,12 @@ pub async fn get_org_secret(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.get_org_secret(&org_id, &key) {
+    let _permit = handlers::decrypt_permit(&state, 1).await;
+    match state
+        .store
+        .get_org_secret(&org_id, &key, Some(&ip))
+        .inspect(|read| state.metrics.observe_read(read))
+    {
         Ok(GetResult::Value(value, webhook_url)) => {
             let _ = state.store.record_audit(AuditEvent::new(
                 ACTION_SECRET_READ,
@@ -1091,14 +1160,20 @@ pub async fn head_org_secret(
             let mut builder = Response::builder()
                 .status(status)
                 .header("X-Sirr-Read-Count", meta.read_count.to_string())
+                .header("X-Sirr-Distinct-Readers", meta.distinct_readers.to_string())
                 .header("X-Sirr-Reads-Remaining", reads_remaining)
                 .header("X-Sirr-Delete", meta.delete.to_string())
-                .header("X-Sirr-Created-At", meta.created_at.to_string());
+                .header("X-Sirr-Created-At", meta.created_at.to_string())
+                .header("X-Sirr-Modified-At", meta.updated_at.to_string());
 
             if let Some(exp) = meta.expires_at {
                 builder = builder.header("X-Sirr-Expires-At", exp.to_string());
             }
 
+            if let Some(ref fp) = meta.value_fingerprint {
+                builder = builder.header("X-Sirr-Value-Fingerprint", fp);
+            }
+
             if sealed {
                 builder = builder.header("X-Sirr-Status", "sealed");
             } else {
@@ -1125,7 +1200,7 @@ pub async fn patch_org_secret(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path((org_id, key)): Path<(String, String)>,
-    Json(body): Json<PatchOrgSecretRequest>,
+    JsonBody(body): JsonBody<PatchOrgSecretRequest>,
 ) -> Response {
     if auth.org_id() != Some(&org_id) {
         return forbidden();
@@ -1143,6 +1218,9 @@ pub async fn patch_org_secret(
         if v.len() > 1_048_576 {
             return bad_request("value exceeds 1 MiB limit");
         }
+        if let Some(message) = value_too_short(&state, v) {
+            return bad_request(&message);
+        }
     }
     if let Some(ttl) = body.ttl_seconds {
         if ttl > MAX_TTL_SECS {
@@ -1171,25 +1249,31 @@ pub async fn patch_org_secret(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    let new_expires_at = body.ttl_seconds.map(|ttl| now_epoch() + ttl as i64);
-
-    match state.store.patch_org_secret(
-        &org_id,
-        &key,
-        body.value.as_deref(),
-        body.max_reads,
-        new_expires_at,
-    ) {
+    let new_expires_at = body.ttl_seconds.map(|ttl| expiry_after(now_epoch(), ttl));
+
+    let event = AuditEvent::new(
+        ACTION_SECRET_PATCH,
+        Some(key.clone()),
+        ip.clone(),
+        true,
+        None,
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (patched, pending) = audited(&state, event, || {
+        state.store.patch_org_secret(
+            &org_id,
+            &key,
+            body.value.as_deref(),
+            body.max_reads,
+            new_expires_at,
+        )
+    });
+    match patched {
         Ok(Some(meta)) => {
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_PATCH,
-                Some(key.clone()),
-                ip,
-                true,
-                None,
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(meta).into_response()
         }
         Ok(None) => {
@@ -1257,18 +1341,25 @@ pub async fn delete_org_secret(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.delete_org_secret(&org_id, &key) {
+    let event = AuditEvent::new(
+        ACTION_SECRET_DELETE,
+        Some(key.clone()),
+        ip,
+        true,
+        None,
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (deleted, pending) = audited(&state, event, || {
+        state.store.delete_org_secret(&org_id, &key)
+    });
+    match deleted {
         Ok(true) => {
+            state.metrics.secrets_deleted.inc();
             info!(key = %key, org_id = %org_id, "audit: secret.delete");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_DELETE,
-                Some(key.clone()),
-                ip,
-                true,
-                None,
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             if let Some(ref sender) = state.webhook_sender {
                 sender.fire("secret.deleted", &key, json!({}));
             }
@@ -1295,19 +1386,29 @@ pub async fn prune_org_secrets(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.prune_org_secrets(&org_id) {
+    let (audit_org_id, principal_id) = (org_id.clone(), auth.principal_id().map(|s| s.to_owned()));
+    let event: StagedEvent = Box::new(move |n| {
+        AuditEvent::new(
+            ACTION_SECRET_PRUNE,
+            None,
+            ip,
+            true,
+            Some(format!("pruned={n}")),
+            Some(audit_org_id),
+            principal_id,
+        )
+    });
+    let (pruned, pending) = audited_with(&state, vec![event], || {
+        state.store.prune_org_secrets(&org_id)
+    });
+    match pruned {
         Ok(pruned_keys) => {
             let n = pruned_keys.len();
+            state.metrics.secrets_pruned.add(n as u64);
             info!(pruned = n, org_id = %org_id, "audit: secret.prune");
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_SECRET_PRUNE,
-                None,
-                ip,
-                true,
-                Some(format!("pruned={n}")),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, n) {
+                return rejected;
+            }
             if let Some(ref sender) = state.webhook_sender {
                 for key in &pruned_keys {
                     sender.fire("secret.expired", key, json!({"reason": "manual_prune"}));
@@ -1342,9 +1443,13 @@ pub async fn org_audit_events(
         return forbidden();
     }
 
+    let mut since = params.since;
+    if let Some(rejected) = crate::handlers::bound_audit_range(&state, &mut since, params.until) {
+        return rejected;
+    }
     let limit = params.limit.unwrap_or(100).min(1000);
     let query = AuditQuery {
-        since: params.since,
+        since,
         until: params.until,
         action: params.action,
         limit,
@@ -1376,6 +1481,11 @@ pub async fn org_audit_events(
 pub struct CreateOrgWebhookRequest {
     pub url: String,
     pub events: Option<Vec<String>>,
+    /// Payload template; see [`webhooks::render_template`].
+    pub template: Option<String>,
+    /// Per-event receivers overriding `url`.
+    #[serde(default)]
+    pub event_urls: BTreeMap<String, String>,
 }
 
 pub async fn create_org_webhook(
@@ -1384,7 +1494,7 @@ pub async fn create_org_webhook(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(org_id): Path<String>,
-    Json(body): Json<CreateOrgWebhookRequest>,
+    JsonBody(body): JsonBody<CreateOrgWebhookRequest>,
 ) -> Response {
     if auth.org_id() != Some(&org_id) && !auth.is_master() {
         return forbidden();
@@ -1398,6 +1508,9 @@ pub async fn create_org_webhook(
     if !body.url.starts_with("http://") && !body.url.starts_with("https://") {
         return bad_request("webhook URL must start with http:// or https://");
     }
+    if let Some(Err(reason)) = body.template.as_deref().map(webhooks::validate_template) {
+        return bad_request(&reason);
+    }
 
     // Check count limit per org.
     match state.store.count_webhooks_for_org(&org_id) {
@@ -1415,6 +1528,23 @@ pub async fn create_org_webhook(
     }
 
     let events = body.events.unwrap_or_else(|| vec!["*".to_string()]);
+    if let Err(reason) = webhooks::validate_event_urls(&body.event_urls, &events) {
+        return bad_request(&reason);
+    }
+    // Registering the same URL twice for overlapping events would deliver
+    // every matching event twice.
+    match state.store.list_webhooks() {
+        Ok(regs) => {
+            if let Some(existing) = webhooks::find_duplicate(&regs, &body.url, &events) {
+                return (
+                    StatusCode::CONFLICT,
+                    Json(json!({"error": "webhook URL already registered for these events", "id": existing.id})),
+                )
+                    .into_response();
+            }
+        }
+        Err(e) => return internal_error(e),
+    }
     let id = webhooks::generate_webhook_id();
     let secret = webhooks::generate_signing_secret();
 
@@ -1425,19 +1555,25 @@ pub async fn create_org_webhook(
         events,
         created_at: now_epoch(),
         org_id: Some(org_id.clone()),
+        template: body.template,
+        event_urls: body.event_urls,
     };
 
-    match state.store.put_webhook(&reg) {
+    let event = AuditEvent::new(
+        ACTION_WEBHOOK_CREATE,
+        None,
+        ip,
+        true,
+        Some(format!("id={id}")),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (written, pending) = audited(&state, event, || state.store.put_webhook(&reg));
+    match written {
         Ok(()) => {
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_WEBHOOK_CREATE,
-                None,
-                ip,
-                true,
-                Some(format!("id={id}")),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             (
                 StatusCode::CREATED,
                 Json(json!({"id": id, "secret": secret})),
@@ -1470,6 +1606,8 @@ pub async fn list_org_webhooks(
                         "url": r.url,
                         "events": r.events,
                         "created_at": r.created_at,
+                        "template": r.template,
+                        "event_urls": r.event_urls,
                     })
                 })
                 .collect();
@@ -1495,17 +1633,21 @@ pub async fn delete_org_webhook(
 
     let ip = extract_ip(&headers, &addr, &state.trusted_proxies);
 
-    match state.store.delete_webhook(&id) {
+    let event = AuditEvent::new(
+        ACTION_WEBHOOK_DELETE,
+        None,
+        ip,
+        true,
+        Some(format!("id={id}")),
+        Some(org_id.clone()),
+        auth.principal_id().map(|s| s.to_owned()),
+    );
+    let (deleted, pending) = audited(&state, event, || state.store.delete_webhook(&id));
+    match deleted {
         Ok(true) => {
-            let _ = state.store.record_audit(AuditEvent::new(
-                ACTION_WEBHOOK_DELETE,
-                None,
-                ip,
-                true,
-                Some(format!("id={id}")),
-                Some(org_id),
-                auth.principal_id().map(|s| s.to_owned()),
-            ));
+            if let Some(rejected) = audit_mutation(&state, pending, 1) {
+                return rejected;
+            }
             Json(json!({"deleted": true})).into_response()
         }
         Ok(false) => not_found("webhook not found"),
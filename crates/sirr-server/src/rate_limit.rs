@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use sha2::{Digest, Sha256};
+
+use crate::auth::ApiKeys;
+
+/// Length of one create-rate window.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Above this many tracked callers, expired windows are swept on insert.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Fixed-window limiter for `POST /secrets`, keyed per API key (or per client
+/// IP for unauthenticated callers). Independent of the global per-IP governor.
+#[derive(Clone)]
+pub struct CreateLimiter {
+    per_minute: u32,
+    windows: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+}
+
+impl CreateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Count one create for `caller`. Returns `Err(retry_after)` when the
+    /// caller has used up the current window.
+    pub fn check(&self, caller: &str) -> Result<(), Duration> {
+        self.check_n(caller, 1)
+    }
+
+    /// Count `n` creates for `caller` at once, or none of them when they
+    /// would not all fit in the current window.
+    pub fn check_n(&self, caller: &str, n: u32) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+
+        if windows.len() >= SWEEP_THRESHOLD {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+        }
+
+        let entry = windows.entry(caller.to_owned()).or_insert((now, 0));
+        if now.duration_since(entry.0) >= WINDOW {
+            *entry = (now, 0);
+        }
+        if entry.1.saturating_add(n) > self.per_minute {
+            return Err(WINDOW.saturating_sub(now.duration_since(entry.0)));
+        }
+        entry.1 += n;
+        Ok(())
+    }
+}
+
+/// Identify the caller for create limiting and idempotency scoping: a hash
+/// of the bearer token when it is one of the configured master keys,
+/// otherwise the client IP. Unverified tokens never pick the bucket, so a
+/// fresh random token per request cannot dodge the limit.
+pub fn caller_id(api_keys: &ApiKeys, headers: &HeaderMap, ip: &str) -> String {
+    let token = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|t| api_keys.find(t).is_some());
+    match token {
+        Some(t) => format!("key:{}", hex::encode(&Sha256::digest(t.as_bytes())[..8])),
+        None => format!("ip:{ip}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_each_caller_independently() {
+        let limiter = CreateLimiter::new(2);
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_ok());
+        let retry = limiter.check("a").unwrap_err();
+        assert!(retry <= WINDOW);
+        assert!(limiter.check("b").is_ok());
+    }
+
+    #[test]
+    fn only_verified_tokens_get_their_own_bucket() {
+        let keys = ApiKeys::unlabeled("master-key");
+        let with_token = |token: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("authorization", format!("Bearer {token}").parse().unwrap());
+            caller_id(&keys, &headers, "10.0.0.1")
+        };
+        assert_eq!(with_token("random-1"), "ip:10.0.0.1");
+        assert_eq!(with_token("random-2"), "ip:10.0.0.1");
+        assert!(with_token("master-key").starts_with("key:"));
+        assert_eq!(
+            caller_id(&keys, &HeaderMap::new(), "10.0.0.1"),
+            "ip:10.0.0.1"
+        );
+    }
+
+    #[test]
+    fn batch_counts_all_or_nothing() {
+        let limiter = CreateLimiter::new(3);
+        assert!(limiter.check_n("a", 2).is_ok());
+        assert!(limiter.check_n("a", 2).is_err());
+        assert!(limiter.check("a").is_ok());
+        assert!(limiter.check("a").is_err());
+    }
+}
@@ -5,22 +5,30 @@ use std::time::Duration;
 use anyhow::{Context, Result};
 use axum::{
     extract::Request,
+    http::StatusCode,
     middleware,
     middleware::Next,
     response::{IntoResponse, Response},
     routing::{delete, get, head, patch, post},
-    Router,
+    Json, Router,
 };
+use serde_json::json;
 use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
+use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::normalize_path::NormalizePath;
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
 use crate::{
     auth::{require_auth, require_master_key},
     handlers::{
-        audit_events, create_secret, create_webhook, delete_secret, delete_webhook, get_secret,
-        head_secret, health, list_secrets, list_webhooks, patch_secret, prune_secrets,
+        audit_events, batch_create_secrets, batch_get_secrets, capacity, create_alias,
+        create_secret, create_webhook, delete_alias, delete_matching_secrets, delete_secret,
+        delete_webhook, describe_secret, export_webhooks, get_secret, get_secret_field,
+        head_secret, health, import_webhooks, list_secrets, list_webhooks, patch_secret,
+        peek_secret, preview_webhook, prune_secrets, ready, self_destruct, set_maintenance, status,
+        touch_secret, version,
     },
     license,
     org_handlers::{
@@ -37,6 +45,9 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub api_key: Option<String>,
+    /// Additional labeled master keys as comma-separated `label:key` pairs
+    /// ($SIRR_API_KEYS). Audit events name the label that authenticated.
+    pub api_keys: Option<String>,
     pub license_key: Option<String>,
     pub data_dir: Option<PathBuf>,
     pub sweep_interval: Duration,
@@ -48,6 +59,9 @@ pub struct ServerConfig {
     pub audit_retention_days: u64,
     pub validation_url: String,
     pub validation_cache_secs: u64,
+    /// Seconds a license validation call may take before SirrLock is treated
+    /// as unavailable and the grace period applies ($SIRR_VALIDATION_TIMEOUT_SECS).
+    pub validation_timeout_secs: u64,
     /// Set `SIRR_HEARTBEAT=false` to disable instance heartbeat reporting.
     pub heartbeat: bool,
     /// Signing key for per-secret webhook URLs ($SIRR_WEBHOOK_SECRET).
@@ -87,6 +101,144 @@ pub struct ServerConfig {
     /// When true, auto-initialize with a default org and admin principal
     /// if no orgs exist yet. Triggered by `--init` or `SIRR_AUTOINIT=true`.
     pub auto_init: bool,
+    /// Path of the liveness probe ($SIRR_HEALTH_PATH, default `/health`).
+    pub health_path: String,
+    /// Path of the readiness probe ($SIRR_READY_PATH, default `/health/ready`).
+    pub ready_path: String,
+    /// Set `SIRR_WEBHOOK_REDELIVER_ON_START=1` to retry stored webhook dead
+    /// letters once at startup.
+    pub webhook_redeliver_on_start: bool,
+    /// Secret creations allowed per API key (or client IP when unauthenticated)
+    /// per minute on `POST /secrets`. 0 disables. $SIRR_CREATE_RATE_LIMIT.
+    pub create_rate_limit: u32,
+    /// Buffer audit events and write up to this many per transaction.
+    /// 0 writes every event immediately. $SIRR_AUDIT_BATCH.
+    pub audit_batch: usize,
+    /// How often buffered audit events are flushed. $SIRR_AUDIT_BATCH_INTERVAL_MS.
+    pub audit_batch_interval: Duration,
+    /// Seconds browsers may cache CORS preflight results ($SIRR_CORS_MAX_AGE).
+    pub cors_max_age: u64,
+    /// Hex-encoded 32-byte encryption key supplied out-of-band ($SIRR_ENCRYPTION_KEY).
+    /// When set, `sirr.key` is neither read nor written, so the data dir may be read-only.
+    pub encryption_key: Option<String>,
+    /// Comma-separated audit actions to record, e.g. `create,delete,patch`
+    /// ($SIRR_AUDIT_ACTIONS). Unset records everything.
+    pub audit_actions: Option<String>,
+    /// Listen backlog for the TCP socket ($SIRR_LISTEN_BACKLOG).
+    pub listen_backlog: i32,
+    /// Idle seconds before TCP keepalive probes start; 0 disables ($SIRR_TCP_KEEPALIVE_SECS).
+    pub tcp_keepalive_secs: u64,
+    /// Namespace prepended to webhook event names ($SIRR_WEBHOOK_EVENT_PREFIX).
+    pub webhook_event_prefix: String,
+    /// Lowest TLS version negotiated when terminating TLS natively: `1.2`
+    /// or `1.3` ($SIRR_TLS_MIN_VERSION). TLS 1.0/1.1 are always rejected.
+    pub tls_min_version: String,
+    /// PEM certificate chain for serving HTTPS on the main listener
+    /// ($SIRR_TLS_CERT). Must be set together with `tls_key_path`.
+    pub tls_cert_path: Option<PathBuf>,
+    /// PEM private key matching `tls_cert_path` ($SIRR_TLS_KEY).
+    pub tls_key_path: Option<PathBuf>,
+    /// Consecutive failed deliveries before a webhook registration's circuit
+    /// opens; 0 disables the breaker ($SIRR_WEBHOOK_BREAKER_THRESHOLD).
+    pub webhook_breaker_threshold: u32,
+    /// Seconds an open circuit skips deliveries before probing again
+    /// ($SIRR_WEBHOOK_BREAKER_COOLDOWN_SECS).
+    pub webhook_breaker_cooldown_secs: u64,
+    /// Extra comma-separated header names redacted from trace logs, on top
+    /// of Authorization, Cookie and X-Sirr-Signature ($SIRR_REDACT_HEADERS).
+    pub redact_headers: Option<String>,
+    /// Externally reachable base URL, e.g. `https://sirr.example.com`
+    /// ($SIRR_PUBLIC_BASE_URL). When set, `POST /secrets` responses include
+    /// the secret's retrieval `url`.
+    pub public_base_url: Option<String>,
+    /// Values decrypted at once across all reads; 0 = one per CPU
+    /// ($SIRR_MAX_CONCURRENT_DECRYPTS).
+    pub max_concurrent_decrypts: usize,
+    /// Serve the admin routes (`/status`, `/metrics`) on this port instead of
+    /// the main one ($SIRR_ADMIN_PORT). Unset keeps them on the main listener.
+    pub admin_port: Option<u16>,
+    /// Interface for the admin listener ($SIRR_ADMIN_HOST, default loopback).
+    pub admin_host: String,
+    /// Maximum secrets stored in the public bucket, including expired ones
+    /// awaiting the sweep; 0 = unlimited ($SIRR_MAX_SECRETS). Creates beyond
+    /// it return 507.
+    pub max_secrets: usize,
+    /// Prune expired/burned secrets and retry when a create hits
+    /// `max_secrets` instead of rejecting it outright ($SIRR_AUTO_PRUNE_ON_FULL).
+    pub auto_prune_on_full: bool,
+    /// Shortest TTL accepted on `POST /secrets`; 0 = no minimum ($SIRR_MIN_TTL_SECONDS).
+    pub min_ttl_seconds: u64,
+    /// Reject `POST /secrets` without `ttl_seconds` ($SIRR_REQUIRE_TTL).
+    pub require_ttl: bool,
+    /// Status returned when a license tier limit is hit: 402 (default),
+    /// 403 or 429 ($SIRR_LIMIT_STATUS).
+    pub limit_status: u16,
+    /// Seconds between background compaction checks; the file is compacted
+    /// when at least a quarter of it is free. None = disabled
+    /// ($SIRR_COMPACT_INTERVAL).
+    pub compact_interval: Option<Duration>,
+    /// Comma-separated key prefixes that `POST /secrets` and
+    /// `PATCH /secrets/{key}` refuse with 403 ($SIRR_RESERVED_KEY_PREFIXES).
+    pub reserved_key_prefixes: String,
+    /// Client IP kept in audit events: `full` (default), `truncate` or
+    /// `none` ($SIRR_AUDIT_IP_MODE).
+    pub audit_ip_mode: String,
+    /// Length of server-generated keys for `POST /secrets` without a `key`,
+    /// clamped to 16–256 ($SIRR_GENERATED_KEY_LENGTH).
+    pub generated_key_length: usize,
+    /// Audit CORS preflights from disallowed origins as `cors.rejected`
+    /// ($SIRR_AUDIT_CORS_REJECTIONS).
+    pub audit_cors_rejections: bool,
+    /// What mutations do when their audit write fails: `ignore` (default),
+    /// `warn` or `fail` ($SIRR_AUDIT_FAILURE_MODE).
+    pub audit_failure_mode: String,
+    /// Deliver webhook events for the same secret key to each receiver one
+    /// at a time, in firing order ($SIRR_WEBHOOK_ORDERED).
+    pub webhook_ordered: bool,
+    /// Widest time range, in days, one audit query may span; open-ended
+    /// queries are clamped to it and wider ones refused. 0 = unlimited
+    /// ($SIRR_AUDIT_MAX_RANGE_DAYS).
+    pub audit_max_range_days: u64,
+    /// What `POST /secrets` does with a key that already exists: `upsert`
+    /// (default) overwrites, `reject` answers 409 ($SIRR_CREATE_MODE).
+    pub create_mode: String,
+    /// How long a create's response is replayed for a repeated
+    /// `Idempotency-Key` ($SIRR_IDEMPOTENCY_TTL_SECONDS, default 24h).
+    pub idempotency_ttl_seconds: u64,
+    /// Shortest `SIRR_API_KEY` accepted without complaint
+    /// ($SIRR_API_KEY_MIN_LENGTH, default 32; 0 disables the check).
+    pub api_key_min_length: usize,
+    /// Refuse to start with a weak `SIRR_API_KEY` instead of warning
+    /// ($SIRR_API_KEY_STRICT).
+    pub api_key_strict: bool,
+    /// Commit durability for store writes: `immediate` (default),
+    /// `eventual` or `none` ($SIRR_DURABILITY).
+    pub durability: String,
+    /// Origins allowed on the unauthenticated informational routes (health,
+    /// version, security.txt) ($SIRR_CORS_PUBLIC_ORIGINS). Falls back to
+    /// `cors_origins`, which always governs the authenticated routes.
+    pub cors_public_origins: Option<String>,
+    /// Confirmation token for `POST /admin/self-destruct`; the endpoint is
+    /// disabled unless set ($SIRR_SELF_DESTRUCT_TOKEN).
+    pub self_destruct_token: Option<String>,
+    /// Smallest value, in bytes, accepted by creates and patches
+    /// ($SIRR_MIN_VALUE_BYTES). 0 allows empty values.
+    pub min_value_bytes: usize,
+    /// Persist webhook retry schedules in the store so a restart resumes
+    /// them ($SIRR_WEBHOOK_PERSIST_RETRIES).
+    pub webhook_persist_retries: bool,
+    /// Days between automatic online key rotations; 0 disables them
+    /// ($SIRR_ROTATE_INTERVAL_DAYS). Needs the key in `sirr.key`.
+    pub rotate_interval_days: u64,
+    /// UTC hour (0-23) a due rotation waits for, to keep it in a quiet
+    /// window ($SIRR_ROTATE_HOUR). Unset rotates as soon as it is due.
+    pub rotate_hour: Option<u32>,
+    /// How long in-flight requests get to finish after SIGTERM or SIGINT
+    /// before the server exits anyway ($SIRR_SHUTDOWN_TIMEOUT, seconds).
+    pub shutdown_timeout: Duration,
+    /// Serve `GET /metrics` without the master key, for scrapers that
+    /// cannot send one ($SIRR_METRICS_PUBLIC).
+    pub metrics_public: bool,
 }
 
 impl Default for ServerConfig {
@@ -98,6 +250,7 @@ impl Default for ServerConfig {
                 .and_then(|p| p.parse().ok())
                 .unwrap_or(39999),
             api_key: std::env::var("SIRR_API_KEY").ok(),
+            api_keys: std::env::var("SIRR_API_KEYS").ok(),
             license_key: std::env::var("SIRR_LICENSE_KEY").ok(),
             data_dir: std::env::var("SIRR_DATA_DIR").ok().map(PathBuf::from),
             sweep_interval: Duration::from_secs(300),
@@ -113,6 +266,10 @@ impl Default for ServerConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(3600),
+            validation_timeout_secs: std::env::var("SIRR_VALIDATION_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
             heartbeat: std::env::var("SIRR_HEARTBEAT")
                 .map(|v| v != "false" && v != "0")
                 .unwrap_or(true),
@@ -146,6 +303,142 @@ impl Default for ServerConfig {
             auto_init: std::env::var("SIRR_AUTOINIT")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(false),
+            health_path: std::env::var("SIRR_HEALTH_PATH").unwrap_or_else(|_| "/health".into()),
+            ready_path: std::env::var("SIRR_READY_PATH").unwrap_or_else(|_| "/health/ready".into()),
+            webhook_redeliver_on_start: std::env::var("SIRR_WEBHOOK_REDELIVER_ON_START")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            create_rate_limit: std::env::var("SIRR_CREATE_RATE_LIMIT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            audit_batch: std::env::var("SIRR_AUDIT_BATCH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            audit_batch_interval: Duration::from_millis(
+                std::env::var("SIRR_AUDIT_BATCH_INTERVAL_MS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(1000),
+            ),
+            cors_max_age: std::env::var("SIRR_CORS_MAX_AGE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            encryption_key: std::env::var("SIRR_ENCRYPTION_KEY").ok(),
+            audit_actions: std::env::var("SIRR_AUDIT_ACTIONS").ok(),
+            listen_backlog: std::env::var("SIRR_LISTEN_BACKLOG")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1024),
+            tcp_keepalive_secs: std::env::var("SIRR_TCP_KEEPALIVE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            webhook_event_prefix: std::env::var("SIRR_WEBHOOK_EVENT_PREFIX").unwrap_or_default(),
+            tls_min_version: std::env::var("SIRR_TLS_MIN_VERSION").unwrap_or_else(|_| "1.2".into()),
+            tls_cert_path: std::env::var("SIRR_TLS_CERT").ok().map(PathBuf::from),
+            tls_key_path: std::env::var("SIRR_TLS_KEY").ok().map(PathBuf::from),
+            webhook_breaker_threshold: std::env::var("SIRR_WEBHOOK_BREAKER_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(crate::webhooks::DEFAULT_BREAKER_THRESHOLD),
+            webhook_breaker_cooldown_secs: std::env::var("SIRR_WEBHOOK_BREAKER_COOLDOWN_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(crate::webhooks::DEFAULT_BREAKER_COOLDOWN.as_secs()),
+            redact_headers: std::env::var("SIRR_REDACT_HEADERS").ok(),
+            public_base_url: std::env::var("SIRR_PUBLIC_BASE_URL").ok(),
+            max_concurrent_decrypts: std::env::var("SIRR_MAX_CONCURRENT_DECRYPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            admin_port: std::env::var("SIRR_ADMIN_PORT")
+                .ok()
+                .and_then(|p| p.parse().ok()),
+            admin_host: std::env::var("SIRR_ADMIN_HOST").unwrap_or_else(|_| "127.0.0.1".into()),
+            max_secrets: std::env::var("SIRR_MAX_SECRETS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            auto_prune_on_full: std::env::var("SIRR_AUTO_PRUNE_ON_FULL")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            min_ttl_seconds: std::env::var("SIRR_MIN_TTL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            require_ttl: std::env::var("SIRR_REQUIRE_TTL")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            limit_status: std::env::var("SIRR_LIMIT_STATUS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(402),
+            compact_interval: std::env::var("SIRR_COMPACT_INTERVAL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            reserved_key_prefixes: std::env::var("SIRR_RESERVED_KEY_PREFIXES").unwrap_or_default(),
+            audit_ip_mode: std::env::var("SIRR_AUDIT_IP_MODE").unwrap_or_else(|_| "full".into()),
+            generated_key_length: std::env::var("SIRR_GENERATED_KEY_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24),
+            audit_cors_rejections: std::env::var("SIRR_AUDIT_CORS_REJECTIONS")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            audit_failure_mode: std::env::var("SIRR_AUDIT_FAILURE_MODE")
+                .unwrap_or_else(|_| "ignore".into()),
+            webhook_ordered: std::env::var("SIRR_WEBHOOK_ORDERED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            audit_max_range_days: std::env::var("SIRR_AUDIT_MAX_RANGE_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            create_mode: std::env::var("SIRR_CREATE_MODE").unwrap_or_else(|_| "upsert".into()),
+            idempotency_ttl_seconds: std::env::var("SIRR_IDEMPOTENCY_TTL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24 * 60 * 60),
+            api_key_min_length: std::env::var("SIRR_API_KEY_MIN_LENGTH")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(32),
+            api_key_strict: std::env::var("SIRR_API_KEY_STRICT")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            durability: std::env::var("SIRR_DURABILITY").unwrap_or_else(|_| "immediate".into()),
+            cors_public_origins: std::env::var("SIRR_CORS_PUBLIC_ORIGINS").ok(),
+            self_destruct_token: std::env::var("SIRR_SELF_DESTRUCT_TOKEN")
+                .ok()
+                .filter(|t| !t.is_empty()),
+            min_value_bytes: std::env::var("SIRR_MIN_VALUE_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            webhook_persist_retries: std::env::var("SIRR_WEBHOOK_PERSIST_RETRIES")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            rotate_interval_days: std::env::var("SIRR_ROTATE_INTERVAL_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            rotate_hour: std::env::var("SIRR_ROTATE_HOUR")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            shutdown_timeout: Duration::from_secs(
+                std::env::var("SIRR_SHUTDOWN_TIMEOUT")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(10),
+            ),
+            metrics_public: std::env::var("SIRR_METRICS_PUBLIC")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
         }
     }
 }
@@ -198,21 +491,143 @@ pub fn resolve_data_dir(data_dir: Option<&PathBuf>) -> Result<PathBuf> {
     }
 }
 
+/// Run the server until SIGTERM or SIGINT, then drain in-flight requests.
 pub async fn run(cfg: ServerConfig) -> Result<()> {
+    run_until(cfg, termination_signal()).await
+}
+
+/// [`run`], stopping when `shutdown` resolves instead of on a signal.
+/// In-flight requests get `cfg.shutdown_timeout` to finish.
+pub async fn run_until(
+    cfg: ServerConfig,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    for (var, path) in [
+        ("SIRR_HEALTH_PATH", &cfg.health_path),
+        ("SIRR_READY_PATH", &cfg.ready_path),
+    ] {
+        if !path.starts_with('/') {
+            anyhow::bail!("{var} must start with '/': {path:?}");
+        }
+    }
+    if cfg.health_path == cfg.ready_path {
+        anyhow::bail!("SIRR_HEALTH_PATH and SIRR_READY_PATH must differ");
+    }
+    // Reject a bad or legacy (1.0/1.1) TLS policy at startup.
+    let tls_min_version = cfg
+        .tls_min_version
+        .parse::<crate::tls::TlsMinVersion>()
+        .map_err(|e| anyhow::anyhow!("SIRR_TLS_MIN_VERSION: {e}"))?;
+    let tls = match (&cfg.tls_cert_path, &cfg.tls_key_path) {
+        (None, None) => None,
+        (Some(cert), Some(key)) => Some(std::sync::Arc::new(crate::tls::load_server_tls(
+            cert,
+            key,
+            tls_min_version,
+        )?)),
+        (Some(_), None) => anyhow::bail!("SIRR_TLS_CERT is set but SIRR_TLS_KEY is not"),
+        (None, Some(_)) => anyhow::bail!("SIRR_TLS_KEY is set but SIRR_TLS_CERT is not"),
+    };
+    if cfg.rotate_hour.is_some_and(|hour| hour > 23) {
+        anyhow::bail!("SIRR_ROTATE_HOUR must be 0-23");
+    }
+    if cfg.rotate_interval_days > 0 && cfg.encryption_key.is_some() {
+        anyhow::bail!(
+            "SIRR_ROTATE_INTERVAL_DAYS needs the key in sirr.key; \
+             a key supplied via SIRR_ENCRYPTION_KEY cannot be replaced"
+        );
+    }
+    let public_base_url = match cfg.public_base_url.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+            Some(url.trim_end_matches('/').to_owned())
+        }
+        Some(url) => {
+            anyhow::bail!("SIRR_PUBLIC_BASE_URL must start with http:// or https://: {url:?}")
+        }
+    };
+    let limit_status = match cfg.limit_status {
+        402 => StatusCode::PAYMENT_REQUIRED,
+        403 => StatusCode::FORBIDDEN,
+        429 => StatusCode::TOO_MANY_REQUESTS,
+        other => anyhow::bail!("SIRR_LIMIT_STATUS must be 402, 403 or 429, got {other}"),
+    };
+    let api_keys = crate::auth::ApiKeys::parse(cfg.api_key.clone(), cfg.api_keys.as_deref())
+        .map_err(|e| anyhow::anyhow!("SIRR_API_KEYS: {e}"))?;
+    for (key, label) in api_keys.iter() {
+        let name = match label {
+            None => "SIRR_API_KEY".to_owned(),
+            Some(label) => format!("SIRR_API_KEYS key {label:?}"),
+        };
+        check_named_key(&name, key, cfg.api_key_min_length, cfg.api_key_strict)?;
+    }
+
     // Resolve data directory.
     let data_dir = resolve_data_dir(cfg.data_dir.as_ref())?;
 
     info!(data_dir = %data_dir.display(), "using data directory");
 
     // Load or generate the encryption key.
-    // Read raw key bytes for instance ID generation (before they're wrapped).
-    let key_path = data_dir.join("sirr.key");
-    let enc_key = load_or_create_key(&data_dir)?;
-    let key_bytes_for_id = std::fs::read(&key_path).ok();
+    // Keep raw key bytes for instance ID generation (before they're moved into the store).
+    let enc_key = load_or_create_key(&data_dir, cfg.encryption_key.as_deref())?;
+    let key_bytes_for_id = Some(enc_key.as_bytes().to_vec());
 
     // Open redb store.
     let db_path = data_dir.join("sirr.db");
-    let store = crate::store::Store::open(&db_path, enc_key).context("open store")?;
+    let mut store = crate::store::Store::open(&db_path, enc_key).context("open store")?;
+    // Background jobs stopped on shutdown.
+    let mut background = Vec::new();
+    if let Some(ref actions) = cfg.audit_actions {
+        let actions: Vec<String> = actions
+            .split(',')
+            .map(|a| a.trim().to_owned())
+            .filter(|a| !a.is_empty())
+            .collect();
+        info!(?actions, "audit restricted to listed actions");
+        store = store.with_audit_actions(actions);
+    }
+    let audit_ip_mode = cfg
+        .audit_ip_mode
+        .parse()
+        .map_err(|e| anyhow::anyhow!("SIRR_AUDIT_IP_MODE: {e}"))?;
+    store = store.with_audit_ip_mode(audit_ip_mode);
+    let durability: crate::store::StoreDurability = cfg
+        .durability
+        .parse()
+        .map_err(|e| anyhow::anyhow!("SIRR_DURABILITY: {e}"))?;
+    if durability != crate::store::StoreDurability::Immediate {
+        warn!(
+            ?durability,
+            "store writes are not durable on commit; a crash can lose recent secrets"
+        );
+    }
+    store = store.with_durability(durability);
+    let audit_failure_mode = cfg
+        .audit_failure_mode
+        .parse()
+        .map_err(|e| anyhow::anyhow!("SIRR_AUDIT_FAILURE_MODE: {e}"))?;
+    if audit_failure_mode == crate::store::AuditFailureMode::Fail && cfg.audit_batch > 0 {
+        anyhow::bail!(
+            "SIRR_AUDIT_FAILURE_MODE=fail writes each audit event with its mutation; \
+             it cannot be combined with SIRR_AUDIT_BATCH"
+        );
+    }
+    let create_mode = cfg
+        .create_mode
+        .parse()
+        .map_err(|e| anyhow::anyhow!("SIRR_CREATE_MODE: {e}"))?;
+    if cfg.max_concurrent_decrypts > 0 {
+        store = store.with_max_concurrent_decrypts(cfg.max_concurrent_decrypts);
+    }
+    if cfg.audit_batch > 0 {
+        store = store.with_audit_batch(cfg.audit_batch);
+        background.push(store.clone().spawn_audit_flush(cfg.audit_batch_interval));
+        info!(
+            batch = cfg.audit_batch,
+            interval_ms = cfg.audit_batch_interval.as_millis() as u64,
+            "audit batching enabled"
+        );
+    }
 
     // Auto-init bootstrap: create default org + admin principal + keys if no orgs exist.
     if cfg.auto_init {
@@ -234,23 +649,79 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
         .map(str::to_string)
         .collect();
     let webhook_allowed_origins = std::sync::Arc::new(webhook_allowed_origins);
+    let reserved_key_prefixes: Vec<String> = cfg
+        .reserved_key_prefixes
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
 
     // Initialize webhook sender.
+    let metrics = std::sync::Arc::new(crate::metrics::Metrics::default());
     let webhook_sender = crate::webhooks::WebhookSender::new(
         store.clone(),
         webhook_instance_id,
         cfg.webhook_secret.clone(),
         webhook_allowed_origins.clone(),
-    );
+    )
+    .with_event_prefix(&cfg.webhook_event_prefix)
+    .with_circuit_breaker(
+        cfg.webhook_breaker_threshold,
+        Duration::from_secs(cfg.webhook_breaker_cooldown_secs),
+    )
+    .with_ordered_delivery(cfg.webhook_ordered)
+    .with_persisted_retries(cfg.webhook_persist_retries)
+    .with_metrics(metrics.clone());
+    if cfg.rotate_interval_days > 0 {
+        let schedule = crate::key_rotation::RotationSchedule {
+            interval: Duration::from_secs(cfg.rotate_interval_days * 86400),
+            hour: cfg.rotate_hour,
+        };
+        info!(days = cfg.rotate_interval_days, hour = ?schedule.hour, "scheduled key rotation enabled");
+        background.push(crate::key_rotation::spawn_key_rotation(
+            store.clone(),
+            data_dir.join("sirr.key"),
+            schedule,
+            Some(webhook_sender.clone()),
+        ));
+    }
+    if cfg.webhook_persist_retries {
+        background.push(
+            webhook_sender
+                .clone()
+                .spawn_retry_worker(Duration::from_secs(1)),
+        );
+    }
+
+    // Retry dead letters left over from a previous run, one every 200ms.
+    if cfg.webhook_redeliver_on_start {
+        let sender = webhook_sender.clone();
+        background.push(tokio::spawn(async move {
+            sender
+                .redeliver_dead_letters(Duration::from_millis(200))
+                .await;
+        }));
+    }
 
     // Spawn background sweeps (with webhook sender for expired events).
-    store
-        .clone()
-        .spawn_sweep(cfg.sweep_interval, Some(webhook_sender.clone()));
+    let sweep_lock = std::sync::Arc::new(tokio::sync::Mutex::new(()));
+    background.push(store.clone().spawn_sweep(
+        cfg.sweep_interval,
+        Some(webhook_sender.clone()),
+        sweep_lock.clone(),
+        metrics.clone(),
+    ));
+    if let Some(interval) = cfg.compact_interval {
+        background.push(store.clone().spawn_compaction(interval, sweep_lock.clone()));
+    }
     let retention_secs = (cfg.audit_retention_days * 86400) as i64;
-    store
-        .clone()
-        .spawn_audit_sweep(cfg.sweep_interval, retention_secs);
+    background.push(
+        store
+            .clone()
+            .spawn_audit_sweep(cfg.sweep_interval, retention_secs),
+    );
+    let shutdown_store = store.clone();
 
     // Validate license key.
     let lic_status = license::effective_status(cfg.license_key.as_deref());
@@ -284,7 +755,8 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
                 cfg.validation_url,
                 cfg.validation_cache_secs,
                 259200, // 72-hour grace period
-            );
+            )
+            .with_timeout(Duration::from_secs(cfg.validation_timeout_secs.max(1)));
             let valid = v.validate_startup(&store).await;
             if !valid {
                 warn!("license rejected online — server will enforce free-tier limits above 100 secrets");
@@ -303,12 +775,14 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
         {
             let instance_id = crate::heartbeat::instance_id_from_key(raw_bytes);
             info!(instance_id = %instance_id, "starting instance heartbeat");
-            crate::heartbeat::spawn_heartbeat(crate::heartbeat::HeartbeatConfig {
-                endpoint: heartbeat_url,
-                license_key: license_key.clone(),
-                instance_id,
-                store: store.clone(),
-            });
+            background.push(crate::heartbeat::spawn_heartbeat(
+                crate::heartbeat::HeartbeatConfig {
+                    endpoint: heartbeat_url,
+                    license_key: license_key.clone(),
+                    instance_id,
+                    store: store.clone(),
+                },
+            ));
         }
     }
 
@@ -334,8 +808,9 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
     let enable_public_bucket = cfg.enable_public_bucket;
 
     let state = AppState {
-        store,
-        api_key: cfg.api_key,
+        store: store.clone(),
+        secrets: std::sync::Arc::new(store),
+        api_keys,
         license: lic_status,
         validator,
         webhook_sender: Some(webhook_sender),
@@ -343,6 +818,46 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
         redact_audit_keys: cfg.redact_audit_keys,
         webhook_allowed_origins,
         enable_public_bucket,
+        started_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64,
+        started: std::time::Instant::now(),
+        create_limiter: (cfg.create_rate_limit > 0)
+            .then(|| crate::rate_limit::CreateLimiter::new(cfg.create_rate_limit)),
+        sweep_lock: sweep_lock.clone(),
+        public_base_url,
+        max_secrets: (cfg.max_secrets > 0).then_some(cfg.max_secrets),
+        auto_prune_on_full: cfg.auto_prune_on_full,
+        min_ttl_seconds: cfg.min_ttl_seconds,
+        require_ttl: cfg.require_ttl,
+        limit_status,
+        reserved_key_prefixes: std::sync::Arc::new(reserved_key_prefixes),
+        generated_key_length: cfg.generated_key_length.clamp(16, 256),
+        key_generator: Default::default(),
+        audit_cors_rejections: cfg.audit_cors_rejections,
+        audit_failure_mode,
+        audit_max_range_secs: (cfg.audit_max_range_days > 0).then(|| {
+            i64::try_from(cfg.audit_max_range_days.saturating_mul(86_400)).unwrap_or(i64::MAX)
+        }),
+        create_mode,
+        idempotency_ttl_secs: cfg.idempotency_ttl_seconds.max(1),
+        maintenance: Default::default(),
+        self_destruct: cfg.self_destruct_token.clone().map(|token| {
+            warn!("POST /admin/self-destruct is enabled");
+            std::sync::Arc::new(crate::handlers::SelfDestruct {
+                token,
+                key_file: cfg
+                    .encryption_key
+                    .is_none()
+                    .then(|| data_dir.join("sirr.key")),
+                shutdown: Default::default(),
+            })
+        }),
+        min_value_bytes: cfg.min_value_bytes,
+        listeners: Default::default(),
+        metrics: metrics.clone(),
+        metrics_public: cfg.metrics_public,
     };
 
     // Per-IP rate limiting: configurable via SIRR_RATE_LIMIT_PER_SECOND / SIRR_RATE_LIMIT_BURST.
@@ -362,15 +877,237 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
         }
     });
 
-    let cors = build_cors(cfg.cors_origins.as_deref(), cfg.cors_methods.as_deref());
+    let cors = build_cors(
+        cfg.cors_origins.as_deref(),
+        cfg.cors_methods.as_deref(),
+        Duration::from_secs(cfg.cors_max_age),
+    );
+    let public_cors = build_cors(
+        cfg.cors_public_origins
+            .as_deref()
+            .or(cfg.cors_origins.as_deref()),
+        cfg.cors_methods.as_deref(),
+        Duration::from_secs(cfg.cors_max_age),
+    );
+
+    // Admin routes move to their own listener when SIRR_ADMIN_PORT is set.
+    let admin_addr: Option<SocketAddr> = cfg
+        .admin_port
+        .map(|port| format!("{}:{}", cfg.admin_host, port).parse())
+        .transpose()
+        .context("invalid SIRR_ADMIN_HOST/SIRR_ADMIN_PORT")?;
+    let governor_conf = std::sync::Arc::new(governor_conf);
+    let redacted = redacted_headers(cfg.redact_headers.as_deref());
+
+    let app = app_router(
+        &state,
+        &cfg.health_path,
+        &cfg.ready_path,
+        public_cors,
+        cors.clone(),
+        admin_addr.is_none(),
+    )
+    .with_state(state.clone())
+    .layer(CatchPanicLayer::custom(panic_response))
+    .layer(middleware::from_fn_with_state(
+        state.clone(),
+        audit_cors_rejections,
+    ))
+    .layer(GovernorLayer::new(governor_conf.clone()))
+    .layer(middleware::from_fn(add_retryable_flag))
+    .layer(middleware::from_fn(add_security_headers));
+    let app = normalize_paths(with_http_tracing(app, redacted.clone()));
+
+    let keepalive =
+        (cfg.tcp_keepalive_secs > 0).then(|| Duration::from_secs(cfg.tcp_keepalive_secs));
+    let listeners = state.listeners.clone();
+    let admin_listener = match admin_addr {
+        None => None,
+        Some(admin_addr) => {
+            if !admin_addr.ip().is_loopback() {
+                warn!(%admin_addr, "admin listener is not bound to a loopback address");
+            }
+            info!(%admin_addr, "sirr admin listening");
+            let listener = bind_listener(admin_addr, cfg.listen_backlog, keepalive)
+                .context("bind admin listener")?;
+            listeners.bound("admin", listener.local_addr()?);
+            Some(listener)
+        }
+    };
+    // SIGTERM/SIGINT (or `shutdown`) and self-destruct both stop every listener.
+    let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+    let self_destruct = state.self_destruct.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            () = shutdown => {}
+            () = self_destructed(self_destruct) => {}
+        }
+        let _ = stop_tx.send(true);
+    });
+    let stopping = move || {
+        let mut stop_rx = stop_rx.clone();
+        async move {
+            let _ = stop_rx.wait_for(|&stop| stop).await;
+        }
+    };
+    let admin_server = admin_listener.map(|listener| {
+        let admin_app = admin_router(&state)
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                crate::metrics::track_latency,
+            ))
+            .layer(middleware::from_fn(json_method_not_allowed))
+            .layer(cors)
+            .with_state(state.clone())
+            .layer(CatchPanicLayer::custom(panic_response))
+            .layer(middleware::from_fn_with_state(state, audit_cors_rejections))
+            .layer(GovernorLayer::new(governor_conf))
+            .layer(middleware::from_fn(add_retryable_flag))
+            .layer(middleware::from_fn(add_security_headers));
+        let admin_app = normalize_paths(with_http_tracing(admin_app, redacted));
+        axum::serve(
+            listener,
+            admin_app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(stopping())
+    });
+
+    let addr: SocketAddr = format!("{}:{}", cfg.host, cfg.port)
+        .parse()
+        .context("invalid host/port")?;
+
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!(%addr, scheme, "sirr server listening");
+    let listener = bind_listener(addr, cfg.listen_backlog, keepalive).context("bind listener")?;
+    listeners.bound("main", listener.local_addr()?);
+
+    let main = serve_main(listener, app, tls, &stopping);
+    let serving = async {
+        match admin_server {
+            Some(admin) => tokio::try_join!(main, async { admin.await }).map(|_| ()),
+            None => main.await,
+        }
+    };
+    let shutdown_timeout = cfg.shutdown_timeout;
+    let drain_deadline = async {
+        stopping().await;
+        info!(timeout = ?shutdown_timeout, "shutting down; draining in-flight requests");
+        tokio::time::sleep(shutdown_timeout).await;
+    };
+    tokio::select! {
+        served = serving => served.context("server error")?,
+        () = drain_deadline => warn!("shutdown timeout elapsed; dropping in-flight requests"),
+    }
+
+    // Stop background jobs between runs: a sweep or compaction in progress
+    // holds `sweep_lock` until it is done.
+    for task in background {
+        task.abort();
+    }
+    let _idle = sweep_lock.lock().await;
+    if let Err(e) = shutdown_store.flush_audit() {
+        warn!(error = %e, "failed to flush buffered audit events on shutdown");
+    }
+    if let Err(e) = shutdown_store.sync() {
+        warn!(error = %e, "failed to sync the store on shutdown");
+    }
+    info!("shutdown complete");
+    Ok(())
+}
+
+/// Resolves on SIGINT (Ctrl-C) or, on Unix, SIGTERM.
+async fn termination_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!(error = %e, "cannot listen for SIGINT");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "cannot listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        () = interrupt => info!("received SIGINT"),
+        () = terminate => info!("received SIGTERM"),
+    }
+}
+
+/// Serve the main listener until a `stopping()` future resolves,
+/// terminating TLS when `tls` is set.
+async fn serve_main<F>(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls: Option<std::sync::Arc<crate::tls::ServerTls>>,
+    stopping: impl Fn() -> F,
+) -> std::io::Result<()>
+where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    #[cfg(feature = "tls")]
+    if let Some(tls) = tls {
+        use axum::serve::ListenerExt;
 
+        // `tap_io` only wraps the listener so `ConnectInfo<SocketAddr>`
+        // applies to it, as it does to a plain `TcpListener`.
+        let listener = crate::tls::TlsListener::new(listener, tls, stopping())?.tap_io(|_| {});
+        return axum::serve(listener, app)
+            .with_graceful_shutdown(stopping())
+            .await;
+    }
+    #[cfg(not(feature = "tls"))]
+    if let Some(tls) = tls {
+        match *tls {}
+    }
+    axum::serve(listener, app)
+        .with_graceful_shutdown(stopping())
+        .await
+}
+
+/// Resolves once `POST /admin/self-destruct` has wiped the instance; never,
+/// when the endpoint is disabled.
+async fn self_destructed(config: Option<std::sync::Arc<crate::handlers::SelfDestruct>>) {
+    match config {
+        Some(config) => {
+            let _ = config.shutdown.subscribe().wait_for(|&done| done).await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Every route served on the main listener. Admin routes are included unless
+/// `with_admin` is false because they have their own listener (`SIRR_ADMIN_PORT`).
+/// `public_cors` covers the unauthenticated informational routes and `cors`
+/// every authenticated one, so writes can keep a stricter origin list.
+fn app_router(
+    state: &AppState,
+    health_path: &str,
+    ready_path: &str,
+    public_cors: CorsLayer,
+    cors: CorsLayer,
+    with_admin: bool,
+) -> Router<AppState> {
     // Public informational routes (no auth, CORS allowed).
-    let public = Router::new()
-        .route("/health", get(health))
-        .route("/robots.txt", get(robots_txt))
-        .route("/security.txt", get(security_txt))
-        .route("/.well-known/security.txt", get(security_txt))
-        .layer(cors.clone());
+    let public = public_router(health_path, ready_path).layer(public_cors);
+
+    // Instance-admin routes (master key only), served in every mode.
+    let admin = if with_admin {
+        admin_router(state).layer(cors.clone())
+    } else {
+        Router::new()
+    };
 
     // Org-protected routes (require_auth middleware: master key or principal key).
     let org_protected = Router::new()
@@ -408,7 +1145,7 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
         .layer(cors.clone());
 
     // Build the merged app depending on whether the public bucket is enabled.
-    let app = if enable_public_bucket {
+    let app = if state.enable_public_bucket {
         // Public bucket open routes: reads and creates carry NO CORS layer intentionally.
         // Without Access-Control-Allow-Origin, browsers block cross-origin reads,
         // preventing a malicious webpage from silently exfiltrating secrets.
@@ -417,18 +1154,31 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
         // so writes don't require the master key any more than reads do.
         let secret_public = Router::new()
             .route("/secrets", post(create_secret))
+            .route("/secrets/batch", post(batch_create_secrets))
+            .route("/secrets/batch-get", post(batch_get_secrets))
             .route("/secrets/{key}", get(get_secret))
-            .route("/secrets/{key}", head(head_secret));
+            .route("/secrets/{key}", head(head_secret))
+            .route("/secrets/{key}/describe", get(describe_secret))
+            .route("/secrets/{key}/field/{field}", get(get_secret_field));
 
         // Protected public bucket routes (require_master_key middleware).
         let protected_public_bucket = Router::new()
             .route("/secrets", get(list_secrets))
+            .route("/secrets", delete(delete_matching_secrets))
+            .route("/capacity", get(capacity))
+            .route("/secrets/{key}/peek", get(peek_secret))
             .route("/secrets/{key}", patch(patch_secret))
             .route("/secrets/{key}", delete(delete_secret))
+            .route("/secrets/{key}/touch", post(touch_secret))
+            .route("/secrets/{key}/aliases", post(create_alias))
+            .route("/secrets/{key}/aliases/{alias}", delete(delete_alias))
             .route("/prune", post(prune_secrets))
             .route("/audit", get(audit_events))
             .route("/webhooks", post(create_webhook))
             .route("/webhooks", get(list_webhooks))
+            .route("/webhooks/export", get(export_webhooks))
+            .route("/webhooks/preview", post(preview_webhook))
+            .route("/webhooks/import", post(import_webhooks))
             .route("/webhooks/{id}", delete(delete_webhook))
             .layer(middleware::from_fn_with_state(
                 state.clone(),
@@ -440,33 +1190,144 @@ pub async fn run(cfg: ServerConfig) -> Result<()> {
             .merge(secret_public)
             .merge(public)
             .merge(protected_public_bucket)
+            .merge(admin)
             .merge(org_protected)
-            .with_state(state)
     } else {
         Router::new()
             .merge(public)
+            .merge(admin)
             .merge(org_protected)
-            .with_state(state)
+    };
+    app.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        crate::metrics::track_latency,
+    ))
+    .layer(middleware::from_fn(json_method_not_allowed))
+    .layer(middleware::from_fn_with_state(
+        state.clone(),
+        reject_writes_in_maintenance,
+    ))
+}
+
+/// Seconds clients are told to wait (`Retry-After`) during maintenance.
+const MAINTENANCE_RETRY_AFTER_SECS: u64 = 60;
+
+/// In maintenance mode, answer every mutating request with `503` and a
+/// `Retry-After`. Reads, including `POST /secrets/batch-get` and
+/// `POST /webhooks/preview`, still go
+/// through, as does the request that ends maintenance.
+async fn reject_writes_in_maintenance(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !is_gated_write(&req) || !state.maintenance.load(std::sync::atomic::Ordering::Relaxed) {
+        return next.run(req).await;
+    }
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(
+            axum::http::header::RETRY_AFTER,
+            MAINTENANCE_RETRY_AFTER_SECS.to_string(),
+        )],
+        Json(json!({
+            "error": "instance is in maintenance mode; writes are disabled",
+            "code": "maintenance",
+        })),
+    )
+        .into_response()
+}
+
+/// Whether `req` mutates state and is held back by maintenance mode. Reads
+/// sent as POST and the emergency admin switches are not.
+fn is_gated_write(req: &Request) -> bool {
+    !(req.method().is_safe()
+        || matches!(
+            req.uri().path(),
+            "/secrets/batch-get"
+                | "/webhooks/preview"
+                | "/admin/maintenance"
+                | "/admin/self-destruct"
+        ))
+}
+
+/// Fewest distinct characters a configured API key may use, so a long run
+/// of one repeated character does not pass the length check.
+const MIN_API_KEY_DISTINCT_CHARS: usize = 8;
+
+/// Startup check that `SIRR_API_KEY` is not trivially guessable: at least
+/// `min_len` characters drawn from a reasonable alphabet. A weak key is
+/// logged as a warning, or refused when `strict`.
+pub fn check_api_key(key: Option<&str>, min_len: usize, strict: bool) -> Result<()> {
+    match key {
+        Some(key) => check_named_key("SIRR_API_KEY", key, min_len, strict),
+        None => Ok(()),
     }
-    .layer(GovernorLayer::new(governor_conf))
-    .layer(middleware::from_fn(add_security_headers))
-    .layer(TraceLayer::new_for_http());
+}
 
-    let addr: SocketAddr = format!("{}:{}", cfg.host, cfg.port)
-        .parse()
-        .context("invalid host/port")?;
+/// [`check_api_key`] for the key called `name` in messages.
+fn check_named_key(name: &str, key: &str, min_len: usize, strict: bool) -> Result<()> {
+    let distinct = key.chars().collect::<std::collections::HashSet<_>>().len();
+    let weakness = if key.chars().count() < min_len {
+        format!("{name} is shorter than {min_len} characters")
+    } else if min_len > 0 && distinct < MIN_API_KEY_DISTINCT_CHARS {
+        format!("{name} uses only {distinct} distinct characters")
+    } else {
+        return Ok(());
+    };
+    if strict {
+        anyhow::bail!("{weakness}; use a random key (e.g. `openssl rand -hex 32`)");
+    }
+    warn!("{weakness}; it may be brute-forced. Set SIRR_API_KEY_STRICT=1 to refuse weak keys");
+    Ok(())
+}
 
-    info!(%addr, "sirr server listening");
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .context("bind listener")?;
+/// Bind the HTTP listener via socket2 so the backlog and TCP keepalive are
+/// configurable. Accepted connections inherit the keepalive setting.
+fn bind_listener(
+    addr: SocketAddr,
+    backlog: i32,
+    keepalive: Option<Duration>,
+) -> Result<tokio::net::TcpListener> {
+    use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
 
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await
-    .context("server error")
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_reuse_address(true)?;
+    if let Some(idle) = keepalive {
+        socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(idle))?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(backlog)?;
+    Ok(tokio::net::TcpListener::from_std(socket.into())?)
+}
+
+/// Public informational routes: liveness/readiness probes at the configured
+/// paths plus robots.txt and security.txt.
+fn public_router(health_path: &str, ready_path: &str) -> Router<AppState> {
+    Router::new()
+        .route(health_path, get(health))
+        .route(ready_path, get(ready))
+        .route("/version", get(version))
+        .route("/robots.txt", get(robots_txt))
+        .route("/security.txt", get(security_txt))
+        .route("/.well-known/security.txt", get(security_txt))
+}
+
+/// Instance-admin routes guarded by `require_master_key`. `/metrics` is
+/// left open when `metrics_public` is set.
+fn admin_router(state: &AppState) -> Router<AppState> {
+    let metrics = Router::new().route("/metrics", get(crate::metrics::metrics));
+    let guarded = Router::new()
+        .route("/status", get(status))
+        .route("/admin/maintenance", post(set_maintenance))
+        .route("/admin/self-destruct", post(self_destruct));
+    let require_master_key = middleware::from_fn_with_state(state.clone(), require_master_key);
+    if state.metrics_public {
+        guarded.layer(require_master_key).merge(metrics)
+    } else {
+        guarded.merge(metrics).layer(require_master_key)
+    }
 }
 
 /// Auto-initialize with a default org, admin principal, and temporary keys.
@@ -538,6 +1399,7 @@ fn auto_init_bootstrap(store: &crate::store::Store) -> Result<()> {
             valid_after: now,
             valid_before,
             created_at: now,
+            max_secrets: None,
         };
         store
             .put_principal_key(&key_record)
@@ -572,8 +1434,28 @@ fn auto_init_bootstrap(store: &crate::store::Store) -> Result<()> {
     Ok(())
 }
 
-fn load_or_create_key(data_dir: &std::path::Path) -> Result<crate::store::crypto::EncryptionKey> {
+fn load_or_create_key(
+    data_dir: &std::path::Path,
+    supplied: Option<&str>,
+) -> Result<crate::store::crypto::EncryptionKey> {
+    if let Some(hex_key) = supplied {
+        let bytes = hex::decode(hex_key.trim()).context("SIRR_ENCRYPTION_KEY is not valid hex")?;
+        return crate::store::crypto::load_key(&bytes).ok_or_else(|| {
+            anyhow::anyhow!(
+                "SIRR_ENCRYPTION_KEY must be 32 bytes (64 hex chars), got {} bytes",
+                bytes.len()
+            )
+        });
+    }
+
     let key_path = data_dir.join("sirr.key");
+    if key_path.with_extension("key.next").exists() {
+        // Left by a scheduled rotation interrupted after its store commit.
+        warn!(
+            "sirr.key.next exists: a key rotation was interrupted; if `sirrd check` \
+             reports failures, the secrets are encrypted under sirr.key.next"
+        );
+    }
     if key_path.exists() {
         let bytes = std::fs::read(&key_path).context("read sirr.key")?;
         crate::store::crypto::load_key(&bytes).ok_or_else(|| {
@@ -584,7 +1466,19 @@ fn load_or_create_key(data_dir: &std::path::Path) -> Result<crate::store::crypto
         })
     } else {
         let key = crate::store::crypto::generate_key();
-        std::fs::write(&key_path, key.as_bytes()).context("write sirr.key")?;
+        if let Err(e) = std::fs::write(&key_path, key.as_bytes()) {
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem
+            ) {
+                anyhow::bail!(
+                    "data dir {} is read-only and no sirr.key exists; \
+                     provide the key via SIRR_ENCRYPTION_KEY (64 hex chars)",
+                    data_dir.display()
+                );
+            }
+            return Err(e).context("write sirr.key");
+        }
         info!("generated new encryption key");
         Ok(key)
     }
@@ -640,6 +1534,52 @@ fn print_banner(
     eprintln!();
 }
 
+/// Record CORS preflights from origins the CORS layer refused as
+/// `cors.rejected`, when `audit_cors_rejections` is on. A preflight counts as
+/// refused when its response carries no `Access-Control-Allow-Origin`. A
+/// failed audit write follows `SIRR_AUDIT_FAILURE_MODE`.
+async fn audit_cors_rejections(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let headers = req.headers();
+    let preflight_origin = (state.audit_cors_rejections
+        && req.method() == http::Method::OPTIONS
+        && headers.contains_key(axum::http::header::ACCESS_CONTROL_REQUEST_METHOD))
+    .then(|| headers.get(axum::http::header::ORIGIN))
+    .flatten()
+    .map(|o| String::from_utf8_lossy(o.as_bytes()).into_owned());
+    let Some(origin) = preflight_origin else {
+        return next.run(req).await;
+    };
+    let ip = req
+        .extensions()
+        .get::<axum::extract::ConnectInfo<SocketAddr>>()
+        .map(|ci| crate::handlers::extract_ip(headers, &ci.0, &state.trusted_proxies))
+        .unwrap_or_else(|| "unknown".into());
+
+    let response = next.run(req).await;
+    if !response
+        .headers()
+        .contains_key(axum::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    {
+        let event = crate::store::AuditEvent::new(
+            crate::store::audit::ACTION_CORS_REJECTED,
+            None,
+            ip,
+            false,
+            Some(format!("origin={origin}")),
+            None,
+            None,
+        );
+        if let Some(failed) = crate::handlers::audit_mutation(&state, event.into(), 0) {
+            return failed;
+        }
+    }
+    response
+}
+
 /// Adds defensive security headers to every response and removes the `Server`
 /// header that would otherwise reveal the axum/hyper/tower stack.
 async fn add_security_headers(req: Request, next: Next) -> Response {
@@ -661,6 +1601,168 @@ async fn add_security_headers(req: Request, next: Next) -> Response {
     response
 }
 
+/// Headers whose values never reach trace logs.
+const DEFAULT_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-sirr-signature",
+];
+
+/// [`DEFAULT_REDACTED_HEADERS`] plus the comma-separated names in `extra`
+/// ($SIRR_REDACT_HEADERS). Invalid names are skipped with a warning.
+pub fn redacted_headers(extra: Option<&str>) -> Vec<http::HeaderName> {
+    let mut names: Vec<http::HeaderName> = DEFAULT_REDACTED_HEADERS
+        .iter()
+        .map(|h| http::HeaderName::from_static(h))
+        .collect();
+    for raw in extra.unwrap_or_default().split(',').map(str::trim) {
+        if raw.is_empty() {
+            continue;
+        }
+        match http::HeaderName::try_from(raw) {
+            Ok(name) if !names.contains(&name) => names.push(name),
+            Ok(_) => {}
+            Err(_) => warn!(header = raw, "ignoring invalid SIRR_REDACT_HEADERS entry"),
+        }
+    }
+    names
+}
+
+/// Wrap `app` in request/response tracing that records headers at debug
+/// level. The values of `redacted` headers are never read: they are logged
+/// as `Sensitive` whatever other layers did to the header map.
+pub fn with_http_tracing(app: Router, redacted: Vec<http::HeaderName>) -> Router {
+    let redacted: std::sync::Arc<[http::HeaderName]> = redacted.into();
+    let on_response = redacted.clone();
+    app.layer(
+        TraceLayer::new_for_http()
+            .make_span_with(move |req: &Request| {
+                tracing::debug_span!(
+                    "request",
+                    method = %req.method(),
+                    uri = %req.uri(),
+                    version = ?req.version(),
+                    headers = ?RedactedHeaders(req.headers(), &redacted),
+                )
+            })
+            .on_response(
+                move |res: &Response, latency: Duration, _span: &tracing::Span| {
+                    tracing::debug!(
+                        latency = ?latency,
+                        status = res.status().as_u16(),
+                        headers = ?RedactedHeaders(res.headers(), &on_response),
+                        "finished processing request"
+                    );
+                },
+            ),
+    )
+}
+
+/// Debug view of a header map that prints `Sensitive` in place of the
+/// values of the listed headers.
+struct RedactedHeaders<'a>(&'a http::HeaderMap, &'a [http::HeaderName]);
+
+impl std::fmt::Debug for RedactedHeaders<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for (name, value) in self.0 {
+            if self.1.contains(name) {
+                map.entry(name, &format_args!("Sensitive"));
+            } else {
+                map.entry(name, value);
+            }
+        }
+        map.finish()
+    }
+}
+
+/// Whether a client should retry a request that failed with `status`:
+/// rate limiting, timeouts and transient server-side failures.
+pub fn is_retryable(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::REQUEST_TIMEOUT
+    ) || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+/// Gives axum's bodyless 405 (known path, wrong method) the crate's JSON
+/// error shape, keeping the `Allow` header it lists the path's methods in.
+/// Done as a response rewrite rather than a method fallback, which would
+/// bypass the CORS layer that answers preflights on each route.
+pub async fn json_method_not_allowed(req: Request, next: Next) -> Response {
+    let method = req.method().clone();
+    let response = next.run(req).await;
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED
+        || response.headers().contains_key(http::header::CONTENT_TYPE)
+    {
+        return response;
+    }
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    let body = Json(json!({
+        "error": format!("method {method} not allowed on this path"),
+        "code": "method_not_allowed",
+    }));
+    (parts, body).into_response()
+}
+
+/// Serves `app` with a trailing slash trimmed from every request path, so
+/// `/secrets/` routes like `/secrets`.
+pub fn normalize_paths(app: Router) -> Router {
+    Router::new().fallback_service(NormalizePath::trim_trailing_slash(app))
+}
+
+/// Turns a handler panic into the crate's JSON error shape instead of a
+/// dropped connection. Logged inside the request's trace span, so the line
+/// carries the method, URI and (redacted) headers of the failing request.
+pub fn panic_response(panic: Box<dyn std::any::Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload");
+    tracing::error!(panic = message, "handler panicked");
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"error": "internal server error", "code": "panic"})),
+    )
+        .into_response()
+}
+
+/// Adds `"retryable": bool` to every JSON error body (`{"error": ...}`) so
+/// SDKs can decide whether to retry without hard-coding status tables.
+pub async fn add_retryable_flag(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, 64 * 1024).await else {
+        return (
+            status,
+            Json(json!({"error": "internal server error", "retryable": true})),
+        )
+            .into_response();
+    };
+    let bytes = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut obj)) if obj.contains_key("error") => {
+            obj.insert("retryable".into(), is_retryable(status).into());
+            parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+            serde_json::to_vec(&obj).unwrap_or_else(|_| bytes.to_vec())
+        }
+        _ => bytes.to_vec(),
+    };
+    Response::from_parts(parts, axum::body::Body::from(bytes))
+}
+
 /// Prints the mandatory security notice when a key was auto-generated.
 /// Bypasses `NO_BANNER`; only `NO_SECURITY_BANNER=1` can suppress it.
 fn print_security_notice(cfg: &ServerConfig) {
@@ -712,7 +1814,7 @@ async fn security_txt() -> impl IntoResponse {
     )
 }
 
-fn build_cors(origins: Option<&str>, methods: Option<&str>) -> CorsLayer {
+fn build_cors(origins: Option<&str>, methods: Option<&str>, max_age: Duration) -> CorsLayer {
     // No origins configured → deny all cross-origin requests.
     let Some(origins_str) = origins else {
         return CorsLayer::new();
@@ -741,4 +1843,926 @@ fn build_cors(origins: Option<&str>, methods: Option<&str>) -> CorsLayer {
         .allow_origin(allowed_origins)
         .allow_methods(allowed_methods)
         .allow_headers(Any)
+        .max_age(max_age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+
+    fn test_state(dir: &tempfile::TempDir) -> AppState {
+        let store = crate::store::Store::open(
+            &dir.path().join("test.db"),
+            crate::store::crypto::generate_key(),
+        )
+        .unwrap();
+        AppState {
+            store: store.clone(),
+            secrets: std::sync::Arc::new(store.clone()),
+            api_keys: Default::default(),
+            license: license::LicenseStatus::Free,
+            validator: None,
+            webhook_sender: None,
+            trusted_proxies: std::sync::Arc::new(vec![]),
+            redact_audit_keys: false,
+            webhook_allowed_origins: std::sync::Arc::new(vec![]),
+            enable_public_bucket: true,
+            started_at: 0,
+            started: std::time::Instant::now(),
+            create_limiter: None,
+            sweep_lock: Default::default(),
+            public_base_url: None,
+            max_secrets: None,
+            auto_prune_on_full: false,
+            min_ttl_seconds: 0,
+            require_ttl: false,
+            limit_status: axum::http::StatusCode::PAYMENT_REQUIRED,
+            reserved_key_prefixes: Default::default(),
+            generated_key_length: 24,
+            key_generator: Default::default(),
+            audit_cors_rejections: false,
+            audit_failure_mode: Default::default(),
+            audit_max_range_secs: None,
+            create_mode: Default::default(),
+            idempotency_ttl_secs: 24 * 60 * 60,
+            maintenance: Default::default(),
+            self_destruct: None,
+            min_value_bytes: 0,
+            listeners: Default::default(),
+            metrics: Default::default(),
+            metrics_public: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_probe_paths_replace_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = public_router("/healthz", "/readyz").with_state(test_state(&dir));
+        let server = TestServer::new(app);
+
+        server.get("/healthz").await.assert_status_ok();
+        let resp = server.get("/readyz").await;
+        resp.assert_status_ok();
+        assert_eq!(resp.json::<serde_json::Value>()["status"], "ready");
+
+        server.get("/health").await.assert_status_not_found();
+        server.get("/health/ready").await.assert_status_not_found();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn supplied_key_works_with_read_only_data_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        let hex_key = "ab".repeat(32);
+        let key = load_or_create_key(dir.path(), Some(&hex_key)).unwrap();
+        assert_eq!(key.as_bytes(), &[0xab; 32]);
+        assert!(!dir.path().join("sirr.key").exists());
+
+        let Err(err) = load_or_create_key(dir.path(), Some("abcd")) else {
+            panic!("short key accepted");
+        };
+        assert!(err.to_string().contains("64 hex chars"));
+
+        std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn listener_with_custom_backlog_accepts_connections() {
+        let listener = bind_listener(
+            "127.0.0.1:0".parse().unwrap(),
+            16,
+            Some(Duration::from_secs(30)),
+        )
+        .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = tokio::spawn(tokio::net::TcpStream::connect(addr));
+        let (accepted, _) = listener.accept().await.unwrap();
+        client.await.unwrap().unwrap();
+        assert!(socket2::SockRef::from(&accepted).keepalive().unwrap());
+    }
+
+    #[tokio::test]
+    async fn tls_cert_without_key_fails_startup() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = ServerConfig {
+            data_dir: Some(dir.path().to_path_buf()),
+            tls_cert_path: Some(dir.path().join("cert.pem")),
+            tls_key_path: None,
+            ..ServerConfig::default()
+        };
+        let err = run(cfg).await.unwrap_err();
+        assert!(err.to_string().contains("SIRR_TLS_KEY"), "{err}");
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn main_listener_rejects_clients_below_minimum_tls_version() {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let cert_der = CertificateDer::from(cert.cert.der().to_vec());
+        let key_der = PrivateKeyDer::try_from(cert.key_pair.serialize_der()).unwrap();
+        let tls = crate::tls::server_config(
+            vec![cert_der.clone()],
+            key_der,
+            crate::tls::TlsMinVersion::Tls13,
+        )
+        .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/", axum::routing::get(|| async { "ok" }));
+        let server = tokio::spawn(serve_main(
+            listener,
+            app,
+            Some(std::sync::Arc::new(tls)),
+            std::future::pending::<()>,
+        ));
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert_der).unwrap();
+        let connect = |version: &'static rustls::SupportedProtocolVersion| {
+            let roots = roots.clone();
+            async move {
+                let cfg = rustls::ClientConfig::builder_with_provider(std::sync::Arc::new(
+                    rustls::crypto::ring::default_provider(),
+                ))
+                .with_protocol_versions(&[version])
+                .unwrap()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+                let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+                let mut tls = tokio_rustls::TlsConnector::from(std::sync::Arc::new(cfg))
+                    .connect(ServerName::try_from("localhost").unwrap(), stream)
+                    .await?;
+                tls.write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                    .await?;
+                let mut buf = Vec::new();
+                tls.read_to_end(&mut buf).await?;
+                Ok::<_, std::io::Error>(String::from_utf8_lossy(&buf).into_owned())
+            }
+        };
+
+        assert!(connect(&rustls::version::TLS12).await.is_err());
+        let response = connect(&rustls::version::TLS13).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("ok"), "{response}");
+        server.abort();
+    }
+
+    #[test]
+    fn weak_api_key_fails_strict_startup_check() {
+        assert!(check_api_key(Some("hunter2"), 32, true).is_err());
+        assert!(check_api_key(Some(&"a".repeat(40)), 32, true).is_err());
+        // Non-strict mode only warns.
+        assert!(check_api_key(Some("hunter2"), 32, false).is_ok());
+        assert!(check_api_key(Some("0123456789abcdef0123456789abcdef"), 32, true).is_ok());
+        assert!(check_api_key(Some("x"), 0, true).is_ok());
+        assert!(check_api_key(None, 32, true).is_ok());
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        for s in [
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ] {
+            assert!(is_retryable(s), "{s}");
+        }
+        for s in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::PAYMENT_REQUIRED,
+            StatusCode::NOT_FOUND,
+            StatusCode::CONFLICT,
+            StatusCode::PRECONDITION_FAILED,
+        ] {
+            assert!(!is_retryable(s), "{s}");
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_panic_becomes_json_500() {
+        let app = Router::new()
+            .route(
+                "/boom",
+                get(|| async {
+                    if true {
+                        panic!("malformed response");
+                    }
+                    "unreachable"
+                }),
+            )
+            .layer(CatchPanicLayer::custom(panic_response))
+            .layer(middleware::from_fn(add_retryable_flag));
+        let server = TestServer::new(app);
+
+        let resp = server.get("/boom").await;
+        resp.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            resp.json::<serde_json::Value>(),
+            json!({"error": "internal server error", "code": "panic", "retryable": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn cors_preflight_carries_max_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let cors = build_cors(
+            Some("https://app.example.com"),
+            None,
+            Duration::from_secs(600),
+        );
+        let app = public_router("/health", "/health/ready")
+            .layer(cors)
+            .with_state(test_state(&dir));
+        let server = TestServer::new(app);
+
+        let resp = server
+            .method(http::Method::OPTIONS, "/health")
+            .add_header("origin", "https://app.example.com")
+            .add_header("access-control-request-method", "GET")
+            .await;
+        resp.assert_status_ok();
+        assert_eq!(resp.header("access-control-max-age"), "600");
+    }
+
+    #[tokio::test]
+    async fn mutating_routes_use_the_stricter_cors_origins() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = test_state(&dir);
+        let max_age = Duration::from_secs(600);
+        let app = app_router(
+            &state,
+            "/health",
+            "/health/ready",
+            build_cors(
+                Some("https://admin.example.com,https://status.example.com"),
+                None,
+                max_age,
+            ),
+            build_cors(Some("https://admin.example.com"), None, max_age),
+            true,
+        )
+        .with_state(state);
+        let server = TestServer::new(app);
+        let preflight = |path: &'static str, origin: &'static str, method: &'static str| {
+            server
+                .method(http::Method::OPTIONS, path)
+                .add_header("origin", origin)
+                .add_header("access-control-request-method", method)
+        };
+
+        let resp = preflight("/health", "https://status.example.com", "GET").await;
+        assert_eq!(
+            resp.header("access-control-allow-origin"),
+            "https://status.example.com"
+        );
+
+        let resp = preflight("/webhooks", "https://status.example.com", "POST").await;
+        assert!(resp.maybe_header("access-control-allow-origin").is_none());
+        let resp = preflight("/secrets/K", "https://status.example.com", "DELETE").await;
+        assert!(resp.maybe_header("access-control-allow-origin").is_none());
+
+        let resp = preflight("/webhooks", "https://admin.example.com", "POST").await;
+        assert_eq!(
+            resp.header("access-control-allow-origin"),
+            "https://admin.example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_cors_preflight_is_audited_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.audit_cors_rejections = true;
+        let cors = build_cors(
+            Some("https://app.example.com"),
+            None,
+            Duration::from_secs(600),
+        );
+        let app = public_router("/health", "/health/ready")
+            .layer(cors)
+            .with_state(state.clone())
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                audit_cors_rejections,
+            ));
+        let server = TestServer::new(app);
+
+        for origin in ["https://app.example.com", "https://evil.example"] {
+            server
+                .method(http::Method::OPTIONS, "/health")
+                .add_header("origin", origin)
+                .add_header("access-control-request-method", "GET")
+                .await;
+        }
+
+        let events = state
+            .store
+            .list_audit(&crate::store::AuditQuery {
+                since: None,
+                until: None,
+                action: Some("cors.rejected".into()),
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].detail.as_deref(),
+            Some("origin=https://evil.example")
+        );
+        assert!(!events[0].success);
+    }
+
+    #[tokio::test]
+    async fn version_matches_package_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = public_router("/health", "/health/ready").with_state(test_state(&dir));
+        let server = TestServer::new(app);
+
+        let resp = server.get("/version").await;
+        resp.assert_status_ok();
+        let body: serde_json::Value = resp.json();
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!body["git_sha"].as_str().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn bound_listeners_are_listed_by_status_not_readiness() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        state
+            .listeners
+            .bound("main", "0.0.0.0:39999".parse().unwrap());
+        state
+            .listeners
+            .bound("admin", "127.0.0.1:39998".parse().unwrap());
+        let server = TestServer::new(
+            public_router("/health", "/health/ready")
+                .merge(admin_router(&state))
+                .with_state(state),
+        );
+
+        let resp = server.get("/health/ready").await;
+        resp.assert_status_ok();
+        assert_eq!(
+            resp.json::<serde_json::Value>(),
+            serde_json::json!({"status": "ready"})
+        );
+
+        let status: serde_json::Value = server
+            .get("/status")
+            .authorization_bearer("master")
+            .await
+            .json();
+        assert_eq!(
+            status["listeners"],
+            serde_json::json!({"main": "0.0.0.0:39999", "admin": "127.0.0.1:39998"})
+        );
+    }
+
+    #[tokio::test]
+    async fn status_reports_increasing_uptime() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let app = admin_router(&state).with_state(state);
+        let server = TestServer::new(app);
+
+        server.get("/status").await.assert_status_unauthorized();
+
+        let first: serde_json::Value = server
+            .get("/status")
+            .authorization_bearer("master")
+            .await
+            .json();
+        assert_eq!(first["version"], env!("CARGO_PKG_VERSION"));
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let second: serde_json::Value = server
+            .get("/status")
+            .authorization_bearer("master")
+            .await
+            .json();
+        assert_eq!(first["started_at"], second["started_at"]);
+        assert!(second["uptime_seconds"].as_u64() > first["uptime_seconds"].as_u64());
+    }
+
+    #[tokio::test]
+    async fn status_counts_webhooks_and_dead_letters() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let server = TestServer::new(admin_router(&state).with_state(state.clone()));
+        let status = || async {
+            server
+                .get("/status")
+                .authorization_bearer("master")
+                .await
+                .json::<serde_json::Value>()
+        };
+
+        let body = status().await;
+        assert_eq!(body["webhook_count"], 0);
+        assert_eq!(body["dead_letter_count"], 0);
+
+        for url in ["https://a.example.com/hook", "https://b.example.com/hook"] {
+            state
+                .store
+                .put_webhook(&crate::webhooks::WebhookRegistration {
+                    id: crate::webhooks::generate_webhook_id(),
+                    url: url.into(),
+                    secret: crate::webhooks::generate_signing_secret(),
+                    events: vec!["*".into()],
+                    created_at: 0,
+                    org_id: None,
+                    template: None,
+                    event_urls: Default::default(),
+                })
+                .unwrap();
+        }
+        state
+            .store
+            .put_dead_letter(&crate::webhooks::DeadLetter {
+                id: 0,
+                url: "https://a.example.com/hook".into(),
+                registration_id: None,
+                body: "{}".into(),
+                attempts: 3,
+                last_error: "timeout".into(),
+                failed_at: 0,
+            })
+            .unwrap();
+
+        let body = status().await;
+        assert_eq!(body["webhook_count"], 2);
+        assert_eq!(body["dead_letter_count"], 1);
+    }
+
+    #[tokio::test]
+    async fn admin_routes_move_to_the_admin_listener() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let main = |with_admin| {
+            TestServer::new(
+                app_router(
+                    &state,
+                    "/health",
+                    "/health/ready",
+                    CorsLayer::new(),
+                    CorsLayer::new(),
+                    with_admin,
+                )
+                .with_state(state.clone()),
+            )
+        };
+
+        // Default: admin routes share the main listener.
+        let shared = main(true);
+        shared
+            .get("/status")
+            .authorization_bearer("master")
+            .await
+            .assert_status_ok();
+
+        // SIRR_ADMIN_PORT set: absent from the main listener...
+        let public = main(false);
+        public
+            .get("/status")
+            .authorization_bearer("master")
+            .await
+            .assert_status_not_found();
+        public
+            .get("/metrics")
+            .authorization_bearer("master")
+            .await
+            .assert_status_not_found();
+        public.get("/health").await.assert_status_ok();
+
+        // ...and served by the admin one.
+        let admin = TestServer::new(admin_router(&state).with_state(state.clone()));
+        admin
+            .get("/status")
+            .authorization_bearer("master")
+            .await
+            .assert_status_ok();
+        admin
+            .get("/secrets")
+            .authorization_bearer("master")
+            .await
+            .assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn trailing_slash_matches_and_wrong_method_is_json_405() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let app = app_router(
+            &state,
+            "/health",
+            "/health/ready",
+            CorsLayer::new(),
+            CorsLayer::new(),
+            true,
+        )
+        .with_state(state.clone())
+        .layer(middleware::from_fn(|mut req: Request, next: Next| {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((
+                    [127, 0, 0, 1],
+                    0,
+                ))));
+            next.run(req)
+        }));
+        let server = TestServer::new(normalize_paths(app));
+
+        server.get("/health/").await.assert_status_ok();
+        server
+            .post("/secrets/")
+            .json(&json!({"key": "SLASH", "value": "v"}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server.get("/secrets/SLASH/").await.assert_status_ok();
+
+        // Auth runs first on protected paths, so a 405 does not reveal them.
+        let resp = server
+            .put("/secrets/SLASH")
+            .authorization_bearer("master")
+            .await;
+        resp.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        let allow = resp.header("allow");
+        let allow = allow.to_str().unwrap();
+        for method in ["GET", "HEAD", "PATCH", "DELETE"] {
+            assert!(allow.contains(method), "{allow}");
+        }
+        let body: serde_json::Value = resp.json();
+        assert_eq!(body["code"], "method_not_allowed");
+        assert!(body["error"].as_str().unwrap().contains("PUT"));
+    }
+
+    #[tokio::test]
+    async fn self_destruct_wipes_secrets_and_key_file_then_shuts_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("sirr.key");
+        std::fs::write(&key_file, [7u8; 32]).unwrap();
+        let next_key_file = dir.path().join("sirr.key.next");
+        std::fs::write(&next_key_file, [8u8; 32]).unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let config = std::sync::Arc::new(crate::handlers::SelfDestruct {
+            token: "burn-it-all".into(),
+            key_file: Some(key_file.clone()),
+            shutdown: Default::default(),
+        });
+        state.self_destruct = Some(config.clone());
+        let app = app_router(
+            &state,
+            "/health",
+            "/health/ready",
+            CorsLayer::new(),
+            CorsLayer::new(),
+            true,
+        )
+        .with_state(state.clone())
+        .layer(middleware::from_fn(|mut req: Request, next: Next| {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((
+                    [127, 0, 0, 1],
+                    0,
+                ))));
+            next.run(req)
+        }));
+        let server = TestServer::new(app);
+        for key in ["A", "B"] {
+            server
+                .post("/secrets")
+                .json(&json!({"key": key, "value": "v"}))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+        let shutdown = tokio::spawn(self_destructed(Some(config)));
+        let destruct = |confirm: &'static str| {
+            server
+                .post("/admin/self-destruct")
+                .authorization_bearer("master")
+                .json(&json!({"confirm": confirm}))
+        };
+
+        destruct("nope").await.assert_status(StatusCode::FORBIDDEN);
+        assert_eq!(state.secrets.list().unwrap().len(), 2);
+        assert!(key_file.exists());
+
+        let resp = destruct("burn-it-all").await;
+        resp.assert_status_ok();
+        assert_eq!(resp.json::<serde_json::Value>()["wiped"], 2);
+        assert!(state.secrets.list().unwrap().is_empty());
+        assert!(!key_file.exists());
+        assert!(!next_key_file.exists());
+        tokio::time::timeout(Duration::from_secs(1), shutdown)
+            .await
+            .expect("shutdown signalled")
+            .unwrap();
+
+        let events = state
+            .store
+            .list_audit(&crate::store::AuditQuery {
+                since: None,
+                until: None,
+                action: Some("instance.self_destruct".into()),
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].success, "newest event is the completed wipe");
+        assert!(!events[1].success);
+    }
+
+    #[tokio::test]
+    async fn maintenance_mode_rejects_writes_but_serves_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let app = app_router(
+            &state,
+            "/health",
+            "/health/ready",
+            CorsLayer::new(),
+            CorsLayer::new(),
+            true,
+        )
+        .with_state(state.clone())
+        .layer(middleware::from_fn(|mut req: Request, next: Next| {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((
+                    [127, 0, 0, 1],
+                    0,
+                ))));
+            next.run(req)
+        }));
+        let server = TestServer::new(app);
+        let create = |key: &str| {
+            server
+                .post("/secrets")
+                .authorization_bearer("master")
+                .json(&json!({"key": key, "value": "v", "delete": false}))
+        };
+        let maintenance = |enabled: bool| {
+            server
+                .post("/admin/maintenance")
+                .authorization_bearer("master")
+                .json(&json!({"enabled": enabled}))
+        };
+        create("KEEP").await.assert_status(StatusCode::CREATED);
+
+        maintenance(true).await.assert_status_ok();
+        let rejected = create("NEW").await;
+        rejected.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(rejected.headers()["retry-after"], "60");
+        assert_eq!(rejected.json::<serde_json::Value>()["code"], "maintenance");
+        server
+            .patch("/secrets/KEEP")
+            .authorization_bearer("master")
+            .json(&json!({"value": "v2"}))
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        server
+            .delete("/secrets/KEEP")
+            .authorization_bearer("master")
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let read: serde_json::Value = server.get("/secrets/KEEP").await.json();
+        assert_eq!(read["value"], "v");
+
+        maintenance(false).await.assert_status_ok();
+        create("NEW").await.assert_status(StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn failed_audit_write_aborts_the_mutation_in_fail_mode() {
+        use redb::TableDefinition;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        state.audit_failure_mode = crate::store::AuditFailureMode::Fail;
+        let app = app_router(
+            &state,
+            "/health",
+            "/health/ready",
+            CorsLayer::new(),
+            CorsLayer::new(),
+            true,
+        )
+        .with_state(state.clone())
+        .layer(middleware::from_fn(|mut req: Request, next: Next| {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((
+                    [127, 0, 0, 1],
+                    0,
+                ))));
+            next.run(req)
+        }));
+        let server = TestServer::new(app);
+        let create = |key: &str| {
+            server
+                .post("/secrets")
+                .authorization_bearer("master")
+                .json(&json!({"key": key, "value": "v", "delete": false}))
+        };
+        let audit_log = TableDefinition::<u64, &[u8]>::new("audit_log");
+        let broken_log = TableDefinition::<&str, &str>::new("audit_log");
+        create("KEEP").await.assert_status(StatusCode::CREATED);
+
+        let txn = state.store.db.begin_write().unwrap();
+        txn.delete_table(audit_log).unwrap();
+        txn.open_table(broken_log).unwrap();
+        txn.commit().unwrap();
+
+        // The audit write shares the mutation's transaction: neither commits.
+        create("A")
+            .await
+            .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(state.store.head("A").unwrap().is_none());
+        server
+            .patch("/secrets/KEEP")
+            .authorization_bearer("master")
+            .json(&json!({"value": "v2"}))
+            .await
+            .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        server
+            .delete("/secrets/KEEP")
+            .authorization_bearer("master")
+            .await
+            .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(state.store.peek("KEEP").unwrap().as_deref(), Some("v"));
+
+        let txn = state.store.db.begin_write().unwrap();
+        txn.delete_table(broken_log).unwrap();
+        txn.open_table(audit_log).unwrap();
+        txn.commit().unwrap();
+
+        create("A").await.assert_status(StatusCode::CREATED);
+        let created = state
+            .store
+            .list_audit(&crate::store::AuditQuery {
+                since: None,
+                until: None,
+                action: Some("secret.create".into()),
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap();
+        assert_eq!(created.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn metrics_exports_audit_totals() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        for action in ["secret.create", "secret.create", "secret.read"] {
+            state
+                .store
+                .record_audit(crate::store::AuditEvent::new(
+                    action,
+                    None,
+                    "127.0.0.1".into(),
+                    true,
+                    None,
+                    None,
+                    None,
+                ))
+                .unwrap();
+        }
+        let app = admin_router(&state).with_state(state);
+        let server = TestServer::new(app);
+
+        server.get("/metrics").await.assert_status_unauthorized();
+        let body = server
+            .get("/metrics")
+            .authorization_bearer("master")
+            .await
+            .text();
+        assert!(body.contains("# TYPE sirr_audit_total counter"));
+        assert!(body.contains(r#"sirr_audit_total{action="secret.create",success="true"} 2"#));
+        assert!(body.contains(r#"sirr_audit_total{action="secret.read",success="true"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn metrics_count_a_create_and_read_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        let app = app_router(
+            &state,
+            "/health",
+            "/health/ready",
+            CorsLayer::new(),
+            CorsLayer::new(),
+            true,
+        )
+        .with_state(state.clone())
+        .layer(middleware::from_fn(|mut req: Request, next: Next| {
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((
+                    [127, 0, 0, 1],
+                    0,
+                ))));
+            next.run(req)
+        }));
+        let server = TestServer::new(app);
+        server
+            .post("/secrets")
+            .json(&json!({"key": "ONCE", "value": "v", "max_reads": 1}))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server.get("/secrets/ONCE").await.assert_status_ok();
+
+        let body = server
+            .get("/metrics")
+            .authorization_bearer("master")
+            .await
+            .text();
+        assert!(body.contains("sirr_secrets_created_total 1\n"), "{body}");
+        assert!(body.contains("sirr_secret_reads_total 1\n"));
+        assert!(body.contains("sirr_secrets_burned_total 1\n"));
+        assert!(body.contains("sirr_secrets_active 0\n"));
+        assert!(body.contains("# TYPE sirr_http_request_duration_seconds histogram"));
+        assert!(
+            body.contains(r#"sirr_http_request_duration_seconds_count{route="/secrets/{key}"} 1"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn metrics_public_opens_only_the_metrics_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = test_state(&dir);
+        state.api_keys = crate::auth::ApiKeys::unlabeled("master");
+        state.metrics_public = true;
+        let server = TestServer::new(admin_router(&state).with_state(state));
+
+        server.get("/metrics").await.assert_status_ok();
+        server.get("/status").await.assert_status_unauthorized();
+    }
+
+    /// `io::Write` sink shared with the test so emitted log lines can be inspected.
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn trace_logs_redact_sensitive_headers() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new().route(
+            "/ping",
+            get(|| async { ([("set-cookie", "session=cookie-secret-value")], "pong") }),
+        );
+        let app = with_http_tracing(app, redacted_headers(Some("x-custom-token")));
+        let server = TestServer::new(app);
+        server
+            .get("/ping")
+            .authorization_bearer("super-secret-master-key")
+            .add_header("x-custom-token", "custom-secret-value")
+            .add_header("x-request-note", "visible-value")
+            .await
+            .assert_status_ok();
+
+        let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("visible-value"), "headers are logged: {logs}");
+        assert!(logs.contains("Sensitive"));
+        assert!(!logs.contains("super-secret-master-key"));
+        assert!(!logs.contains("custom-secret-value"));
+        assert!(logs.contains("finished processing request"));
+        assert!(!logs.contains("cookie-secret-value"));
+    }
 }
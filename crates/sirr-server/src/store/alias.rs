@@ -0,0 +1,111 @@
+//! Alternate names for public-bucket secrets, e.g. the old name during a
+//! rename. An alias always points at a stored key, never at another alias,
+//! so chains and cycles cannot form: every write that removes a secret
+//! removes its aliases in the same transaction, and no secret can be stored
+//! under a name that is an alias.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use redb::{ReadableTable, Table, TableDefinition};
+
+use super::db::SECRETS;
+
+/// alias → canonical key.
+pub(crate) const ALIASES: TableDefinition<&str, &str> = TableDefinition::new("aliases");
+
+/// Why an alias was refused.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AliasError {
+    #[error("an alias cannot name the secret it points at")]
+    SelfAlias,
+    #[error("secret not found")]
+    NotFound,
+    #[error("a secret is already stored under that name")]
+    KeyExists,
+    #[error("alias already points at another secret")]
+    Taken,
+}
+
+/// The key `name` reads from: `name` itself when a secret is stored under
+/// it, else the key it aliases, else `name` unchanged.
+pub(crate) fn resolve(
+    secrets: &impl ReadableTable<&'static str, &'static [u8]>,
+    aliases: &impl ReadableTable<&'static str, &'static str>,
+    name: &str,
+) -> Result<String> {
+    if secrets.get(name)?.is_some() {
+        return Ok(name.to_owned());
+    }
+    Ok(match aliases.get(name)? {
+        Some(target) => target.value().to_owned(),
+        None => name.to_owned(),
+    })
+}
+
+/// Remove every alias pointing at one of `keys`. Called in the transaction
+/// that removes them, so an alias never outlives its secret to block its
+/// own name or to resolve to a later secret stored under the key.
+pub(crate) fn remove_aliases_to<'k>(
+    aliases: &mut Table<&str, &str>,
+    keys: impl IntoIterator<Item = &'k str>,
+) -> Result<()> {
+    let keys: BTreeSet<&str> = keys.into_iter().collect();
+    if !keys.is_empty() {
+        aliases.retain(|_, target| !keys.contains(target))?;
+    }
+    Ok(())
+}
+
+impl super::db::Store {
+    /// Make `alias` another name for `key` in reads and HEAD. When `key` is
+    /// itself an alias, the new alias points at its canonical key instead.
+    /// Returns the canonical key, or why the alias was refused.
+    pub fn put_alias(&self, alias: &str, key: &str) -> Result<Result<String, AliasError>> {
+        let write_txn = self.db.begin_write()?;
+        let outcome = {
+            let secrets = write_txn.open_table(SECRETS)?;
+            let mut aliases = write_txn.open_table(ALIASES)?;
+            let canonical = resolve(&secrets, &aliases, key)?;
+            let existing = aliases.get(alias)?.map(|t| t.value().to_owned());
+            if canonical == alias {
+                Err(AliasError::SelfAlias)
+            } else if secrets.get(canonical.as_str())?.is_none() {
+                Err(AliasError::NotFound)
+            } else if secrets.get(alias)?.is_some() {
+                Err(AliasError::KeyExists)
+            } else if existing.is_some_and(|t| t != canonical) {
+                Err(AliasError::Taken)
+            } else {
+                aliases.insert(alias, canonical.as_str())?;
+                Ok(canonical)
+            }
+        };
+        if outcome.is_ok() {
+            self.commit_mutation(write_txn, 1)?;
+        }
+        Ok(outcome)
+    }
+
+    /// Remove `alias` if it points at `key`. Returns true if it did.
+    pub fn delete_alias(&self, alias: &str, key: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut aliases = write_txn.open_table(ALIASES)?;
+            let points_here = aliases.get(alias)?.is_some_and(|t| t.value() == key);
+            if points_here {
+                aliases.remove(alias)?;
+            }
+            points_here
+        };
+        self.commit_mutation(write_txn, usize::from(removed))?;
+        Ok(removed)
+    }
+
+    /// The key `alias` points at, if it is an alias.
+    pub fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        let read_txn = self.db.begin_read()?;
+        let aliases = read_txn.open_table(ALIASES)?;
+        Ok(aliases.get(alias)?.map(|t| t.value().to_owned()))
+    }
+}
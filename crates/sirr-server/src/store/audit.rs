@@ -1,27 +1,43 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
+use super::model::{decode_bincode, encode_versioned, split_versioned};
+
 // ── Action constants ─────────────────────────────────────────────────────────
 
 pub const ACTION_SECRET_CREATE: &str = "secret.create";
 pub const ACTION_SECRET_READ: &str = "secret.read";
+pub const ACTION_SECRET_DESCRIBE: &str = "secret.describe";
+pub const ACTION_SECRET_PEEK: &str = "secret.peek";
 pub const ACTION_SECRET_BURNED: &str = "secret.burned";
 pub const ACTION_SECRET_DELETE: &str = "secret.delete";
 pub const ACTION_SECRET_PATCH: &str = "secret.patch";
+pub const ACTION_SECRET_TOUCH: &str = "secret.touch";
 pub const ACTION_SECRET_LIST: &str = "secret.list";
 pub const ACTION_SECRET_PRUNE: &str = "secret.prune";
 pub const ACTION_SECRET_EXPIRED: &str = "secret.expired";
+pub const ACTION_SECRET_ALIAS: &str = "secret.alias";
 pub const ACTION_WEBHOOK_CREATE: &str = "webhook.create";
 pub const ACTION_WEBHOOK_DELETE: &str = "webhook.delete";
+pub const ACTION_WEBHOOK_EXPORT: &str = "webhook.export";
+pub const ACTION_WEBHOOK_IMPORT: &str = "webhook.import";
 pub const ACTION_KEY_CREATE: &str = "key.create";
 pub const ACTION_KEY_DELETE: &str = "key.delete";
+pub const ACTION_KEY_ROTATE: &str = "key.rotate";
 pub const ACTION_ORG_CREATE: &str = "org.create";
 pub const ACTION_ORG_DELETE: &str = "org.delete";
 pub const ACTION_PRINCIPAL_CREATE: &str = "principal.create";
 pub const ACTION_PRINCIPAL_DELETE: &str = "principal.delete";
 pub const ACTION_ROLE_CREATE: &str = "role.create";
 pub const ACTION_ROLE_DELETE: &str = "role.delete";
+pub const ACTION_CORS_REJECTED: &str = "cors.rejected";
+pub const ACTION_MAINTENANCE: &str = "instance.maintenance";
+pub const ACTION_SELF_DESTRUCT: &str = "instance.self_destruct";
 
 // ── AuditEvent ───────────────────────────────────────────────────────────────
 
@@ -38,6 +54,37 @@ pub struct AuditEvent {
     pub org_id: Option<String>,
     #[serde(default)]
     pub principal_id: Option<String>,
+    /// Label of the `SIRR_API_KEYS` entry that authenticated the request.
+    #[serde(default)]
+    pub key_label: Option<String>,
+}
+
+/// Layout version written by [`AuditEvent::encode`]. Bump it, and add a
+/// legacy struct, whenever the event's fields change.
+const AUDIT_EVENT_VERSION: u8 = 1;
+
+/// [`AuditEvent`] as stored before the layout was versioned.
+#[derive(Deserialize)]
+struct LegacyAuditEvent {
+    id: u64,
+    timestamp: i64,
+    action: String,
+    key: Option<String>,
+    source_ip: String,
+    success: bool,
+    detail: Option<String>,
+    org_id: Option<String>,
+    principal_id: Option<String>,
+}
+
+tokio::task_local! {
+    static KEY_LABEL: String;
+}
+
+/// Run `fut` with every [`AuditEvent`] created inside it attributed to the
+/// master API key labelled `label`.
+pub async fn attributed_to<F: std::future::Future>(label: String, fut: F) -> F::Output {
+    KEY_LABEL.scope(label, fut).await
 }
 
 impl AuditEvent {
@@ -65,6 +112,34 @@ impl AuditEvent {
             detail,
             org_id,
             principal_id,
+            key_label: KEY_LABEL.try_with(Clone::clone).ok(),
+        }
+    }
+
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        encode_versioned(AUDIT_EVENT_VERSION, self)
+    }
+
+    /// Decode the current layout or an event written before versioning.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        match split_versioned(bytes)? {
+            (AUDIT_EVENT_VERSION, payload) => decode_bincode(payload),
+            (0, payload) => {
+                let old: LegacyAuditEvent = decode_bincode(payload)?;
+                Ok(Self {
+                    id: old.id,
+                    timestamp: old.timestamp,
+                    action: old.action,
+                    key: old.key,
+                    source_ip: old.source_ip,
+                    success: old.success,
+                    detail: old.detail,
+                    org_id: old.org_id,
+                    principal_id: old.principal_id,
+                    key_label: None,
+                })
+            }
+            (version, _) => anyhow::bail!("unknown audit event layout version {version}"),
         }
     }
 }
@@ -78,3 +153,158 @@ pub struct AuditQuery {
     pub limit: usize,
     pub org_id: Option<String>,
 }
+
+// ── AuditCounters ────────────────────────────────────────────────────────────
+
+/// In-process running totals of recorded audit events, keyed by
+/// `(action, success)`. Exported as `sirr_audit_total` so audit activity can
+/// be alerted on without querying redb. Reset on restart.
+#[derive(Debug, Default)]
+pub struct AuditCounters {
+    counts: Mutex<BTreeMap<(String, bool), u64>>,
+}
+
+impl AuditCounters {
+    pub fn record(&self, action: &str, success: bool) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        *counts.entry((action.to_owned(), success)).or_insert(0) += 1;
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<(String, bool), u64> {
+        self.counts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+// ── AuditIpMode ──────────────────────────────────────────────────────────────
+
+/// How much of the client IP is kept in stored audit events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuditIpMode {
+    /// The address as seen (default).
+    #[default]
+    Full,
+    /// Last IPv4 octet / last 80 IPv6 bits zeroed.
+    Truncate,
+    /// Replaced with [`AuditIpMode::PLACEHOLDER`].
+    None,
+}
+
+impl AuditIpMode {
+    /// Stored in place of the address in `none` mode.
+    pub const PLACEHOLDER: &'static str = "-";
+
+    /// The form of `ip` to store. Values that are not IP addresses are kept
+    /// in `truncate` mode.
+    pub fn apply(self, ip: &str) -> String {
+        match self {
+            Self::Full => ip.to_owned(),
+            Self::None => Self::PLACEHOLDER.to_owned(),
+            Self::Truncate => match ip.parse::<IpAddr>() {
+                Ok(IpAddr::V4(v4)) => {
+                    let [a, b, c, _] = v4.octets();
+                    Ipv4Addr::new(a, b, c, 0).to_string()
+                }
+                Ok(IpAddr::V6(v6)) => {
+                    let bits = u128::from(v6) & !((1u128 << 80) - 1);
+                    Ipv6Addr::from(bits).to_string()
+                }
+                Err(_) => ip.to_owned(),
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for AuditIpMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "truncate" => Ok(Self::Truncate),
+            "none" => Ok(Self::None),
+            other => Err(format!(
+                "unknown audit IP mode {other:?} (expected full, truncate or none)"
+            )),
+        }
+    }
+}
+
+// ── AuditFailureMode ─────────────────────────────────────────────────────────
+
+/// What a mutating request does when its audit event cannot be written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuditFailureMode {
+    /// Carry on silently (default).
+    #[default]
+    Ignore,
+    /// Carry on, logging the failure at error level.
+    Warn,
+    /// Write the event in the mutation's own transaction (see
+    /// [`with_staged`]), so a failed audit write aborts the mutation and the
+    /// request fails with 500. Incompatible with batched audit writes.
+    Fail,
+}
+
+impl std::str::FromStr for AuditFailureMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "warn" => Ok(Self::Warn),
+            "fail" => Ok(Self::Fail),
+            other => Err(format!(
+                "unknown audit failure mode {other:?} (expected ignore, warn or fail)"
+            )),
+        }
+    }
+}
+
+// ── Staged events ────────────────────────────────────────────────────────────
+
+/// Builds a mutation's audit event from the number of records it changed.
+pub type StagedEvent = Box<dyn FnOnce(usize) -> AuditEvent>;
+
+thread_local! {
+    /// Events waiting for the next mutation committed on this thread.
+    static STAGED: RefCell<Vec<StagedEvent>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Restores the previously staged events when [`with_staged`] returns or
+/// unwinds, so nothing leaks into an unrelated commit.
+struct Unstage(Option<Vec<StagedEvent>>);
+
+impl Drop for Unstage {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            STAGED.set(previous);
+        }
+    }
+}
+
+/// Run `mutate` with `events` staged: the first write transaction it
+/// commits that changes anything writes them too (`Store::commit_mutation`),
+/// so the change and its audit events commit or abort together. Returns
+/// `mutate`'s result and the events no commit took, because nothing changed
+/// or the backend does not stage; the caller records those itself.
+pub fn with_staged<T>(
+    events: Vec<StagedEvent>,
+    mutate: impl FnOnce() -> T,
+) -> (T, Vec<StagedEvent>) {
+    let mut unstage = Unstage(Some(STAGED.replace(events)));
+    let result = mutate();
+    let left = STAGED.replace(unstage.0.take().unwrap_or_default());
+    (result, left)
+}
+
+/// Take the staged events for a commit that changed `changed` records.
+pub(crate) fn take_staged(changed: usize) -> Vec<AuditEvent> {
+    STAGED
+        .take()
+        .into_iter()
+        .map(|event| event(changed))
+        .collect()
+}
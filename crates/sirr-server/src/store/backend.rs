@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use super::alias::AliasError;
+use super::audit::{AuditEvent, AuditQuery};
+use super::crypto::EncryptionKey;
+use super::db::{GetResult, Store};
+use super::idempotency::IdempotentResponse;
+use super::model::{
+    AnnotationsPatch, ConditionalDelete, DeleteCriteria, NewSecret, Precondition, PruneReason,
+    PruneScope, PutConflict, PutReport, SecretMeta, SecretStatus,
+};
+use crate::webhooks::WebhookRegistration;
+
+/// Storage operations behind the public `/secrets` bucket, its audit log and
+/// webhook registrations. Handlers talk to this trait so alternative backends
+/// (in-memory for tests, external databases) can be swapped in without
+/// touching them. [`Store`] is the redb-backed production implementation.
+pub trait SecretStore: Send + Sync {
+    /// Cheap liveness probe used by the readiness endpoint.
+    fn ping(&self) -> Result<()>;
+
+    /// Insert or overwrite a secret. Fails if its key is an alias.
+    fn put(&self, secret: &NewSecret) -> Result<()>;
+
+    /// Insert or overwrite every secret in `secrets` atomically: either all
+    /// are stored or none are. Returns, per secret and in order, whether it
+    /// replaced an existing one, or the first conflict: a key that is an
+    /// alias, when `create_only` a key holding a live secret, or new keys
+    /// taking the public bucket past `max_secrets`.
+    fn put_many(
+        &self,
+        secrets: &[NewSecret],
+        create_only: bool,
+        max_secrets: Option<usize>,
+    ) -> Result<Result<PutReport, PutConflict>>;
+
+    /// Read a secret, counting the read and applying burn/seal rules. An
+    /// alias reads its canonical secret, sharing its read count.
+    fn get(&self, key: &str) -> Result<GetResult>;
+
+    /// [`get`](Self::get) on behalf of the client at `reader`, which counts
+    /// towards the secret's `distinct_readers`. Backends that do not track
+    /// readers can leave this as a plain `get`.
+    fn get_from(&self, key: &str, reader: &str) -> Result<GetResult> {
+        let _ = reader;
+        self.get(key)
+    }
+
+    /// [`get_from`](Self::get_from) for several keys, in order. Backends
+    /// that can should do this in one transaction.
+    fn get_many(&self, keys: &[String], reader: &str) -> Result<Vec<GetResult>> {
+        keys.iter().map(|key| self.get_from(key, reader)).collect()
+    }
+
+    /// Metadata and sealed flag without counting a read. An alias resolves
+    /// to its canonical secret, whose key the metadata carries.
+    fn head(&self, key: &str) -> Result<Option<(SecretMeta, bool)>>;
+
+    /// Value without counting a read or applying burn/seal rules.
+    fn peek(&self, key: &str) -> Result<Option<String>>;
+
+    /// Permits bounding concurrent decrypting reads, awaited by async callers
+    /// before `get`/`get_many`/`peek`. `None` = unbounded.
+    fn decrypt_limiter(&self) -> Option<&super::DecryptLimiter> {
+        None
+    }
+
+    /// Update a `delete=false` secret in place, resetting its read counter.
+    /// Annotation edits are applied and validated inside the same update.
+    /// `delete: Some(false)` also admits an unread `delete=true` secret and
+    /// makes it seal-on-read (see [`SecretRecord::patch_conflict`]).
+    ///
+    /// [`SecretRecord::patch_conflict`]: super::model::SecretRecord::patch_conflict
+    fn patch(
+        &self,
+        key: &str,
+        value: Option<&str>,
+        max_reads: Option<u32>,
+        ttl_seconds: Option<u64>,
+        annotations: AnnotationsPatch,
+        delete: Option<bool>,
+    ) -> Result<Option<SecretMeta>>;
+
+    /// Reset a secret's expiry to `ttl_seconds` from now, leaving its value
+    /// and read counter as they are.
+    fn touch(&self, key: &str, ttl_seconds: u64) -> Result<Option<SecretMeta>>;
+
+    /// Delete a secret. Returns true if it existed.
+    fn delete(&self, key: &str) -> Result<bool>;
+
+    /// [`delete`](Self::delete), returning the secret's metadata as it was
+    /// just before removal. Backends that can should read and remove it in
+    /// one transaction.
+    fn delete_with_meta(&self, key: &str) -> Result<Option<SecretMeta>> {
+        let Some((meta, _)) = self.head(key)? else {
+            return Ok(None);
+        };
+        Ok(self.delete(key)?.then_some(meta))
+    }
+
+    /// Delete `key` only if `precondition` holds for its current metadata.
+    /// The check and the removal must happen atomically, so a concurrent
+    /// write can never land between them.
+    fn delete_if(&self, key: &str, precondition: &Precondition) -> Result<ConditionalDelete>;
+
+    /// Delete every secret matching `criteria`, returning their keys.
+    /// Must refuse an empty filter rather than delete everything.
+    fn delete_matching(&self, criteria: &DeleteCriteria) -> Result<Vec<String>>;
+
+    /// Delete every secret and alias this backend holds, returning how many
+    /// secrets.
+    fn wipe_secrets(&self) -> Result<usize>;
+
+    /// Make `alias` another name for `key` (or for the canonical key, when
+    /// `key` is itself an alias) in reads and HEAD. Returns the canonical
+    /// key, or why the alias was refused.
+    fn put_alias(&self, alias: &str, key: &str) -> Result<Result<String, AliasError>>;
+
+    /// Remove `alias` if it points at `key`. Returns true if it did.
+    fn delete_alias(&self, alias: &str, key: &str) -> Result<bool>;
+
+    /// The key `alias` points at, if it is an alias.
+    fn resolve_alias(&self, alias: &str) -> Result<Option<String>>;
+
+    /// Metadata for all non-expired secrets.
+    fn list(&self) -> Result<Vec<SecretMeta>>;
+
+    /// Metadata and status for all secrets, including expired ones not yet swept.
+    fn list_with_status(&self) -> Result<Vec<(SecretMeta, SecretStatus)>>;
+
+    /// Secrets in the public bucket, including expired ones not yet swept,
+    /// counted without decoding them.
+    fn count_public(&self) -> Result<usize>;
+
+    /// Remove expired and burned secrets, returning their keys.
+    fn prune(&self) -> Result<Vec<String>> {
+        Ok(self
+            .prune_by(PruneScope::default())?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// Remove the secrets `scope` admits, returning each key with the reason
+    /// it was removable.
+    fn prune_by(&self, scope: PruneScope) -> Result<Vec<(String, PruneReason)>>;
+
+    /// Re-encrypt stored values under `new_key`. Returns the number rotated.
+    fn rotate(&self, new_key: &EncryptionKey, new_key_version: u8) -> Result<usize>;
+
+    fn record_audit(&self, event: AuditEvent) -> Result<()>;
+    fn list_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>>;
+    /// Running `(action, success)` totals of recorded audit events.
+    fn audit_counts(&self) -> BTreeMap<(String, bool), u64>;
+
+    fn put_webhook(&self, reg: &WebhookRegistration) -> Result<()>;
+    /// Insert or overwrite every registration in `regs` atomically, unless
+    /// that would leave more than `cap` registered. Returns false, writing
+    /// nothing, when it would.
+    fn put_webhooks(&self, regs: &[WebhookRegistration], cap: usize) -> Result<bool>;
+    fn list_webhooks(&self) -> Result<Vec<WebhookRegistration>>;
+    fn delete_webhook(&self, id: &str) -> Result<bool>;
+    fn count_webhooks(&self) -> Result<usize>;
+
+    /// Live response recorded for an `Idempotency-Key`, if any.
+    fn get_idempotent(&self, key: &str) -> Result<Option<IdempotentResponse>>;
+    fn put_idempotent(&self, key: &str, status: u16, body: &str, ttl_seconds: u64) -> Result<()>;
+}
+
+impl SecretStore for Store {
+    fn ping(&self) -> Result<()> {
+        Store::ping(self)
+    }
+
+    fn put(&self, secret: &NewSecret) -> Result<()> {
+        Store::put(self, secret)
+    }
+
+    fn put_many(
+        &self,
+        secrets: &[NewSecret],
+        create_only: bool,
+        max_secrets: Option<usize>,
+    ) -> Result<Result<PutReport, PutConflict>> {
+        Store::put_many(self, secrets, create_only, max_secrets)
+    }
+
+    fn get(&self, key: &str) -> Result<GetResult> {
+        Store::get(self, key)
+    }
+
+    fn get_from(&self, key: &str, reader: &str) -> Result<GetResult> {
+        Store::get_from(self, key, reader)
+    }
+
+    fn get_many(&self, keys: &[String], reader: &str) -> Result<Vec<GetResult>> {
+        Store::get_many(self, keys, reader)
+    }
+
+    fn head(&self, key: &str) -> Result<Option<(SecretMeta, bool)>> {
+        Store::head(self, key)
+    }
+
+    fn peek(&self, key: &str) -> Result<Option<String>> {
+        Store::peek(self, key)
+    }
+
+    fn decrypt_limiter(&self) -> Option<&super::DecryptLimiter> {
+        Some(Store::decrypt_limiter(self))
+    }
+
+    fn patch(
+        &self,
+        key: &str,
+        value: Option<&str>,
+        max_reads: Option<u32>,
+        ttl_seconds: Option<u64>,
+        annotations: AnnotationsPatch,
+        delete: Option<bool>,
+    ) -> Result<Option<SecretMeta>> {
+        Store::patch(
+            self,
+            key,
+            value,
+            max_reads,
+            ttl_seconds,
+            annotations,
+            delete,
+        )
+    }
+
+    fn touch(&self, key: &str, ttl_seconds: u64) -> Result<Option<SecretMeta>> {
+        Store::touch(self, key, ttl_seconds)
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        Store::delete(self, key)
+    }
+
+    fn delete_with_meta(&self, key: &str) -> Result<Option<SecretMeta>> {
+        Store::delete_with_meta(self, key)
+    }
+
+    fn delete_if(&self, key: &str, precondition: &Precondition) -> Result<ConditionalDelete> {
+        Store::delete_if(self, key, precondition)
+    }
+
+    fn delete_matching(&self, criteria: &DeleteCriteria) -> Result<Vec<String>> {
+        Store::delete_matching(self, criteria)
+    }
+
+    fn wipe_secrets(&self) -> Result<usize> {
+        Store::wipe_secrets(self)
+    }
+
+    fn put_alias(&self, alias: &str, key: &str) -> Result<Result<String, AliasError>> {
+        Store::put_alias(self, alias, key)
+    }
+
+    fn delete_alias(&self, alias: &str, key: &str) -> Result<bool> {
+        Store::delete_alias(self, alias, key)
+    }
+
+    fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        Store::resolve_alias(self, alias)
+    }
+
+    fn list(&self) -> Result<Vec<SecretMeta>> {
+        Store::list(self)
+    }
+
+    fn list_with_status(&self) -> Result<Vec<(SecretMeta, SecretStatus)>> {
+        Store::list_with_status(self)
+    }
+
+    fn count_public(&self) -> Result<usize> {
+        Store::count_public(self)
+    }
+
+    fn prune_by(&self, scope: PruneScope) -> Result<Vec<(String, PruneReason)>> {
+        Store::prune_by(self, scope)
+    }
+
+    fn rotate(&self, new_key: &EncryptionKey, new_key_version: u8) -> Result<usize> {
+        Store::rotate(self, new_key, new_key_version)
+    }
+
+    fn record_audit(&self, event: AuditEvent) -> Result<()> {
+        Store::record_audit(self, event)
+    }
+
+    fn list_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        Store::list_audit(self, query)
+    }
+
+    fn audit_counts(&self) -> BTreeMap<(String, bool), u64> {
+        Store::audit_counts(self)
+    }
+
+    fn put_webhook(&self, reg: &WebhookRegistration) -> Result<()> {
+        Store::put_webhook(self, reg)
+    }
+
+    fn put_webhooks(&self, regs: &[WebhookRegistration], cap: usize) -> Result<bool> {
+        Store::put_webhooks(self, regs, cap)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<WebhookRegistration>> {
+        Store::list_webhooks(self)
+    }
+
+    fn delete_webhook(&self, id: &str) -> Result<bool> {
+        Store::delete_webhook(self, id)
+    }
+
+    fn count_webhooks(&self) -> Result<usize> {
+        Store::count_webhooks(self)
+    }
+
+    fn get_idempotent(&self, key: &str) -> Result<Option<IdempotentResponse>> {
+        Store::get_idempotent(self, key)
+    }
+
+    fn put_idempotent(&self, key: &str, status: u16, body: &str, ttl_seconds: u64) -> Result<()> {
+        Store::put_idempotent(self, key, status, body, ttl_seconds)
+    }
+}
@@ -3,7 +3,9 @@ use chacha20poly1305::{
     aead::{Aead, KeyInit, OsRng},
     ChaCha20Poly1305, Key, Nonce,
 };
-use rand::RngCore;
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
 use zeroize::ZeroizeOnDrop;
 
 /// 32-byte encryption key.
@@ -18,17 +20,45 @@ impl EncryptionKey {
 
 /// Encrypt `plaintext` with `key`, returning `(ciphertext, nonce)`.
 pub fn encrypt(key: &EncryptionKey, plaintext: &[u8]) -> Result<(Vec<u8>, [u8; 12])> {
-    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    encrypt_with(&mut OsRng, key, plaintext)
+}
 
+/// [`encrypt`] drawing the nonce from `rng` (e.g. a seeded RNG in tests).
+pub fn encrypt_with(
+    rng: &mut (impl RngCore + CryptoRng),
+    key: &EncryptionKey,
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, [u8; 12])> {
     let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from(nonce_bytes);
+    rng.fill_bytes(&mut nonce_bytes);
+    Ok((seal(key, plaintext, &nonce_bytes)?, nonce_bytes))
+}
 
-    let ciphertext = cipher
-        .encrypt(&nonce, plaintext)
-        .map_err(|e| anyhow::anyhow!("encrypt: {e}"))?;
+/// [`encrypt`] with the nonce derived from `seq` instead of drawn at random,
+/// so identical inputs give identical ciphertext. For golden-file tests only:
+/// a nonce repeats whenever `seq` does under the same key, e.g. after a
+/// restart, and a repeated ChaCha20-Poly1305 nonce leaks both plaintexts.
+#[cfg(any(test, feature = "deterministic-nonce"))]
+pub fn encrypt_sequenced(
+    key: &EncryptionKey,
+    plaintext: &[u8],
+    seq: u64,
+) -> Result<(Vec<u8>, [u8; 12])> {
+    let mut derive = <Hmac<Sha256> as Mac>::new_from_slice(key.as_bytes()).expect("any key length");
+    derive.update(b"sirr sequence nonce v1");
+    let subkey = derive.finalize().into_bytes();
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&subkey).expect("any key length");
+    mac.update(&seq.to_le_bytes());
+    let digest = mac.finalize().into_bytes();
+    let nonce_bytes: [u8; 12] = digest[..12].try_into().expect("12 bytes");
+    Ok((seal(key, plaintext, &nonce_bytes)?, nonce_bytes))
+}
 
-    Ok((ciphertext, nonce_bytes))
+fn seal(key: &EncryptionKey, plaintext: &[u8], nonce_bytes: &[u8; 12]) -> Result<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    cipher
+        .encrypt(&Nonce::from(*nonce_bytes), plaintext)
+        .map_err(|e| anyhow::anyhow!("encrypt: {e}"))
 }
 
 /// Decrypt `ciphertext` with `key` and `nonce`, returning plaintext.
@@ -43,10 +73,39 @@ pub fn decrypt(key: &EncryptionKey, ciphertext: &[u8], nonce_bytes: &[u8; 12]) -
     Ok(plaintext)
 }
 
+/// Keyed fingerprint of a plaintext value: hex HMAC-SHA256 under a subkey
+/// derived from `key`. Changes whenever the value does, but unlike a bare
+/// hash cannot be used to confirm guesses offline without the key.
+pub fn fingerprint(key: &EncryptionKey, plaintext: &[u8]) -> String {
+    let mut derive = <Hmac<Sha256> as Mac>::new_from_slice(key.as_bytes()).expect("any key length");
+    derive.update(b"sirr value fingerprint v1");
+    let subkey = derive.finalize().into_bytes();
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&subkey).expect("any key length");
+    mac.update(plaintext);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Keyed 64-bit tag for a reader address, stored instead of the address so
+/// records count distinct readers without holding their IPs.
+pub fn reader_tag(key: &EncryptionKey, reader: &str) -> u64 {
+    let mut derive = <Hmac<Sha256> as Mac>::new_from_slice(key.as_bytes()).expect("any key length");
+    derive.update(b"sirr reader tag v1");
+    let subkey = derive.finalize().into_bytes();
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&subkey).expect("any key length");
+    mac.update(reader.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    u64::from_le_bytes(digest[..8].try_into().expect("8 bytes"))
+}
+
 /// Generate a random 32-byte encryption key (no Argon2id derivation).
 pub fn generate_key() -> EncryptionKey {
+    generate_key_with(&mut OsRng)
+}
+
+/// [`generate_key`] drawing from `rng`.
+pub fn generate_key_with(rng: &mut (impl RngCore + CryptoRng)) -> EncryptionKey {
     let mut key = [0u8; 32];
-    OsRng.fill_bytes(&mut key);
+    rng.fill_bytes(&mut key);
     EncryptionKey(key)
 }
 
@@ -81,6 +140,17 @@ mod tests {
         assert!(decrypt(&key2, &ct, &nonce).is_err());
     }
 
+    #[test]
+    fn fingerprint_is_keyed_and_value_sensitive() {
+        let key = generate_key();
+        assert_eq!(fingerprint(&key, b"v1"), fingerprint(&key, b"v1"));
+        assert_ne!(fingerprint(&key, b"v1"), fingerprint(&key, b"v2"));
+        assert_ne!(
+            fingerprint(&key, b"v1"),
+            fingerprint(&generate_key(), b"v1")
+        );
+    }
+
     #[test]
     fn generate_key_round_trip() {
         let key = generate_key();
@@ -89,4 +159,19 @@ mod tests {
         let pt = decrypt(&key, &ct, &nonce).unwrap();
         assert_eq!(pt, plaintext);
     }
+
+    #[test]
+    fn seeded_rng_gives_reproducible_keys_and_nonces() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let key = generate_key_with(&mut rng);
+            let (ct, nonce) = encrypt_with(&mut rng, &key, b"payload").unwrap();
+            assert_eq!(decrypt(&key, &ct, &nonce).unwrap(), b"payload");
+            (*key.as_bytes(), ct, nonce)
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
 }
@@ -1,34 +1,62 @@
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::fs::{File, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
-use redb::{Database, ReadableTable, TableDefinition};
+use redb::{
+    Database, Durability, ReadTransaction, ReadableTable, TableDefinition, TransactionError,
+    WriteTransaction,
+};
 use tokio::time;
 use tracing::{debug, info, warn};
 
-use super::audit::{AuditEvent, AuditQuery};
+use super::audit::{AuditCounters, AuditEvent, AuditIpMode, AuditQuery};
 use super::crypto::EncryptionKey;
-use super::model::{SecretMeta, SecretRecord};
-
-const SECRETS: TableDefinition<&str, &[u8]> = TableDefinition::new("secrets");
+use super::limiter::{default_permits, DecryptLimiter};
+use super::model::{
+    expiry_after, AnnotationsPatch, ConditionalDelete, DeleteCriteria, LegacySecretRecord,
+    NewSecret, Precondition, PruneReason, PruneScope, PutConflict, PutReport, ReadNotifyThrottle,
+    SecretKind, SecretMeta, SecretRecord, SecretStatus, WritingKey,
+};
+
+pub(crate) const SECRETS: TableDefinition<&str, &[u8]> = TableDefinition::new("secrets");
+
+/// Error message for a decrypted value that is not UTF-8. Every write path
+/// takes a `&str`, so this only appears for records written outside the API.
+pub const VALUE_NOT_UTF8: &str = "secret value is not valid UTF-8";
 const AUDIT_LOG: TableDefinition<u64, &[u8]> = TableDefinition::new("audit_log");
 const COUNTERS: TableDefinition<&str, u64> = TableDefinition::new("counters");
+/// Store-wide settings that are not secrets, e.g. the sealed reader tag key.
+const META: TableDefinition<&str, &[u8]> = TableDefinition::new("meta");
+const READER_TAG_KEY: &str = "reader_tag_key";
 const AUDIT_SEQ_KEY: &str = "audit_seq";
+const REVISION_SEQ_KEY: &str = "secret_revision";
+
+/// Scheduled compaction is skipped unless at least this fraction of the
+/// file is free pages.
+const COMPACT_MIN_FREE_RATIO: f64 = 0.25;
 
-/// Marker byte for v2 record format (with key version tracking).
-/// Legacy records (v1) start with a bincode varint for Vec length (always >= 16
-/// for ChaCha20Poly1305 ciphertext), so 0x01 is unambiguous.
+/// Marker byte for v2 record format (with key version tracking), holding a
+/// [`LegacySecretRecord`]. Legacy records (v1) start with a bincode varint for
+/// Vec length (always >= 16 for ChaCha20Poly1305 ciphertext), so 0x01 is
+/// unambiguous.
 const RECORD_V2_MARKER: u8 = 0x01;
 
+/// Marker byte for v3 records: the key version, then the current
+/// [`SecretRecord`] layout. Written for every new or rewritten record.
+const RECORD_V3_MARKER: u8 = 0x02;
+
 /// Result of a secret retrieval.
 #[derive(Debug, PartialEq)]
 pub enum GetResult {
     /// Secret found and decrypted. Read counter was incremented.
-    /// Contains (value, webhook_url).
+    /// Contains (value, webhook_url if subscribed to `secret.read`).
     Value(String, Option<String>),
     /// Secret found, decrypted, and burned (final read with delete=true).
-    /// Contains (value, webhook_url).
+    /// Contains (value, webhook_url if subscribed to `secret.burned`).
     Burned(String, Option<String>),
     /// Secret exists but is sealed (delete=false, reads exhausted).
     Sealed,
@@ -36,23 +64,206 @@ pub enum GetResult {
     NotFound,
 }
 
+/// Take an exclusive lock on the sidecar `<path>.lock` so two sirr processes
+/// never share one database file. The holder's PID is written into the file
+/// for the error message a second instance reports.
+fn lock_data_file(path: &Path) -> Result<File> {
+    let mut lock_path = PathBuf::from(path).into_os_string();
+    lock_path.push(".lock");
+    let lock_path = PathBuf::from(lock_path);
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .with_context(|| format!("open lock file {}", lock_path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let mut holder = String::new();
+            let _ = file.read_to_string(&mut holder);
+            let holder = match holder.trim() {
+                "" => String::new(),
+                pid => format!(" (pid {pid})"),
+            };
+            anyhow::bail!(
+                "database {} is already in use by another sirr process{holder}; \
+                 give each instance its own SIRR_DATA_DIR",
+                path.display()
+            );
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(e).with_context(|| format!("lock {}", lock_path.display()));
+        }
+    }
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// How hard a write transaction's commit works to reach the disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StoreDurability {
+    /// Persisted before `commit` returns (default).
+    #[default]
+    Immediate,
+    /// Queued for persistence when `commit` returns; a crash shortly after
+    /// can lose the most recent writes.
+    Eventual,
+    /// Persisted only when a later durable commit, or a clean shutdown,
+    /// flushes it. A crash loses every write since then, and freed pages are
+    /// not reclaimed in between, so the file grows faster.
+    None,
+}
+
+impl StoreDurability {
+    fn redb(self) -> Durability {
+        match self {
+            Self::Immediate => Durability::Immediate,
+            Self::Eventual => Durability::Eventual,
+            Self::None => Durability::None,
+        }
+    }
+}
+
+impl std::str::FromStr for StoreDurability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "immediate" => Ok(Self::Immediate),
+            "eventual" => Ok(Self::Eventual),
+            "none" => Ok(Self::None),
+            other => Err(format!(
+                "unknown durability {other:?} (expected immediate, eventual or none)"
+            )),
+        }
+    }
+}
+
+/// The redb handle shared by every `Store` clone. Transactions hold the lock
+/// shared only while they begin; compaction takes it exclusively.
+pub(crate) struct SharedDb {
+    inner: RwLock<Database>,
+    path: PathBuf,
+    /// Applied to relaxed write transactions (see `Store::with_durability`).
+    durability: Mutex<StoreDurability>,
+}
+
+// Same signatures as `redb::Database`, so existing call sites are unchanged.
+#[allow(clippy::result_large_err)]
+impl SharedDb {
+    pub(crate) fn begin_read(&self) -> Result<ReadTransaction, TransactionError> {
+        self.inner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .begin_read()
+    }
+
+    /// Always `Immediate`: deletes, burns, rotation and everything else a
+    /// crash must not undo.
+    pub(crate) fn begin_write(&self) -> Result<WriteTransaction, TransactionError> {
+        let mut txn = self
+            .inner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .begin_write()?;
+        txn.set_durability(Durability::Immediate);
+        Ok(txn)
+    }
+
+    /// At the configured durability, for writes whose loss on a crash is
+    /// acceptable: plain puts and read-count bumps. A read that burns its
+    /// secret must switch back with `commit_read` before committing.
+    pub(crate) fn begin_write_relaxed(&self) -> Result<WriteTransaction, TransactionError> {
+        let mut txn = self.begin_write()?;
+        txn.set_durability(self.durability().redb());
+        Ok(txn)
+    }
+
+    pub(crate) fn durability(&self) -> StoreDurability {
+        *self.durability.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// File sizes around a compaction pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactReport {
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+/// Outcome of [`Store::check`].
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Records that decoded and decrypted cleanly.
+    pub ok: usize,
+    /// Expired records awaiting the sweep, not decrypted.
+    pub expired: usize,
+    /// Table key and error for every record that did not.
+    pub failed: Vec<(String, String)>,
+}
+
 /// Thread-safe handle to the redb store.
 #[derive(Clone)]
 pub struct Store {
-    pub(crate) db: Arc<Database>,
+    pub(crate) db: Arc<SharedDb>,
+    /// Shared by every clone so an online [`rotate`](Self::rotate) takes
+    /// effect everywhere at once.
+    key: Arc<RwLock<ActiveKey>>,
+    /// Key reader addresses are tagged and value fingerprints computed
+    /// under (see `crypto::reader_tag`, `crypto::fingerprint`). Persisted
+    /// sealed under the active key and resealed by `rotate`, so tags and
+    /// fingerprints stay the same across rotations.
+    reader_tag_key: Arc<EncryptionKey>,
+    /// Pending audit events when batching is enabled (see `with_audit_batch`).
+    audit_batch: Option<Arc<AuditBatch>>,
+    /// When set, only matching audit actions are recorded (see `with_audit_actions`).
+    audit_actions: Option<Arc<Vec<String>>>,
+    /// Per-action totals of every audit event passed to `record_audit`.
+    audit_counts: Arc<AuditCounters>,
+    /// How much of `source_ip` is stored (see `with_audit_ip_mode`).
+    audit_ip_mode: AuditIpMode,
+    /// Bounds concurrent decrypting reads (see `with_max_concurrent_decrypts`).
+    decrypt_limiter: DecryptLimiter,
+    /// Next nonce sequence number in deterministic mode (see
+    /// `with_deterministic_nonces`); `None` draws random nonces.
+    #[cfg(any(test, feature = "deterministic-nonce"))]
+    nonce_seq: Option<Arc<std::sync::atomic::AtomicU64>>,
+    /// Exclusive lock on `<db>.lock`, released when the last clone is dropped.
+    _lock: Arc<File>,
+}
+
+/// Key new values are encrypted under, plus the one it replaced so reads
+/// that raced an online rotation can still decrypt what they fetched.
+struct ActiveKey {
     key: Arc<EncryptionKey>,
-    key_version: u8,
+    version: u8,
+    previous: Option<Arc<EncryptionKey>>,
+}
+
+/// Buffer of audit events awaiting a single-transaction flush.
+struct AuditBatch {
+    max_events: usize,
+    pending: Mutex<Vec<AuditEvent>>,
 }
 
 impl Store {
     /// Open (or create) the database at `path`, using `key` for encryption.
+    /// New records are tagged with the highest key version already stored.
     pub fn open(path: &Path, key: EncryptionKey) -> Result<Self> {
-        Self::open_versioned(path, key, 1)
+        let store = Self::open_versioned(path, key, 1)?;
+        let version = store.max_key_version()?;
+        store.key.write().unwrap_or_else(|e| e.into_inner()).version = version;
+        Ok(store)
     }
 
     /// Open (or create) the database at `path`, using `key` with an explicit version tag.
     /// The `key_version` is stored alongside each encrypted record to support key rotation.
     pub fn open_versioned(path: &Path, key: EncryptionKey, key_version: u8) -> Result<Self> {
+        let lock = lock_data_file(path)?;
         let db = Database::create(path).context("open redb database")?;
 
         // Ensure all tables exist.
@@ -60,7 +271,13 @@ impl Store {
         write_txn.open_table(SECRETS)?;
         write_txn.open_table(AUDIT_LOG)?;
         write_txn.open_table(COUNTERS)?;
+        write_txn.open_table(META)?;
         write_txn.open_table(super::webhooks::WEBHOOKS)?;
+        write_txn.open_table(super::webhooks::DEAD_LETTERS)?;
+        write_txn.open_table(super::webhooks::PENDING_RETRIES)?;
+        super::webhooks::index_pending_retries(&write_txn)?;
+        write_txn.open_table(super::idempotency::IDEMPOTENCY)?;
+        write_txn.open_table(super::alias::ALIASES)?;
         // Legacy api_keys table: kept so existing databases don't lose the table on open.
         const LEGACY_API_KEYS: TableDefinition<&str, &[u8]> = TableDefinition::new("api_keys");
         write_txn.open_table(LEGACY_API_KEYS)?;
@@ -89,11 +306,184 @@ impl Store {
             write_txn.commit()?;
         }
 
-        Ok(Self {
-            db: Arc::new(db),
-            key: Arc::new(key),
+        let reader_tag_key = load_reader_tag_key(&db, &key)?;
+        Ok(Self::from_db(
+            db,
+            path,
+            lock,
+            key,
             key_version,
-        })
+            reader_tag_key,
+        ))
+    }
+
+    /// Open the existing database at `path` for offline inspection, without
+    /// creating the file or any table. Fails if there is no database there.
+    pub fn open_existing(path: &Path, key: EncryptionKey) -> Result<Self> {
+        if !path.is_file() {
+            anyhow::bail!("no database at {}", path.display());
+        }
+        let lock = lock_data_file(path)?;
+        let db = Database::open(path).context("open redb database")?;
+        // Inspection never reads secrets, so no reader is ever tagged.
+        let reader_tag_key = super::crypto::generate_key();
+        Ok(Self::from_db(db, path, lock, key, 1, reader_tag_key))
+    }
+
+    fn from_db(
+        db: Database,
+        path: &Path,
+        lock: File,
+        key: EncryptionKey,
+        key_version: u8,
+        reader_tag_key: EncryptionKey,
+    ) -> Self {
+        Self {
+            db: Arc::new(SharedDb {
+                inner: RwLock::new(db),
+                path: path.to_path_buf(),
+                durability: Mutex::default(),
+            }),
+            key: Arc::new(RwLock::new(ActiveKey {
+                key: Arc::new(key),
+                version: key_version,
+                previous: None,
+            })),
+            reader_tag_key: Arc::new(reader_tag_key),
+            audit_batch: None,
+            audit_actions: None,
+            audit_counts: Arc::default(),
+            audit_ip_mode: AuditIpMode::default(),
+            decrypt_limiter: DecryptLimiter::new(default_permits()),
+            #[cfg(any(test, feature = "deterministic-nonce"))]
+            nonce_seq: None,
+            _lock: Arc::new(lock),
+        }
+    }
+
+    /// Allow at most `permits` decrypting reads at once; further reads wait
+    /// for a free slot in [`decrypt_limiter`](Self::decrypt_limiter).
+    /// Defaults to the number of CPUs.
+    pub fn with_max_concurrent_decrypts(mut self, permits: usize) -> Self {
+        self.decrypt_limiter = DecryptLimiter::new(permits);
+        self
+    }
+
+    /// Semaphore async callers await before a decrypting read (`get`,
+    /// `get_many`, `peek`, org reads). The store itself never waits on it,
+    /// so no permit is ever awaited with the write lock held.
+    pub fn decrypt_limiter(&self) -> &DecryptLimiter {
+        &self.decrypt_limiter
+    }
+
+    /// Derive nonces from a counter starting at `start` instead of the OS
+    /// RNG, so the same writes in the same order produce the same ciphertext.
+    /// Test builds only: the counter restarts with the process, and reusing
+    /// a nonce under one key breaks ChaCha20-Poly1305. Random nonces are the
+    /// production default.
+    #[cfg(any(test, feature = "deterministic-nonce"))]
+    pub fn with_deterministic_nonces(mut self, start: u64) -> Self {
+        warn!("deterministic nonces enabled; never use this store in production");
+        self.nonce_seq = Some(Arc::new(std::sync::atomic::AtomicU64::new(start)));
+        self
+    }
+
+    /// Encrypt under `key` with a random nonce, or the next sequenced one in
+    /// deterministic mode.
+    fn encrypt(&self, key: &EncryptionKey, plaintext: &[u8]) -> Result<(Vec<u8>, [u8; 12])> {
+        #[cfg(any(test, feature = "deterministic-nonce"))]
+        if let Some(seq) = &self.nonce_seq {
+            let seq = seq.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return super::crypto::encrypt_sequenced(key, plaintext, seq);
+        }
+        super::crypto::encrypt(key, plaintext)
+    }
+
+    /// Current key and the version new records are tagged with. Writers
+    /// take this after opening their write transaction, so a rotation
+    /// cannot swap the key between encryption and commit.
+    fn active_key(&self) -> (Arc<EncryptionKey>, u8) {
+        let active = self.key.read().unwrap_or_else(|e| e.into_inner());
+        (active.key.clone(), active.version)
+    }
+
+    /// Key for a record tagged `key_version`: the replaced key for records
+    /// a rotation has not yet rewritten, otherwise the current one.
+    fn key_for(&self, key_version: u8) -> Arc<EncryptionKey> {
+        let active = self.key.read().unwrap_or_else(|e| e.into_inner());
+        match &active.previous {
+            Some(previous) if key_version != active.version => previous.clone(),
+            _ => active.key.clone(),
+        }
+    }
+
+    /// Decrypt a record's value.
+    fn decrypt_value(&self, record: &SecretRecord, key_version: u8) -> Result<String> {
+        let key = self.key_for(key_version);
+        let plaintext = super::crypto::decrypt(&key, &record.value_encrypted, &record.nonce)
+            .context("decrypt value")?;
+        String::from_utf8(plaintext).context(VALUE_NOT_UTF8)
+    }
+
+    /// Record only the listed audit actions. Each entry matches either a full
+    /// action (`secret.read`) or its verb (`read` matches `secret.read`).
+    pub fn with_audit_actions(mut self, actions: Vec<String>) -> Self {
+        self.audit_actions = Some(Arc::new(actions));
+        self
+    }
+
+    /// Commit durability for plain puts and read-count bumps, shared by all
+    /// clones. Deletes, burns, rotation and the rest always commit
+    /// `Immediate`, so a crash can lose a recent secret but never bring back
+    /// one that was removed. Defaults to [`StoreDurability::Immediate`].
+    pub fn with_durability(self, durability: StoreDurability) -> Self {
+        *self.db.durability.lock().unwrap_or_else(|e| e.into_inner()) = durability;
+        self
+    }
+
+    /// Truncate or drop client IPs before audit events are stored.
+    pub fn with_audit_ip_mode(mut self, mode: AuditIpMode) -> Self {
+        self.audit_ip_mode = mode;
+        self
+    }
+
+    /// True if `action` passes the `with_audit_actions` filter.
+    fn audit_enabled(&self, action: &str) -> bool {
+        let Some(allowed) = &self.audit_actions else {
+            return true;
+        };
+        let verb = action.rsplit('.').next().unwrap_or(action);
+        allowed.iter().any(|a| a == action || a == verb)
+    }
+
+    /// Buffer audit events and write them in batches of up to `max_events`
+    /// per transaction. Buffered events are lost if the process crashes
+    /// before a flush; pair with `spawn_audit_flush` to bound that window.
+    pub fn with_audit_batch(mut self, max_events: usize) -> Self {
+        self.audit_batch = Some(Arc::new(AuditBatch {
+            max_events: max_events.max(1),
+            pending: Mutex::new(Vec::new()),
+        }));
+        self
+    }
+
+    /// Cheap liveness probe for the database: opens a read transaction and
+    /// the secrets table. Used by the readiness endpoint.
+    pub fn ping(&self) -> Result<()> {
+        let read_txn = self.db.begin_read()?;
+        read_txn.open_table(SECRETS)?;
+        Ok(())
+    }
+
+    /// Make every earlier commit durable, whatever [`with_durability`]
+    /// chose, with an empty `Immediate` commit. Called on shutdown.
+    ///
+    /// [`with_durability`]: Self::with_durability
+    pub fn sync(&self) -> Result<()> {
+        let mut txn = self.db.begin_write()?;
+        txn.set_durability(Durability::Immediate);
+        txn.commit()?;
+        Ok(())
     }
 
     fn now() -> i64 {
@@ -103,199 +493,467 @@ impl Store {
             .as_secs() as i64
     }
 
-    /// Insert or overwrite a secret.
-    pub fn put(
+    /// Insert or overwrite a secret. Fails if its key is an alias; see
+    /// [`put_many`](Self::put_many).
+    #[tracing::instrument(name = "store.put", level = "debug", skip_all, fields(key = %secret.key))]
+    pub fn put(&self, secret: &NewSecret) -> Result<()> {
+        self.put_many(std::slice::from_ref(secret), false, None)?
+            .map(drop)
+            .map_err(Into::into)
+    }
+
+    /// Insert or overwrite every secret in `secrets` in one transaction:
+    /// either all are stored or none are. Returns, per secret and in order,
+    /// whether it replaced an existing one, or the first conflict: a key
+    /// that is an alias, when `create_only` a key that already holds a live
+    /// secret, or new keys taking the public bucket past `max_secrets`.
+    #[tracing::instrument(name = "store.put_many", level = "debug", skip_all, fields(count = secrets.len()))]
+    pub fn put_many(
         &self,
-        secret_key: &str,
-        value: &str,
-        ttl_seconds: Option<u64>,
-        max_reads: Option<u32>,
-        delete: bool,
-        webhook_url: Option<String>,
-    ) -> Result<()> {
+        secrets: &[NewSecret],
+        create_only: bool,
+        max_secrets: Option<usize>,
+    ) -> Result<Result<PutReport, PutConflict>> {
         let now = Self::now();
-        // Cap ttl before casting to avoid u64→i64 wrapping (u64::MAX as i64 == -1).
-        // i64::MAX seconds is ~292 years — well beyond any practical TTL.
-        let expires_at = ttl_seconds
-            .map(|ttl| ttl.min((i64::MAX - now) as u64) as i64)
-            .map(|ttl| now + ttl);
+        let write_txn = self.db.begin_write_relaxed()?;
+        let mut replaced = Vec::with_capacity(secrets.len());
+        let mut stored = None;
+        {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let aliases = write_txn.open_table(super::alias::ALIASES)?;
+            for s in secrets {
+                if let Some(target) = aliases.get(s.key.as_str())? {
+                    // Dropping the uncommitted txn discards earlier inserts.
+                    return Ok(Err(PutConflict::Aliased {
+                        key: s.key.clone(),
+                        target: target.value().to_owned(),
+                    }));
+                }
+                if create_only {
+                    if let Some(existing) = table.get(s.key.as_str())? {
+                        if !decode(existing.value())?.0.is_expired(now) {
+                            return Ok(Err(PutConflict::Exists(s.key.clone())));
+                        }
+                    }
+                }
+                let bytes = self.encode_new(next_revision(&write_txn)?, s, now)?;
+                replaced.push(table.insert(s.key.as_str(), bytes.as_slice())?.is_some());
+            }
+            if let Some(max) = max_secrets {
+                let count = count_public(&table)?;
+                // Overwrites alone never count as growth.
+                if count > max && replaced.contains(&false) {
+                    return Ok(Err(PutConflict::Full { max }));
+                }
+                stored = Some(count);
+            }
+        }
+        self.commit_mutation(write_txn, secrets.len())?;
 
-        let (value_encrypted, nonce) =
-            super::crypto::encrypt(&self.key, value.as_bytes()).context("encrypt value")?;
+        debug!(count = secrets.len(), "stored secrets");
+        Ok(Ok(PutReport { replaced, stored }))
+    }
+
+    /// Encrypt `secret`'s value and encode it as a fresh, unread
+    /// public-bucket record.
+    fn encode_new(&self, revision: u64, secret: &NewSecret, now: i64) -> Result<Vec<u8>> {
+        let (key, key_version) = self.active_key();
+        let (value_encrypted, nonce) = self
+            .encrypt(&key, secret.value.as_bytes())
+            .context("encrypt value")?;
 
         let record = SecretRecord {
             value_encrypted,
             nonce,
             created_at: now,
-            expires_at,
-            max_reads,
+            updated_at: now,
+            expires_at: secret.ttl_seconds.map(|ttl| expiry_after(now, ttl)),
+            max_reads: secret.max_reads,
             read_count: 0,
-            delete,
-            webhook_url,
+            delete: secret.delete,
+            webhook_url: secret.webhook_url.clone(),
+            webhook_events: secret.webhook_events.clone(),
             owner_id: None,
             org_id: None,
             allowed_keys: None,
+            created_by: secret.created_by.clone(),
+            annotations: secret.annotations.clone(),
+            kind: secret.kind,
+            read_notify: secret.read_notify,
+            read_notified_at: None,
+            reader_tags: Vec::new(),
+            last_accessed_at: None,
+            value_fingerprint: Some(super::crypto::fingerprint(
+                &self.reader_tag_key,
+                secret.value.as_bytes(),
+            )),
+            revision,
+            created_by_key: None,
         };
-
-        let bytes = encode(&record, self.key_version)?;
-        let write_txn = self.db.begin_write()?;
-        {
-            let mut table = write_txn.open_table(SECRETS)?;
-            table.insert(secret_key, bytes.as_slice())?;
-        }
-        write_txn.commit()?;
-
-        debug!(key = %secret_key, "stored secret");
-        Ok(())
+        encode(&record, key_version)
     }
 
     /// Retrieve a secret's value, incrementing its read counter.
     /// Returns `GetResult::NotFound` if the key doesn't exist or has expired / burned.
     /// Returns `GetResult::Sealed` if the secret exists but reads are exhausted (delete=false).
     /// Returns `GetResult::Value(value)` on success.
+    /// Aliases (see [`put_alias`](Self::put_alias)) read their canonical
+    /// secret and share its read count.
+    #[tracing::instrument(name = "store.get", level = "debug", skip_all, fields(key = %secret_key))]
     pub fn get(&self, secret_key: &str) -> Result<GetResult> {
-        self.get_by_table_key(secret_key)
+        self.get_public(secret_key, None)
+    }
+
+    /// [`get`](Self::get), counting `reader` (a client address) towards the
+    /// secret's distinct readers.
+    pub fn get_from(&self, secret_key: &str, reader: &str) -> Result<GetResult> {
+        self.get_public(secret_key, Some(reader))
+    }
+
+    /// Get-and-increment for a public-bucket key or alias.
+    fn get_public(&self, secret_key: &str, reader: Option<&str>) -> Result<GetResult> {
+        let now = Self::now();
+        let reader = reader.map(|r| super::crypto::reader_tag(&self.reader_tag_key, r));
+        let write_txn = self.db.begin_write_relaxed()?;
+        let result = {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+            let key = super::alias::resolve(&table, &aliases, secret_key)?;
+            self.read_in_table(&mut table, Some(&mut aliases), &key, now, reader)?
+        };
+        commit_read(write_txn, [&result])?;
+        Ok(result)
     }
 
     /// Internal helper that performs the get-and-increment logic for any table key.
     /// Both public-bucket `get()` and org-scoped `get_org_secret()` delegate here.
-    fn get_by_table_key(&self, table_key: &str) -> Result<GetResult> {
+    fn get_by_table_key(&self, table_key: &str, reader: Option<&str>) -> Result<GetResult> {
         let now = Self::now();
+        let reader = reader.map(|r| super::crypto::reader_tag(&self.reader_tag_key, r));
 
         // We need a write transaction to atomically increment read_count.
-        let write_txn = self.db.begin_write()?;
+        let write_txn = self.db.begin_write_relaxed()?;
         let result = {
             let mut table = write_txn.open_table(SECRETS)?;
+            self.read_in_table(&mut table, None, table_key, now, reader)?
+        };
+        commit_read(write_txn, [&result])?;
+        Ok(result)
+    }
 
-            // Read the raw bytes and immediately clone them so the AccessGuard
-            // (which borrows `table`) is dropped before any mutation.
-            let raw_bytes: Option<Vec<u8>> =
-                table.get(table_key)?.map(|guard| guard.value().to_vec());
+    /// Read several public-bucket secrets in one write transaction, applying
+    /// burn/seal rules to each. Results are in `secret_keys` order.
+    pub fn get_many(&self, secret_keys: &[String], reader: &str) -> Result<Vec<GetResult>> {
+        let now = Self::now();
+        let reader = super::crypto::reader_tag(&self.reader_tag_key, reader);
+        let write_txn = self.db.begin_write_relaxed()?;
+        let results = {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+            secret_keys
+                .iter()
+                .map(|key| {
+                    let key = super::alias::resolve(&table, &aliases, key)?;
+                    self.read_in_table(&mut table, Some(&mut aliases), &key, now, Some(reader))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        commit_read(write_txn, &results)?;
+        Ok(results)
+    }
 
-            match raw_bytes {
-                None => GetResult::NotFound,
-                Some(bytes) => {
-                    let (mut record, record_key_version) = decode(&bytes)?;
+    /// Get-and-increment for one key inside an open write transaction.
+    /// `aliases` is the public bucket's alias table, cleaned up when the
+    /// key is removed; org-scoped keys have no aliases.
+    fn read_in_table(
+        &self,
+        table: &mut redb::Table<&str, &[u8]>,
+        aliases: Option<&mut redb::Table<&str, &str>>,
+        table_key: &str,
+        now: i64,
+        reader: Option<u64>,
+    ) -> Result<GetResult> {
+        // Read the raw bytes and immediately clone them so the AccessGuard
+        // (which borrows `table`) is dropped before any mutation.
+        let raw_bytes: Option<Vec<u8>> = table.get(table_key)?.map(|guard| guard.value().to_vec());
 
-                    if record.is_expired(now) {
-                        table.remove(table_key)?;
-                        debug!(key = %table_key, "lazy-evicted expired secret");
-                        GetResult::NotFound
-                    } else if record.is_sealed() {
-                        GetResult::Sealed
-                    } else {
-                        record.read_count += 1;
-
-                        let plaintext = super::crypto::decrypt(
-                            &self.key,
-                            &record.value_encrypted,
-                            &record.nonce,
-                        )
-                        .context("decrypt value")?;
-
-                        let value = String::from_utf8(plaintext)
-                            .context("secret value is not valid UTF-8")?;
-
-                        let webhook_url = record.webhook_url.clone();
-                        if record.is_burned() {
-                            table.remove(table_key)?;
-                            debug!(key = %table_key, "burned after final read");
-                            GetResult::Burned(value, webhook_url)
-                        } else {
-                            let updated = encode(&record, record_key_version)?;
-                            table.insert(table_key, updated.as_slice())?;
-                            GetResult::Value(value, webhook_url)
-                        }
-                    }
-                }
-            }
+        let Some(bytes) = raw_bytes else {
+            return Ok(GetResult::NotFound);
         };
-        write_txn.commit()?;
-        Ok(result)
+        let (mut record, record_key_version) = decode(&bytes)?;
+
+        if record.is_expired(now) {
+            table.remove(table_key)?;
+            if let Some(aliases) = aliases {
+                super::alias::remove_aliases_to(aliases, [table_key])?;
+            }
+            debug!(key = %table_key, "lazy-evicted expired secret");
+            return Ok(GetResult::NotFound);
+        }
+        if record.is_sealed() {
+            return Ok(GetResult::Sealed);
+        }
+        record.read_count += 1;
+        record.last_accessed_at = Some(now);
+        if let Some(tag) = reader {
+            record.note_reader(tag);
+        }
+
+        let value = self.decrypt_value(&record, record_key_version)?;
+
+        if record.is_burned() {
+            table.remove(table_key)?;
+            if let Some(aliases) = aliases {
+                super::alias::remove_aliases_to(aliases, [table_key])?;
+            }
+            debug!(key = %table_key, "burned after final read");
+            Ok(GetResult::Burned(
+                value,
+                record.webhook_url_for("secret.burned"),
+            ))
+        } else {
+            let webhook_url = record
+                .webhook_url_for("secret.read")
+                .filter(|_| record.take_read_notification(now));
+            let updated = encode(&record, record_key_version)?;
+            table.insert(table_key, updated.as_slice())?;
+            Ok(GetResult::Value(value, webhook_url))
+        }
     }
 
     /// Delete a secret by key. Returns true if it existed.
+    #[tracing::instrument(name = "store.delete", level = "debug", skip_all, fields(key = %secret_key))]
     pub fn delete(&self, secret_key: &str) -> Result<bool> {
         let write_txn = self.db.begin_write()?;
         let existed = {
             let mut table = write_txn.open_table(SECRETS)?;
             // Clone the guard value immediately so the borrow ends before commit.
             let existed = table.remove(secret_key)?.is_some();
+            let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+            super::alias::remove_aliases_to(&mut aliases, [secret_key])?;
             existed
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(existed))?;
         Ok(existed)
     }
 
+    /// Delete a secret, returning its metadata as it was just before
+    /// removal. The record is read and removed in one write transaction,
+    /// which is aborted if the record cannot be decoded.
+    pub fn delete_with_meta(&self, secret_key: &str) -> Result<Option<SecretMeta>> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let bytes = table
+                .remove(secret_key)?
+                .map(|guard| guard.value().to_vec());
+            // Decode before committing: an error here drops the uncommitted
+            // transaction, so the secret is not gone when we report failure.
+            let meta = bytes
+                .map(|bytes| decode(&bytes).map(|(record, _)| record.meta(secret_key)))
+                .transpose()?;
+            let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+            super::alias::remove_aliases_to(&mut aliases, [secret_key])?;
+            meta
+        };
+        self.commit_mutation(write_txn, usize::from(removed.is_some()))?;
+        Ok(removed)
+    }
+
+    /// Delete a secret only if `precondition` holds for its current
+    /// metadata, evaluated in the same write transaction as the removal so a
+    /// concurrent patch cannot slip in between. An expired secret counts as
+    /// not found.
+    pub fn delete_if(
+        &self,
+        secret_key: &str,
+        precondition: &Precondition,
+    ) -> Result<ConditionalDelete> {
+        let now = Self::now();
+        let write_txn = self.db.begin_write()?;
+        let outcome = {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let raw = table.get(secret_key)?.map(|guard| guard.value().to_vec());
+            match raw {
+                None => ConditionalDelete::NotFound,
+                Some(bytes) => {
+                    let (record, _) = decode(&bytes)?;
+                    let meta = record.meta(secret_key);
+                    if record.is_expired(now) {
+                        ConditionalDelete::NotFound
+                    } else if !precondition.holds(&meta) {
+                        ConditionalDelete::PreconditionFailed
+                    } else {
+                        table.remove(secret_key)?;
+                        let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+                        super::alias::remove_aliases_to(&mut aliases, [secret_key])?;
+                        ConditionalDelete::Deleted(Box::new(meta))
+                    }
+                }
+            }
+        };
+        let changed = usize::from(matches!(outcome, ConditionalDelete::Deleted(_)));
+        self.commit_mutation(write_txn, changed)?;
+        Ok(outcome)
+    }
+
     /// List metadata for all non-expired secrets.
+    #[tracing::instrument(name = "store.list", level = "debug", skip_all, fields(rows = tracing::field::Empty))]
     pub fn list(&self) -> Result<Vec<SecretMeta>> {
+        let metas: Vec<SecretMeta> = self
+            .list_with_status()?
+            .into_iter()
+            .filter(|(_, status)| *status != SecretStatus::ExpiredPendingSweep)
+            .map(|(meta, _)| meta)
+            .collect();
+        tracing::Span::current().record("rows", metas.len());
+        Ok(metas)
+    }
+
+    /// List metadata and lifecycle status for every stored public secret,
+    /// including TTL-expired ones the sweep hasn't removed yet.
+    pub fn list_with_status(&self) -> Result<Vec<(SecretMeta, SecretStatus)>> {
         let now = Self::now();
         let read_txn = self.db.begin_read()?;
         let table = read_txn.open_table(SECRETS)?;
 
-        let mut metas = Vec::new();
+        let mut out = Vec::new();
         for item in table.iter()? {
             let (k, v) = item?;
             let (record, _kv) = decode(v.value())?;
-            if !record.is_expired(now) {
-                metas.push(SecretMeta {
-                    key: k.value().to_owned(),
-                    created_at: record.created_at,
-                    expires_at: record.expires_at,
-                    max_reads: record.max_reads,
-                    read_count: record.read_count,
-                    delete: record.delete,
-                    owner_id: record.owner_id.clone(),
-                    org_id: record.org_id.clone(),
-                });
-            }
+            out.push((record.meta(k.value()), record.status(now)));
         }
-        Ok(metas)
+        Ok(out)
+    }
+
+    /// Number of secrets in the public bucket, including TTL-expired ones
+    /// the sweep hasn't removed yet. Only keys are read.
+    pub fn count_public(&self) -> Result<usize> {
+        let read_txn = self.db.begin_read()?;
+        count_public(&read_txn.open_table(SECRETS)?)
     }
 
-    /// Remove all expired secrets. Returns the names of removed keys.
+    /// Remove all expired and burned secrets. Returns the names of removed keys.
     pub fn prune(&self) -> Result<Vec<String>> {
+        Ok(self
+            .prune_by(PruneScope::default())?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect())
+    }
+
+    /// Remove the secrets `scope` admits, returning each removed key with
+    /// the reason it was removable.
+    #[tracing::instrument(name = "store.prune", level = "debug", skip_all, fields(rows = tracing::field::Empty))]
+    pub fn prune_by(&self, scope: PruneScope) -> Result<Vec<(String, PruneReason)>> {
         let now = Self::now();
 
-        // Collect expired keys in a read pass first.
-        let expired_keys: Vec<String> = {
+        // Collect matching keys in a read pass first.
+        let pruned: Vec<(String, PruneReason)> = {
             let read_txn = self.db.begin_read()?;
             let table = read_txn.open_table(SECRETS)?;
             let mut keys = Vec::new();
             for item in table.iter()? {
                 let (k, v) = item?;
                 let (record, _kv) = decode(v.value())?;
-                if record.is_expired(now) || record.is_burned() {
-                    keys.push(k.value().to_owned());
+                if let Some(reason) = record.prune_reason(now).filter(|r| scope.admits(*r)) {
+                    keys.push((k.value().to_owned(), reason));
                 }
             }
             keys
         };
 
-        if expired_keys.is_empty() {
+        if pruned.is_empty() {
             return Ok(vec![]);
         }
 
         let write_txn = self.db.begin_write()?;
         {
             let mut table = write_txn.open_table(SECRETS)?;
-            for key in &expired_keys {
+            for (key, _) in &pruned {
                 table.remove(key.as_str())?;
             }
+            let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+            super::alias::remove_aliases_to(
+                &mut aliases,
+                pruned.iter().map(|(key, _)| key.as_str()),
+            )?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, pruned.len())?;
 
-        let removed = expired_keys.len();
+        let removed = pruned.len();
+        tracing::Span::current().record("rows", removed);
         if removed > 0 {
-            info!(removed, "pruned expired secrets");
+            info!(removed, ?scope, "pruned secrets");
+        }
+        Ok(pruned)
+    }
+
+    /// Remove every public secret matching `criteria` in one write
+    /// transaction, returning the removed keys. Refuses an empty filter.
+    pub fn delete_matching(&self, criteria: &DeleteCriteria) -> Result<Vec<String>> {
+        if criteria.is_empty() {
+            anyhow::bail!("refusing to delete with no criteria");
+        }
+
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let mut keys = Vec::new();
+            for item in table.iter()? {
+                let (k, v) = item?;
+                let (record, _kv) = decode(v.value())?;
+                if record.org_id.is_none() && criteria.matches(&record) {
+                    keys.push(k.value().to_owned());
+                }
+            }
+            for key in &keys {
+                table.remove(key.as_str())?;
+            }
+            let mut aliases = write_txn.open_table(super::alias::ALIASES)?;
+            super::alias::remove_aliases_to(&mut aliases, keys.iter().map(String::as_str))?;
+            keys
+        };
+        self.commit_mutation(write_txn, removed.len())?;
+
+        if !removed.is_empty() {
+            info!(removed = removed.len(), "bulk-deleted secrets");
         }
-        Ok(expired_keys)
+        Ok(removed)
+    }
+
+    /// Delete every secret, public and org-scoped, and every alias, in one
+    /// transaction. Returns how many secrets were removed.
+    pub fn wipe_secrets(&self) -> Result<usize> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(SECRETS)?;
+            let mut removed = 0;
+            table.retain(|_, _| {
+                removed += 1;
+                false
+            })?;
+            write_txn
+                .open_table(super::alias::ALIASES)?
+                .retain(|_, _| false)?;
+            removed
+        };
+        write_txn.commit()?;
+        warn!(removed, "wiped all secrets");
+        Ok(removed)
     }
 
     /// Retrieve metadata for a secret without incrementing read_count.
     /// Returns (meta, is_sealed). Returns None if not found or TTL-expired.
+    /// An alias resolves to its canonical key, which the metadata names.
     pub fn head(&self, secret_key: &str) -> Result<Option<(SecretMeta, bool)>> {
-        self.head_by_table_key(secret_key, secret_key)
+        let key = {
+            let read_txn = self.db.begin_read()?;
+            let table = read_txn.open_table(SECRETS)?;
+            let aliases = read_txn.open_table(super::alias::ALIASES)?;
+            super::alias::resolve(&table, &aliases, secret_key)?
+        };
+        self.head_by_table_key(&key, &key)
     }
 
     /// Internal helper for head logic. `table_key` is the key in SECRETS,
@@ -319,33 +977,41 @@ impl Store {
                     return Ok(None);
                 }
                 let sealed = record.is_sealed();
-                Ok(Some((
-                    SecretMeta {
-                        key: display_key.to_owned(),
-                        created_at: record.created_at,
-                        expires_at: record.expires_at,
-                        max_reads: record.max_reads,
-                        read_count: record.read_count,
-                        delete: record.delete,
-                        owner_id: record.owner_id.clone(),
-                        org_id: record.org_id.clone(),
-                    },
-                    sealed,
-                )))
+                Ok(Some((record.meta(display_key), sealed)))
             }
         }
     }
 
+    /// Decrypt a secret without counting a read or applying burn/seal rules.
+    /// Returns `Ok(None)` only if the key doesn't exist or has TTL-expired.
+    /// Privileged: callers must restrict this to instance admins.
+    #[tracing::instrument(name = "store.peek", level = "debug", skip_all, fields(key = %secret_key))]
+    pub fn peek(&self, secret_key: &str) -> Result<Option<String>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SECRETS)?;
+        let Some(guard) = table.get(secret_key)? else {
+            return Ok(None);
+        };
+        let (record, key_version) = decode(guard.value())?;
+        if record.is_expired(Self::now()) {
+            return Ok(None);
+        }
+        Ok(Some(self.decrypt_value(&record, key_version)?))
+    }
+
     /// Update an existing secret (only if delete=false).
     /// Resets read_count to 0. Returns updated metadata.
     /// Returns Err if the secret has delete=true.
     /// Returns Ok(None) if not found or TTL-expired.
+    #[tracing::instrument(name = "store.patch", level = "debug", skip_all, fields(key = %secret_key))]
     pub fn patch(
         &self,
         secret_key: &str,
         new_value: Option<&str>,
         new_max_reads: Option<u32>,
         new_ttl_seconds: Option<u64>,
+        new_annotations: AnnotationsPatch,
+        new_delete: Option<bool>,
     ) -> Result<Option<SecretMeta>> {
         let now = Self::now();
 
@@ -359,15 +1025,15 @@ impl Store {
             match raw_bytes {
                 None => Ok(None),
                 Some(bytes) => {
-                    let (mut record, record_key_version) = decode(&bytes)?;
+                    let (mut record, mut record_key_version) = decode(&bytes)?;
 
                     if record.is_expired(now) {
                         table.remove(secret_key)?;
                         return Ok(None);
                     }
 
-                    if record.delete {
-                        anyhow::bail!("cannot patch a secret with delete=true");
+                    if let Some(conflict) = record.patch_conflict(new_delete) {
+                        anyhow::bail!(conflict);
                     }
 
                     // Sealed secrets have exhausted their read limit and are immutable.
@@ -377,11 +1043,22 @@ impl Store {
                         anyhow::bail!("sealed: secret read limit exhausted");
                     }
 
+                    if let Some(delete) = new_delete {
+                        record.delete = delete;
+                    }
+
                     if let Some(val) = new_value {
-                        let (encrypted, nonce) = super::crypto::encrypt(&self.key, val.as_bytes())
+                        let (key, key_version) = self.active_key();
+                        let (encrypted, nonce) = self
+                            .encrypt(&key, val.as_bytes())
                             .context("encrypt patched value")?;
                         record.value_encrypted = encrypted;
                         record.nonce = nonce;
+                        record.value_fingerprint = Some(super::crypto::fingerprint(
+                            &self.reader_tag_key,
+                            val.as_bytes(),
+                        ));
+                        record_key_version = key_version;
                     }
 
                     if let Some(max) = new_max_reads {
@@ -389,39 +1066,82 @@ impl Store {
                     }
 
                     if let Some(ttl) = new_ttl_seconds {
-                        record.expires_at = Some(now + ttl.min((i64::MAX - now) as u64) as i64);
+                        record.expires_at = Some(expiry_after(now, ttl));
+                    }
+
+                    if !new_annotations.is_empty() {
+                        record.annotations = new_annotations
+                            .apply(&record.annotations)
+                            .map_err(|reason| anyhow::anyhow!("invalid annotations: {reason}"))?;
                     }
 
                     record.read_count = 0;
+                    record.updated_at = now;
+                    record.revision = next_revision(&write_txn)?;
 
                     let updated = encode(&record, record_key_version)?;
                     table.insert(secret_key, updated.as_slice())?;
 
-                    Ok(Some(SecretMeta {
-                        key: secret_key.to_owned(),
-                        created_at: record.created_at,
-                        expires_at: record.expires_at,
-                        max_reads: record.max_reads,
-                        read_count: 0,
-                        delete: record.delete,
-                        owner_id: record.owner_id.clone(),
-                        org_id: record.org_id.clone(),
-                    }))
+                    Ok(Some(record.meta(secret_key)))
                 }
             }
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(matches!(result, Ok(Some(_)))))?;
         result
     }
 
-    // ── Org-scoped secret methods ───────────────────────────────────────
+    /// Push a secret's expiry to `ttl_seconds` from now. Only the metadata
+    /// is rewritten: the ciphertext is carried over untouched, and the read
+    /// counter and `updated_at` are left alone.
+    pub fn touch(&self, secret_key: &str, ttl_seconds: u64) -> Result<Option<SecretMeta>> {
+        let now = Self::now();
 
-    /// Build the compound table key for an org-scoped secret: "{org_id}:{key}".
-    fn org_secret_key(org_id: &str, key: &str) -> String {
-        format!("{org_id}:{key}")
+        let write_txn = self.db.begin_write()?;
+        let result = {
+            let mut table = write_txn.open_table(SECRETS)?;
+
+            let raw_bytes: Option<Vec<u8>> =
+                table.get(secret_key)?.map(|guard| guard.value().to_vec());
+
+            match raw_bytes {
+                None => Ok(None),
+                Some(bytes) => {
+                    let (mut record, record_key_version) = decode(&bytes)?;
+
+                    if record.is_expired(now) {
+                        table.remove(secret_key)?;
+                        return Ok(None);
+                    }
+
+                    // Keeping a consumed secret alive would only delay its sweep.
+                    if record.is_sealed() {
+                        anyhow::bail!("sealed: secret read limit exhausted");
+                    }
+
+                    record.expires_at = Some(expiry_after(now, ttl_seconds));
+                    record.revision = next_revision(&write_txn)?;
+
+                    let updated = encode(&record, record_key_version)?;
+                    table.insert(secret_key, updated.as_slice())?;
+
+                    Ok(Some(record.meta(secret_key)))
+                }
+            }
+        };
+        self.commit_mutation(write_txn, usize::from(matches!(result, Ok(Some(_)))))?;
+        result
+    }
+
+    // ── Org-scoped secret methods ───────────────────────────────────────
+
+    /// Build the compound table key for an org-scoped secret: "{org_id}:{key}".
+    fn org_secret_key(org_id: &str, key: &str) -> String {
+        format!("{org_id}:{key}")
     }
 
-    /// Insert or overwrite an org-scoped secret.
+    /// Insert or overwrite an org-scoped secret. A write by `writer` is
+    /// refused once that key holds `max_secrets` live secrets in the org;
+    /// overwriting one of them does not count as another.
     #[allow(clippy::too_many_arguments)]
     pub fn put_org_secret(
         &self,
@@ -434,43 +1154,82 @@ impl Store {
         webhook_url: Option<String>,
         owner_id: Option<&str>,
         allowed_keys: Option<Vec<String>>,
-    ) -> Result<()> {
+        writer: Option<WritingKey<'_>>,
+    ) -> Result<Result<(), PutConflict>> {
         let now = Self::now();
+        let table_key = Self::org_secret_key(org_id, key);
 
-        let (value_encrypted, nonce) =
-            super::crypto::encrypt(&self.key, value.as_bytes()).context("encrypt value")?;
+        let write_txn = self.db.begin_write_relaxed()?;
+        if let Some(WritingKey {
+            id,
+            name,
+            max_secrets: Some(max),
+        }) = writer
+        {
+            let table = write_txn.open_table(SECRETS)?;
+            let held = count_live_by_key(&table, org_id, id, &table_key, now)?;
+            if held >= max as usize {
+                return Ok(Err(PutConflict::Quota {
+                    key_name: name.to_owned(),
+                    max,
+                }));
+            }
+        }
+        let (enc_key, key_version) = self.active_key();
+        let (value_encrypted, nonce) = self
+            .encrypt(&enc_key, value.as_bytes())
+            .context("encrypt value")?;
 
         let record = SecretRecord {
             value_encrypted,
             nonce,
             created_at: now,
+            updated_at: now,
             expires_at,
             max_reads,
             read_count: 0,
             delete,
             webhook_url,
+            webhook_events: None,
             owner_id: owner_id.map(|s| s.to_owned()),
             org_id: Some(org_id.to_owned()),
             allowed_keys,
+            created_by: writer.map(|w| w.name.to_owned()),
+            annotations: BTreeMap::new(),
+            kind: SecretKind::Text,
+            read_notify: ReadNotifyThrottle::default(),
+            read_notified_at: None,
+            reader_tags: Vec::new(),
+            last_accessed_at: None,
+            value_fingerprint: Some(super::crypto::fingerprint(
+                &self.reader_tag_key,
+                value.as_bytes(),
+            )),
+            revision: next_revision(&write_txn)?,
+            created_by_key: writer.map(|w| w.id.to_owned()),
         };
 
-        let table_key = Self::org_secret_key(org_id, key);
-        let bytes = encode(&record, self.key_version)?;
-        let write_txn = self.db.begin_write()?;
+        let bytes = encode(&record, key_version)?;
         {
             let mut table = write_txn.open_table(SECRETS)?;
             table.insert(table_key.as_str(), bytes.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
 
         debug!(org_id = %org_id, key = %key, "stored org-scoped secret");
-        Ok(())
+        Ok(Ok(()))
     }
 
-    /// Retrieve an org-scoped secret, incrementing its read counter.
-    pub fn get_org_secret(&self, org_id: &str, key: &str) -> Result<GetResult> {
+    /// Retrieve an org-scoped secret, incrementing its read counter and
+    /// counting `reader`, if given, towards its distinct readers.
+    pub fn get_org_secret(
+        &self,
+        org_id: &str,
+        key: &str,
+        reader: Option<&str>,
+    ) -> Result<GetResult> {
         let table_key = Self::org_secret_key(org_id, key);
-        self.get_by_table_key(&table_key)
+        self.get_by_table_key(&table_key, reader)
     }
 
     /// Retrieve metadata for an org-scoped secret without incrementing read_count.
@@ -488,7 +1247,7 @@ impl Store {
             let existed = table.remove(table_key.as_str())?.is_some();
             existed
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(existed))?;
         Ok(existed)
     }
 
@@ -523,16 +1282,7 @@ impl Store {
             }
             // Strip the prefix from the display key.
             let display_key = &key_str[prefix.len()..];
-            metas.push(SecretMeta {
-                key: display_key.to_owned(),
-                created_at: record.created_at,
-                expires_at: record.expires_at,
-                max_reads: record.max_reads,
-                read_count: record.read_count,
-                delete: record.delete,
-                owner_id: record.owner_id.clone(),
-                org_id: record.org_id.clone(),
-            });
+            metas.push(record.meta(display_key));
         }
         Ok(metas)
     }
@@ -589,7 +1339,7 @@ impl Store {
             match raw_bytes {
                 None => Ok(None),
                 Some(bytes) => {
-                    let (mut record, record_key_version) = decode(&bytes)?;
+                    let (mut record, mut record_key_version) = decode(&bytes)?;
 
                     if record.is_expired(now) {
                         table.remove(table_key.as_str())?;
@@ -605,10 +1355,17 @@ impl Store {
                     }
 
                     if let Some(val) = new_value {
-                        let (encrypted, nonce) = super::crypto::encrypt(&self.key, val.as_bytes())
+                        let (key, key_version) = self.active_key();
+                        let (encrypted, nonce) = self
+                            .encrypt(&key, val.as_bytes())
                             .context("encrypt patched value")?;
                         record.value_encrypted = encrypted;
                         record.nonce = nonce;
+                        record.value_fingerprint = Some(super::crypto::fingerprint(
+                            &self.reader_tag_key,
+                            val.as_bytes(),
+                        ));
+                        record_key_version = key_version;
                     }
 
                     if let Some(max) = new_max_reads {
@@ -620,24 +1377,17 @@ impl Store {
                     }
 
                     record.read_count = 0;
+                    record.updated_at = now;
+                    record.revision = next_revision(&write_txn)?;
 
                     let updated = encode(&record, record_key_version)?;
                     table.insert(table_key.as_str(), updated.as_slice())?;
 
-                    Ok(Some(SecretMeta {
-                        key: key.to_owned(),
-                        created_at: record.created_at,
-                        expires_at: record.expires_at,
-                        max_reads: record.max_reads,
-                        read_count: 0,
-                        delete: record.delete,
-                        owner_id: record.owner_id.clone(),
-                        org_id: record.org_id.clone(),
-                    }))
+                    Ok(Some(record.meta(key)))
                 }
             }
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(matches!(result, Ok(Some(_)))))?;
         result
     }
 
@@ -675,7 +1425,7 @@ impl Store {
                 table.remove(key.as_str())?;
             }
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, expired_keys.len())?;
 
         // Return display keys (without prefix).
         let display_keys: Vec<String> = expired_keys
@@ -693,34 +1443,108 @@ impl Store {
     // ── Audit log ─────────────────────────────────────────────────────────
 
     /// Record an audit event. Allocates a monotonic ID via the counters table.
+    /// Events filtered out by `with_audit_actions` are counted but not stored.
+    /// With batching enabled the event is buffered and written on the next flush.
     pub fn record_audit(&self, mut event: AuditEvent) -> Result<()> {
+        self.audit_counts.record(&event.action, event.success);
+        if !self.audit_enabled(&event.action) {
+            return Ok(());
+        }
+        event.source_ip = self.audit_ip_mode.apply(&event.source_ip);
+        let Some(batch) = &self.audit_batch else {
+            return self.write_audit_events(&mut [event]);
+        };
+        let full = {
+            let mut pending = batch.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(event);
+            pending.len() >= batch.max_events
+        };
+        if full {
+            self.flush_audit()?;
+        }
+        Ok(())
+    }
+
+    /// Running `(action, success)` totals of audit events since startup.
+    pub fn audit_counts(&self) -> BTreeMap<(String, bool), u64> {
+        self.audit_counts.snapshot()
+    }
+
+    /// Write any buffered audit events. No-op when batching is disabled.
+    pub fn flush_audit(&self) -> Result<()> {
+        let Some(batch) = &self.audit_batch else {
+            return Ok(());
+        };
+        // Hold the lock across the write so concurrent flushes cannot
+        // interleave and IDs follow the order events were recorded in.
+        let mut pending = batch.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        // On failure the events stay buffered, in order, for the next flush.
+        self.write_audit_events(&mut pending)?;
+        pending.clear();
+        Ok(())
+    }
+
+    /// Assign sequential IDs and insert `events` in a single transaction.
+    fn write_audit_events(&self, events: &mut [AuditEvent]) -> Result<()> {
         let write_txn = self.db.begin_write()?;
-        {
-            let mut counters = write_txn.open_table(COUNTERS)?;
-            let seq = counters.get(AUDIT_SEQ_KEY)?.map(|g| g.value()).unwrap_or(0) + 1;
-            counters.insert(AUDIT_SEQ_KEY, seq)?;
-            event.id = seq;
+        insert_audit_events(&write_txn, events)?;
+        write_txn.commit()?;
+        Ok(())
+    }
 
-            let bytes = bincode::serde::encode_to_vec(&event, bincode::config::standard())
-                .context("bincode encode audit event")?;
-            let mut audit = write_txn.open_table(AUDIT_LOG)?;
-            audit.insert(event.id, bytes.as_slice())?;
+    /// Commit `write_txn`, which changed `changed` records, together with
+    /// the audit events staged for it (see [`audit::with_staged`]). A
+    /// transaction that changed nothing leaves them staged.
+    ///
+    /// [`audit::with_staged`]: super::audit::with_staged
+    pub(crate) fn commit_mutation(
+        &self,
+        write_txn: WriteTransaction,
+        changed: usize,
+    ) -> Result<()> {
+        if changed > 0 {
+            for event in super::audit::take_staged(changed) {
+                self.record_audit_in(&write_txn, event)?;
+            }
         }
         write_txn.commit()?;
         Ok(())
     }
 
+    /// [`record_audit`](Self::record_audit) inside `write_txn`, bypassing
+    /// the batch, so the event commits or aborts with the change it records.
+    fn record_audit_in(&self, write_txn: &WriteTransaction, mut event: AuditEvent) -> Result<()> {
+        self.audit_counts.record(&event.action, event.success);
+        if !self.audit_enabled(&event.action) {
+            return Ok(());
+        }
+        event.source_ip = self.audit_ip_mode.apply(&event.source_ip);
+        insert_audit_events(write_txn, &mut [event])
+    }
+
     /// List audit events matching the query, most recent first.
+    /// Buffered events are flushed first so queries always see them.
     pub fn list_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        self.flush_audit()?;
         let read_txn = self.db.begin_read()?;
         let table = read_txn.open_table(AUDIT_LOG)?;
 
+        // Seek past everything newer than `until` instead of decoding it.
+        let newest = match query.until {
+            None => table.last()?.map(|(k, _)| k.value()),
+            Some(until) => last_audit_id_until(&table, until)?,
+        };
+        let Some(newest) = newest else {
+            return Ok(Vec::new());
+        };
+
         let mut events = Vec::new();
-        for item in table.iter()?.rev() {
+        for item in table.range(..=newest)?.rev() {
             let (_k, v) = item?;
-            let (event, _): (AuditEvent, _) =
-                bincode::serde::decode_from_slice(v.value(), bincode::config::standard())
-                    .context("bincode decode audit event")?;
+            let event = AuditEvent::decode(v.value()).context("decode audit event")?;
 
             if let Some(since) = query.since {
                 if event.timestamp < since {
@@ -761,9 +1585,7 @@ impl Store {
             let mut ids = Vec::new();
             for item in table.iter()? {
                 let (k, v) = item?;
-                let (event, _): (AuditEvent, _) =
-                    bincode::serde::decode_from_slice(v.value(), bincode::config::standard())
-                        .context("bincode decode audit for prune")?;
+                let event = AuditEvent::decode(v.value()).context("decode audit for prune")?;
                 if event.timestamp < cutoff {
                     ids.push(k.value());
                 } else {
@@ -794,7 +1616,11 @@ impl Store {
     }
 
     /// Spawn a background task that prunes old audit events periodically.
-    pub fn spawn_audit_sweep(self, interval: Duration, retention_seconds: i64) {
+    pub fn spawn_audit_sweep(
+        self,
+        interval: Duration,
+        retention_seconds: i64,
+    ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
             let mut ticker = time::interval(interval);
             ticker.tick().await; // skip first immediate tick
@@ -804,23 +1630,47 @@ impl Store {
                     warn!(error = %e, "audit sweep error");
                 }
             }
-        });
+        })
+    }
+
+    /// Spawn a background Tokio task that flushes buffered audit events every
+    /// `interval`. Only useful when batching is enabled.
+    pub fn spawn_audit_flush(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            ticker.tick().await; // skip first immediate tick
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.flush_audit() {
+                    warn!(error = %e, "audit flush error");
+                }
+            }
+        })
     }
 
     /// Spawn a background Tokio task that calls `prune()` every `interval`.
     /// If a `WebhookSender` is provided, fires `secret.expired` for each pruned key.
+    /// Ticks that find `sweep_lock` held (an on-demand prune is running) are skipped.
+    /// Pruned secrets are counted in `metrics`.
     pub fn spawn_sweep(
         self,
         interval: Duration,
         webhook_sender: Option<crate::webhooks::WebhookSender>,
-    ) {
+        sweep_lock: Arc<tokio::sync::Mutex<()>>,
+        metrics: Arc<crate::metrics::Metrics>,
+    ) -> tokio::task::JoinHandle<()> {
         tokio::spawn(async move {
             let mut ticker = time::interval(interval);
             ticker.tick().await; // skip first immediate tick
             loop {
                 ticker.tick().await;
+                let Ok(_running) = sweep_lock.try_lock() else {
+                    debug!("sweep already running; skipping tick");
+                    continue;
+                };
                 match self.prune() {
                     Ok(pruned_keys) => {
+                        metrics.secrets_pruned.add(pruned_keys.len() as u64);
                         if let Some(ref sender) = webhook_sender {
                             for key in &pruned_keys {
                                 sender.fire(
@@ -835,8 +1685,82 @@ impl Store {
                         warn!(error = %e, "background sweep error");
                     }
                 }
+                if let Err(e) = self.prune_idempotency() {
+                    warn!(error = %e, "idempotency sweep error");
+                }
             }
-        });
+        })
+    }
+
+    /// Size of the database file in bytes.
+    fn file_len(&self) -> Result<u64> {
+        Ok(std::fs::metadata(&self.db.path)
+            .context("stat database file")?
+            .len())
+    }
+
+    /// Fraction of the database file not occupied by allocated pages.
+    pub fn free_ratio(&self) -> Result<f64> {
+        let stats = self.db.begin_write()?.stats()?;
+        let file_len = self.file_len()?;
+        if file_len == 0 {
+            return Ok(0.0);
+        }
+        let allocated = stats.allocated_pages() * stats.page_size() as u64;
+        Ok(1.0 - (allocated as f64 / file_len as f64).min(1.0))
+    }
+
+    /// Compact the database file, returning its size before and after.
+    /// Blocks new transactions until done and fails if any are still open.
+    pub fn compact(&self) -> Result<CompactReport> {
+        let before_bytes = self.file_len()?;
+        let mut db = self.db.inner.write().unwrap_or_else(|e| e.into_inner());
+        while db.compact().context("compact database")? {}
+        drop(db);
+        Ok(CompactReport {
+            before_bytes,
+            after_bytes: self.file_len()?,
+        })
+    }
+
+    /// Spawn a background task that compacts the database every `interval`
+    /// when at least [`COMPACT_MIN_FREE_RATIO`] of the file is free. Shares
+    /// `sweep_lock` with the prune sweep so the two never run together.
+    pub fn spawn_compaction(
+        self,
+        interval: Duration,
+        sweep_lock: Arc<tokio::sync::Mutex<()>>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = time::interval(interval);
+            ticker.tick().await; // skip first immediate tick
+            loop {
+                ticker.tick().await;
+                let Ok(_running) = sweep_lock.try_lock() else {
+                    debug!("sweep running; skipping compaction tick");
+                    continue;
+                };
+                let store = self.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let ratio = store.free_ratio()?;
+                    if ratio < COMPACT_MIN_FREE_RATIO {
+                        return Ok(None);
+                    }
+                    store.compact().map(Some)
+                })
+                .await;
+                match result {
+                    Ok(Ok(Some(report))) => info!(
+                        before_bytes = report.before_bytes,
+                        after_bytes = report.after_bytes,
+                        "database compacted"
+                    ),
+                    Ok(Ok(None)) => debug!("compaction skipped: little free space"),
+                    Ok(Err(e)) => warn!(error = %e, "background compaction error"),
+                    Err(e) => warn!(error = %e, "background compaction task failed"),
+                }
+            }
+        })
     }
 
     /// Return the highest key version found across all stored records.
@@ -853,6 +1777,24 @@ impl Store {
         Ok(max)
     }
 
+    /// Version tag for the next rotation: the first after the current one,
+    /// wrapping past 255 back to 1, that no stored record still carries.
+    pub fn next_key_version(&self) -> Result<u8> {
+        let current = self.active_key().1;
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SECRETS)?;
+        let mut used = [false; 256];
+        used[usize::from(current)] = true;
+        for item in table.iter()? {
+            let (_k, v) = item?;
+            used[usize::from(decode(v.value())?.1)] = true;
+        }
+        (1..=255u8)
+            .map(|step| ((u16::from(current) + u16::from(step) - 1) % 255 + 1) as u8)
+            .find(|v| !used[usize::from(*v)])
+            .context("every key version is in use")
+    }
+
     // ── Org CRUD ──────────────────────────────────────────────────────────
 
     /// Insert or overwrite an org record.
@@ -865,7 +1807,7 @@ impl Store {
             let mut table = write_txn.open_table(super::org::ORGS)?;
             table.insert(org.id.as_str(), bytes.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
         Ok(())
     }
 
@@ -926,7 +1868,7 @@ impl Store {
             let existed = table.remove(id)?.is_some();
             existed
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(existed))?;
         Ok(existed)
     }
 
@@ -944,7 +1886,7 @@ impl Store {
             let mut table = write_txn.open_table(super::org::PRINCIPALS)?;
             table.insert(key.as_str(), bytes.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
         Ok(())
     }
 
@@ -1011,9 +1953,8 @@ impl Store {
                 let hash = v.value().to_vec();
                 if let Some(key_guard) = keys_table.get(hash.as_slice())? {
                     let key_bytes = key_guard.value().to_vec();
-                    let (key_record, _): (super::org::PrincipalKeyRecord, _) =
-                        bincode::serde::decode_from_slice(&key_bytes, bincode::config::standard())
-                            .context("bincode decode principal key")?;
+                    let key_record = super::org::PrincipalKeyRecord::decode(&key_bytes)
+                        .context("decode principal key")?;
                     if key_record.valid_before > now {
                         anyhow::bail!("cannot delete principal {principal_id}: has active keys");
                     }
@@ -1028,7 +1969,7 @@ impl Store {
             let existed = table.remove(compound_key.as_str())?.is_some();
             existed
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(existed))?;
         Ok(existed)
     }
 
@@ -1037,8 +1978,7 @@ impl Store {
     /// Insert a principal key record. Writes to both PRINCIPAL_KEYS (hash->record)
     /// and PRINCIPAL_KEY_IX ("{principal_id}:{key_id}"->hash).
     pub fn put_principal_key(&self, key: &super::org::PrincipalKeyRecord) -> Result<()> {
-        let bytes = bincode::serde::encode_to_vec(key, bincode::config::standard())
-            .context("bincode encode principal key")?;
+        let bytes = key.encode().context("encode principal key")?;
         let ix_key = format!("{}:{}", key.principal_id, key.id);
 
         let write_txn = self.db.begin_write()?;
@@ -1049,7 +1989,7 @@ impl Store {
             let mut ix_table = write_txn.open_table(super::org::PRINCIPAL_KEY_IX)?;
             ix_table.insert(ix_key.as_str(), key.key_hash.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
         Ok(())
     }
 
@@ -1065,9 +2005,8 @@ impl Store {
         match raw {
             None => Ok(None),
             Some(bytes) => {
-                let (record, _): (super::org::PrincipalKeyRecord, _) =
-                    bincode::serde::decode_from_slice(&bytes, bincode::config::standard())
-                        .context("bincode decode principal key")?;
+                let record = super::org::PrincipalKeyRecord::decode(&bytes)
+                    .context("decode principal key")?;
                 Ok(Some(record))
             }
         }
@@ -1092,9 +2031,8 @@ impl Store {
             let hash = v.value().to_vec();
             if let Some(guard) = keys_table.get(hash.as_slice())? {
                 let key_bytes = guard.value().to_vec();
-                let (record, _): (super::org::PrincipalKeyRecord, _) =
-                    bincode::serde::decode_from_slice(&key_bytes, bincode::config::standard())
-                        .context("bincode decode principal key")?;
+                let record = super::org::PrincipalKeyRecord::decode(&key_bytes)
+                    .context("decode principal key")?;
                 records.push(record);
             }
         }
@@ -1126,7 +2064,7 @@ impl Store {
             let mut keys_table = write_txn.open_table(super::org::PRINCIPAL_KEYS)?;
             keys_table.remove(hash.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
         Ok(true)
     }
 
@@ -1151,7 +2089,7 @@ impl Store {
             let mut table = write_txn.open_table(super::org::ROLES)?;
             table.insert(key.as_str(), bytes.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
         Ok(())
     }
 
@@ -1243,39 +2181,66 @@ impl Store {
             let existed = table.remove(key.as_str())?.is_some();
             existed
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(existed))?;
         Ok(existed)
     }
 
-    /// Re-encrypt all non-expired records with `new_key`, tagging them with
-    /// `new_key_version`. The current `self.key` is used to decrypt.
-    /// Returns the number of records rotated.
-    pub fn rotate(&self, new_key: &EncryptionKey, new_key_version: u8) -> Result<usize> {
+    /// Decode and decrypt every live secret, public and org-scoped, with
+    /// the current key, reporting the ones that fail (corruption or a key
+    /// mismatch). Expired records are only counted: the sweep removes them
+    /// unread. Read-only: nothing is modified, not even expired records.
+    pub fn check(&self) -> Result<CheckReport> {
         let now = Self::now();
-
-        // Read pass: collect all raw bytes keyed by secret name.
-        let entries: Vec<(String, Vec<u8>)> = {
-            let read_txn = self.db.begin_read()?;
-            let table = read_txn.open_table(SECRETS)?;
-            let mut out = Vec::new();
-            for item in table.iter()? {
-                let (k, v) = item?;
-                out.push((k.value().to_owned(), v.value().to_vec()));
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SECRETS)?;
+        let mut report = CheckReport::default();
+        for item in table.iter()? {
+            let (k, v) = item?;
+            let outcome = decode(v.value()).and_then(|(record, key_version)| {
+                if record.is_expired(now) {
+                    return Ok(None);
+                }
+                self.decrypt_value(&record, key_version).map(Some)
+            });
+            match outcome {
+                Ok(None) => report.expired += 1,
+                Ok(Some(_)) => report.ok += 1,
+                Err(e) => report.failed.push((k.value().to_owned(), format!("{e:#}"))),
             }
-            out
-        };
-
-        if entries.is_empty() {
-            return Ok(0);
         }
+        Ok(report)
+    }
+
+    /// Re-encrypt all non-expired records with `new_key`, tagging them with
+    /// `new_key_version`, then make it the key for new writes on every clone
+    /// of this store. Safe while serving: the rewrite happens in one write
+    /// transaction, so other writers wait for it and then use `new_key`.
+    /// Records a `key.rotate` audit event in that transaction, so a failed
+    /// audit write leaves everything under the old key. Returns the number
+    /// of records rotated.
+    #[tracing::instrument(
+        name = "store.rotate",
+        level = "debug",
+        skip_all,
+        fields(new_key_version, rows = tracing::field::Empty)
+    )]
+    pub fn rotate(&self, new_key: &EncryptionKey, new_key_version: u8) -> Result<usize> {
+        let now = Self::now();
+        let old_key_version = self.active_key().1;
+        // Keep audit IDs in recording order across the in-txn event below.
+        self.flush_audit()?;
 
-        // Write pass: decrypt with old key, re-encrypt with new key.
         let write_txn = self.db.begin_write()?;
         let mut count = 0usize;
         {
             let mut table = write_txn.open_table(SECRETS)?;
+            // Collect first: the table cannot be iterated while it is written.
+            let entries: Vec<(String, Vec<u8>)> = table
+                .iter()?
+                .map(|item| item.map(|(k, v)| (k.value().to_owned(), v.value().to_vec())))
+                .collect::<Result<_, _>>()?;
             for (key, raw_bytes) in &entries {
-                let (record, _old_version) = decode(raw_bytes)?;
+                let (record, old_version) = decode(raw_bytes)?;
 
                 // Skip expired records — they'll be pruned normally.
                 if record.is_expired(now) {
@@ -1283,26 +2248,45 @@ impl Store {
                 }
 
                 // Decrypt with old key.
-                let plaintext =
-                    super::crypto::decrypt(&self.key, &record.value_encrypted, &record.nonce)
-                        .context("decrypt for rotation")?;
+                let plaintext = super::crypto::decrypt(
+                    &self.key_for(old_version),
+                    &record.value_encrypted,
+                    &record.nonce,
+                )
+                .context("decrypt for rotation")?;
 
                 // Re-encrypt with new key.
-                let (new_encrypted, new_nonce) =
-                    super::crypto::encrypt(new_key, &plaintext).context("encrypt for rotation")?;
+                let (new_encrypted, new_nonce) = self
+                    .encrypt(new_key, &plaintext)
+                    .context("encrypt for rotation")?;
 
                 let new_record = SecretRecord {
                     value_encrypted: new_encrypted,
                     nonce: new_nonce,
                     created_at: record.created_at,
+                    updated_at: record.updated_at,
                     expires_at: record.expires_at,
                     max_reads: record.max_reads,
                     read_count: record.read_count,
                     delete: record.delete,
                     webhook_url: record.webhook_url.clone(),
+                    webhook_events: record.webhook_events.clone(),
                     owner_id: record.owner_id.clone(),
                     org_id: record.org_id.clone(),
                     allowed_keys: record.allowed_keys.clone(),
+                    created_by: record.created_by.clone(),
+                    annotations: record.annotations.clone(),
+                    kind: record.kind,
+                    value_fingerprint: Some(super::crypto::fingerprint(
+                        &self.reader_tag_key,
+                        &plaintext,
+                    )),
+                    read_notify: record.read_notify,
+                    read_notified_at: record.read_notified_at,
+                    reader_tags: record.reader_tags.clone(),
+                    last_accessed_at: record.last_accessed_at,
+                    revision: record.revision,
+                    created_by_key: record.created_by_key.clone(),
                 };
 
                 let new_bytes = encode(&new_record, new_key_version)?;
@@ -1310,45 +2294,247 @@ impl Store {
                 count += 1;
             }
         }
-        write_txn.commit()?;
+        {
+            let (sealed, nonce) = self
+                .encrypt(new_key, self.reader_tag_key.as_bytes())
+                .context("reseal reader tag key")?;
+            let mut meta = write_txn.open_table(META)?;
+            meta.insert(
+                READER_TAG_KEY,
+                [nonce.as_slice(), &sealed].concat().as_slice(),
+            )?;
+        }
+        self.record_audit_in(
+            &write_txn,
+            AuditEvent::new(
+                super::audit::ACTION_KEY_ROTATE,
+                None,
+                "local".into(),
+                true,
+                Some(format!(
+                    "old_key_version={old_key_version} new_key_version={new_key_version} rotated={count}"
+                )),
+                None,
+                None,
+            ),
+        )?;
+        {
+            // Held across the commit so no reader sees rotated records while
+            // the old key is still current.
+            let mut active = self.key.write().unwrap_or_else(|e| e.into_inner());
+            write_txn.commit()?;
+            let new_key = super::crypto::load_key(new_key.as_bytes()).expect("32-byte key");
+            active.previous = Some(std::mem::replace(&mut active.key, Arc::new(new_key)));
+            active.version = new_key_version;
+        }
 
+        tracing::Span::current().record("rows", count);
         info!(rotated = count, new_key_version, "key rotation complete");
         Ok(count)
     }
 }
 
-/// Encode a SecretRecord in v2 format: `[RECORD_V2_MARKER, key_version] + bincode(record)`.
+/// Commit a read transaction begun with `begin_write_relaxed`, durably if
+/// any of `results` burned its secret.
+fn commit_read<'a>(
+    mut write_txn: WriteTransaction,
+    results: impl IntoIterator<Item = &'a GetResult>,
+) -> Result<()> {
+    if results
+        .into_iter()
+        .any(|r| matches!(r, GetResult::Burned(..)))
+    {
+        write_txn.set_durability(Durability::Immediate);
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// Allocate the next secret revision in `write_txn`. Revisions are unique
+/// across the store, so a key deleted and created again never repeats an
+/// ETag.
+fn next_revision(write_txn: &WriteTransaction) -> Result<u64> {
+    let mut counters = write_txn.open_table(COUNTERS)?;
+    let next = counters
+        .get(REVISION_SEQ_KEY)?
+        .map_or(0, |g| g.value())
+        .saturating_add(1);
+    counters.insert(REVISION_SEQ_KEY, next)?;
+    Ok(next)
+}
+
+/// The reader tag key stored in `db`, sealed under `key`; created on first
+/// open. If it was sealed under a different key, readers are tagged under a
+/// fresh key for this process only and distinct reader counts restart.
+fn load_reader_tag_key(db: &Database, key: &EncryptionKey) -> Result<EncryptionKey> {
+    let write_txn = db.begin_write()?;
+    let tag_key = {
+        let mut meta = write_txn.open_table(META)?;
+        let stored = meta.get(READER_TAG_KEY)?.map(|v| v.value().to_vec());
+        if let Some(stored) = stored {
+            let unsealed = stored
+                .split_first_chunk::<12>()
+                .and_then(|(nonce, sealed)| {
+                    let plain =
+                        zeroize::Zeroizing::new(super::crypto::decrypt(key, sealed, nonce).ok()?);
+                    super::crypto::load_key(&plain)
+                });
+            return Ok(unsealed.unwrap_or_else(|| {
+                warn!("reader tag key is sealed under another key; distinct reader counts restart");
+                super::crypto::generate_key()
+            }));
+        }
+        let tag_key = super::crypto::generate_key();
+        let (sealed, nonce) = super::crypto::encrypt(key, tag_key.as_bytes())?;
+        meta.insert(
+            READER_TAG_KEY,
+            [nonce.as_slice(), &sealed].concat().as_slice(),
+        )?;
+        tag_key
+    };
+    write_txn.commit()?;
+    Ok(tag_key)
+}
+
+/// ID of the newest audit event recorded at or before `until`, by binary
+/// search: IDs are assigned in recording order, so timestamps rise with them.
+fn last_audit_id_until(
+    table: &impl ReadableTable<u64, &'static [u8]>,
+    until: i64,
+) -> Result<Option<u64>> {
+    let timestamp = |bytes: &[u8]| -> Result<i64> {
+        Ok(AuditEvent::decode(bytes)
+            .context("decode audit event")?
+            .timestamp)
+    };
+    let (Some((first, first_event)), Some((last, last_event))) = (table.first()?, table.last()?)
+    else {
+        return Ok(None);
+    };
+    if timestamp(first_event.value())? > until {
+        return Ok(None);
+    }
+    if timestamp(last_event.value())? <= until {
+        return Ok(Some(last.value()));
+    }
+    // The answer is an ID in `lo..hi`, and `lo` is one.
+    let (mut lo, mut hi) = (first.value(), last.value());
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        let Some(item) = table.range(mid..hi)?.next() else {
+            hi = mid;
+            continue;
+        };
+        let (id, event) = item?;
+        if timestamp(event.value())? <= until {
+            lo = id.value();
+        } else {
+            hi = id.value();
+        }
+    }
+    Ok(Some(lo))
+}
+
+/// Assign the next sequential IDs to `events` and insert them in `write_txn`.
+fn insert_audit_events(write_txn: &WriteTransaction, events: &mut [AuditEvent]) -> Result<()> {
+    let mut counters = write_txn.open_table(COUNTERS)?;
+    let mut audit = write_txn.open_table(AUDIT_LOG)?;
+    let mut seq = counters.get(AUDIT_SEQ_KEY)?.map(|g| g.value()).unwrap_or(0);
+    for event in events {
+        seq += 1;
+        event.id = seq;
+        let bytes = event.encode().context("encode audit event")?;
+        audit.insert(event.id, bytes.as_slice())?;
+    }
+    counters.insert(AUDIT_SEQ_KEY, seq)?;
+    Ok(())
+}
+
+/// Records in the public bucket, counted by key alone. Org-scoped secrets
+/// live under `<org_id>:<key>`, and public key names never hold a `:`.
+fn count_public(table: &impl ReadableTable<&'static str, &'static [u8]>) -> Result<usize> {
+    let mut count = 0usize;
+    for item in table.iter()? {
+        let (k, _) = item?;
+        if !k.value().contains(':') {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Live (non-expired, non-burned) secrets in `org_id` created by principal
+/// key `key_id`, other than the one stored under `except`.
+fn count_live_by_key(
+    table: &redb::Table<&str, &[u8]>,
+    org_id: &str,
+    key_id: &str,
+    except: &str,
+    now: i64,
+) -> Result<usize> {
+    let prefix = format!("{org_id}:");
+    let mut count = 0usize;
+    for item in table.range(prefix.as_str()..)? {
+        let (k, v) = item?;
+        if !k.value().starts_with(&prefix) {
+            break;
+        }
+        if k.value() == except {
+            continue;
+        }
+        let (record, _kv) = decode(v.value())?;
+        if record.created_by_key.as_deref() == Some(key_id)
+            && !record.is_expired(now)
+            && !record.is_burned()
+        {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Encode a SecretRecord in v3 format: `[RECORD_V3_MARKER, key_version] + bincode(record)`.
 fn encode(record: &SecretRecord, key_version: u8) -> Result<Vec<u8>> {
     let payload = bincode::serde::encode_to_vec(record, bincode::config::standard())
         .context("bincode encode")?;
     let mut out = Vec::with_capacity(2 + payload.len());
-    out.push(RECORD_V2_MARKER);
+    out.push(RECORD_V3_MARKER);
     out.push(key_version);
     out.extend_from_slice(&payload);
     Ok(out)
 }
 
 /// Decode bytes into `(SecretRecord, key_version)`.
-/// Handles both v2 format (prefixed) and legacy v1 format (raw bincode).
+/// Handles the v3 format, v2 (prefixed legacy layout) and v1 (raw bincode).
 fn decode(bytes: &[u8]) -> Result<(SecretRecord, u8)> {
     if bytes.is_empty() {
         anyhow::bail!("empty record");
     }
-    if bytes[0] == RECORD_V2_MARKER {
+    match bytes[0] {
+        RECORD_V3_MARKER | RECORD_V2_MARKER if bytes.len() < 3 => {
+            anyhow::bail!("truncated record")
+        }
+        // v3 format: [0x02, key_version, bincode...]
+        RECORD_V3_MARKER => {
+            let (record, _) =
+                bincode::serde::decode_from_slice(&bytes[2..], bincode::config::standard())
+                    .context("bincode decode v3")?;
+            Ok((record, bytes[1]))
+        }
         // v2 format: [0x01, key_version, bincode...]
-        if bytes.len() < 3 {
-            anyhow::bail!("truncated v2 record");
-        }
-        let key_version = bytes[1];
-        let (record, _) =
-            bincode::serde::decode_from_slice(&bytes[2..], bincode::config::standard())
-                .context("bincode decode v2")?;
-        Ok((record, key_version))
-    } else {
+        RECORD_V2_MARKER => {
+            let (record, _): (LegacySecretRecord, _) =
+                bincode::serde::decode_from_slice(&bytes[2..], bincode::config::standard())
+                    .context("bincode decode v2")?;
+            Ok((record.into(), bytes[1]))
+        }
         // Legacy v1: raw bincode, no version prefix. Assume key_version = 1.
-        let (record, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())
-            .context("bincode decode")?;
-        Ok((record, 1))
+        _ => {
+            let (record, _): (LegacySecretRecord, _) =
+                bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+                    .context("bincode decode")?;
+            Ok((record.into(), 1))
+        }
     }
 }
 
@@ -1368,7 +2554,13 @@ mod tests {
     #[test]
     fn put_get_delete() {
         let (s, _dir) = make_store();
-        s.put("MY_KEY", "my-value", None, None, true, None).unwrap();
+        s.put(&NewSecret {
+            key: "MY_KEY".into(),
+            value: "my-value".into(),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
         assert_eq!(
             s.get("MY_KEY").unwrap(),
             GetResult::Value("my-value".into(), None)
@@ -1380,7 +2572,14 @@ mod tests {
     #[test]
     fn read_limit_burn() {
         let (s, _dir) = make_store();
-        s.put("BURN", "secret", None, Some(1), true, None).unwrap();
+        s.put(&NewSecret {
+            key: "BURN".into(),
+            value: "secret".into(),
+            max_reads: Some(1),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
         assert_eq!(
             s.get("BURN").unwrap(),
             GetResult::Burned("secret".into(), None)
@@ -1393,16 +2592,65 @@ mod tests {
     fn ttl_expiry() {
         let (s, _dir) = make_store();
         // TTL = 0 means already expired.
-        s.put("EXPIRED", "value", Some(0), None, true, None)
-            .unwrap();
+        s.put(&NewSecret {
+            key: "EXPIRED".into(),
+            value: "value".into(),
+            ttl_seconds: Some(0),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
         assert_eq!(s.get("EXPIRED").unwrap(), GetResult::NotFound);
     }
 
+    #[test]
+    fn huge_ttl_clamps_to_far_future() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "FOREVER".into(),
+            value: "value".into(),
+            ttl_seconds: Some(u64::MAX),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        let (meta, _) = s.head("FOREVER").unwrap().unwrap();
+        assert_eq!(meta.expires_at, Some(i64::MAX));
+        assert!(matches!(s.get("FOREVER").unwrap(), GetResult::Value(..)));
+
+        let meta = s
+            .patch(
+                "FOREVER",
+                None,
+                None,
+                Some(u64::MAX - 1),
+                AnnotationsPatch::default(),
+                None,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(meta.expires_at, Some(i64::MAX));
+        assert!(s.prune().unwrap().is_empty());
+    }
+
     #[test]
     fn list_excludes_expired() {
         let (s, _dir) = make_store();
-        s.put("LIVE", "v", Some(3600), None, true, None).unwrap();
-        s.put("DEAD", "v", Some(0), None, true, None).unwrap();
+        s.put(&NewSecret {
+            key: "LIVE".into(),
+            value: "v".into(),
+            ttl_seconds: Some(3600),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
+        s.put(&NewSecret {
+            key: "DEAD".into(),
+            value: "v".into(),
+            ttl_seconds: Some(0),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
         let metas = s.list().unwrap();
         assert!(metas.iter().any(|m| m.key == "LIVE"));
         assert!(!metas.iter().any(|m| m.key == "DEAD"));
@@ -1411,7 +2659,14 @@ mod tests {
     #[test]
     fn head_returns_meta_without_incrementing() {
         let (s, _dir) = make_store();
-        s.put("H", "val", None, Some(5), true, None).unwrap();
+        s.put(&NewSecret {
+            key: "H".into(),
+            value: "val".into(),
+            max_reads: Some(5),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
         let (meta, sealed) = s.head("H").unwrap().unwrap();
         assert_eq!(meta.read_count, 0);
         assert_eq!(meta.max_reads, Some(5));
@@ -1423,14 +2678,27 @@ mod tests {
     #[test]
     fn head_returns_none_for_expired() {
         let (s, _dir) = make_store();
-        s.put("HE", "val", Some(0), None, true, None).unwrap();
+        s.put(&NewSecret {
+            key: "HE".into(),
+            value: "val".into(),
+            ttl_seconds: Some(0),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
         assert!(s.head("HE").unwrap().is_none());
     }
 
     #[test]
     fn head_returns_sealed_status() {
         let (s, _dir) = make_store();
-        s.put("HS", "val", None, Some(1), false, None).unwrap();
+        s.put(&NewSecret {
+            key: "HS".into(),
+            value: "val".into(),
+            max_reads: Some(1),
+            ..NewSecret::default()
+        })
+        .unwrap();
         s.get("HS").unwrap(); // read once, hits limit
         let (meta, sealed) = s.head("HS").unwrap().unwrap();
         assert!(sealed);
@@ -1440,29 +2708,128 @@ mod tests {
     #[test]
     fn patch_updates_value_and_resets_count() {
         let (s, _dir) = make_store();
-        s.put("P", "old", None, Some(5), false, None).unwrap();
+        s.put(&NewSecret {
+            key: "P".into(),
+            value: "old".into(),
+            max_reads: Some(5),
+            ..NewSecret::default()
+        })
+        .unwrap();
         s.get("P").unwrap(); // read_count = 1
-        let meta = s.patch("P", Some("new"), None, None).unwrap().unwrap();
+        let meta = s
+            .patch(
+                "P",
+                Some("new"),
+                None,
+                None,
+                AnnotationsPatch::default(),
+                None,
+            )
+            .unwrap()
+            .unwrap();
         assert_eq!(meta.read_count, 0); // reset
         assert_eq!(s.get("P").unwrap(), GetResult::Value("new".into(), None));
     }
 
     #[test]
-    fn patch_rejects_delete_true_secret() {
+    fn annotations_stored_and_replaced_on_patch() {
         let (s, _dir) = make_store();
-        s.put("PD", "val", None, None, true, None).unwrap();
-        let err = s.patch("PD", Some("new"), None, None);
-        assert!(err.is_err()); // should error for delete=true
-    }
+        let mut notes = BTreeMap::new();
+        notes.insert("runbook".to_owned(), "https://wiki/db".to_owned());
+        s.put(&NewSecret {
+            key: "A".into(),
+            value: "v".into(),
+            annotations: notes.clone(),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        assert_eq!(s.list().unwrap()[0].annotations, notes);
 
-    #[test]
-    fn patch_rejects_sealed_secret() {
-        let (s, _dir) = make_store();
-        s.put("PS", "val", None, Some(1), false, None).unwrap();
-        s.get("PS").unwrap(); // exhaust the one allowed read — now sealed
+        let mut replaced = BTreeMap::new();
+        replaced.insert("owner".to_owned(), "platform".to_owned());
+        let meta = s
+            .patch(
+                "A",
+                None,
+                None,
+                None,
+                AnnotationsPatch::replace(replaced.clone()),
+                None,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(meta.annotations, replaced);
+        // Omitting annotations on patch leaves them untouched.
+        let meta = s
+            .patch(
+                "A",
+                Some("v2"),
+                None,
+                None,
+                AnnotationsPatch::default(),
+                None,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(meta.annotations, replaced);
+    }
+
+    #[test]
+    fn burn_returns_webhook_url_only_for_subscribed_events() {
+        let (s, _dir) = make_store();
+        let url = "https://hooks.example.com/sirr".to_owned();
+        s.put(&NewSecret {
+            key: "W".into(),
+            value: "v".into(),
+            max_reads: Some(2),
+            delete: true,
+            webhook_url: Some(url.clone()),
+            webhook_events: Some(vec!["secret.burned".into()]),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        assert_eq!(s.get("W").unwrap(), GetResult::Value("v".into(), None));
+        assert_eq!(
+            s.get("W").unwrap(),
+            GetResult::Burned("v".into(), Some(url))
+        );
+    }
+
+    #[test]
+    fn patch_rejects_delete_true_secret() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "PD".into(),
+            value: "val".into(),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
+        let err = s.patch(
+            "PD",
+            Some("new"),
+            None,
+            None,
+            AnnotationsPatch::default(),
+            None,
+        );
+        assert!(err.is_err()); // should error for delete=true
+    }
+
+    #[test]
+    fn patch_rejects_sealed_secret() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "PS".into(),
+            value: "val".into(),
+            max_reads: Some(1),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        s.get("PS").unwrap(); // exhaust the one allowed read — now sealed
         assert_eq!(s.get("PS").unwrap(), GetResult::Sealed);
         // Patching a sealed secret must fail — read limit is a security boundary.
-        let err = s.patch("PS", None, Some(5), None);
+        let err = s.patch("PS", None, Some(5), None, AnnotationsPatch::default(), None);
         assert!(err.is_err());
         assert!(err.unwrap_err().to_string().contains("sealed"));
     }
@@ -1470,23 +2837,52 @@ mod tests {
     #[test]
     fn patch_works_on_unexhausted_secret() {
         let (s, _dir) = make_store();
-        s.put("PU", "val", None, Some(3), false, None).unwrap();
+        s.put(&NewSecret {
+            key: "PU".into(),
+            value: "val".into(),
+            max_reads: Some(3),
+            ..NewSecret::default()
+        })
+        .unwrap();
         s.get("PU").unwrap(); // one of three reads used — not sealed
-        s.patch("PU", Some("new"), None, None).unwrap();
+        s.patch(
+            "PU",
+            Some("new"),
+            None,
+            None,
+            AnnotationsPatch::default(),
+            None,
+        )
+        .unwrap();
         assert_eq!(s.get("PU").unwrap(), GetResult::Value("new".into(), None));
     }
 
     #[test]
     fn patch_not_found() {
         let (s, _dir) = make_store();
-        let result = s.patch("NOPE", Some("val"), None, None).unwrap();
+        let result = s
+            .patch(
+                "NOPE",
+                Some("val"),
+                None,
+                None,
+                AnnotationsPatch::default(),
+                None,
+            )
+            .unwrap();
         assert!(result.is_none());
     }
 
     #[test]
     fn get_sealed_returns_sealed_variant() {
         let (s, _dir) = make_store();
-        s.put("GS", "val", None, Some(1), false, None).unwrap();
+        s.put(&NewSecret {
+            key: "GS".into(),
+            value: "val".into(),
+            max_reads: Some(1),
+            ..NewSecret::default()
+        })
+        .unwrap();
         assert!(matches!(s.get("GS").unwrap(), GetResult::Value(..)));
         assert!(matches!(s.get("GS").unwrap(), GetResult::Sealed));
     }
@@ -1771,10 +3167,11 @@ mod tests {
             valid_after: 1700000000,
             valid_before: 9999999999,
             created_at: 1700000000,
+            max_secrets: None,
         };
         // Manually insert the key into both tables so delete_principal can find it
         {
-            let bytes = bincode::serde::encode_to_vec(&key, bincode::config::standard()).unwrap();
+            let bytes = key.encode().unwrap();
             let ix_key = format!("{}:{}", key.principal_id, key.id);
             let write_txn = s.db.begin_write().unwrap();
             {
@@ -1815,6 +3212,7 @@ mod tests {
             valid_after: 1700000000,
             valid_before: 1800000000,
             created_at: 1700000000,
+            max_secrets: None,
         };
         s.put_principal_key(&key).unwrap();
 
@@ -1920,7 +3318,9 @@ mod tests {
             None,
             Some("p1"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
         s.put_org_secret(
             "org_b",
@@ -1932,16 +3332,18 @@ mod tests {
             None,
             Some("p2"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
 
         // Each org gets its own value
         assert_eq!(
-            s.get_org_secret("org_a", "DB_PASS").unwrap(),
+            s.get_org_secret("org_a", "DB_PASS", None).unwrap(),
             GetResult::Value("alpha-pass".into(), None)
         );
         assert_eq!(
-            s.get_org_secret("org_b", "DB_PASS").unwrap(),
+            s.get_org_secret("org_b", "DB_PASS", None).unwrap(),
             GetResult::Value("beta-pass".into(), None)
         );
 
@@ -1951,11 +3353,11 @@ mod tests {
         // Delete from one org doesn't affect the other
         assert!(s.delete_org_secret("org_a", "DB_PASS").unwrap());
         assert_eq!(
-            s.get_org_secret("org_a", "DB_PASS").unwrap(),
+            s.get_org_secret("org_a", "DB_PASS", None).unwrap(),
             GetResult::NotFound
         );
         assert_eq!(
-            s.get_org_secret("org_b", "DB_PASS").unwrap(),
+            s.get_org_secret("org_b", "DB_PASS", None).unwrap(),
             GetResult::Value("beta-pass".into(), None)
         );
     }
@@ -1974,7 +3376,9 @@ mod tests {
             None,
             Some("alice"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
         s.put_org_secret(
             "org_1",
@@ -1986,7 +3390,9 @@ mod tests {
             None,
             Some("bob"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
         s.put_org_secret(
             "org_1",
@@ -1998,7 +3404,9 @@ mod tests {
             None,
             Some("alice"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
 
         // List all for org
@@ -2033,7 +3441,9 @@ mod tests {
             None,
             Some("alice"),
             Some(vec!["deploy-key".into(), "ci-key".into()]),
+            None,
         )
+        .unwrap()
         .unwrap();
 
         // Secret with no restriction
@@ -2047,7 +3457,9 @@ mod tests {
             None,
             Some("alice"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
 
         // Allowed key passes
@@ -2086,7 +3498,9 @@ mod tests {
             None,
             Some("alice"),
             None,
+            None,
         )
+        .unwrap()
         .unwrap();
 
         // head
@@ -2096,7 +3510,7 @@ mod tests {
         assert!(!sealed);
 
         // read once
-        s.get_org_secret("org_1", "PATCHME").unwrap();
+        s.get_org_secret("org_1", "PATCHME", None).unwrap();
 
         // patch
         let meta = s
@@ -2107,7 +3521,7 @@ mod tests {
 
         // verify new value
         assert_eq!(
-            s.get_org_secret("org_1", "PATCHME").unwrap(),
+            s.get_org_secret("org_1", "PATCHME", None).unwrap(),
             GetResult::Value("new".into(), None)
         );
     }
@@ -2142,4 +3556,1139 @@ mod tests {
         assert!(err.is_err());
         assert!(err.unwrap_err().to_string().contains("in use"));
     }
+
+    #[test]
+    fn org_secret_quota_counts_live_secrets_by_key_id() {
+        let (s, _dir) = make_store();
+        let key = |id, max_secrets| WritingKey {
+            id,
+            name: "ci",
+            max_secrets,
+        };
+        let put = |org: &str, name: &str, max_reads, writer| {
+            s.put_org_secret(
+                org,
+                name,
+                "v",
+                None,
+                max_reads,
+                true,
+                None,
+                Some("alice"),
+                None,
+                Some(writer),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(put("org_1", "A", None, key("k1", Some(2))), Ok(()));
+        // Burned secrets no longer count against the quota.
+        assert_eq!(put("org_1", "B", Some(1), key("k1", Some(2))), Ok(()));
+        s.get_org_secret("org_1", "B", None).unwrap();
+        assert_eq!(put("org_1", "C", None, key("k1", Some(2))), Ok(()));
+        assert_eq!(
+            put("org_1", "D", None, key("k1", Some(2))),
+            Err(PutConflict::Quota {
+                key_name: "ci".into(),
+                max: 2
+            })
+        );
+        assert!(matches!(
+            s.get_org_secret("org_1", "D", None).unwrap(),
+            GetResult::NotFound
+        ));
+
+        // Overwriting a secret the key already holds does not add one.
+        assert_eq!(put("org_1", "A", None, key("k1", Some(2))), Ok(()));
+        // Another key with the same name, or another org, has its own count.
+        assert_eq!(put("org_1", "D", None, key("k2", Some(1))), Ok(()));
+        assert_eq!(put("org_2", "A", None, key("k1", Some(2))), Ok(()));
+    }
+
+    #[test]
+    fn store_operations_emit_spans() {
+        use std::sync::Mutex;
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+
+        /// Records the names of every span opened while it is installed.
+        struct SpanNames(Arc<Mutex<Vec<String>>>);
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanNames {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                _id: &tracing::span::Id,
+                _ctx: Context<'_, S>,
+            ) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(attrs.metadata().name().to_owned());
+            }
+        }
+
+        let (s, _dir) = make_store();
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(SpanNames(names.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            s.put(&NewSecret {
+                key: "K".into(),
+                value: "v".into(),
+                delete: true,
+                ..NewSecret::default()
+            })
+            .unwrap();
+            s.get("K").unwrap();
+            s.list().unwrap();
+        });
+
+        let names = names.lock().unwrap();
+        assert!(names.contains(&"store.put".to_owned()));
+        assert!(names.contains(&"store.get".to_owned()));
+        assert!(names.contains(&"store.list".to_owned()));
+    }
+
+    /// Audit rows actually committed to the table, bypassing any buffer.
+    fn audit_rows(s: &Store) -> u64 {
+        use redb::ReadableTableMetadata;
+        let read_txn = s.db.begin_read().unwrap();
+        read_txn.open_table(AUDIT_LOG).unwrap().len().unwrap()
+    }
+
+    #[test]
+    fn batched_audit_matches_immediate_audit() {
+        let record_all = |s: &Store| {
+            for (i, action) in ["secret.create", "secret.read", "secret.delete"]
+                .iter()
+                .enumerate()
+            {
+                s.record_audit(AuditEvent::new(
+                    action,
+                    Some(format!("K{i}")),
+                    "127.0.0.1".into(),
+                    true,
+                    None,
+                    None,
+                    None,
+                ))
+                .unwrap();
+            }
+        };
+        let query = AuditQuery {
+            since: None,
+            until: None,
+            action: None,
+            limit: 100,
+            org_id: None,
+        };
+        let summary = |events: Vec<AuditEvent>| {
+            events
+                .into_iter()
+                .map(|e| (e.id, e.action, e.key))
+                .collect::<Vec<_>>()
+        };
+
+        let (immediate, _d1) = make_store();
+        record_all(&immediate);
+
+        let (batched, _d2) = make_store();
+        let batched = batched.with_audit_batch(10);
+        record_all(&batched);
+        // Nothing has been written yet; a query flushes the buffer first.
+        assert_eq!(audit_rows(&batched), 0);
+        assert_eq!(
+            summary(batched.list_audit(&query).unwrap()),
+            summary(immediate.list_audit(&query).unwrap())
+        );
+
+        // Reaching the batch size flushes without a query.
+        let (small, _d3) = make_store();
+        let small = small.with_audit_batch(2);
+        record_all(&small);
+        assert_eq!(audit_rows(&small), 2);
+    }
+
+    #[test]
+    fn failed_audit_flush_keeps_the_events_for_the_next_one() {
+        use redb::TableDefinition;
+
+        let (s, _dir) = make_store();
+        let s = s.with_audit_batch(10);
+        for key in ["A", "B"] {
+            s.record_audit(AuditEvent::new(
+                "secret.create",
+                Some(key.into()),
+                "127.0.0.1".into(),
+                true,
+                None,
+                None,
+                None,
+            ))
+            .unwrap();
+        }
+        let broken_log = TableDefinition::<&str, &str>::new("audit_log");
+        let txn = s.db.begin_write().unwrap();
+        txn.delete_table(AUDIT_LOG).unwrap();
+        txn.open_table(broken_log).unwrap();
+        txn.commit().unwrap();
+        assert!(s.flush_audit().is_err());
+
+        let txn = s.db.begin_write().unwrap();
+        txn.delete_table(broken_log).unwrap();
+        txn.open_table(AUDIT_LOG).unwrap();
+        txn.commit().unwrap();
+        s.flush_audit().unwrap();
+        let keys: Vec<_> = s
+            .list_audit(&AuditQuery {
+                since: None,
+                until: None,
+                action: None,
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.id, e.key.unwrap()))
+            .collect();
+        assert_eq!(keys, [(2, "B".to_owned()), (1, "A".to_owned())]);
+    }
+
+    #[test]
+    fn audit_actions_match_full_name_or_verb() {
+        let (s, _dir) = make_store();
+        assert!(s.audit_enabled("secret.read"));
+        let s = s.with_audit_actions(vec!["create".into(), "webhook.delete".into()]);
+        assert!(s.audit_enabled("secret.create"));
+        assert!(s.audit_enabled("org.create"));
+        assert!(s.audit_enabled("webhook.delete"));
+        assert!(!s.audit_enabled("secret.delete"));
+        assert!(!s.audit_enabled("secret.read"));
+    }
+
+    #[test]
+    fn record_audit_counts_each_action() {
+        let (s, _dir) = make_store();
+        let s = s.with_audit_actions(vec!["create".into()]);
+        let event = |action: &str, success: bool| {
+            AuditEvent::new(action, None, "127.0.0.1".into(), success, None, None, None)
+        };
+        s.record_audit(event("secret.create", true)).unwrap();
+        s.record_audit(event("secret.create", true)).unwrap();
+        s.record_audit(event("secret.read", false)).unwrap();
+
+        let counts = s.audit_counts();
+        assert_eq!(counts[&("secret.create".to_owned(), true)], 2);
+        // Counted even though the action filter keeps it out of the log.
+        assert_eq!(counts[&("secret.read".to_owned(), false)], 1);
+        assert!(!counts.contains_key(&("secret.read".to_owned(), true)));
+    }
+
+    #[test]
+    fn etag_changes_on_every_write_but_not_on_reads_or_rotation() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "K".into(),
+            value: "v".into(),
+            max_reads: Some(10),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        let etag = || s.head("K").unwrap().unwrap().0.etag();
+        let created = etag();
+
+        s.get("K").unwrap();
+        assert_eq!(etag(), created);
+        s.touch("K", 3600).unwrap();
+        let touched = etag();
+        assert_ne!(touched, created);
+        s.rotate(&super::super::crypto::generate_key(), 2).unwrap();
+        assert_eq!(etag(), touched);
+
+        let stale = Precondition {
+            if_match: Some(vec![created]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            s.delete_if("K", &stale).unwrap(),
+            ConditionalDelete::PreconditionFailed
+        ));
+        let current = Precondition {
+            if_match: Some(vec![touched]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            s.delete_if("K", &current).unwrap(),
+            ConditionalDelete::Deleted(meta) if meta.read_count == 1
+        ));
+        assert!(matches!(
+            s.delete_if("K", &current).unwrap(),
+            ConditionalDelete::NotFound
+        ));
+    }
+
+    #[test]
+    fn reader_tags_survive_rotation_and_reopen() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let s = Store::open(&path, super::super::crypto::generate_key()).unwrap();
+        s.put(&NewSecret {
+            key: "K".into(),
+            value: "v".into(),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        let readers = |s: &Store| s.head("K").unwrap().unwrap().0.distinct_readers;
+
+        s.get_from("K", "10.0.0.1").unwrap();
+        let new_key = super::super::crypto::generate_key();
+        let new_key_bytes = *new_key.as_bytes();
+        s.rotate(&new_key, 2).unwrap();
+        s.get_from("K", "10.0.0.1").unwrap();
+        assert_eq!(readers(&s), 1);
+        drop(s);
+
+        let key = super::super::crypto::load_key(&new_key_bytes).unwrap();
+        let s = Store::open(&path, key).unwrap();
+        s.get_from("K", "10.0.0.1").unwrap();
+        assert_eq!(readers(&s), 1);
+        s.get_from("K", "10.0.0.2").unwrap();
+        assert_eq!(readers(&s), 2);
+    }
+
+    #[test]
+    fn value_fingerprints_survive_rotation() {
+        let (s, _dir) = make_store();
+        for (k, v) in [("A", "one"), ("B", "two")] {
+            s.put(&NewSecret {
+                key: k.to_owned(),
+                value: v.to_owned(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+        let fingerprint = |k: &str| s.head(k).unwrap().unwrap().0.value_fingerprint;
+        let before = [fingerprint("A"), fingerprint("B")];
+        assert!(before.iter().all(Option::is_some));
+
+        s.rotate(&super::super::crypto::generate_key(), 2).unwrap();
+        assert_eq!([fingerprint("A"), fingerprint("B")], before);
+
+        // A value written under the new key fingerprints as it did before.
+        s.patch(
+            "A",
+            Some("one"),
+            None,
+            None,
+            AnnotationsPatch::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(fingerprint("A"), before[0]);
+    }
+
+    #[test]
+    fn delete_with_meta_keeps_a_record_it_cannot_decode() {
+        let (s, _dir) = make_store();
+        let txn = s.db.begin_write().unwrap();
+        txn.open_table(SECRETS)
+            .unwrap()
+            .insert("BAD", [RECORD_V3_MARKER, 1, 0xff].as_slice())
+            .unwrap();
+        txn.commit().unwrap();
+
+        assert!(s.delete_with_meta("BAD").is_err());
+        let read_txn = s.db.begin_read().unwrap();
+        let table = read_txn.open_table(SECRETS).unwrap();
+        assert!(table.get("BAD").unwrap().is_some());
+    }
+
+    #[test]
+    fn rotate_records_key_rotate_audit_event() {
+        let (s, _dir) = make_store();
+        for k in ["A", "B"] {
+            s.put(&NewSecret {
+                key: k.to_owned(),
+                value: "v".into(),
+                delete: true,
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+        let new_key = super::super::crypto::generate_key();
+        assert_eq!(s.rotate(&new_key, 2).unwrap(), 2);
+
+        let events = s
+            .list_audit(&AuditQuery {
+                since: None,
+                until: None,
+                action: Some("key.rotate".into()),
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].detail.as_deref(),
+            Some("old_key_version=1 new_key_version=2 rotated=2")
+        );
+    }
+
+    #[test]
+    fn records_written_before_the_layout_changes_still_decode() {
+        use serde::Serialize;
+
+        /// `SecretRecord` exactly as the first release stored it.
+        #[derive(Serialize)]
+        struct BaselineRecord {
+            value_encrypted: Vec<u8>,
+            nonce: [u8; 12],
+            created_at: i64,
+            expires_at: Option<i64>,
+            max_reads: Option<u32>,
+            read_count: u32,
+            delete: bool,
+            webhook_url: Option<String>,
+            owner_id: Option<String>,
+            org_id: Option<String>,
+            allowed_keys: Option<Vec<String>>,
+        }
+
+        let (s, _dir) = make_store();
+        let (value_encrypted, nonce) =
+            super::super::crypto::encrypt(&s.active_key().0, b"old-value").unwrap();
+        let baseline = BaselineRecord {
+            value_encrypted,
+            nonce,
+            created_at: 1_700_000_000,
+            expires_at: None,
+            max_reads: Some(5),
+            read_count: 1,
+            delete: false,
+            webhook_url: Some("https://hooks.example.com/x".into()),
+            owner_id: None,
+            org_id: None,
+            allowed_keys: None,
+        };
+        let raw = bincode::serde::encode_to_vec(&baseline, bincode::config::standard()).unwrap();
+        let mut prefixed = vec![RECORD_V2_MARKER, 1];
+        prefixed.extend_from_slice(&raw);
+        let txn = s.db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(SECRETS).unwrap();
+            table.insert("V1", raw.as_slice()).unwrap();
+            table.insert("V2", prefixed.as_slice()).unwrap();
+        }
+        txn.commit().unwrap();
+
+        for key in ["V1", "V2"] {
+            let (meta, _) = s.head(key).unwrap().unwrap();
+            assert_eq!((meta.created_at, meta.read_count), (1_700_000_000, 1));
+            assert_eq!(meta.updated_at, 1_700_000_000);
+            assert!(matches!(s.get(key).unwrap(), GetResult::Value(v, _) if v == "old-value"));
+        }
+        // The read rewrote the record in the current layout.
+        let txn = s.db.begin_read().unwrap();
+        let table = txn.open_table(SECRETS).unwrap();
+        assert_eq!(
+            table.get("V1").unwrap().unwrap().value()[0],
+            RECORD_V3_MARKER
+        );
+        assert_eq!(s.check().unwrap().ok, 2);
+    }
+
+    #[test]
+    fn audit_until_seeks_to_the_newest_matching_event() {
+        let (s, _dir) = make_store();
+        // Two events per second from t=1000, with the oldest ten pruned.
+        let mut events: Vec<_> = (0..60)
+            .map(|i| {
+                let mut event = AuditEvent::new("probe", None, "ip".into(), true, None, None, None);
+                event.timestamp = 1000 + i / 2;
+                event
+            })
+            .collect();
+        s.write_audit_events(&mut events).unwrap();
+        let txn = s.db.begin_write().unwrap();
+        {
+            let mut table = txn.open_table(AUDIT_LOG).unwrap();
+            for id in 1..=10 {
+                table.remove(id).unwrap();
+            }
+        }
+        txn.commit().unwrap();
+
+        let read_txn = s.db.begin_read().unwrap();
+        let table = read_txn.open_table(AUDIT_LOG).unwrap();
+        assert_eq!(last_audit_id_until(&table, 999).unwrap(), None);
+        assert_eq!(last_audit_id_until(&table, 1004).unwrap(), None);
+        assert_eq!(last_audit_id_until(&table, 1005).unwrap(), Some(12));
+        assert_eq!(last_audit_id_until(&table, 1017).unwrap(), Some(36));
+        assert_eq!(last_audit_id_until(&table, 2000).unwrap(), Some(60));
+
+        let query = AuditQuery {
+            since: Some(1010),
+            until: Some(1012),
+            action: None,
+            limit: 100,
+            org_id: None,
+        };
+        let ids: Vec<u64> = s.list_audit(&query).unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, [26, 25, 24, 23, 22, 21]);
+    }
+
+    #[test]
+    fn audit_events_from_before_versioning_still_list() {
+        use serde::Serialize;
+
+        /// `AuditEvent` exactly as the first release stored it.
+        #[derive(Serialize)]
+        struct BaselineEvent {
+            id: u64,
+            timestamp: i64,
+            action: String,
+            key: Option<String>,
+            source_ip: String,
+            success: bool,
+            detail: Option<String>,
+            org_id: Option<String>,
+            principal_id: Option<String>,
+        }
+
+        let (s, _dir) = make_store();
+        let old = BaselineEvent {
+            id: 1,
+            timestamp: Store::now() - 10,
+            action: "secret.create".into(),
+            key: Some("OLD".into()),
+            source_ip: "10.0.0.1".into(),
+            success: true,
+            detail: None,
+            org_id: None,
+            principal_id: None,
+        };
+        let raw = bincode::serde::encode_to_vec(&old, bincode::config::standard()).unwrap();
+        let txn = s.db.begin_write().unwrap();
+        {
+            txn.open_table(AUDIT_LOG)
+                .unwrap()
+                .insert(1, raw.as_slice())
+                .unwrap();
+            txn.open_table(COUNTERS)
+                .unwrap()
+                .insert(AUDIT_SEQ_KEY, 1)
+                .unwrap();
+        }
+        txn.commit().unwrap();
+
+        s.record_audit(AuditEvent::new(
+            "secret.delete",
+            Some("OLD".into()),
+            "10.0.0.1".into(),
+            true,
+            None,
+            None,
+            None,
+        ))
+        .unwrap();
+        let events = s
+            .list_audit(&AuditQuery {
+                since: None,
+                until: None,
+                action: None,
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap();
+        let actions: Vec<_> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["secret.delete", "secret.create"]);
+        assert_eq!(events[1].key.as_deref(), Some("OLD"));
+        assert!(events[1].key_label.is_none());
+        assert_eq!(s.prune_audit(5).unwrap(), 1);
+    }
+
+    #[test]
+    fn rotation_rolls_back_when_its_audit_event_cannot_be_written() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "A".into(),
+            value: "v".into(),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        // Swap the audit table for one of another type so every audit write fails.
+        let txn = s.db.begin_write().unwrap();
+        txn.delete_table(AUDIT_LOG).unwrap();
+        txn.open_table(TableDefinition::<&str, &str>::new("audit_log"))
+            .unwrap();
+        txn.commit().unwrap();
+        assert!(s
+            .record_audit(AuditEvent::new(
+                "probe",
+                None,
+                "ip".into(),
+                true,
+                None,
+                None,
+                None
+            ))
+            .is_err());
+
+        let new_key = super::super::crypto::generate_key();
+        assert!(s.rotate(&new_key, 2).is_err());
+
+        // Nothing moved to the new key, so the key on disk still opens everything.
+        assert_eq!(s.max_key_version().unwrap(), 1);
+        let report = s.check().unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert!(matches!(s.get("A").unwrap(), GetResult::Value(v, _) if v == "v"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn reads_complete_under_a_single_decrypt_permit() {
+        let (store, _dir) = make_store();
+        let store = store.with_max_concurrent_decrypts(1);
+        store
+            .put(&NewSecret {
+                key: "K".into(),
+                value: "v".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+
+        let active = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let (store, active, peak) = (store.clone(), active.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let _permit = store.decrypt_limiter().acquire().await;
+                    let now = active.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, std::sync::atomic::Ordering::SeqCst);
+                    let value = if i % 2 == 0 {
+                        store.peek("K").unwrap()
+                    } else {
+                        match store.get("K").unwrap() {
+                            GetResult::Value(v, _) => Some(v),
+                            other => panic!("unexpected {other:?}"),
+                        }
+                    };
+                    active.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                    value
+                })
+            })
+            .collect();
+        for h in handles {
+            assert_eq!(h.await.unwrap().as_deref(), Some("v"));
+        }
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn second_open_of_same_db_fails_with_lock_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let first = Store::open(&path, super::super::crypto::generate_key()).unwrap();
+
+        let err = Store::open(&path, super::super::crypto::generate_key())
+            .err()
+            .expect("second open must fail");
+        assert!(err.to_string().contains("already in use"), "{err}");
+        assert!(err.to_string().contains(&std::process::id().to_string()));
+
+        drop(first);
+        Store::open(&path, super::super::crypto::generate_key()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn scheduled_compaction_reclaims_space_after_bulk_delete() {
+        let (s, _dir) = make_store();
+        let value = "x".repeat(4096);
+        for i in 0..500 {
+            s.put(&NewSecret {
+                key: format!("K{i}"),
+                value: value.to_owned(),
+                delete: true,
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+        for i in 0..500 {
+            s.delete(&format!("K{i}")).unwrap();
+        }
+        let before = s.file_len().unwrap();
+        assert!(s.free_ratio().unwrap() >= COMPACT_MIN_FREE_RATIO);
+
+        s.clone()
+            .spawn_compaction(Duration::from_millis(50), Arc::default());
+        let mut after = before;
+        for _ in 0..100 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            after = s.file_len().unwrap();
+            if after < before {
+                break;
+            }
+        }
+        assert!(after < before, "file did not shrink: {before} -> {after}");
+        s.ping().unwrap();
+    }
+
+    #[test]
+    fn audit_ip_mode_truncates_or_drops_source_ip() {
+        let record = |mode: AuditIpMode, ip: &str| {
+            let (s, _dir) = make_store();
+            let s = s.with_audit_ip_mode(mode);
+            s.record_audit(AuditEvent::new(
+                "secret.read",
+                None,
+                ip.into(),
+                true,
+                None,
+                None,
+                None,
+            ))
+            .unwrap();
+            let query = AuditQuery {
+                since: None,
+                until: None,
+                action: None,
+                limit: 10,
+                org_id: None,
+            };
+            s.list_audit(&query).unwrap()[0].source_ip.clone()
+        };
+        assert_eq!(record(AuditIpMode::Full, "203.0.113.45"), "203.0.113.45");
+        assert_eq!(record(AuditIpMode::Truncate, "203.0.113.45"), "203.0.113.0");
+        assert_eq!(
+            record(AuditIpMode::Truncate, "2001:db8:abcd:12:34::1"),
+            "2001:db8:abcd::"
+        );
+        assert_eq!(record(AuditIpMode::None, "203.0.113.45"), "-");
+    }
+
+    #[test]
+    fn delete_matching_only_removes_public_secrets_expiring_before_cutoff() {
+        let (s, _dir) = make_store();
+        for (key, ttl) in [("SOON", Some(60)), ("LATER", Some(7200)), ("FOREVER", None)] {
+            s.put(&NewSecret {
+                key: key.to_owned(),
+                value: "v".into(),
+                ttl_seconds: ttl,
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+        let cutoff = Store::now() + 3600;
+        s.put_org_secret(
+            "org_a",
+            "SOON",
+            "v",
+            Some(Store::now() + 60),
+            None,
+            false,
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(s.delete_matching(&DeleteCriteria::default()).is_err());
+        let criteria = DeleteCriteria {
+            expires_before: Some(cutoff),
+            ..Default::default()
+        };
+        assert_eq!(s.delete_matching(&criteria).unwrap(), vec!["SOON"]);
+        assert!(s.head("SOON").unwrap().is_none());
+        assert!(s.head("LATER").unwrap().is_some());
+        assert!(s.head("FOREVER").unwrap().is_some());
+        assert!(s.head_org_secret("org_a", "SOON").unwrap().is_some());
+    }
+
+    #[test]
+    fn aliases_point_at_stored_keys_only() {
+        use crate::store::AliasError;
+
+        let (s, _dir) = make_store();
+        for key in ["NEW", "OTHER"] {
+            s.put(&NewSecret {
+                key: key.to_owned(),
+                value: "v".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+
+        assert_eq!(s.put_alias("OLD", "NEW").unwrap(), Ok("NEW".into()));
+        // Re-adding the same alias is a no-op; an alias of an alias is flattened.
+        assert_eq!(s.put_alias("OLD", "NEW").unwrap(), Ok("NEW".into()));
+        assert_eq!(s.put_alias("OLDER", "OLD").unwrap(), Ok("NEW".into()));
+        assert_eq!(s.resolve_alias("OLDER").unwrap().as_deref(), Some("NEW"));
+
+        // Pointing NEW's own alias back at it would be a cycle.
+        assert_eq!(
+            s.put_alias("NEW", "OLD").unwrap(),
+            Err(AliasError::SelfAlias)
+        );
+        assert_eq!(
+            s.put_alias("OTHER", "NEW").unwrap(),
+            Err(AliasError::KeyExists)
+        );
+        assert_eq!(s.put_alias("OLD", "OTHER").unwrap(), Err(AliasError::Taken));
+        assert_eq!(
+            s.put_alias("X", "MISSING").unwrap(),
+            Err(AliasError::NotFound)
+        );
+
+        let (meta, _) = s.head("OLDER").unwrap().unwrap();
+        assert_eq!(meta.key, "NEW");
+        assert!(!s.delete_alias("OLD", "OTHER").unwrap());
+        assert!(s.delete_alias("OLD", "NEW").unwrap());
+        assert!(s.head("OLD").unwrap().is_none());
+    }
+
+    #[test]
+    fn put_many_conflicts_store_nothing() {
+        let (s, _dir) = make_store();
+        let secret = |key: &str, value: &str| NewSecret {
+            key: key.into(),
+            value: value.into(),
+            ..NewSecret::default()
+        };
+        s.put_many(&[secret("TAKEN", "old")], false, None)
+            .unwrap()
+            .unwrap();
+        s.put_alias("ALIAS", "TAKEN").unwrap().unwrap();
+
+        assert_eq!(
+            s.put_many(&[secret("FRESH", "v"), secret("TAKEN", "new")], true, None)
+                .unwrap(),
+            Err(PutConflict::Exists("TAKEN".into()))
+        );
+        assert_eq!(
+            s.put_many(&[secret("FRESH", "v"), secret("ALIAS", "v")], false, None)
+                .unwrap(),
+            Err(PutConflict::Aliased {
+                key: "ALIAS".into(),
+                target: "TAKEN".into()
+            })
+        );
+        assert!(s.head("FRESH").unwrap().is_none());
+        assert_eq!(s.peek("TAKEN").unwrap().as_deref(), Some("old"));
+
+        assert_eq!(
+            s.put_many(&[secret("FRESH", "v"), secret("TAKEN", "new")], false, None)
+                .unwrap(),
+            Ok(PutReport {
+                replaced: vec![false, true],
+                stored: None
+            })
+        );
+    }
+
+    #[test]
+    fn put_many_refuses_new_keys_past_the_cap() {
+        let (s, _dir) = make_store();
+        let secret = |key: &str| NewSecret {
+            key: key.into(),
+            value: "v".into(),
+            ..NewSecret::default()
+        };
+        // Org-scoped secrets live outside the public bucket.
+        s.put_org_secret("org_1", "A", "v", None, None, true, None, None, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            s.put_many(&[secret("A"), secret("B")], false, Some(2))
+                .unwrap()
+                .unwrap()
+                .stored,
+            Some(2)
+        );
+        assert_eq!(s.count_public().unwrap(), 2);
+
+        assert_eq!(
+            s.put_many(&[secret("A"), secret("C")], false, Some(2))
+                .unwrap(),
+            Err(PutConflict::Full { max: 2 })
+        );
+        assert!(s.head("C").unwrap().is_none());
+        // Overwrites alone never count as growth.
+        assert_eq!(
+            s.put_many(&[secret("A"), secret("B")], false, Some(2))
+                .unwrap(),
+            Ok(PutReport {
+                replaced: vec![true, true],
+                stored: Some(2)
+            })
+        );
+    }
+
+    #[test]
+    fn removing_a_secret_removes_its_aliases() {
+        let (s, _dir) = make_store();
+        let secret = |key: &str, ttl_seconds: Option<u64>, max_reads: Option<u32>| NewSecret {
+            key: key.into(),
+            value: "v".into(),
+            ttl_seconds,
+            max_reads,
+            delete: true,
+            ..NewSecret::default()
+        };
+        let aliased = |key: &str| {
+            s.put_alias(&format!("{key}_OLD"), key).unwrap().unwrap();
+        };
+        let has_alias = |key: &str| s.resolve_alias(&format!("{key}_OLD")).unwrap().is_some();
+        s.put_many(
+            &[
+                secret("DELETED", None, None),
+                secret("BURNED", None, Some(1)),
+                secret("MATCHED", Some(60), None),
+                secret("KEPT", None, None),
+            ],
+            false,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        for key in ["DELETED", "BURNED", "MATCHED", "KEPT"] {
+            aliased(key);
+        }
+        // An alias name cannot be reused for a secret while it stands.
+        assert!(s
+            .put(&NewSecret {
+                key: "KEPT_OLD".into(),
+                value: "v".into(),
+                ..NewSecret::default()
+            })
+            .is_err());
+
+        assert!(s.delete("DELETED").unwrap());
+        assert!(matches!(s.get("BURNED").unwrap(), GetResult::Burned(..)));
+        let criteria = DeleteCriteria {
+            expires_before: Some(Store::now() + 3600),
+            not_accessed_since: None,
+        };
+        assert_eq!(s.delete_matching(&criteria).unwrap(), vec!["MATCHED"]);
+        assert!(!has_alias("DELETED"));
+        assert!(!has_alias("BURNED"));
+        assert!(!has_alias("MATCHED"));
+        assert!(has_alias("KEPT"));
+
+        // A new secret under a removed key is not reachable by the old alias,
+        // and the old alias name is free again.
+        s.put_many(&[secret("DELETED", None, None)], false, None)
+            .unwrap()
+            .unwrap();
+        assert!(s.head("DELETED_OLD").unwrap().is_none());
+        s.put_many(&[secret("DELETED_OLD", None, None)], true, None)
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn read_notify_every_fifth_read_only_returns_webhook_on_reads_5_and_10() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "HOT".into(),
+            value: "v".into(),
+            webhook_url: Some("https://hooks.example.com/read".into()),
+            read_notify: ReadNotifyThrottle {
+                every: Some(5),
+                min_interval_secs: None,
+            },
+            ..NewSecret::default()
+        })
+        .unwrap();
+        let notified: Vec<usize> = (1..=12)
+            .filter(|_| matches!(s.get("HOT").unwrap(), GetResult::Value(_, Some(_))))
+            .collect();
+        assert_eq!(notified, [5, 10]);
+    }
+
+    #[test]
+    fn non_utf8_value_fails_cleanly_without_counting_a_read() {
+        let (s, _dir) = make_store();
+        s.put(&NewSecret {
+            key: "BIN".into(),
+            value: "placeholder".into(),
+            max_reads: Some(1),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
+        // Swap in a value no API path could have written.
+        {
+            let write_txn = s.db.begin_write().unwrap();
+            {
+                let mut table = write_txn.open_table(SECRETS).unwrap();
+                let bytes = table.get("BIN").unwrap().unwrap().value().to_vec();
+                let (mut record, version) = decode(&bytes).unwrap();
+                let (ct, nonce) =
+                    super::super::crypto::encrypt(&s.active_key().0, &[0xff, 0xfe]).unwrap();
+                record.value_encrypted = ct;
+                record.nonce = nonce;
+                table
+                    .insert("BIN", encode(&record, version).unwrap().as_slice())
+                    .unwrap();
+            }
+            write_txn.commit().unwrap();
+        }
+
+        let Err(err) = s.get("BIN") else {
+            panic!("non-UTF-8 value must not be returned");
+        };
+        assert_eq!(err.to_string(), VALUE_NOT_UTF8);
+        // The failed read rolled back: the one-shot secret is still there, unread.
+        let (meta, _) = s.head("BIN").unwrap().unwrap();
+        assert_eq!(meta.read_count, 0);
+    }
+
+    #[test]
+    fn configured_durability_applies_to_write_transactions() {
+        // redb only allows persistent savepoints in immediate-durability
+        // transactions, which makes the level observable from outside.
+        let relaxed_allows_savepoint = |s: &Store| {
+            s.db.begin_write_relaxed()
+                .unwrap()
+                .persistent_savepoint()
+                .is_ok()
+        };
+
+        let (s, _dir) = make_store();
+        assert_eq!(s.db.durability(), StoreDurability::Immediate);
+        assert!(relaxed_allows_savepoint(&s));
+
+        for level in [StoreDurability::Eventual, StoreDurability::None] {
+            let s = s.clone().with_durability(level);
+            assert!(!relaxed_allows_savepoint(&s), "{level:?}");
+            // Deletes, burns and rotation never relax.
+            assert!(s.db.begin_write().unwrap().persistent_savepoint().is_ok());
+            s.put(&NewSecret {
+                key: "K".into(),
+                value: "v".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+            assert!(matches!(s.get("K").unwrap(), GetResult::Value(..)));
+        }
+        assert_eq!(
+            "EVENTUAL".parse::<StoreDurability>(),
+            Ok(StoreDurability::Eventual)
+        );
+        assert!("fsync".parse::<StoreDurability>().is_err());
+    }
+
+    #[test]
+    fn check_flags_records_that_fail_to_decrypt() {
+        let (s, _dir) = make_store();
+        for key in ["GOOD", "BAD", "EXPIRED"] {
+            s.put(&NewSecret {
+                key: key.to_owned(),
+                value: "value".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+        {
+            let write_txn = s.db.begin_write().unwrap();
+            {
+                let mut table = write_txn.open_table(SECRETS).unwrap();
+                for key in ["BAD", "EXPIRED"] {
+                    let bytes = table.get(key).unwrap().unwrap().value().to_vec();
+                    let (mut record, version) = decode(&bytes).unwrap();
+                    record.value_encrypted[0] ^= 0xff;
+                    if key == "EXPIRED" {
+                        record.expires_at = Some(1);
+                    }
+                    table
+                        .insert(key, encode(&record, version).unwrap().as_slice())
+                        .unwrap();
+                }
+            }
+            write_txn.commit().unwrap();
+        }
+
+        let report = s.check().unwrap();
+        assert_eq!(report.ok, 1);
+        assert_eq!(report.expired, 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "BAD");
+        // Read-only: the broken record is still there for inspection.
+        assert!(s.head("BAD").unwrap().is_some());
+    }
+
+    #[test]
+    fn open_existing_never_creates_a_database() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.db");
+        let key = || super::super::crypto::load_key(&[7; 32]).unwrap();
+        assert!(Store::open_existing(&path, key()).is_err());
+        assert!(!path.exists());
+
+        let s = Store::open(&path, key()).unwrap();
+        s.put(&NewSecret {
+            key: "K".into(),
+            value: "v".into(),
+            ..NewSecret::default()
+        })
+        .unwrap();
+        drop(s);
+        let report = Store::open_existing(&path, key()).unwrap().check().unwrap();
+        assert_eq!((report.ok, report.failed.len()), (1, 0));
+    }
+
+    #[test]
+    fn deterministic_nonces_give_stable_ciphertext_and_random_ones_do_not() {
+        let sealed = |deterministic: bool| {
+            let dir = tempdir().unwrap();
+            let key = super::super::crypto::load_key(&[7; 32]).unwrap();
+            let mut s = Store::open(&dir.path().join("test.db"), key).unwrap();
+            if deterministic {
+                s = s.with_deterministic_nonces(0);
+            }
+            s.put(&NewSecret {
+                key: "K".into(),
+                value: "value".into(),
+                ..NewSecret::default()
+            })
+            .unwrap();
+            let read_txn = s.db.begin_read().unwrap();
+            let table = read_txn.open_table(SECRETS).unwrap();
+            let (record, version) = decode(table.get("K").unwrap().unwrap().value()).unwrap();
+            assert_eq!(s.decrypt_value(&record, version).unwrap(), "value");
+            (record.value_encrypted.clone(), record.nonce)
+        };
+
+        assert_eq!(sealed(true), sealed(true));
+        assert_ne!(sealed(false), sealed(false));
+    }
+
+    #[test]
+    fn prune_by_reason_expired_leaves_tombstones() {
+        let (s, _dir) = make_store();
+        for (key, ttl, max_reads) in [("DEAD", Some(0), None), ("SEALED", None, Some(1))] {
+            s.put(&NewSecret {
+                key: key.to_owned(),
+                value: "v".into(),
+                ttl_seconds: ttl,
+                max_reads,
+                ..NewSecret::default()
+            })
+            .unwrap();
+        }
+        assert!(matches!(s.get("SEALED").unwrap(), GetResult::Value(..)));
+
+        assert_eq!(
+            s.prune_by(PruneScope::Expired).unwrap(),
+            vec![("DEAD".to_owned(), PruneReason::Expired)]
+        );
+        assert!(s.prune().unwrap().is_empty(), "sweeps keep tombstones");
+        assert!(s.head("SEALED").unwrap().is_some());
+        assert_eq!(
+            s.prune_by(PruneScope::Tombstoned).unwrap(),
+            vec![("SEALED".to_owned(), PruneReason::Tombstoned)]
+        );
+    }
 }
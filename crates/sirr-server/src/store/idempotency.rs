@@ -0,0 +1,90 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use redb::{ReadableTableMetadata, TableDefinition};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const IDEMPOTENCY: TableDefinition<&str, &[u8]> = TableDefinition::new("idempotency");
+
+/// Response recorded for an `Idempotency-Key`, replayed on retries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotentResponse {
+    pub status: u16,
+    /// JSON response body. Never contains secret values.
+    pub body: String,
+    pub expires_at: i64,
+}
+
+impl IdempotentResponse {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+pub(crate) fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+impl super::db::Store {
+    /// Look up the live response recorded for `key`, if any.
+    pub fn get_idempotent(&self, key: &str) -> Result<Option<IdempotentResponse>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(IDEMPOTENCY)?;
+        let Some(guard) = table.get(key)? else {
+            return Ok(None);
+        };
+        let (resp, _): (IdempotentResponse, _) =
+            bincode::serde::decode_from_slice(guard.value(), bincode::config::standard())
+                .context("bincode decode idempotent response")?;
+        Ok(Some(resp).filter(|r| !r.is_expired(now())))
+    }
+
+    /// Record the response for `key`, kept for `ttl_seconds`.
+    pub fn put_idempotent(
+        &self,
+        key: &str,
+        status: u16,
+        body: &str,
+        ttl_seconds: u64,
+    ) -> Result<()> {
+        let resp = IdempotentResponse {
+            status,
+            body: body.to_owned(),
+            expires_at: super::model::expiry_after(now(), ttl_seconds),
+        };
+        let bytes = bincode::serde::encode_to_vec(&resp, bincode::config::standard())
+            .context("bincode encode idempotent response")?;
+
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(IDEMPOTENCY)?;
+            table.insert(key, bytes.as_slice())?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Drop expired idempotency records. Returns how many were removed.
+    pub fn prune_idempotency(&self) -> Result<usize> {
+        let now = now();
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(IDEMPOTENCY)?;
+            let before = table.len()?;
+            table.retain(|_, v| {
+                bincode::serde::decode_from_slice::<IdempotentResponse, _>(
+                    v,
+                    bincode::config::standard(),
+                )
+                .map(|(r, _)| !r.is_expired(now))
+                .unwrap_or(false)
+            })?;
+            (before - table.len()?) as usize
+        };
+        write_txn.commit()?;
+        Ok(removed)
+    }
+}
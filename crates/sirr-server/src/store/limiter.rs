@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Async counting semaphore bounding concurrent decrypting reads.
+/// Decryption is CPU-bound, so a burst of reads of large values is throttled
+/// here instead of saturating every core. Callers await a permit before the
+/// store opens its write transaction, so a waiting read never holds the
+/// writer lock or blocks a runtime thread.
+#[derive(Clone)]
+pub struct DecryptLimiter {
+    permits: Arc<Semaphore>,
+    max: u32,
+}
+
+/// Held for the duration of one read; releases its permits on drop.
+pub struct DecryptPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl DecryptLimiter {
+    pub(crate) fn new(permits: usize) -> Self {
+        let max = u32::try_from(permits.max(1)).unwrap_or(u32::MAX);
+        Self {
+            permits: Arc::new(Semaphore::new(max as usize)),
+            max,
+        }
+    }
+
+    /// Wait for a free permit.
+    pub async fn acquire(&self) -> DecryptPermit {
+        self.acquire_many(1).await
+    }
+
+    /// Wait for one permit per value a read decrypts, so a batch read
+    /// weighs as much as that many single reads. Capped at the limiter's
+    /// size, where the batch has every permit to itself.
+    pub async fn acquire_many(&self, decrypts: usize) -> DecryptPermit {
+        let n = u32::try_from(decrypts)
+            .unwrap_or(u32::MAX)
+            .clamp(1, self.max);
+        let permit = self
+            .permits
+            .clone()
+            .acquire_many_owned(n)
+            .await
+            .expect("decrypt semaphore is never closed");
+        DecryptPermit { _permit: permit }
+    }
+}
+
+/// Default permit count: one per available CPU.
+pub(crate) fn default_permits() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrency_is_bounded_by_permits() {
+        let limiter = DecryptLimiter::new(3);
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..32)
+            .map(|_| {
+                let (limiter, active, peak) = (limiter.clone(), active.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire().await;
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for h in handles {
+            h.await.unwrap();
+        }
+
+        let peak = peak.load(Ordering::SeqCst);
+        assert!((2..=3).contains(&peak), "peak concurrency {peak}");
+        assert_eq!(limiter.permits.available_permits(), 3);
+    }
+
+    #[tokio::test]
+    async fn batch_reads_take_a_permit_per_decrypt() {
+        let limiter = DecryptLimiter::new(3);
+        let batch = limiter.acquire_many(2).await;
+        assert_eq!(limiter.permits.available_permits(), 1);
+        drop(batch);
+
+        // A batch larger than the limiter takes every permit, not more.
+        let batch = limiter.acquire_many(10).await;
+        assert_eq!(limiter.permits.available_permits(), 0);
+        drop(batch);
+        assert_eq!(limiter.permits.available_permits(), 3);
+    }
+}
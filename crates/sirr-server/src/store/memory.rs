@@ -0,0 +1,536 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use super::alias::AliasError;
+use super::audit::{AuditCounters, AuditEvent, AuditQuery};
+use super::backend::SecretStore;
+use super::crypto::{self, EncryptionKey};
+use super::db::GetResult;
+use super::idempotency::IdempotentResponse;
+use super::model::{
+    expiry_after, AnnotationsPatch, ConditionalDelete, DeleteCriteria, NewSecret, Precondition,
+    PruneReason, PruneScope, PutConflict, PutReport, SecretMeta, SecretRecord, SecretStatus,
+};
+use crate::webhooks::WebhookRegistration;
+
+/// Non-persistent [`SecretStore`] for tests and ephemeral deployments.
+/// Values are held in plaintext; the record's ciphertext fields stay empty.
+#[derive(Clone)]
+pub struct MemoryStore {
+    inner: Arc<Mutex<Inner>>,
+    /// Random per-instance key for value fingerprints.
+    fingerprint_key: Arc<EncryptionKey>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            inner: Arc::default(),
+            fingerprint_key: Arc::new(crypto::generate_key()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    secrets: BTreeMap<String, (String, SecretRecord)>,
+    /// alias → canonical key.
+    aliases: BTreeMap<String, String>,
+    audit: Vec<AuditEvent>,
+    audit_counts: AuditCounters,
+    webhooks: BTreeMap<String, WebhookRegistration>,
+    idempotency: BTreeMap<String, IdempotentResponse>,
+    /// Last revision handed out (see `SecretRecord::revision`).
+    revision_seq: u64,
+}
+
+impl Inner {
+    fn next_revision(&mut self) -> u64 {
+        self.revision_seq += 1;
+        self.revision_seq
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64
+    }
+
+    /// Get-and-increment, noting `reader` when given.
+    fn read(&self, key: &str, reader: Option<u64>) -> Result<GetResult> {
+        let now = Self::now();
+        let mut inner = self.lock();
+        let key = inner.resolve(key);
+        let key = key.as_str();
+        let Some((value, record)) = inner.secrets.get_mut(key) else {
+            return Ok(GetResult::NotFound);
+        };
+        if record.is_expired(now) {
+            inner.remove(key);
+            return Ok(GetResult::NotFound);
+        }
+        if record.is_sealed() {
+            return Ok(GetResult::Sealed);
+        }
+        record.read_count += 1;
+        record.last_accessed_at = Some(now);
+        if let Some(tag) = reader {
+            record.note_reader(tag);
+        }
+        if record.is_burned() {
+            let (value, record) = inner.remove(key).expect("entry present");
+            return Ok(GetResult::Burned(
+                value,
+                record.webhook_url_for("secret.burned"),
+            ));
+        }
+        let webhook_url = record
+            .webhook_url_for("secret.read")
+            .filter(|_| record.take_read_notification(now));
+        Ok(GetResult::Value(value.clone(), webhook_url))
+    }
+}
+
+impl Inner {
+    /// `name`, or the key it aliases when no secret is stored under it.
+    fn resolve(&self, name: &str) -> String {
+        match self.aliases.get(name) {
+            Some(target) if !self.secrets.contains_key(name) => target.clone(),
+            _ => name.to_owned(),
+        }
+    }
+
+    /// Remove the secret under `key` and every alias pointing at it.
+    fn remove(&mut self, key: &str) -> Option<(String, SecretRecord)> {
+        self.aliases.retain(|_, target| target != key);
+        self.secrets.remove(key)
+    }
+}
+
+impl SecretStore for MemoryStore {
+    fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn put(&self, secret: &NewSecret) -> Result<()> {
+        self.put_many(std::slice::from_ref(secret), false, None)?
+            .map(drop)
+            .map_err(Into::into)
+    }
+
+    fn put_many(
+        &self,
+        secrets: &[NewSecret],
+        create_only: bool,
+        max_secrets: Option<usize>,
+    ) -> Result<Result<PutReport, PutConflict>> {
+        let now = Self::now();
+        let mut inner = self.lock();
+        for s in secrets {
+            if let Some(target) = inner.aliases.get(&s.key) {
+                return Ok(Err(PutConflict::Aliased {
+                    key: s.key.clone(),
+                    target: target.clone(),
+                }));
+            }
+            let live = inner
+                .secrets
+                .get(&s.key)
+                .is_some_and(|(_, r)| !r.is_expired(now));
+            if create_only && live {
+                return Ok(Err(PutConflict::Exists(s.key.clone())));
+            }
+        }
+        let stored = match max_secrets {
+            Some(max) => {
+                let mut keys: HashSet<&str> = inner
+                    .secrets
+                    .keys()
+                    .map(String::as_str)
+                    .filter(|k| !k.contains(':'))
+                    .collect();
+                let before = keys.len();
+                keys.extend(secrets.iter().map(|s| s.key.as_str()));
+                if keys.len() > max && keys.len() > before {
+                    return Ok(Err(PutConflict::Full { max }));
+                }
+                Some(keys.len())
+            }
+            None => None,
+        };
+        let replaced = secrets
+            .iter()
+            .map(|s| {
+                let record = SecretRecord {
+                    value_encrypted: Vec::new(),
+                    nonce: [0; 12],
+                    created_at: now,
+                    updated_at: now,
+                    expires_at: s.ttl_seconds.map(|ttl| expiry_after(now, ttl)),
+                    max_reads: s.max_reads,
+                    read_count: 0,
+                    delete: s.delete,
+                    webhook_url: s.webhook_url.clone(),
+                    webhook_events: s.webhook_events.clone(),
+                    owner_id: None,
+                    org_id: None,
+                    allowed_keys: None,
+                    created_by: s.created_by.clone(),
+                    annotations: s.annotations.clone(),
+                    kind: s.kind,
+                    read_notify: s.read_notify,
+                    read_notified_at: None,
+                    reader_tags: Vec::new(),
+                    last_accessed_at: None,
+                    value_fingerprint: Some(crypto::fingerprint(
+                        &self.fingerprint_key,
+                        s.value.as_bytes(),
+                    )),
+                    revision: inner.next_revision(),
+                    created_by_key: None,
+                };
+                inner
+                    .secrets
+                    .insert(s.key.clone(), (s.value.clone(), record))
+                    .is_some()
+            })
+            .collect();
+        Ok(Ok(PutReport { replaced, stored }))
+    }
+
+    fn get(&self, key: &str) -> Result<GetResult> {
+        self.read(key, None)
+    }
+
+    fn get_from(&self, key: &str, reader: &str) -> Result<GetResult> {
+        self.read(key, Some(crypto::reader_tag(&self.fingerprint_key, reader)))
+    }
+
+    fn head(&self, key: &str) -> Result<Option<(SecretMeta, bool)>> {
+        let now = Self::now();
+        let inner = self.lock();
+        let key = inner.resolve(key);
+        Ok(inner
+            .secrets
+            .get(&key)
+            .filter(|(_, r)| !r.is_expired(now))
+            .map(|(_, r)| (r.meta(&key), r.is_sealed())))
+    }
+
+    fn peek(&self, key: &str) -> Result<Option<String>> {
+        let now = Self::now();
+        Ok(self
+            .lock()
+            .secrets
+            .get(key)
+            .filter(|(_, r)| !r.is_expired(now))
+            .map(|(v, _)| v.clone()))
+    }
+
+    fn patch(
+        &self,
+        key: &str,
+        value: Option<&str>,
+        max_reads: Option<u32>,
+        ttl_seconds: Option<u64>,
+        annotations: AnnotationsPatch,
+        delete: Option<bool>,
+    ) -> Result<Option<SecretMeta>> {
+        let now = Self::now();
+        let mut inner = self.lock();
+        let revision = inner.next_revision();
+        let Some((stored, record)) = inner.secrets.get_mut(key) else {
+            return Ok(None);
+        };
+        if record.is_expired(now) {
+            inner.remove(key);
+            return Ok(None);
+        }
+        if let Some(conflict) = record.patch_conflict(delete) {
+            anyhow::bail!(conflict);
+        }
+        if record.is_sealed() {
+            anyhow::bail!("sealed: secret read limit exhausted");
+        }
+        if let Some(delete) = delete {
+            record.delete = delete;
+        }
+        if let Some(v) = value {
+            *stored = v.to_owned();
+            record.value_fingerprint =
+                Some(crypto::fingerprint(&self.fingerprint_key, v.as_bytes()));
+        }
+        if let Some(max) = max_reads {
+            record.max_reads = Some(max);
+        }
+        if let Some(ttl) = ttl_seconds {
+            record.expires_at = Some(expiry_after(now, ttl));
+        }
+        if !annotations.is_empty() {
+            record.annotations = annotations
+                .apply(&record.annotations)
+                .map_err(|reason| anyhow::anyhow!("invalid annotations: {reason}"))?;
+        }
+        record.read_count = 0;
+        record.updated_at = now;
+        record.revision = revision;
+        Ok(Some(record.meta(key)))
+    }
+
+    fn touch(&self, key: &str, ttl_seconds: u64) -> Result<Option<SecretMeta>> {
+        let now = Self::now();
+        let mut inner = self.lock();
+        let revision = inner.next_revision();
+        let Some((_, record)) = inner.secrets.get_mut(key) else {
+            return Ok(None);
+        };
+        if record.is_expired(now) {
+            inner.remove(key);
+            return Ok(None);
+        }
+        if record.is_sealed() {
+            anyhow::bail!("sealed: secret read limit exhausted");
+        }
+        record.expires_at = Some(expiry_after(now, ttl_seconds));
+        record.revision = revision;
+        Ok(Some(record.meta(key)))
+    }
+
+    fn delete(&self, key: &str) -> Result<bool> {
+        Ok(self.lock().remove(key).is_some())
+    }
+
+    fn delete_with_meta(&self, key: &str) -> Result<Option<SecretMeta>> {
+        Ok(self.lock().remove(key).map(|(_, r)| r.meta(key)))
+    }
+
+    fn delete_if(&self, key: &str, precondition: &Precondition) -> Result<ConditionalDelete> {
+        let now = Self::now();
+        let mut inner = self.lock();
+        let Some(meta) = inner
+            .secrets
+            .get(key)
+            .filter(|(_, r)| !r.is_expired(now))
+            .map(|(_, r)| r.meta(key))
+        else {
+            return Ok(ConditionalDelete::NotFound);
+        };
+        if !precondition.holds(&meta) {
+            return Ok(ConditionalDelete::PreconditionFailed);
+        }
+        inner.remove(key);
+        Ok(ConditionalDelete::Deleted(Box::new(meta)))
+    }
+
+    fn delete_matching(&self, criteria: &DeleteCriteria) -> Result<Vec<String>> {
+        if criteria.is_empty() {
+            anyhow::bail!("refusing to delete with no criteria");
+        }
+        let mut inner = self.lock();
+        let removed: Vec<String> = inner
+            .secrets
+            .iter()
+            .filter(|(_, (_, r))| criteria.matches(r))
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in &removed {
+            inner.remove(key);
+        }
+        Ok(removed)
+    }
+
+    fn wipe_secrets(&self) -> Result<usize> {
+        let mut inner = self.lock();
+        let removed = inner.secrets.len();
+        inner.secrets.clear();
+        inner.aliases.clear();
+        Ok(removed)
+    }
+
+    fn put_alias(&self, alias: &str, key: &str) -> Result<Result<String, AliasError>> {
+        let mut inner = self.lock();
+        let canonical = inner.resolve(key);
+        Ok(if canonical == alias {
+            Err(AliasError::SelfAlias)
+        } else if !inner.secrets.contains_key(&canonical) {
+            Err(AliasError::NotFound)
+        } else if inner.secrets.contains_key(alias) {
+            Err(AliasError::KeyExists)
+        } else if inner.aliases.get(alias).is_some_and(|t| *t != canonical) {
+            Err(AliasError::Taken)
+        } else {
+            inner.aliases.insert(alias.to_owned(), canonical.clone());
+            Ok(canonical)
+        })
+    }
+
+    fn delete_alias(&self, alias: &str, key: &str) -> Result<bool> {
+        let mut inner = self.lock();
+        let points_here = inner.aliases.get(alias).is_some_and(|t| t == key);
+        if points_here {
+            inner.aliases.remove(alias);
+        }
+        Ok(points_here)
+    }
+
+    fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        Ok(self.lock().aliases.get(alias).cloned())
+    }
+
+    fn list(&self) -> Result<Vec<SecretMeta>> {
+        let now = Self::now();
+        Ok(self
+            .lock()
+            .secrets
+            .iter()
+            .filter(|(_, (_, r))| !r.is_expired(now))
+            .map(|(k, (_, r))| r.meta(k))
+            .collect())
+    }
+
+    fn list_with_status(&self) -> Result<Vec<(SecretMeta, SecretStatus)>> {
+        let now = Self::now();
+        Ok(self
+            .lock()
+            .secrets
+            .iter()
+            .map(|(k, (_, r))| (r.meta(k), r.status(now)))
+            .collect())
+    }
+
+    fn count_public(&self) -> Result<usize> {
+        Ok(self
+            .lock()
+            .secrets
+            .keys()
+            .filter(|k| !k.contains(':'))
+            .count())
+    }
+
+    fn prune_by(&self, scope: PruneScope) -> Result<Vec<(String, PruneReason)>> {
+        let now = Self::now();
+        let mut inner = self.lock();
+        let removed: Vec<(String, PruneReason)> = inner
+            .secrets
+            .iter()
+            .filter_map(|(k, (_, r))| {
+                let reason = r.prune_reason(now).filter(|r| scope.admits(*r))?;
+                Some((k.clone(), reason))
+            })
+            .collect();
+        for (key, _) in &removed {
+            inner.remove(key);
+        }
+        Ok(removed)
+    }
+
+    /// Values are not encrypted in memory, so rotation only reports how many
+    /// live secrets a real backend would have re-encrypted.
+    fn rotate(&self, _new_key: &EncryptionKey, _new_key_version: u8) -> Result<usize> {
+        let now = Self::now();
+        Ok(self
+            .lock()
+            .secrets
+            .values()
+            .filter(|(_, r)| !r.is_expired(now))
+            .count())
+    }
+
+    fn record_audit(&self, mut event: AuditEvent) -> Result<()> {
+        let mut inner = self.lock();
+        inner.audit_counts.record(&event.action, event.success);
+        event.id = inner.audit.len() as u64 + 1;
+        inner.audit.push(event);
+        Ok(())
+    }
+
+    fn list_audit(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>> {
+        Ok(self
+            .lock()
+            .audit
+            .iter()
+            .rev()
+            .filter(|e| query.since.is_none_or(|since| e.timestamp >= since))
+            .filter(|e| query.until.is_none_or(|until| e.timestamp <= until))
+            .filter(|e| query.action.as_ref().is_none_or(|a| e.action == *a))
+            .filter(|e| {
+                query
+                    .org_id
+                    .as_ref()
+                    .is_none_or(|o| e.org_id.as_deref() == Some(o.as_str()))
+            })
+            .take(query.limit)
+            .cloned()
+            .collect())
+    }
+
+    fn audit_counts(&self) -> BTreeMap<(String, bool), u64> {
+        self.lock().audit_counts.snapshot()
+    }
+
+    fn put_webhook(&self, reg: &WebhookRegistration) -> Result<()> {
+        self.lock().webhooks.insert(reg.id.clone(), reg.clone());
+        Ok(())
+    }
+
+    fn put_webhooks(&self, regs: &[WebhookRegistration], cap: usize) -> Result<bool> {
+        let mut inner = self.lock();
+        let added = regs
+            .iter()
+            .filter(|reg| !inner.webhooks.contains_key(&reg.id))
+            .count();
+        if inner.webhooks.len() + added > cap {
+            return Ok(false);
+        }
+        for reg in regs {
+            inner.webhooks.insert(reg.id.clone(), reg.clone());
+        }
+        Ok(true)
+    }
+
+    fn list_webhooks(&self) -> Result<Vec<WebhookRegistration>> {
+        Ok(self.lock().webhooks.values().cloned().collect())
+    }
+
+    fn delete_webhook(&self, id: &str) -> Result<bool> {
+        Ok(self.lock().webhooks.remove(id).is_some())
+    }
+
+    fn count_webhooks(&self) -> Result<usize> {
+        Ok(self.lock().webhooks.len())
+    }
+
+    fn get_idempotent(&self, key: &str) -> Result<Option<IdempotentResponse>> {
+        let now = Self::now();
+        Ok(self
+            .lock()
+            .idempotency
+            .get(key)
+            .filter(|r| !r.is_expired(now))
+            .cloned())
+    }
+
+    fn put_idempotent(&self, key: &str, status: u16, body: &str, ttl_seconds: u64) -> Result<()> {
+        let resp = IdempotentResponse {
+            status,
+            body: body.to_owned(),
+            expires_at: expiry_after(Self::now(), ttl_seconds),
+        };
+        self.lock().idempotency.insert(key.to_owned(), resp);
+        Ok(())
+    }
+}
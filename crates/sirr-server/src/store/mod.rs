@@ -1,14 +1,26 @@
+pub mod alias;
 pub mod audit;
+pub mod backend;
 pub mod crypto;
 pub mod db;
+pub mod idempotency;
+pub mod limiter;
+pub mod memory;
 pub mod model;
 pub mod org;
 pub mod permissions;
 pub mod webhooks;
 
-pub use audit::{AuditEvent, AuditQuery};
-pub use db::{GetResult, Store};
-pub use model::{SecretMeta, SecretRecord};
+pub use alias::AliasError;
+pub use audit::{AuditEvent, AuditFailureMode, AuditIpMode, AuditQuery};
+pub use backend::SecretStore;
+pub use db::{CheckReport, GetResult, Store, StoreDurability};
+pub use idempotency::IdempotentResponse;
+pub use limiter::{DecryptLimiter, DecryptPermit};
+pub use memory::MemoryStore;
+pub use model::{
+    ConditionalDelete, Precondition, PutConflict, PutReport, SecretMeta, SecretRecord,
+};
 pub use org::{
     builtin_roles, validate_metadata, OrgRecord, PrincipalKeyRecord, PrincipalRecord, RoleRecord,
 };
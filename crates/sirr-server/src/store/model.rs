@@ -1,6 +1,12 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use zeroize::ZeroizeOnDrop;
 
+/// A stored secret. bincode encodes fields by position, so `#[serde(default)]`
+/// does not let older rows decode: any change to this layout needs a new
+/// record format marker in `db.rs` and a legacy struct to decode the old one
+/// (see [`LegacySecretRecord`]).
 #[derive(Debug, Clone, Serialize, Deserialize, ZeroizeOnDrop)]
 pub struct SecretRecord {
     pub value_encrypted: Vec<u8>,
@@ -14,6 +20,9 @@ pub struct SecretRecord {
     /// Optional per-secret webhook URL for event notifications.
     #[serde(default)]
     pub webhook_url: Option<String>,
+    /// Events delivered to `webhook_url`; `None` delivers every event.
+    #[serde(default)]
+    pub webhook_events: Option<Vec<String>>,
     /// Principal that created this secret (multi-tenant).
     #[serde(default)]
     pub owner_id: Option<String>,
@@ -23,6 +32,310 @@ pub struct SecretRecord {
     /// If set, only these principal key IDs may read the secret.
     #[serde(default)]
     pub allowed_keys: Option<Vec<String>>,
+    /// Name of the principal key, or label of the master key, that created
+    /// this secret.
+    #[serde(default)]
+    pub created_by: Option<String>,
+    /// Free-form, non-indexed notes (descriptions, runbook links, ...).
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub annotations: BTreeMap<String, String>,
+    /// Shape of the value; structured secrets hold a JSON object.
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub kind: SecretKind,
+    /// Keyed HMAC of the plaintext (see `crypto::fingerprint`), refreshed
+    /// whenever the value is written.
+    #[serde(default)]
+    pub value_fingerprint: Option<String>,
+    /// Last put or patch; `0` on records written before this field existed.
+    #[serde(default)]
+    pub updated_at: i64,
+    /// Sampling applied to `secret.read` deliveries to `webhook_url`.
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub read_notify: ReadNotifyThrottle,
+    /// When `webhook_url` was last sent a `secret.read` notification.
+    #[serde(default)]
+    pub read_notified_at: Option<i64>,
+    /// Tags (see `crypto::reader_tag`) of distinct addresses that read the
+    /// value, capped at [`MAX_TRACKED_READERS`].
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub reader_tags: Vec<u64>,
+    /// When the value was last read successfully; `None` if never.
+    #[serde(default)]
+    pub last_accessed_at: Option<i64>,
+    /// Store-wide unique number allocated by every write to the record (put,
+    /// patch, touch); `0` on records written before this field existed.
+    /// Reads and key rotation leave it alone. Part of the ETag.
+    pub revision: u64,
+    /// ID of the principal key that created this secret; what the key's
+    /// `max_secrets` quota counts by.
+    #[serde(default)]
+    pub created_by_key: Option<String>,
+}
+
+/// [`SecretRecord`] as stored before the layout was versioned: raw bincode
+/// or behind the key-version marker alone. Only ever decoded.
+#[derive(Debug, Deserialize, ZeroizeOnDrop)]
+pub struct LegacySecretRecord {
+    pub value_encrypted: Vec<u8>,
+    pub nonce: [u8; 12],
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub max_reads: Option<u32>,
+    pub read_count: u32,
+    pub delete: bool,
+    pub webhook_url: Option<String>,
+    pub owner_id: Option<String>,
+    pub org_id: Option<String>,
+    pub allowed_keys: Option<Vec<String>>,
+}
+
+impl From<LegacySecretRecord> for SecretRecord {
+    fn from(mut old: LegacySecretRecord) -> Self {
+        Self {
+            value_encrypted: std::mem::take(&mut old.value_encrypted),
+            nonce: old.nonce,
+            created_at: old.created_at,
+            expires_at: old.expires_at,
+            max_reads: old.max_reads,
+            read_count: old.read_count,
+            delete: old.delete,
+            webhook_url: old.webhook_url.take(),
+            webhook_events: None,
+            owner_id: old.owner_id.take(),
+            org_id: old.org_id.take(),
+            allowed_keys: old.allowed_keys.take(),
+            created_by: None,
+            annotations: BTreeMap::new(),
+            kind: SecretKind::Text,
+            value_fingerprint: None,
+            updated_at: old.created_at,
+            read_notify: ReadNotifyThrottle::default(),
+            read_notified_at: None,
+            reader_tags: Vec::new(),
+            last_accessed_at: None,
+            revision: 0,
+            created_by_key: None,
+        }
+    }
+}
+
+/// Leading byte of a versioned raw-bincode record (principal keys, webhook
+/// registrations, audit events). Unversioned rows start with a bincode varint,
+/// and 0xFF is never a valid varint tag, so the two framings cannot collide.
+pub(crate) const VERSIONED_MARKER: u8 = 0xFF;
+
+/// Encode `value` as `[VERSIONED_MARKER, version] + bincode(value)`.
+pub(crate) fn encode_versioned<T: Serialize>(version: u8, value: &T) -> anyhow::Result<Vec<u8>> {
+    let payload = bincode::serde::encode_to_vec(value, bincode::config::standard())?;
+    let mut out = Vec::with_capacity(2 + payload.len());
+    out.push(VERSIONED_MARKER);
+    out.push(version);
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Split a record written by [`encode_versioned`] into its layout version and
+/// payload. Rows from before versioning come back as version 0.
+pub(crate) fn split_versioned(bytes: &[u8]) -> anyhow::Result<(u8, &[u8])> {
+    match bytes {
+        [VERSIONED_MARKER, version, payload @ ..] => Ok((*version, payload)),
+        [VERSIONED_MARKER] => anyhow::bail!("truncated record"),
+        _ => Ok((0, bytes)),
+    }
+}
+
+/// Decode a bincode payload in whichever layout the caller names.
+pub(crate) fn decode_bincode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> anyhow::Result<T> {
+    let (value, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())?;
+    Ok(value)
+}
+
+/// Distinct readers remembered per secret. Past this, `distinct_readers`
+/// stays at the cap rather than growing the record.
+pub const MAX_TRACKED_READERS: usize = 32;
+
+/// Sampling for a secret's own `secret.read` webhook under heavy reads.
+/// Both limits apply when set; the default notifies on every read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadNotifyThrottle {
+    /// Notify only on every Nth read (reads N, 2N, ...).
+    #[serde(default)]
+    pub every: Option<u32>,
+    /// Notify at most once per this many seconds.
+    #[serde(default)]
+    pub min_interval_secs: Option<u64>,
+}
+
+/// How a secret's value is interpreted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretKind {
+    /// Opaque string (the default).
+    #[default]
+    Text,
+    /// JSON object whose fields can be read individually.
+    Structured,
+}
+
+/// Upper bound on the combined size of all annotation keys and values.
+pub const MAX_ANNOTATIONS_BYTES: usize = 16 * 1024;
+
+/// Upper bound on the number of annotations on one secret.
+pub const MAX_ANNOTATIONS: usize = 64;
+
+/// Check annotation keys are non-empty and the map fits within
+/// [`MAX_ANNOTATIONS`] and [`MAX_ANNOTATIONS_BYTES`].
+pub fn validate_annotations(annotations: &BTreeMap<String, String>) -> Result<(), String> {
+    if annotations.keys().any(|k| k.is_empty()) {
+        return Err("annotation keys must not be empty".into());
+    }
+    if annotations.len() > MAX_ANNOTATIONS {
+        return Err(format!(
+            "annotations exceed {MAX_ANNOTATIONS} entry limit ({} entries)",
+            annotations.len()
+        ));
+    }
+    let total: usize = annotations.iter().map(|(k, v)| k.len() + v.len()).sum();
+    if total > MAX_ANNOTATIONS_BYTES {
+        return Err(format!(
+            "annotations exceed {MAX_ANNOTATIONS_BYTES} byte limit ({total} bytes)"
+        ));
+    }
+    Ok(())
+}
+
+/// How a patch changes a secret's annotations: an optional full replacement,
+/// then individual keys set and removed on top of the result.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationsPatch {
+    pub replace: Option<BTreeMap<String, String>>,
+    pub set: BTreeMap<String, String>,
+    pub remove: Vec<String>,
+}
+
+impl AnnotationsPatch {
+    /// Replace the whole map, as a plain `annotations` patch does.
+    pub fn replace(annotations: BTreeMap<String, String>) -> Self {
+        Self {
+            replace: Some(annotations),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replace.is_none() && self.set.is_empty() && self.remove.is_empty()
+    }
+
+    /// The annotations after applying this patch to `current`, checked
+    /// with [`validate_annotations`].
+    pub fn apply(
+        &self,
+        current: &BTreeMap<String, String>,
+    ) -> Result<BTreeMap<String, String>, String> {
+        let mut next = self.replace.clone().unwrap_or_else(|| current.clone());
+        next.extend(self.set.clone());
+        for key in &self.remove {
+            next.remove(key);
+        }
+        validate_annotations(&next)?;
+        Ok(next)
+    }
+}
+
+/// One secret of a batch write: the arguments of a single `put`.
+#[derive(Debug, Clone, Default)]
+pub struct NewSecret {
+    pub key: String,
+    pub value: String,
+    pub ttl_seconds: Option<u64>,
+    pub max_reads: Option<u32>,
+    pub delete: bool,
+    pub webhook_url: Option<String>,
+    pub webhook_events: Option<Vec<String>>,
+    pub annotations: BTreeMap<String, String>,
+    pub kind: SecretKind,
+    pub read_notify: ReadNotifyThrottle,
+    /// Label of the master key behind the write, kept as `created_by`.
+    pub created_by: Option<String>,
+}
+
+/// Why a write was refused. Nothing is stored when one is returned.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PutConflict {
+    /// The write was create-only and a live secret holds the key.
+    #[error("secret {0:?} already exists")]
+    Exists(String),
+    /// The key is an alias of another secret.
+    #[error("{key:?} is an alias of {target:?}")]
+    Aliased { key: String, target: String },
+    /// The principal key already holds its `max_secrets` live org secrets.
+    #[error("key {key_name:?} has reached its quota of {max} secrets")]
+    Quota { key_name: String, max: u32 },
+    /// New keys would take the public bucket past its `max_secrets` cap.
+    #[error("secret store is full ({max} secrets)")]
+    Full { max: usize },
+}
+
+/// A batch write that went through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PutReport {
+    /// Per secret and in order, whether it replaced an existing one.
+    pub replaced: Vec<bool>,
+    /// Secrets in the public bucket after the write; counted only when the
+    /// write was capped.
+    pub stored: Option<usize>,
+}
+
+/// Principal key behind an org secret write: recorded on the secret, with
+/// the `max_secrets` quota [`Store::put_org_secret`] enforces for it.
+///
+/// [`Store::put_org_secret`]: super::Store::put_org_secret
+#[derive(Debug, Clone, Copy)]
+pub struct WritingKey<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub max_secrets: Option<u32>,
+}
+
+/// Filter for bulk deletes. Every set criterion must match; an empty
+/// filter matches nothing, so callers cannot wipe the bucket by accident.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeleteCriteria {
+    /// Secrets whose expiry is set and earlier than this unix timestamp.
+    /// Secrets without a TTL never match.
+    pub expires_before: Option<i64>,
+    /// Secrets not read since this unix timestamp. Never-read secrets
+    /// count from their creation time.
+    pub not_accessed_since: Option<i64>,
+}
+
+impl DeleteCriteria {
+    pub fn is_empty(&self) -> bool {
+        self.expires_before.is_none() && self.not_accessed_since.is_none()
+    }
+
+    pub fn matches(&self, record: &SecretRecord) -> bool {
+        !self.is_empty()
+            && self
+                .expires_before
+                .is_none_or(|cutoff| record.expires_at.is_some_and(|exp| exp < cutoff))
+            && self
+                .not_accessed_since
+                .is_none_or(|cutoff| record.last_accessed_at.unwrap_or(record.created_at) < cutoff)
+    }
+}
+
+/// Absolute expiry `ttl_seconds` after `now`. Overflow clamps to `i64::MAX`
+/// (effectively never) instead of wrapping into the past.
+pub fn expiry_after(now: i64, ttl_seconds: u64) -> i64 {
+    i64::try_from(ttl_seconds)
+        .ok()
+        .and_then(|ttl| now.checked_add(ttl))
+        .unwrap_or(i64::MAX)
 }
 
 fn default_delete() -> bool {
@@ -40,10 +353,158 @@ impl SecretRecord {
         self.delete && matches!(self.max_reads, Some(max) if self.read_count >= max)
     }
 
+    /// Whether the read just counted in `read_count` passes the
+    /// [`ReadNotifyThrottle`], recording the notification time if it does.
+    pub fn take_read_notification(&mut self, now: i64) -> bool {
+        let throttle = self.read_notify;
+        if throttle
+            .every
+            .is_some_and(|n| n > 0 && !self.read_count.is_multiple_of(n))
+        {
+            return false;
+        }
+        if let (Some(interval), Some(last)) = (throttle.min_interval_secs, self.read_notified_at) {
+            if now.saturating_sub(last) < i64::try_from(interval).unwrap_or(i64::MAX) {
+                return false;
+            }
+        }
+        self.read_notified_at = Some(now);
+        true
+    }
+
+    /// The per-secret webhook URL, if one is set and subscribed to `event`.
+    pub fn webhook_url_for(&self, event: &str) -> Option<String> {
+        let subscribed = match &self.webhook_events {
+            Some(events) => events.iter().any(|e| e == "*" || e == event),
+            None => true,
+        };
+        self.webhook_url.clone().filter(|_| subscribed)
+    }
+
+    /// Count `tag` as a reader unless it is already known or the cap is reached.
+    pub fn note_reader(&mut self, tag: u64) {
+        if self.reader_tags.len() < MAX_TRACKED_READERS && !self.reader_tags.contains(&tag) {
+            self.reader_tags.push(tag);
+        }
+    }
+
+    /// Why a patch carrying `new_delete` may not touch this record, if it may
+    /// not. A `delete=true` secret only accepts a patch that turns it into a
+    /// seal-on-read secret (`delete: false`), and only before its first read:
+    /// resetting the counter would hand out reads the burn already spent.
+    pub fn patch_conflict(&self, new_delete: Option<bool>) -> Option<&'static str> {
+        if !self.delete {
+            None
+        } else if new_delete != Some(false) {
+            Some("cannot patch a secret with delete=true unless the patch sets delete=false")
+        } else if self.read_count > 0 {
+            Some("cannot patch a delete=true secret that has already been read")
+        } else {
+            None
+        }
+    }
+
     /// Returns true if this record is sealed (delete=false and read limit hit).
     pub fn is_sealed(&self) -> bool {
         !self.delete && matches!(self.max_reads, Some(max) if self.read_count >= max)
     }
+
+    /// Lifecycle state at `now`; expiry takes precedence over sealing.
+    pub fn status(&self, now: i64) -> SecretStatus {
+        if self.is_expired(now) {
+            SecretStatus::ExpiredPendingSweep
+        } else if self.is_sealed() {
+            SecretStatus::Sealed
+        } else {
+            SecretStatus::Active
+        }
+    }
+
+    /// Why a prune would remove this record at `now`, if it would.
+    pub fn prune_reason(&self, now: i64) -> Option<PruneReason> {
+        if self.is_expired(now) {
+            Some(PruneReason::Expired)
+        } else if self.is_burned() {
+            Some(PruneReason::Burned)
+        } else if self.is_sealed() {
+            Some(PruneReason::Tombstoned)
+        } else {
+            None
+        }
+    }
+
+    /// Metadata view of this record under `key`.
+    pub fn meta(&self, key: &str) -> SecretMeta {
+        SecretMeta {
+            key: key.to_owned(),
+            created_at: self.created_at,
+            updated_at: self.updated_at.max(self.created_at),
+            expires_at: self.expires_at,
+            max_reads: self.max_reads,
+            read_count: self.read_count,
+            delete: self.delete,
+            owner_id: self.owner_id.clone(),
+            org_id: self.org_id.clone(),
+            annotations: self.annotations.clone(),
+            kind: self.kind,
+            value_fingerprint: self.value_fingerprint.clone(),
+            distinct_readers: self.reader_tags.len() as u32,
+            created_by: self.created_by.clone(),
+            last_accessed_at: self.last_accessed_at,
+            revision: self.revision,
+        }
+    }
+}
+
+/// Lifecycle state reported by `GET /secrets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SecretStatus {
+    /// Readable.
+    Active,
+    /// `delete=false` and out of reads; retained but unreadable.
+    Sealed,
+    /// TTL passed; removed by the next sweep or read.
+    ExpiredPendingSweep,
+}
+
+/// Why a record is removable by prune.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneReason {
+    /// TTL passed.
+    Expired,
+    /// `delete=true` and out of reads, not yet removed.
+    Burned,
+    /// Sealed (`delete=false`, out of reads): kept so a patch can re-arm it.
+    Tombstoned,
+}
+
+/// Which records a prune removes (`POST /prune?reason=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneScope {
+    /// Expired and burned records, as the background sweep removes.
+    #[default]
+    #[serde(skip)]
+    Swept,
+    Expired,
+    Burned,
+    Tombstoned,
+    /// Swept records and tombstones.
+    All,
+}
+
+impl PruneScope {
+    pub fn admits(self, reason: PruneReason) -> bool {
+        match self {
+            Self::Swept => reason != PruneReason::Tombstoned,
+            Self::Expired => reason == PruneReason::Expired,
+            Self::Burned => reason == PruneReason::Burned,
+            Self::Tombstoned => reason == PruneReason::Tombstoned,
+            Self::All => true,
+        }
+    }
 }
 
 /// Metadata returned on list/describe endpoints — never includes the value.
@@ -51,12 +512,84 @@ impl SecretRecord {
 pub struct SecretMeta {
     pub key: String,
     pub created_at: i64,
+    /// Time of the last put or patch; equals `created_at` until patched.
+    #[serde(default)]
+    pub updated_at: i64,
     pub expires_at: Option<i64>,
     pub max_reads: Option<u32>,
     pub read_count: u32,
     pub delete: bool,
     pub owner_id: Option<String>,
     pub org_id: Option<String>,
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+    #[serde(default, rename = "type")]
+    pub kind: SecretKind,
+    /// Changes whenever the value does; lets pollers detect updates without
+    /// consuming a read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_fingerprint: Option<String>,
+    /// Distinct client addresses that have read the value, up to
+    /// [`MAX_TRACKED_READERS`].
+    #[serde(default)]
+    pub distinct_readers: u32,
+    /// Name of the principal key, or label of the master key, that created
+    /// the secret; absent for unlabeled-master-key and anonymous writes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// When the value was last read; `null` if it never has been.
+    #[serde(default)]
+    pub last_accessed_at: Option<i64>,
+    /// Changes on every write to the secret, metadata-only ones included.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl SecretMeta {
+    /// Strong entity tag sent as `ETag` and compared against `If-Match`:
+    /// `updated_at` and `revision`, so any write changes it and reads or key
+    /// rotation do not.
+    pub fn etag(&self) -> String {
+        format!("\"{}-{}\"", self.updated_at, self.revision)
+    }
+}
+
+/// Conditions a delete must meet, checked against the record inside the
+/// deleting transaction. Unset fields always hold.
+#[derive(Debug, Clone, Default)]
+pub struct Precondition {
+    /// `If-Match` entity tags; one must equal [`SecretMeta::etag`], or be `*`.
+    pub if_match: Option<Vec<String>>,
+    /// `If-Unmodified-Since`: `updated_at` must not be later.
+    pub if_unmodified_since: Option<i64>,
+}
+
+impl Precondition {
+    pub fn is_empty(&self) -> bool {
+        self.if_match.is_none() && self.if_unmodified_since.is_none()
+    }
+
+    pub fn holds(&self, meta: &SecretMeta) -> bool {
+        let etag = meta.etag();
+        let matches = self.if_match.as_ref().is_none_or(|tags| {
+            tags.iter()
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        });
+        matches
+            && self
+                .if_unmodified_since
+                .is_none_or(|since| meta.updated_at <= since)
+    }
+}
+
+/// Outcome of a conditional delete.
+#[derive(Debug)]
+pub enum ConditionalDelete {
+    NotFound,
+    /// The secret exists but the precondition failed; nothing was removed.
+    PreconditionFailed,
+    /// Removed; metadata as it was just before.
+    Deleted(Box<SecretMeta>),
 }
 
 #[cfg(test)]
@@ -68,14 +601,26 @@ mod tests {
             value_encrypted: vec![],
             nonce: [0u8; 12],
             created_at: 1000,
+            updated_at: 1000,
             expires_at: None,
             max_reads,
             read_count,
             delete,
             webhook_url: None,
+            webhook_events: None,
             owner_id: None,
             org_id: None,
             allowed_keys: None,
+            created_by: None,
+            annotations: BTreeMap::new(),
+            kind: SecretKind::Text,
+            value_fingerprint: None,
+            read_notify: ReadNotifyThrottle::default(),
+            read_notified_at: None,
+            reader_tags: Vec::new(),
+            last_accessed_at: None,
+            revision: 0,
+            created_by_key: None,
         }
     }
 
@@ -110,4 +655,74 @@ mod tests {
         assert!(!r.is_burned());
         assert!(!r.is_sealed());
     }
+
+    #[test]
+    fn delete_flip_needs_explicit_false_on_unread_secret() {
+        assert!(make_record(false, Some(3), 1)
+            .patch_conflict(None)
+            .is_none());
+        let unread = make_record(true, Some(3), 0);
+        assert!(unread.patch_conflict(None).is_some());
+        assert!(unread.patch_conflict(Some(true)).is_some());
+        assert!(unread.patch_conflict(Some(false)).is_none());
+        assert!(make_record(true, Some(3), 1)
+            .patch_conflict(Some(false))
+            .is_some());
+        assert!(make_record(true, Some(3), 3)
+            .patch_conflict(Some(false))
+            .is_some());
+    }
+
+    #[test]
+    fn read_notifications_respect_min_interval() {
+        let mut r = make_record(false, None, 0);
+        r.read_notify.min_interval_secs = Some(60);
+        assert!(r.take_read_notification(1000));
+        assert!(!r.take_read_notification(1059));
+        assert!(r.take_read_notification(1060));
+    }
+
+    #[test]
+    fn annotations_total_size_is_capped() {
+        let mut a = BTreeMap::new();
+        a.insert("runbook".to_owned(), "https://wiki/db".to_owned());
+        assert!(validate_annotations(&a).is_ok());
+        a.insert("notes".to_owned(), "x".repeat(MAX_ANNOTATIONS_BYTES));
+        assert!(validate_annotations(&a).is_err());
+        let mut empty_key = BTreeMap::new();
+        empty_key.insert(String::new(), "v".to_owned());
+        assert!(validate_annotations(&empty_key).is_err());
+    }
+
+    #[test]
+    fn webhook_url_for_respects_subscription() {
+        let mut r = make_record(true, None, 0);
+        assert_eq!(r.webhook_url_for("secret.read"), None);
+        r.webhook_url = Some("https://hooks.example.com/x".into());
+        assert!(r.webhook_url_for("secret.read").is_some());
+        r.webhook_events = Some(vec!["secret.burned".into()]);
+        assert_eq!(r.webhook_url_for("secret.read"), None);
+        assert!(r.webhook_url_for("secret.burned").is_some());
+    }
+
+    #[test]
+    fn expiry_after_clamps_instead_of_wrapping() {
+        assert_eq!(expiry_after(1_000, 60), 1_060);
+        assert_eq!(expiry_after(1_000, u64::MAX), i64::MAX);
+        assert_eq!(expiry_after(1_000, i64::MAX as u64), i64::MAX);
+        assert_eq!(expiry_after(i64::MAX - 1, 5), i64::MAX);
+    }
+
+    #[test]
+    fn reader_tags_are_distinct_and_bounded() {
+        let mut r = make_record(false, None, 0);
+        r.note_reader(1);
+        r.note_reader(1);
+        r.note_reader(2);
+        assert_eq!(r.meta("K").distinct_readers, 2);
+        for tag in 0..100 {
+            r.note_reader(tag);
+        }
+        assert_eq!(r.reader_tags.len(), MAX_TRACKED_READERS);
+    }
 }
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use super::model::{decode_bincode, encode_versioned, split_versioned};
 use super::permissions::Permissions;
 
 // ── Table definitions ────────────────────────────────────────────────────────
@@ -44,6 +45,53 @@ pub struct PrincipalKeyRecord {
     pub valid_after: i64,
     pub valid_before: i64,
     pub created_at: i64,
+    /// Maximum number of live secrets this key may own. `None` = unlimited.
+    pub max_secrets: Option<u32>,
+}
+
+/// Layout version written by [`PrincipalKeyRecord::encode`]. Bump it, and add
+/// a legacy struct, whenever the record's fields change.
+const PRINCIPAL_KEY_VERSION: u8 = 1;
+
+/// [`PrincipalKeyRecord`] as stored before the layout was versioned.
+#[derive(Deserialize)]
+struct LegacyPrincipalKeyRecord {
+    id: String,
+    principal_id: String,
+    org_id: String,
+    name: String,
+    key_hash: Vec<u8>,
+    valid_after: i64,
+    valid_before: i64,
+    created_at: i64,
+}
+
+impl PrincipalKeyRecord {
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        encode_versioned(PRINCIPAL_KEY_VERSION, self)
+    }
+
+    /// Decode the current layout or a row written before versioning.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        match split_versioned(bytes)? {
+            (PRINCIPAL_KEY_VERSION, payload) => decode_bincode(payload),
+            (0, payload) => {
+                let old: LegacyPrincipalKeyRecord = decode_bincode(payload)?;
+                Ok(Self {
+                    id: old.id,
+                    principal_id: old.principal_id,
+                    org_id: old.org_id,
+                    name: old.name,
+                    key_hash: old.key_hash,
+                    valid_after: old.valid_after,
+                    valid_before: old.valid_before,
+                    created_at: old.created_at,
+                    max_secrets: None,
+                })
+            }
+            (version, _) => anyhow::bail!("unknown principal key layout version {version}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -218,6 +266,7 @@ mod tests {
             valid_after: 1700000000,
             valid_before: 1800000000,
             created_at: 1700000000,
+            max_secrets: None,
         };
         let bytes = bincode::serde::encode_to_vec(&rec, bincode::config::standard()).unwrap();
         let (decoded, _): (PrincipalKeyRecord, _) =
@@ -228,6 +277,45 @@ mod tests {
         assert_eq!(decoded.valid_before, rec.valid_before);
     }
 
+    #[test]
+    fn principal_key_records_from_before_versioning_still_decode() {
+        #[derive(Serialize)]
+        struct BaselineKey {
+            id: String,
+            principal_id: String,
+            org_id: String,
+            name: String,
+            key_hash: Vec<u8>,
+            valid_after: i64,
+            valid_before: i64,
+            created_at: i64,
+        }
+        let old = BaselineKey {
+            id: "pk_1".into(),
+            principal_id: "p_1".into(),
+            org_id: "org_1".into(),
+            name: "default".into(),
+            key_hash: vec![0xAA; 32],
+            valid_after: 1700000000,
+            valid_before: 1800000000,
+            created_at: 1700000000,
+        };
+        let bytes = bincode::serde::encode_to_vec(&old, bincode::config::standard()).unwrap();
+        let decoded = PrincipalKeyRecord::decode(&bytes).unwrap();
+        assert_eq!(decoded.key_hash, old.key_hash);
+        assert_eq!(decoded.valid_before, old.valid_before);
+        assert_eq!(decoded.created_at, old.created_at);
+        assert_eq!(decoded.max_secrets, None);
+
+        let capped = PrincipalKeyRecord {
+            max_secrets: Some(5),
+            ..decoded
+        };
+        let round = PrincipalKeyRecord::decode(&capped.encode().unwrap()).unwrap();
+        assert_eq!(round.max_secrets, Some(5));
+        assert_eq!(round.created_at, old.created_at);
+    }
+
     #[test]
     fn role_record_bincode_round_trip() {
         for role in builtin_roles() {
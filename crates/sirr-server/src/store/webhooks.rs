@@ -1,25 +1,120 @@
 use anyhow::{Context, Result};
-use redb::{ReadableTable, ReadableTableMetadata, TableDefinition};
+use redb::{ReadableTable, ReadableTableMetadata, TableDefinition, WriteTransaction};
 
-use crate::webhooks::WebhookRegistration;
+use super::model::{decode_bincode, encode_versioned, split_versioned};
+use crate::webhooks::{DeadLetter, PendingRetry, WebhookRegistration};
 
 pub(crate) const WEBHOOKS: TableDefinition<&str, &[u8]> = TableDefinition::new("webhooks");
+pub(crate) const DEAD_LETTERS: TableDefinition<u64, &[u8]> =
+    TableDefinition::new("webhook_dead_letters");
+pub(crate) const PENDING_RETRIES: TableDefinition<u64, &[u8]> =
+    TableDefinition::new("webhook_pending_retries");
+/// [`PENDING_RETRIES`] IDs keyed by next-attempt time, so due retries are
+/// found without scanning every stored retry.
+pub(crate) const PENDING_RETRY_DUE: TableDefinition<(i64, u64), ()> =
+    TableDefinition::new("webhook_pending_retry_due");
+
+/// Layout version written by [`encode_webhook`]. Bump it, and add a legacy
+/// struct, whenever [`WebhookRegistration`]'s fields change.
+const WEBHOOK_VERSION: u8 = 1;
+
+/// [`WebhookRegistration`] as stored before the layout was versioned.
+#[derive(serde::Deserialize)]
+struct LegacyWebhookRegistration {
+    id: String,
+    url: String,
+    secret: String,
+    events: Vec<String>,
+    created_at: i64,
+    org_id: Option<String>,
+}
+
+fn decode_retry(bytes: &[u8]) -> Result<PendingRetry> {
+    let (retry, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+        .context("bincode decode pending retry")?;
+    Ok(retry)
+}
+
+/// Rebuild [`PENDING_RETRY_DUE`] if it does not cover every stored retry,
+/// as for retries stored before the index existed.
+pub(crate) fn index_pending_retries(write_txn: &WriteTransaction) -> Result<()> {
+    let table = write_txn.open_table(PENDING_RETRIES)?;
+    let mut due = write_txn.open_table(PENDING_RETRY_DUE)?;
+    if due.len()? == table.len()? {
+        return Ok(());
+    }
+    due.retain(|_, _| false)?;
+    for item in table.iter()? {
+        let (_k, v) = item?;
+        let retry = decode_retry(v.value())?;
+        due.insert((retry.next_attempt_at_ms, retry.id), ())?;
+    }
+    Ok(())
+}
+
+fn encode_webhook(reg: &WebhookRegistration) -> Result<Vec<u8>> {
+    encode_versioned(WEBHOOK_VERSION, reg).context("encode webhook")
+}
+
+fn decode_webhook(bytes: &[u8]) -> Result<WebhookRegistration> {
+    match split_versioned(bytes)? {
+        (WEBHOOK_VERSION, payload) => decode_bincode(payload).context("decode webhook"),
+        (0, payload) => {
+            let old: LegacyWebhookRegistration =
+                decode_bincode(payload).context("decode legacy webhook")?;
+            Ok(WebhookRegistration {
+                id: old.id,
+                url: old.url,
+                secret: old.secret,
+                events: old.events,
+                created_at: old.created_at,
+                org_id: old.org_id,
+                template: None,
+                event_urls: Default::default(),
+            })
+        }
+        (version, _) => anyhow::bail!("unknown webhook layout version {version}"),
+    }
+}
 
 impl super::db::Store {
     /// Insert or overwrite a webhook registration.
     pub fn put_webhook(&self, reg: &WebhookRegistration) -> Result<()> {
-        let bytes = bincode::serde::encode_to_vec(reg, bincode::config::standard())
-            .context("bincode encode webhook")?;
+        let bytes = encode_webhook(reg)?;
 
         let write_txn = self.db.begin_write()?;
         {
             let mut table = write_txn.open_table(WEBHOOKS)?;
             table.insert(reg.id.as_str(), bytes.as_slice())?;
         }
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, 1)?;
         Ok(())
     }
 
+    /// Insert or overwrite every registration in `regs` in one transaction,
+    /// unless that would leave more than `cap` registered. Returns false,
+    /// writing nothing, when it would.
+    pub fn put_webhooks(&self, regs: &[WebhookRegistration], cap: usize) -> Result<bool> {
+        let encoded = regs
+            .iter()
+            .map(|reg| Ok((reg.id.as_str(), encode_webhook(reg)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(WEBHOOKS)?;
+            for (id, bytes) in &encoded {
+                table.insert(*id, bytes.as_slice())?;
+            }
+            if table.len()? as usize > cap {
+                // Dropping the uncommitted txn discards the inserts.
+                return Ok(false);
+            }
+        }
+        self.commit_mutation(write_txn, regs.len())?;
+        Ok(true)
+    }
+
     /// List all registered webhooks.
     pub fn list_webhooks(&self) -> Result<Vec<WebhookRegistration>> {
         let read_txn = self.db.begin_read()?;
@@ -28,11 +123,7 @@ impl super::db::Store {
         let mut regs = Vec::new();
         for item in table.iter()? {
             let (_k, v) = item?;
-            let bytes: &[u8] = v.value();
-            let (reg, _): (WebhookRegistration, _) =
-                bincode::serde::decode_from_slice(bytes, bincode::config::standard())
-                    .context("bincode decode webhook")?;
-            regs.push(reg);
+            regs.push(decode_webhook(v.value())?);
         }
         Ok(regs)
     }
@@ -45,7 +136,7 @@ impl super::db::Store {
             let existed = table.remove(id)?.is_some();
             existed
         };
-        write_txn.commit()?;
+        self.commit_mutation(write_txn, usize::from(existed))?;
         Ok(existed)
     }
 
@@ -64,10 +155,7 @@ impl super::db::Store {
         let mut regs = Vec::new();
         for item in table.iter()? {
             let (_k, v) = item?;
-            let bytes: &[u8] = v.value();
-            let (reg, _): (WebhookRegistration, _) =
-                bincode::serde::decode_from_slice(bytes, bincode::config::standard())
-                    .context("bincode decode webhook")?;
+            let reg = decode_webhook(v.value())?;
             if reg.org_id.as_deref() == Some(org_id) {
                 regs.push(reg);
             }
@@ -79,4 +167,264 @@ impl super::db::Store {
     pub fn count_webhooks_for_org(&self, org_id: &str) -> Result<usize> {
         Ok(self.list_webhooks_for_org(org_id)?.len())
     }
+
+    // ── Dead letters ─────────────────────────────────────────────────────
+
+    /// Persist a delivery that exhausted its retries. Allocates the next ID
+    /// and returns it.
+    pub fn put_dead_letter(&self, letter: &DeadLetter) -> Result<u64> {
+        let write_txn = self.db.begin_write()?;
+        let id = {
+            let mut table = write_txn.open_table(DEAD_LETTERS)?;
+            let id = table.last()?.map(|(k, _)| k.value()).unwrap_or(0) + 1;
+            let mut letter = letter.clone();
+            letter.id = id;
+            let bytes = bincode::serde::encode_to_vec(&letter, bincode::config::standard())
+                .context("bincode encode dead letter")?;
+            table.insert(id, bytes.as_slice())?;
+            id
+        };
+        write_txn.commit()?;
+        Ok(id)
+    }
+
+    /// List dead letters, oldest first.
+    pub fn list_dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(DEAD_LETTERS)?;
+
+        let mut letters = Vec::new();
+        for item in table.iter()? {
+            let (_k, v) = item?;
+            let (letter, _): (DeadLetter, _) =
+                bincode::serde::decode_from_slice(v.value(), bincode::config::standard())
+                    .context("bincode decode dead letter")?;
+            letters.push(letter);
+        }
+        Ok(letters)
+    }
+
+    /// Delete a dead letter by ID. Returns true if it existed.
+    pub fn delete_dead_letter(&self, id: u64) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let existed = {
+            let mut table = write_txn.open_table(DEAD_LETTERS)?;
+            let existed = table.remove(id)?.is_some();
+            existed
+        };
+        write_txn.commit()?;
+        Ok(existed)
+    }
+
+    /// Count stored dead letters.
+    pub fn count_dead_letters(&self) -> Result<usize> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(DEAD_LETTERS)?;
+        Ok(table.len()? as usize)
+    }
+
+    // ── Pending retries ──────────────────────────────────────────────────
+
+    /// Insert a retry (`id == 0` allocates the next ID) or overwrite an
+    /// existing one. Returns its ID.
+    pub fn put_pending_retry(&self, retry: &PendingRetry) -> Result<u64> {
+        let write_txn = self.db.begin_write()?;
+        let id = {
+            let mut table = write_txn.open_table(PENDING_RETRIES)?;
+            let mut due = write_txn.open_table(PENDING_RETRY_DUE)?;
+            let mut retry = retry.clone();
+            if retry.id == 0 {
+                retry.id = table.last()?.map(|(k, _)| k.value()).unwrap_or(0) + 1;
+            }
+            let bytes = bincode::serde::encode_to_vec(&retry, bincode::config::standard())
+                .context("bincode encode pending retry")?;
+            if let Some(old) = table.insert(retry.id, bytes.as_slice())? {
+                let old = decode_retry(old.value())?;
+                due.remove((old.next_attempt_at_ms, old.id))?;
+            }
+            due.insert((retry.next_attempt_at_ms, retry.id), ())?;
+            retry.id
+        };
+        write_txn.commit()?;
+        Ok(id)
+    }
+
+    /// List pending retries, oldest first.
+    pub fn list_pending_retries(&self) -> Result<Vec<PendingRetry>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(PENDING_RETRIES)?;
+
+        let mut retries = Vec::new();
+        for item in table.iter()? {
+            let (_k, v) = item?;
+            retries.push(decode_retry(v.value())?);
+        }
+        Ok(retries)
+    }
+
+    /// Pending retries due at or before `now_ms`, soonest first. Only the
+    /// due part of the index is read.
+    pub fn due_pending_retries(&self, now_ms: i64) -> Result<Vec<PendingRetry>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(PENDING_RETRIES)?;
+        let due = read_txn.open_table(PENDING_RETRY_DUE)?;
+
+        let mut retries = Vec::new();
+        for item in due.range(..=(now_ms, u64::MAX))? {
+            let (k, _) = item?;
+            let (_, id) = k.value();
+            if let Some(v) = table.get(id)? {
+                retries.push(decode_retry(v.value())?);
+            }
+        }
+        Ok(retries)
+    }
+
+    /// Delete a pending retry by ID. Returns true if it existed.
+    pub fn delete_pending_retry(&self, id: u64) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let existed = {
+            let mut table = write_txn.open_table(PENDING_RETRIES)?;
+            let mut due = write_txn.open_table(PENDING_RETRY_DUE)?;
+            let removed = table.remove(id)?;
+            if let Some(ref old) = removed {
+                let old = decode_retry(old.value())?;
+                due.remove((old.next_attempt_at_ms, id))?;
+            }
+            removed.is_some()
+        };
+        write_txn.commit()?;
+        Ok(existed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[test]
+    fn registrations_from_before_versioning_still_decode() {
+        #[derive(Serialize)]
+        struct BaselineRegistration {
+            id: String,
+            url: String,
+            secret: String,
+            events: Vec<String>,
+            created_at: i64,
+            org_id: Option<String>,
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let store = super::super::db::Store::open(
+            &dir.path().join("test.db"),
+            super::super::crypto::generate_key(),
+        )
+        .unwrap();
+        let old = BaselineRegistration {
+            id: "wh_old".into(),
+            url: "https://hooks.example.com/a".into(),
+            secret: "whsec_x".into(),
+            events: vec!["secret.burned".into()],
+            created_at: 1700000000,
+            org_id: Some("org_1".into()),
+        };
+        let raw = bincode::serde::encode_to_vec(&old, bincode::config::standard()).unwrap();
+        let write_txn = store.db.begin_write().unwrap();
+        write_txn
+            .open_table(WEBHOOKS)
+            .unwrap()
+            .insert("wh_old", raw.as_slice())
+            .unwrap();
+        write_txn.commit().unwrap();
+
+        let regs = store.list_webhooks_for_org("org_1").unwrap();
+        assert_eq!(regs.len(), 1);
+        assert_eq!(regs[0].url, old.url);
+        assert_eq!(regs[0].events, old.events);
+        assert!(regs[0].template.is_none());
+        assert!(regs[0].event_urls.is_empty());
+
+        let mut updated = regs[0].clone();
+        updated.template = Some("{{event}}".into());
+        store.put_webhook(&updated).unwrap();
+        let regs = store.list_webhooks().unwrap();
+        assert_eq!(regs[0].template.as_deref(), Some("{{event}}"));
+        assert_eq!(regs[0].created_at, old.created_at);
+    }
+
+    #[test]
+    fn put_webhooks_writes_all_or_nothing_within_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = super::super::db::Store::open(
+            &dir.path().join("test.db"),
+            super::super::crypto::generate_key(),
+        )
+        .unwrap();
+        let reg = |id: &str| WebhookRegistration {
+            id: id.into(),
+            url: format!("https://hooks.example.com/{id}"),
+            secret: "whsec_x".into(),
+            events: vec!["*".into()],
+            created_at: 1700000000,
+            org_id: None,
+            template: None,
+            event_urls: Default::default(),
+        };
+        assert!(store.put_webhooks(&[reg("a"), reg("b")], 3).unwrap());
+        // Overwriting "b" fits; adding "c" and "d" would make four.
+        assert!(!store
+            .put_webhooks(&[reg("b"), reg("c"), reg("d")], 3)
+            .unwrap());
+        assert_eq!(store.count_webhooks().unwrap(), 2);
+        assert!(store.put_webhooks(&[reg("b"), reg("c")], 3).unwrap());
+        assert_eq!(store.count_webhooks().unwrap(), 3);
+    }
+
+    #[test]
+    fn due_retries_follow_reschedules_and_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let store =
+            super::super::db::Store::open(&path, super::super::crypto::generate_key()).unwrap();
+        let retry = |id: u64, at: i64| PendingRetry {
+            id,
+            url: "https://hooks.example.com/x".into(),
+            registration_id: None,
+            body: "{}".into(),
+            attempts: 1,
+            next_attempt_at_ms: at,
+            last_error: String::new(),
+        };
+        let due = |store: &super::super::db::Store, now: i64| -> Vec<u64> {
+            store
+                .due_pending_retries(now)
+                .unwrap()
+                .iter()
+                .map(|r| r.id)
+                .collect()
+        };
+        for at in [100, 300, 200] {
+            store.put_pending_retry(&retry(0, at)).unwrap();
+        }
+        assert_eq!(due(&store, 250), [1, 3]);
+
+        store.put_pending_retry(&retry(1, 400)).unwrap();
+        assert_eq!(due(&store, 250), [3]);
+        assert!(store.delete_pending_retry(3).unwrap());
+        assert!(due(&store, 250).is_empty());
+        assert_eq!(due(&store, 1000), [2, 1]);
+
+        // Retries stored without the index are indexed on the next open.
+        let txn = store.db.begin_write().unwrap();
+        txn.open_table(PENDING_RETRY_DUE)
+            .unwrap()
+            .retain(|_, _| false)
+            .unwrap();
+        txn.commit().unwrap();
+        assert!(due(&store, 1000).is_empty());
+        drop(store);
+        let store =
+            super::super::db::Store::open(&path, super::super::crypto::generate_key()).unwrap();
+        assert_eq!(due(&store, 1000), [2, 1]);
+    }
 }
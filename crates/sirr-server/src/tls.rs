@@ -0,0 +1,282 @@
+//! Native TLS: minimum protocol version, the cipher suites offered and the
+//! listener used when sirr terminates TLS itself.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Lowest TLS protocol version the server will negotiate. TLS 1.0 and 1.1
+/// are never accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TlsMinVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+impl FromStr for TlsMinVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().trim_start_matches("tls") {
+            "1.2" => Ok(Self::Tls12),
+            "1.3" => Ok(Self::Tls13),
+            "1.0" | "1.1" => Err(format!("TLS {s} is not supported; use 1.2 or 1.3")),
+            _ => Err(format!("unknown TLS version {s:?}; use 1.2 or 1.3")),
+        }
+    }
+}
+
+impl fmt::Display for TlsMinVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tls12 => "1.2",
+            Self::Tls13 => "1.3",
+        })
+    }
+}
+
+#[cfg(feature = "tls")]
+pub use policy::*;
+
+/// Server-side TLS settings the main listener terminates with. Without the
+/// `tls` feature this type is uninhabited, so HTTPS cannot be configured.
+#[cfg(feature = "tls")]
+pub type ServerTls = rustls::ServerConfig;
+#[cfg(not(feature = "tls"))]
+pub enum ServerTls {}
+
+/// Load the PEM certificate chain and key for `SIRR_TLS_CERT`/`SIRR_TLS_KEY`.
+/// Fails when sirr was built without the `tls` feature.
+pub fn load_server_tls(
+    cert_path: &std::path::Path,
+    key_path: &std::path::Path,
+    min: TlsMinVersion,
+) -> anyhow::Result<ServerTls> {
+    #[cfg(feature = "tls")]
+    return load_server_config(cert_path, key_path, min);
+    #[cfg(not(feature = "tls"))]
+    {
+        let _ = (cert_path, key_path, min);
+        anyhow::bail!("SIRR_TLS_CERT/SIRR_TLS_KEY need sirr built with the `tls` feature")
+    }
+}
+
+#[cfg(feature = "tls")]
+mod policy {
+    use std::future::Future;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use anyhow::{Context, Result};
+    use rustls::crypto::{ring, CryptoProvider};
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use rustls::{SupportedCipherSuite, SupportedProtocolVersion};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::{mpsc, Semaphore};
+    use tokio_rustls::{server::TlsStream, TlsAcceptor};
+
+    use super::TlsMinVersion;
+
+    /// AEAD-only suites with forward secrecy. TLS 1.2 suites are only
+    /// offered when the minimum version allows TLS 1.2.
+    pub const SAFE_CIPHER_SUITES: &[SupportedCipherSuite] = &[
+        ring::cipher_suite::TLS13_AES_256_GCM_SHA384,
+        ring::cipher_suite::TLS13_AES_128_GCM_SHA256,
+        ring::cipher_suite::TLS13_CHACHA20_POLY1305_SHA256,
+        ring::cipher_suite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+        ring::cipher_suite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+        ring::cipher_suite::TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256,
+        ring::cipher_suite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+        ring::cipher_suite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+        ring::cipher_suite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256,
+    ];
+
+    static TLS12_AND_UP: &[&SupportedProtocolVersion] =
+        &[&rustls::version::TLS13, &rustls::version::TLS12];
+    static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+
+    fn protocol_versions(min: TlsMinVersion) -> &'static [&'static SupportedProtocolVersion] {
+        match min {
+            TlsMinVersion::Tls12 => TLS12_AND_UP,
+            TlsMinVersion::Tls13 => TLS13_ONLY,
+        }
+    }
+
+    /// Build a rustls `ServerConfig` restricted to `min` and newer and to
+    /// [`SAFE_CIPHER_SUITES`].
+    pub fn server_config(
+        certs: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+        min: TlsMinVersion,
+    ) -> Result<rustls::ServerConfig> {
+        let provider = CryptoProvider {
+            cipher_suites: SAFE_CIPHER_SUITES.to_vec(),
+            ..ring::default_provider()
+        };
+        rustls::ServerConfig::builder_with_provider(Arc::new(provider))
+            .with_protocol_versions(protocol_versions(min))
+            .context("TLS protocol versions")?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("TLS certificate/key")
+    }
+
+    /// Like [`server_config`], reading a PEM certificate chain and private key.
+    pub fn load_server_config(
+        cert_path: &Path,
+        key_path: &Path,
+        min: TlsMinVersion,
+    ) -> Result<rustls::ServerConfig> {
+        let certs = CertificateDer::pem_file_iter(cert_path)
+            .with_context(|| format!("read TLS certificate: {}", cert_path.display()))?
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("parse TLS certificate: {}", cert_path.display()))?;
+        let key = PrivateKeyDer::from_pem_file(key_path)
+            .with_context(|| format!("read TLS key: {}", key_path.display()))?;
+        server_config(certs, key, min)
+    }
+
+    /// A client that has not finished its handshake by now is dropped.
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Accepted connections queued for `axum::serve` once their handshake
+    /// is done.
+    const HANDSHAKE_BACKLOG: usize = 64;
+
+    /// Handshakes in progress (or waiting for the backlog) at once. Past
+    /// this, new connections stay in the kernel's accept queue.
+    const MAX_CONCURRENT_HANDSHAKES: usize = 256;
+
+    /// `axum::serve` listener that terminates TLS. Handshakes run on their
+    /// own tasks, so a slow or stalled client never holds up the accept
+    /// loop; only completed handshakes are handed to the server.
+    pub struct TlsListener {
+        ready: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+        local_addr: SocketAddr,
+    }
+
+    impl TlsListener {
+        /// Accept on `listener` until `shutdown` resolves, which closes the
+        /// socket, or until the server drops the returned listener.
+        pub fn new(
+            listener: TcpListener,
+            config: Arc<rustls::ServerConfig>,
+            shutdown: impl Future<Output = ()> + Send + 'static,
+        ) -> io::Result<Self> {
+            let local_addr = listener.local_addr()?;
+            let acceptor = TlsAcceptor::from(config);
+            let (tx, ready) = mpsc::channel(HANDSHAKE_BACKLOG);
+            let handshakes = Arc::new(Semaphore::new(MAX_CONCURRENT_HANDSHAKES));
+            tokio::spawn(async move {
+                tokio::pin!(shutdown);
+                while !tx.is_closed() {
+                    let next = async {
+                        let permit = handshakes
+                            .clone()
+                            .acquire_owned()
+                            .await
+                            .expect("semaphore is never closed");
+                        loop {
+                            match listener.accept().await {
+                                Ok(conn) => return (permit, conn),
+                                Err(e) => {
+                                    // Same back-off axum applies to accept errors
+                                    // such as running out of file descriptors.
+                                    tracing::error!(error = %e, "accept error");
+                                    tokio::time::sleep(Duration::from_secs(1)).await;
+                                }
+                            }
+                        }
+                    };
+                    let (permit, (stream, peer)) = tokio::select! {
+                        () = &mut shutdown => break,
+                        next = next => next,
+                    };
+                    let acceptor = acceptor.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let _permit = permit;
+                        match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                        {
+                            Ok(Ok(tls)) => {
+                                let _ = tx.send((tls, peer)).await;
+                            }
+                            Ok(Err(e)) => {
+                                tracing::debug!(%peer, error = %e, "TLS handshake failed")
+                            }
+                            Err(_) => tracing::debug!(%peer, "TLS handshake timed out"),
+                        }
+                    });
+                }
+            });
+            Ok(Self { ready, local_addr })
+        }
+    }
+
+    impl axum::serve::Listener for TlsListener {
+        type Io = TlsStream<TcpStream>;
+        type Addr = SocketAddr;
+
+        async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+            match self.ready.recv().await {
+                Some(conn) => conn,
+                // The accept task only exits once this receiver is gone.
+                None => std::future::pending().await,
+            }
+        }
+
+        fn local_addr(&self) -> io::Result<Self::Addr> {
+            Ok(self.local_addr)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn listener_releases_the_port_on_shutdown() {
+        use std::sync::Arc;
+
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let cert_der = CertificateDer::from(cert.cert.der().to_vec());
+        let key_der = PrivateKeyDer::try_from(cert.key_pair.serialize_der()).unwrap();
+        let server_cfg = server_config(vec![cert_der], key_der, TlsMinVersion::Tls12).unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let _tls = TlsListener::new(listener, Arc::new(server_cfg), async {
+            let _ = stop_rx.await;
+        })
+        .unwrap();
+        assert!(std::net::TcpListener::bind(addr).is_err());
+
+        // No connection arrives, yet the socket closes once shutdown fires.
+        stop_tx.send(()).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            while std::net::TcpListener::bind(addr).is_err() {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("port released after shutdown");
+    }
+
+    #[test]
+    fn min_version_rejects_legacy_protocols() {
+        assert_eq!("1.2".parse(), Ok(TlsMinVersion::Tls12));
+        assert_eq!("TLS1.3".parse(), Ok(TlsMinVersion::Tls13));
+        assert!("1.0".parse::<TlsMinVersion>().is_err());
+        assert!("1.1".parse::<TlsMinVersion>().is_err());
+        assert!("ssl3".parse::<TlsMinVersion>().is_err());
+    }
+}
@@ -16,6 +16,30 @@ use crate::store::Store;
 
 const ACTION_LICENSE_VALIDATE: &str = "license.validate";
 
+/// How long a validation call may take unless overridden with
+/// [`OnlineValidator::with_timeout`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Why a validation call produced no verdict. An explicit `valid: false`
+/// response is a verdict, not an error.
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    #[error("no response within {0:?}")]
+    Timeout(Duration),
+    #[error("{0}")]
+    Unreachable(#[from] reqwest::Error),
+}
+
+impl ValidationError {
+    /// Short tag used in audit details and logs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Timeout(_) => "timeout",
+            Self::Unreachable(_) => "unreachable",
+        }
+    }
+}
+
 /// JSON response from `GET /api/validate?key=...`.
 #[derive(Debug, Deserialize)]
 pub struct ValidationResponse {
@@ -45,6 +69,7 @@ pub struct OnlineValidator {
     cache: Arc<RwLock<Option<CachedValidation>>>,
     cache_ttl: Duration,
     grace_period: Duration,
+    timeout: Duration,
 }
 
 impl OnlineValidator {
@@ -55,7 +80,6 @@ impl OnlineValidator {
         grace_period_secs: u64,
     ) -> Self {
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(5))
             .build()
             .expect("build reqwest client");
 
@@ -66,13 +90,25 @@ impl OnlineValidator {
             cache: Arc::new(RwLock::new(None)),
             cache_ttl: Duration::from_secs(cache_ttl_secs),
             grace_period: Duration::from_secs(grace_period_secs),
+            timeout: DEFAULT_TIMEOUT,
         }
     }
 
+    /// Give up on a validation call after `timeout` and treat SirrLock as
+    /// unavailable, so the grace period applies.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     /// Call the SirrLock validation endpoint.
-    async fn validate_remote(&self) -> Result<ValidationResponse, reqwest::Error> {
+    async fn validate_remote(&self) -> Result<ValidationResponse, ValidationError> {
         let url = format!("{}?key={}", self.validation_url, self.license_key);
-        self.client.get(&url).send().await?.json().await
+        let call = async { self.client.get(&url).send().await?.json().await };
+        match tokio::time::timeout(self.timeout, call).await {
+            Ok(resp) => Ok(resp?),
+            Err(_) => Err(ValidationError::Timeout(self.timeout)),
+        }
     }
 
     /// Run at server startup. Awaits the first validation; if unreachable, warns
@@ -125,13 +161,13 @@ impl OnlineValidator {
                 valid
             }
             Err(e) => {
-                warn!(error = %e, "SirrLock unreachable at startup — allowing degraded mode");
+                warn!(error = %e, kind = e.kind(), "SirrLock unavailable at startup — allowing degraded mode");
                 let _ = store.record_audit(AuditEvent::new(
                     ACTION_LICENSE_VALIDATE,
                     None,
                     "server".into(),
                     true,
-                    Some(format!("startup;unreachable;error={e}")),
+                    Some(format!("startup;{};error={e}", e.kind())),
                     None,
                     None,
                 ));
@@ -229,7 +265,7 @@ impl OnlineValidator {
                     ));
                 }
                 Err(e) => {
-                    warn!(error = %e, "background license revalidation failed");
+                    warn!(error = %e, kind = e.kind(), "background license revalidation failed");
                     // Update checked_at so we don't spam revalidation on every request.
                     let mut guard = this.cache.write().await;
                     if let Some(ref mut c) = *guard {
@@ -241,7 +277,7 @@ impl OnlineValidator {
                         None,
                         "server".into(),
                         false,
-                        Some(format!("revalidate;unreachable;error={e}")),
+                        Some(format!("revalidate;{};error={e}", e.kind())),
                         None,
                         None,
                     ));
@@ -399,4 +435,53 @@ mod tests {
         // Give background task time to complete.
         tokio::time::sleep(Duration::from_millis(200)).await;
     }
+
+    #[tokio::test]
+    async fn stalled_validator_times_out_into_grace() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/validate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"valid": false, "reason": "expired"}))
+                    .set_delay(Duration::from_secs(30)),
+            )
+            .mount(&mock)
+            .await;
+
+        let (store, _dir) = make_store();
+        let timeout = Duration::from_millis(200);
+        let v = OnlineValidator::new(
+            "sirr_lic_test".into(),
+            format!("{}/api/validate", mock.uri()),
+            0,
+            259200,
+        )
+        .with_timeout(timeout);
+
+        let started = Instant::now();
+        assert!(v.validate_startup(&store).await, "timeout is not a denial");
+        assert!(started.elapsed() < timeout * 5);
+
+        // Stale cache: answered from the grace period while revalidation stalls.
+        let started = Instant::now();
+        assert!(v.is_valid(&store).await);
+        assert!(started.elapsed() < timeout);
+        tokio::time::sleep(timeout * 2).await;
+
+        let details: Vec<String> = store
+            .list_audit(&AuditQuery {
+                since: None,
+                until: None,
+                action: Some("license.validate".into()),
+                limit: 100,
+                org_id: None,
+            })
+            .unwrap()
+            .into_iter()
+            .filter_map(|e| e.detail)
+            .collect();
+        assert!(details.iter().any(|d| d.starts_with("startup;timeout;")));
+        assert!(details.iter().any(|d| d.starts_with("revalidate;timeout;")));
+    }
 }
@@ -1,12 +1,14 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::net::IpAddr;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use hmac::{Hmac, Mac};
 use ipnet::IpNet;
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 use crate::store::Store;
 
@@ -23,10 +25,33 @@ pub struct WebhookRegistration {
     pub created_at: i64,
     #[serde(default)]
     pub org_id: Option<String>,
+    /// Optional payload template (see [`render_template`]); `None` sends the
+    /// default [`WebhookEvent`] JSON.
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Per-event receivers overriding `url`, keyed by exact event name
+    /// (`secret.burned`). Events without an entry go to `url`.
+    #[serde(default)]
+    pub event_urls: BTreeMap<String, String>,
+}
+
+impl WebhookRegistration {
+    /// Receiver for `event_type`: its `event_urls` entry, else `url`.
+    pub fn url_for(&self, event_type: &str) -> &str {
+        self.event_urls
+            .get(event_type)
+            .map(String::as_str)
+            .unwrap_or(&self.url)
+    }
 }
 
+/// Shape version of [`WebhookEvent`] payloads, sent as `schema_version` and
+/// in the `X-Sirr-Webhook-Version` header. Bump when the payload changes.
+pub const WEBHOOK_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct WebhookEvent {
+    pub schema_version: u32,
     pub event: String,
     pub key: String,
     pub timestamp: i64,
@@ -34,9 +59,57 @@ pub struct WebhookEvent {
     pub detail: serde_json::Value,
 }
 
+/// A delivery that failed every retry attempt, kept for later redelivery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Allocated by the store.
+    pub id: u64,
+    pub url: String,
+    /// Global registration the delivery belonged to. `None` = per-secret URL,
+    /// signed with `SIRR_WEBHOOK_SECRET`.
+    pub registration_id: Option<String>,
+    /// Serialized `WebhookEvent`, replayed byte-for-byte.
+    pub body: String,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: i64,
+}
+
+/// A failed delivery waiting for its next attempt, persisted so a restart
+/// resumes the schedule (see [`WebhookSender::with_persisted_retries`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingRetry {
+    /// Allocated by the store.
+    pub id: u64,
+    pub url: String,
+    /// As on [`DeadLetter`]: `None` = per-secret URL.
+    pub registration_id: Option<String>,
+    /// Request body, sent byte-for-byte on every attempt.
+    pub body: String,
+    /// Attempts made so far.
+    pub attempts: u32,
+    /// Unix milliseconds at which the next attempt is due.
+    pub next_attempt_at_ms: i64,
+    pub last_error: String,
+}
+
 /// Maximum number of global webhooks per instance.
 pub const MAX_WEBHOOKS: usize = 10;
 
+/// Events a per-secret `webhook_url` can subscribe to.
+pub const SECRET_WEBHOOK_EVENTS: &[&str] = &["secret.read", "secret.burned"];
+
+/// Delivery attempts before an event is dead-lettered.
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Base delay between attempts; doubles after each failure.
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+/// Stored retries [`WebhookSender::retry_due`] sends at once.
+const MAX_CONCURRENT_RETRIES: usize = 16;
+/// Consecutive failed deliveries that open a registration's circuit.
+pub const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+/// How long an open circuit skips deliveries before probing again.
+pub const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+
 // ── SSRF guard ───────────────────────────────────────────────────────────────
 
 /// Private, loopback, and link-local ranges that must never be webhook targets.
@@ -81,20 +154,7 @@ pub fn validate_webhook_url(url: &str, allowed_origins: &[String]) -> Result<(),
         return Err("webhook_url must use https://".to_string());
     }
 
-    let host = uri
-        .host()
-        .ok_or_else(|| "webhook_url is missing a host".to_string())?;
-
-    // Strip IPv6 brackets before parsing.
-    let bare = host.trim_matches(|c| c == '[' || c == ']');
-    if let Ok(ip) = bare.parse::<IpAddr>() {
-        if is_private_ip(ip) {
-            return Err(
-                "webhook_url must not target private, loopback, or link-local addresses"
-                    .to_string(),
-            );
-        }
-    }
+    check_public_host(&uri)?;
 
     if allowed_origins.is_empty() {
         return Err(
@@ -113,6 +173,37 @@ pub fn validate_webhook_url(url: &str, allowed_origins: &[String]) -> Result<(),
     Ok(())
 }
 
+/// Refuse a URL whose host is a bare private, loopback, or link-local IP.
+fn check_public_host(uri: &http::Uri) -> Result<(), String> {
+    let host = uri
+        .host()
+        .ok_or_else(|| "webhook_url is missing a host".to_string())?;
+
+    // Strip IPv6 brackets before parsing.
+    let bare = host.trim_matches(|c| c == '[' || c == ']');
+    if let Ok(ip) = bare.parse::<IpAddr>() {
+        if is_private_ip(ip) {
+            return Err(
+                "webhook_url must not target private, loopback, or link-local addresses"
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// SSRF check for a global registration URL: http(s) and not a private
+/// address. Unlike [`validate_webhook_url`] no origin allowlist applies.
+pub fn validate_registration_url(url: &str) -> Result<(), String> {
+    let uri: http::Uri = url
+        .parse()
+        .map_err(|_| "webhook_url is not a valid URL".to_string())?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        return Err("webhook URL must start with http:// or https://".to_string());
+    }
+    check_public_host(&uri)
+}
+
 // ── WebhookSender ────────────────────────────────────────────────────────────
 
 #[derive(Clone)]
@@ -125,6 +216,46 @@ pub struct WebhookSender {
     /// Allowlist of URL prefixes for per-secret webhook URLs
     /// (from SIRR_WEBHOOK_ALLOWED_ORIGINS).  Empty = disabled.
     pub allowed_origins: Arc<Vec<String>>,
+    max_attempts: u32,
+    retry_backoff: Duration,
+    /// Namespace prepended to delivered event names (from SIRR_WEBHOOK_EVENT_PREFIX).
+    event_prefix: String,
+    /// Consecutive failures that open a circuit; 0 disables the breaker.
+    breaker_threshold: u32,
+    breaker_cooldown: Duration,
+    /// Circuit state per global registration ID.
+    breakers: Arc<Mutex<HashMap<String, Breaker>>>,
+    /// Serialize deliveries per receiver and secret key (SIRR_WEBHOOK_ORDERED).
+    ordered: bool,
+    key_queues: KeyQueues,
+    /// Keep retry schedules in the store instead of in task memory
+    /// (SIRR_WEBHOOK_PERSIST_RETRIES).
+    persist_retries: bool,
+    /// Delivery attempt counters for `GET /metrics`.
+    metrics: Arc<crate::metrics::Metrics>,
+}
+
+/// A delivery waiting for the one ahead of it when ordering is on.
+struct Delivery {
+    url: String,
+    event: WebhookEvent,
+    hmac_secret: String,
+    registration_id: Option<String>,
+    template: Option<String>,
+}
+
+/// Pending deliveries per `(url, secret key)`. An entry exists exactly while
+/// a worker task is draining it.
+type KeyQueues = Arc<Mutex<HashMap<(String, String), VecDeque<Delivery>>>>;
+
+/// Per-registration circuit breaker state.
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    /// Set while the circuit is open (or half-open once the cooldown passed).
+    opened_at: Option<Instant>,
+    /// A half-open probe delivery is in flight.
+    probing: bool,
 }
 
 impl WebhookSender {
@@ -145,18 +276,150 @@ impl WebhookSender {
             instance_id,
             per_secret_signing_key,
             allowed_origins,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            event_prefix: String::new(),
+            breaker_threshold: DEFAULT_BREAKER_THRESHOLD,
+            breaker_cooldown: DEFAULT_BREAKER_COOLDOWN,
+            breakers: Arc::default(),
+            ordered: false,
+            key_queues: Arc::default(),
+            persist_retries: false,
+            metrics: Arc::default(),
         }
     }
 
-    /// Fire webhook events to all matching global registrations.
-    pub fn fire(&self, event_type: &str, key: &str, detail: serde_json::Value) {
-        let event = WebhookEvent {
-            event: event_type.to_owned(),
+    /// Namespace delivered event names, e.g. `prod` turns `secret.burned` into
+    /// `prod.secret.burned`. Subscriptions still match the unprefixed name.
+    pub fn with_event_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_end_matches('.');
+        self.event_prefix = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{prefix}.")
+        };
+        self
+    }
+
+    /// Override the retry policy (attempts per delivery, base backoff).
+    pub fn with_retry_policy(mut self, max_attempts: u32, retry_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Open a registration's circuit after `threshold` consecutive failed
+    /// deliveries, skipping it for `cooldown` before a single probe. 0 disables.
+    pub fn with_circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+        self.breaker_threshold = threshold;
+        self.breaker_cooldown = cooldown;
+        self
+    }
+
+    /// Deliver events for the same secret key to each receiver one at a time,
+    /// in the order they fired. Other keys and receivers stay concurrent.
+    pub fn with_ordered_delivery(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// After a failed first attempt, store the delivery with its next-attempt
+    /// time instead of sleeping in a task, so retries survive a restart. Due
+    /// retries are sent by [`retry_due`](Self::retry_due), which
+    /// [`spawn_retry_worker`](Self::spawn_retry_worker) runs periodically.
+    /// With ordered delivery on, a stored retry no longer holds back later
+    /// events for the same key.
+    pub fn with_persisted_retries(mut self, persist: bool) -> Self {
+        self.persist_retries = persist;
+        self
+    }
+
+    /// Count delivery attempts into `metrics` instead of a private set.
+    pub fn with_metrics(mut self, metrics: Arc<crate::metrics::Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Whether a delivery to `registration_id` may go out now. Once the
+    /// cooldown has passed, exactly one probe is let through (half-open).
+    fn circuit_allows(&self, registration_id: &str) -> bool {
+        if self.breaker_threshold == 0 {
+            return true;
+        }
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(b) = breakers.get_mut(registration_id) else {
+            return true;
+        };
+        match b.opened_at {
+            None => true,
+            Some(opened) if !b.probing && opened.elapsed() >= self.breaker_cooldown => {
+                b.probing = true;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Feed a delivery outcome into the registration's breaker.
+    fn record_outcome(&self, registration_id: &str, delivered: bool) {
+        if self.breaker_threshold == 0 {
+            return;
+        }
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        if delivered {
+            if breakers
+                .remove(registration_id)
+                .is_some_and(|b| b.opened_at.is_some())
+            {
+                info!(registration_id, "webhook circuit closed");
+            }
+            return;
+        }
+        let b = breakers.entry(registration_id.to_owned()).or_default();
+        b.consecutive_failures += 1;
+        if b.probing || b.consecutive_failures >= self.breaker_threshold {
+            if b.opened_at.is_none() {
+                warn!(
+                    registration_id,
+                    failures = b.consecutive_failures,
+                    "webhook circuit opened"
+                );
+            }
+            b.opened_at = Some(Instant::now());
+            b.probing = false;
+        }
+    }
+
+    /// The event delivered for `event_type` on `key`, stamped now.
+    fn event(&self, event_type: &str, key: &str, detail: serde_json::Value) -> WebhookEvent {
+        WebhookEvent {
+            schema_version: WEBHOOK_SCHEMA_VERSION,
+            event: format!("{}{event_type}", self.event_prefix),
             key: key.to_owned(),
             timestamp: now(),
             instance_id: self.instance_id.clone(),
             detail,
-        };
+        }
+    }
+
+    /// Body and `X-Sirr-Signature` value a delivery of `event_type` would
+    /// carry when signed with `hmac_secret`, without sending anything.
+    pub fn preview(
+        &self,
+        event_type: &str,
+        key: &str,
+        detail: serde_json::Value,
+        template: Option<&str>,
+        hmac_secret: &str,
+    ) -> Result<(String, String), String> {
+        let body = event_body(&self.event(event_type, key, detail), template)?;
+        let signature = format!("sha256={}", compute_signature(hmac_secret, &body));
+        Ok((body, signature))
+    }
+
+    /// Fire webhook events to all matching global registrations.
+    pub fn fire(&self, event_type: &str, key: &str, detail: serde_json::Value) {
+        let event = self.event(event_type, key, detail);
 
         let registrations = match self.store.list_webhooks() {
             Ok(regs) => regs,
@@ -168,12 +431,29 @@ impl WebhookSender {
 
         for reg in registrations {
             if matches_event(&reg.events, event_type) {
-                let sender = self.clone();
-                let event = event.clone();
-                let url = reg.url.clone();
-                let secret = reg.secret.clone();
-                tokio::spawn(async move {
-                    sender.deliver(&url, &event, &secret).await;
+                if !self.circuit_allows(&reg.id) {
+                    // Keep the event for redelivery without spending a task
+                    // and retries on an endpoint that is known to be down.
+                    match event_body(&event, reg.template.as_deref()) {
+                        Ok(body) => self.dead_letter(
+                            reg.url_for(event_type),
+                            Some(&reg.id),
+                            body,
+                            0,
+                            "circuit open".into(),
+                        ),
+                        Err(e) => {
+                            warn!(error = %e, url = %reg.url_for(event_type), "failed to build webhook body")
+                        }
+                    }
+                    continue;
+                }
+                self.dispatch(Delivery {
+                    url: reg.url_for(event_type).to_owned(),
+                    event: event.clone(),
+                    hmac_secret: reg.secret,
+                    registration_id: Some(reg.id),
+                    template: reg.template,
                 });
             }
         }
@@ -198,50 +478,361 @@ impl WebhookSender {
             return;
         }
 
-        let event = WebhookEvent {
-            event: event_type.to_owned(),
-            key: key.to_owned(),
-            timestamp: now(),
-            instance_id: self.instance_id.clone(),
-            detail,
-        };
+        let event = self.event(event_type, key, detail);
 
+        self.dispatch(Delivery {
+            url: url.to_owned(),
+            event,
+            hmac_secret: signing_key,
+            registration_id: None,
+            template: None,
+        });
+    }
+
+    /// Hand a delivery to a background task. With ordering on, it queues
+    /// behind any in-flight delivery of the same key to the same URL and a
+    /// single worker drains that queue front to back.
+    fn dispatch(&self, delivery: Delivery) {
         let sender = self.clone();
-        let url = url.to_owned();
+        if !self.ordered {
+            tokio::spawn(async move { sender.deliver_queued(&delivery).await });
+            return;
+        }
+
+        let queue_key = (delivery.url.clone(), delivery.event.key.clone());
+        {
+            let mut queues = self.key_queues.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(queue) = queues.get_mut(&queue_key) {
+                queue.push_back(delivery);
+                return;
+            }
+            queues.insert(queue_key.clone(), VecDeque::new());
+        }
         tokio::spawn(async move {
-            sender.deliver(&url, &event, &signing_key).await;
+            let mut next = Some(delivery);
+            while let Some(delivery) = next {
+                sender.deliver_queued(&delivery).await;
+                let mut queues = sender.key_queues.lock().unwrap_or_else(|e| e.into_inner());
+                next = queues.get_mut(&queue_key).and_then(VecDeque::pop_front);
+                if next.is_none() {
+                    queues.remove(&queue_key);
+                }
+            }
         });
     }
 
-    /// POST the event payload to the given URL with HMAC signature.
-    async fn deliver(&self, url: &str, event: &WebhookEvent, hmac_secret: &str) {
-        let body = match serde_json::to_string(event) {
+    async fn deliver_queued(&self, d: &Delivery) {
+        self.deliver(
+            &d.url,
+            &d.event,
+            &d.hmac_secret,
+            d.registration_id.as_deref(),
+            d.template.as_deref(),
+        )
+        .await;
+    }
+
+    /// Deliver an event, retrying with exponential backoff. When every attempt
+    /// fails the payload is stored as a dead letter.
+    async fn deliver(
+        &self,
+        url: &str,
+        event: &WebhookEvent,
+        hmac_secret: &str,
+        registration_id: Option<&str>,
+        template: Option<&str>,
+    ) {
+        let body = match event_body(event, template) {
             Ok(b) => b,
             Err(e) => {
-                warn!(error = %e, url, "failed to serialize webhook event");
+                warn!(error = %e, url, "failed to build webhook body");
                 return;
             }
         };
 
-        let signature = compute_signature(hmac_secret, &body);
+        if self.persist_retries {
+            match self.send_once(url, &body, hmac_secret).await {
+                Ok(()) => {
+                    if let Some(id) = registration_id {
+                        self.record_outcome(id, true);
+                    }
+                }
+                Err(e) => {
+                    warn!(url, attempt = 1, error = %e, "webhook delivery failed");
+                    self.retry_later(PendingRetry {
+                        id: 0,
+                        url: url.to_owned(),
+                        registration_id: registration_id.map(str::to_owned),
+                        body,
+                        attempts: 1,
+                        next_attempt_at_ms: 0,
+                        last_error: e,
+                    });
+                }
+            }
+            return;
+        }
+
+        let result = self.send_with_retry(url, &body, hmac_secret).await;
+        if let Some(id) = registration_id {
+            self.record_outcome(id, result.is_ok());
+        }
+        if let Err(last_error) = result {
+            self.dead_letter(url, registration_id, body, self.max_attempts, last_error);
+        }
+    }
+
+    /// Schedule the next attempt of a failed delivery, or dead-letter it once
+    /// `max_attempts` is spent. The delay doubles with each attempt made.
+    fn retry_later(&self, mut retry: PendingRetry) {
+        if retry.attempts >= self.max_attempts {
+            if retry.id != 0 {
+                let _ = self.store.delete_pending_retry(retry.id);
+            }
+            if let Some(ref id) = retry.registration_id {
+                self.record_outcome(id, false);
+            }
+            self.dead_letter(
+                &retry.url,
+                retry.registration_id.as_deref(),
+                retry.body,
+                retry.attempts,
+                retry.last_error,
+            );
+            return;
+        }
+        let delay = self.retry_backoff * 2u32.saturating_pow(retry.attempts - 1);
+        retry.next_attempt_at_ms = now_millis().saturating_add(delay.as_millis() as i64);
+        if let Err(e) = self.store.put_pending_retry(&retry) {
+            warn!(url = %retry.url, error = %e, "failed to store webhook retry");
+            self.dead_letter(
+                &retry.url,
+                retry.registration_id.as_deref(),
+                retry.body,
+                retry.attempts,
+                retry.last_error,
+            );
+        }
+    }
+
+    /// Make one attempt at every stored retry whose time has come, up to
+    /// [`MAX_CONCURRENT_RETRIES`] at once. Returns the number delivered.
+    pub async fn retry_due(&self) -> usize {
+        let retries = match self.store.due_pending_retries(now_millis()) {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(error = %e, "failed to list webhook retries");
+                return 0;
+            }
+        };
+
+        let mut delivered = 0;
+        let mut sends = tokio::task::JoinSet::new();
+        for retry in retries {
+            if sends.len() >= MAX_CONCURRENT_RETRIES {
+                if let Some(sent) = sends.join_next().await {
+                    delivered += usize::from(sent.unwrap_or(false));
+                }
+            }
+            let sender = self.clone();
+            sends.spawn(async move { sender.retry_once(retry).await });
+        }
+        while let Some(sent) = sends.join_next().await {
+            delivered += usize::from(sent.unwrap_or(false));
+        }
+        delivered
+    }
+
+    /// One attempt at a stored retry. Returns whether it was delivered.
+    async fn retry_once(&self, mut retry: PendingRetry) -> bool {
+        let secret = match self.signing_secret(retry.registration_id.as_deref()) {
+            Ok(secret) => secret,
+            Err(e) => {
+                warn!(error = %e, "failed to list webhooks for retry");
+                return false;
+            }
+        };
+        let Some(secret) = secret else {
+            let _ = self.store.delete_pending_retry(retry.id);
+            return false;
+        };
+
+        retry.attempts += 1;
+        match self.send_once(&retry.url, &retry.body, &secret).await {
+            Ok(()) => {
+                let _ = self.store.delete_pending_retry(retry.id);
+                if let Some(ref id) = retry.registration_id {
+                    self.record_outcome(id, true);
+                }
+                true
+            }
+            Err(e) => {
+                warn!(url = %retry.url, attempt = retry.attempts, error = %e, "webhook delivery failed");
+                retry.last_error = e;
+                self.retry_later(retry);
+                false
+            }
+        }
+    }
+
+    /// Run [`retry_due`](Self::retry_due) every `interval`.
+    pub fn spawn_retry_worker(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.retry_due().await;
+            }
+        })
+    }
+
+    /// Signing key for a delivery: the registration's secret, or
+    /// `SIRR_WEBHOOK_SECRET` for per-secret URLs. `None` once the
+    /// registration (or key) is gone and there is nothing to deliver to.
+    fn signing_secret(&self, registration_id: Option<&str>) -> anyhow::Result<Option<String>> {
+        Ok(match registration_id {
+            Some(reg_id) => self
+                .store
+                .list_webhooks()?
+                .into_iter()
+                .find(|r| r.id == reg_id)
+                .map(|r| r.secret),
+            None => self.per_secret_signing_key.clone(),
+        })
+    }
+
+    /// Store an undelivered payload for later redelivery.
+    fn dead_letter(
+        &self,
+        url: &str,
+        registration_id: Option<&str>,
+        body: String,
+        attempts: u32,
+        last_error: String,
+    ) {
+        let letter = DeadLetter {
+            id: 0,
+            url: url.to_owned(),
+            registration_id: registration_id.map(str::to_owned),
+            body,
+            attempts,
+            last_error,
+            failed_at: now(),
+        };
+        if let Err(e) = self.store.put_dead_letter(&letter) {
+            warn!(url, error = %e, "failed to store webhook dead letter");
+        }
+    }
+
+    /// POST `body` up to `max_attempts` times. Returns the last error on failure.
+    async fn send_with_retry(
+        &self,
+        url: &str,
+        body: &str,
+        hmac_secret: &str,
+    ) -> Result<(), String> {
+        let mut backoff = self.retry_backoff;
+        let mut last_error = String::new();
+        for attempt in 1..=self.max_attempts {
+            match self.send_once(url, body, hmac_secret).await {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    warn!(url, attempt, error = %e, "webhook delivery failed");
+                    last_error = e;
+                }
+            }
+            if attempt < self.max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Single signed POST. Non-2xx responses count as failures.
+    async fn send_once(&self, url: &str, body: &str, hmac_secret: &str) -> Result<(), String> {
+        let result = self.post_signed(url, body, hmac_secret).await;
+        match result {
+            Ok(()) => self.metrics.webhook_deliveries_ok.inc(),
+            Err(_) => self.metrics.webhook_deliveries_failed.inc(),
+        }
+        result
+    }
+
+    async fn post_signed(&self, url: &str, body: &str, hmac_secret: &str) -> Result<(), String> {
+        let signature = compute_signature(hmac_secret, body);
 
-        let result = self
+        let resp = self
             .client
             .post(url)
             .header("Content-Type", "application/json")
             .header("X-Sirr-Signature", format!("sha256={signature}"))
-            .body(body)
+            .header(
+                "X-Sirr-Webhook-Version",
+                schema_version_of(body).to_string(),
+            )
+            .body(body.to_owned())
             .send()
-            .await;
+            .await
+            .map_err(|e| e.to_string())?;
 
-        match result {
-            Ok(resp) => {
-                debug!(url, status = %resp.status(), "webhook delivered");
-            }
+        if resp.status().is_success() {
+            debug!(url, status = %resp.status(), "webhook delivered");
+            Ok(())
+        } else {
+            Err(format!("receiver responded {}", resp.status()))
+        }
+    }
+
+    /// Re-attempt every stored dead letter, pausing `throttle` between them so
+    /// a recovering receiver isn't flooded. Letters that deliver are removed;
+    /// the rest stay for the next run. Returns the number redelivered.
+    pub async fn redeliver_dead_letters(&self, throttle: Duration) -> usize {
+        let letters = match self.store.list_dead_letters() {
+            Ok(l) => l,
             Err(e) => {
-                warn!(url, error = %e, "webhook delivery failed");
+                warn!(error = %e, "failed to list webhook dead letters");
+                return 0;
+            }
+        };
+
+        let mut delivered = 0;
+        for (i, letter) in letters.iter().enumerate() {
+            if i > 0 && !throttle.is_zero() {
+                tokio::time::sleep(throttle).await;
+            }
+
+            let secret = match self.signing_secret(letter.registration_id.as_deref()) {
+                Ok(secret) => secret,
+                Err(e) => {
+                    warn!(error = %e, "failed to list webhooks for redelivery");
+                    continue;
+                }
+            };
+            // Registration deleted (or signing key removed) since the failure:
+            // nothing to deliver to any more.
+            let Some(secret) = secret else {
+                let _ = self.store.delete_dead_letter(letter.id);
+                continue;
+            };
+
+            if self
+                .send_with_retry(&letter.url, &letter.body, &secret)
+                .await
+                .is_ok()
+            {
+                let _ = self.store.delete_dead_letter(letter.id);
+                delivered += 1;
             }
         }
+        if !letters.is_empty() {
+            info!(
+                delivered,
+                remaining = letters.len() - delivered,
+                "webhook dead-letter redelivery complete"
+            );
+        }
+        delivered
     }
 }
 
@@ -251,6 +842,112 @@ fn matches_event(subscribed: &[String], event_type: &str) -> bool {
     subscribed.iter().any(|e| e == "*" || e == event_type)
 }
 
+/// Longest accepted payload template, in bytes.
+pub const MAX_TEMPLATE_LEN: usize = 4096;
+
+/// Render a registration's payload template. The only syntax is `{{field}}`,
+/// replaced by the JSON encoding of that [`WebhookEvent`] field (`event`,
+/// `key`, `timestamp`, `instance_id`, `schema_version`, `detail`). Values are
+/// always emitted as JSON, never spliced in raw, so event data cannot break
+/// out of the surrounding document. The result must itself be JSON.
+pub fn render_template(template: &str, event: &WebhookEvent) -> Result<String, String> {
+    let fields = serde_json::to_value(event).map_err(|e| e.to_string())?;
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or("unclosed {{ in template")?;
+        let name = after[..end].trim();
+        let value = fields.get(name).ok_or_else(|| {
+            format!(
+                "unknown template field {name:?}; expected event, key, timestamp, \
+                 instance_id, schema_version or detail"
+            )
+        })?;
+        out.push_str(&value.to_string());
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    serde_json::from_str::<serde_json::Value>(&out)
+        .map_err(|e| format!("template does not render to JSON: {e}"))?;
+    Ok(out)
+}
+
+/// Registration-time check of a payload template against a sample event.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.len() > MAX_TEMPLATE_LEN {
+        return Err(format!("template exceeds {MAX_TEMPLATE_LEN} bytes"));
+    }
+    let sample = WebhookEvent {
+        schema_version: WEBHOOK_SCHEMA_VERSION,
+        event: "secret.created".into(),
+        key: "KEY".into(),
+        timestamp: 0,
+        instance_id: "sirr".into(),
+        detail: serde_json::json!({}),
+    };
+    render_template(template, &sample).map(drop)
+}
+
+/// Most per-event URL overrides on one registration.
+pub const MAX_EVENT_URLS: usize = 16;
+
+/// Registration-time check of per-event URLs: each names an exact event the
+/// registration subscribes to and passes the same SSRF check as `url`.
+pub fn validate_event_urls(
+    event_urls: &BTreeMap<String, String>,
+    events: &[String],
+) -> Result<(), String> {
+    if event_urls.len() > MAX_EVENT_URLS {
+        return Err(format!("at most {MAX_EVENT_URLS} event_urls"));
+    }
+    for (event, url) in event_urls {
+        if event == "*" || !matches_event(events, event) {
+            return Err(format!(
+                "event_urls key {event:?} is not an event this webhook subscribes to"
+            ));
+        }
+        validate_registration_url(url)?;
+    }
+    Ok(())
+}
+
+/// Request body for `event`: the rendered template, or the event itself.
+fn event_body(event: &WebhookEvent, template: Option<&str>) -> Result<String, String> {
+    match template {
+        Some(template) => render_template(template, event),
+        None => serde_json::to_string(event).map_err(|e| e.to_string()),
+    }
+}
+
+/// Existing registration that would deliver some of `events` to `url` a
+/// second time. URLs compare exactly, ignoring a trailing slash, so the same
+/// host with a different path is not a duplicate.
+pub fn find_duplicate<'a>(
+    existing: &'a [WebhookRegistration],
+    url: &str,
+    events: &[String],
+) -> Option<&'a WebhookRegistration> {
+    let url = url.trim_end_matches('/');
+    existing.iter().find(|reg| {
+        reg.url.trim_end_matches('/') == url
+            && (events
+                .iter()
+                .any(|e| e == "*" || matches_event(&reg.events, e)))
+    })
+}
+
+/// `schema_version` of a serialized event. Dead letters are replayed
+/// byte-for-byte, so the header follows the body rather than the current
+/// version; bodies predating the field are version 1.
+fn schema_version_of(body: &str) -> u64 {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("schema_version").and_then(|v| v.as_u64()))
+        .unwrap_or(1)
+}
+
 /// Compute HMAC-SHA256 hex digest.
 pub fn compute_signature(secret: &str, body: &str) -> String {
     let mut mac =
@@ -261,17 +958,25 @@ pub fn compute_signature(secret: &str, body: &str) -> String {
 
 /// Generate a webhook signing secret: "whsec_" + 32 random hex chars.
 pub fn generate_signing_secret() -> String {
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let bytes: [u8; 16] = rng.gen();
+    generate_signing_secret_with(&mut rand::rngs::OsRng)
+}
+
+/// [`generate_signing_secret`] drawing from `rng`.
+pub fn generate_signing_secret_with(rng: &mut (impl RngCore + CryptoRng)) -> String {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
     format!("whsec_{}", hex::encode(bytes))
 }
 
 /// Generate a webhook registration ID: 16 random hex chars.
 pub fn generate_webhook_id() -> String {
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let bytes: [u8; 8] = rng.gen();
+    generate_webhook_id_with(&mut rand::rngs::OsRng)
+}
+
+/// [`generate_webhook_id`] drawing from `rng`.
+pub fn generate_webhook_id_with(rng: &mut (impl RngCore + CryptoRng)) -> String {
+    let mut bytes = [0u8; 8];
+    rng.fill_bytes(&mut bytes);
     hex::encode(bytes)
 }
 
@@ -282,6 +987,13 @@ fn now() -> i64 {
         .as_secs() as i64
 }
 
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,6 +1095,46 @@ mod tests {
         assert!(!matches_event(&events, "secret.read"));
     }
 
+    #[test]
+    fn find_duplicate_requires_same_url_and_overlapping_events() {
+        let reg = |url: &str, events: &[&str]| WebhookRegistration {
+            id: url.into(),
+            url: url.into(),
+            secret: String::new(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            created_at: 0,
+            org_id: None,
+            template: None,
+            event_urls: Default::default(),
+        };
+        let existing = vec![
+            reg("https://hooks.example.com/a", &["secret.read"]),
+            reg("https://hooks.example.com/all", &["*"]),
+        ];
+        let events = |e: &[&str]| e.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+
+        assert!(
+            find_duplicate(&existing, "https://hooks.example.com/a/", &events(&["*"])).is_some()
+        );
+        assert!(find_duplicate(
+            &existing,
+            "https://hooks.example.com/all",
+            &events(&["secret.burned"])
+        )
+        .is_some());
+        // Same URL, disjoint events.
+        assert!(find_duplicate(
+            &existing,
+            "https://hooks.example.com/a",
+            &events(&["secret.burned"])
+        )
+        .is_none());
+        // Same host, different path.
+        assert!(
+            find_duplicate(&existing, "https://hooks.example.com/b", &events(&["*"])).is_none()
+        );
+    }
+
     #[test]
     fn generate_signing_secret_format() {
         let secret = generate_signing_secret();
@@ -395,4 +1147,499 @@ mod tests {
         let id = generate_webhook_id();
         assert_eq!(id.len(), 16); // 8 bytes = 16 hex chars
     }
+
+    #[test]
+    fn seeded_rng_gives_reproducible_ids_and_secrets() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (
+                generate_webhook_id_with(&mut rng),
+                generate_signing_secret_with(&mut rng),
+            )
+        };
+        let (id, secret) = run(42);
+        assert_eq!((id.clone(), secret.clone()), run(42));
+        assert_ne!((id.clone(), secret.clone()), run(43));
+        assert_eq!(id.len(), 16);
+        assert!(secret.starts_with("whsec_"));
+    }
+
+    // ── Retries and dead letters ─────────────────────────────────────────
+
+    use wiremock::matchers::{header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn make_sender() -> (WebhookSender, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::open(
+            &dir.path().join("test.db"),
+            crate::store::crypto::generate_key(),
+        )
+        .unwrap();
+        let sender = WebhookSender::new(store, "test".into(), None, Arc::new(vec![]))
+            .with_retry_policy(2, Duration::from_millis(10));
+        (sender, dir)
+    }
+
+    fn register(sender: &WebhookSender, url: String) -> WebhookRegistration {
+        let reg = WebhookRegistration {
+            id: generate_webhook_id(),
+            url,
+            secret: generate_signing_secret(),
+            events: vec!["*".into()],
+            created_at: now(),
+            org_id: None,
+            template: None,
+            event_urls: Default::default(),
+        };
+        sender.store.put_webhook(&reg).unwrap();
+        reg
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_dead_lettered() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let reg = register(&sender, format!("{}/hook", mock.uri()));
+        let event = WebhookEvent {
+            schema_version: WEBHOOK_SCHEMA_VERSION,
+            event: "secret.created".into(),
+            key: "K".into(),
+            timestamp: now(),
+            instance_id: "test".into(),
+            detail: serde_json::json!({}),
+        };
+        sender
+            .deliver(&reg.url, &event, &reg.secret, Some(&reg.id), None)
+            .await;
+
+        let letters = sender.store.list_dead_letters().unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].registration_id.as_deref(), Some(reg.id.as_str()));
+        assert_eq!(letters[0].attempts, 2);
+        assert!(letters[0].last_error.contains("500"));
+    }
+
+    #[tokio::test]
+    async fn dead_letters_redelivered_when_receiver_recovers() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header_exists("X-Sirr-Signature"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let reg = register(&sender, format!("{}/hook", mock.uri()));
+        sender
+            .store
+            .put_dead_letter(&DeadLetter {
+                id: 0,
+                url: reg.url.clone(),
+                registration_id: Some(reg.id.clone()),
+                body: r#"{"event":"secret.created"}"#.into(),
+                attempts: 3,
+                last_error: "connection refused".into(),
+                failed_at: now(),
+            })
+            .unwrap();
+
+        let delivered = sender.redeliver_dead_letters(Duration::ZERO).await;
+        assert_eq!(delivered, 1);
+        assert_eq!(sender.store.count_dead_letters().unwrap(), 0);
+
+        let received = mock.received_requests().await.unwrap();
+        let body = String::from_utf8(received[0].body.clone()).unwrap();
+        assert_eq!(body, r#"{"event":"secret.created"}"#);
+        let sig = received[0].headers.get("X-Sirr-Signature").unwrap();
+        assert_eq!(
+            sig.to_str().unwrap(),
+            format!("sha256={}", compute_signature(&reg.secret, &body))
+        );
+        // Stored before schema_version existed: delivered as version 1.
+        assert_eq!(received[0].headers["X-Sirr-Webhook-Version"], "1");
+    }
+
+    #[tokio::test]
+    async fn payload_and_header_carry_schema_version() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        register(&sender, format!("{}/hook", mock.uri()));
+        sender.fire("secret.created", "K", serde_json::json!({}));
+
+        for _ in 0..50 {
+            if !mock.received_requests().await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let received = mock.received_requests().await.unwrap();
+        assert_eq!(received.len(), 1);
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["schema_version"], WEBHOOK_SCHEMA_VERSION);
+        assert_eq!(
+            received[0].headers["X-Sirr-Webhook-Version"],
+            WEBHOOK_SCHEMA_VERSION.to_string().as_str()
+        );
+    }
+
+    #[tokio::test]
+    async fn event_prefix_applied_to_payload_but_not_subscription() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let sender = sender.with_event_prefix("prod");
+        let mut reg = register(&sender, format!("{}/hook", mock.uri()));
+        reg.events = vec!["secret.burned".into()];
+        sender.store.put_webhook(&reg).unwrap();
+
+        sender.fire("secret.read", "K", serde_json::json!({}));
+        sender.fire("secret.burned", "K", serde_json::json!({}));
+
+        // Deliveries are spawned; give them a moment to land.
+        for _ in 0..50 {
+            if !mock.received_requests().await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let received = mock.received_requests().await.unwrap();
+        assert_eq!(received.len(), 1);
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["event"], "prod.secret.burned");
+    }
+
+    #[tokio::test]
+    async fn failing_webhook_trips_breaker_and_recovers_after_cooldown() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let sender = sender
+            .with_retry_policy(1, Duration::ZERO)
+            .with_circuit_breaker(2, Duration::from_millis(200));
+        let reg = register(&sender, format!("{}/hook", mock.uri()));
+        let event = WebhookEvent {
+            schema_version: WEBHOOK_SCHEMA_VERSION,
+            event: "secret.created".into(),
+            key: "K".into(),
+            timestamp: now(),
+            instance_id: "test".into(),
+            detail: serde_json::json!({}),
+        };
+
+        for _ in 0..2 {
+            sender
+                .deliver(&reg.url, &event, &reg.secret, Some(&reg.id), None)
+                .await;
+        }
+        assert!(!sender.circuit_allows(&reg.id));
+
+        // Open circuit: the event is dead-lettered without a request.
+        sender.fire("secret.created", "K", serde_json::json!({}));
+        let letters = sender.store.list_dead_letters().unwrap();
+        assert_eq!(letters.len(), 3);
+        assert_eq!(letters[2].last_error, "circuit open");
+        assert_eq!(letters[2].attempts, 0);
+        assert_eq!(mock.received_requests().await.unwrap().len(), 2);
+
+        // After the cooldown one probe goes out; its success closes the circuit.
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(sender.circuit_allows(&reg.id));
+        assert!(!sender.circuit_allows(&reg.id), "only one half-open probe");
+        sender
+            .deliver(&reg.url, &event, &reg.secret, Some(&reg.id), None)
+            .await;
+        assert_eq!(mock.received_requests().await.unwrap().len(), 3);
+        assert!(sender.circuit_allows(&reg.id));
+        assert_eq!(sender.store.count_dead_letters().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn ordered_delivery_keeps_per_key_order_across_retries() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let sender = sender
+            .with_retry_policy(2, Duration::from_millis(100))
+            .with_ordered_delivery(true);
+        register(&sender, format!("{}/hook", mock.uri()));
+
+        // The first event fails once and is retried after a backoff; the
+        // second must wait for it instead of overtaking.
+        sender.fire("secret.created", "K", serde_json::json!({}));
+        sender.fire("secret.read", "K", serde_json::json!({}));
+
+        for _ in 0..50 {
+            if mock.received_requests().await.unwrap().len() >= 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let events: Vec<String> = mock
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| {
+                let body: serde_json::Value = serde_json::from_slice(&r.body).unwrap();
+                body["event"].as_str().unwrap().to_owned()
+            })
+            .collect();
+        assert_eq!(events, ["secret.created", "secret.created", "secret.read"]);
+        // The drained queue is dropped once the worker sees the last response.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(sender.key_queues.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn template_substitutes_fields_as_json_only() {
+        let event = WebhookEvent {
+            schema_version: WEBHOOK_SCHEMA_VERSION,
+            event: "secret.read".into(),
+            key: r#"K", "admin": true, "x": ""#.into(),
+            timestamp: 1700000000,
+            instance_id: "sirr-1".into(),
+            detail: serde_json::json!({"reason": "ttl"}),
+        };
+        let body = render_template(
+            r#"{"text": {{ key }}, "kind": {{event}}, "at": {{timestamp}}, "info": {{detail}}}"#,
+            &event,
+        )
+        .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!({
+                "text": event.key,
+                "kind": "secret.read",
+                "at": 1700000000,
+                "info": {"reason": "ttl"},
+            })
+        );
+
+        assert!(validate_template(r#"{"v": {{value}}}"#).is_err());
+        assert!(validate_template(r#"{"k": {{key}"#).is_err());
+        assert!(validate_template(r#"text {{key}}"#).is_err());
+        assert!(validate_template(&"x".repeat(MAX_TEMPLATE_LEN + 1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn templated_delivery_signs_the_rendered_body() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let mut reg = register(&sender, format!("{}/hook", mock.uri()));
+        reg.template = Some(r#"{"text": {{event}}, "secret": {{key}}}"#.into());
+        sender.store.put_webhook(&reg).unwrap();
+        sender.fire("secret.burned", "DB_URL", serde_json::json!({}));
+
+        for _ in 0..50 {
+            if !mock.received_requests().await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let received = mock.received_requests().await.unwrap();
+        let body = String::from_utf8(received[0].body.clone()).unwrap();
+        assert_eq!(body, r#"{"text": "secret.burned", "secret": "DB_URL"}"#);
+        assert_eq!(
+            received[0].headers["X-Sirr-Signature"].to_str().unwrap(),
+            format!("sha256={}", compute_signature(&reg.secret, &body))
+        );
+    }
+
+    #[tokio::test]
+    async fn event_urls_route_each_event_to_its_own_receiver() {
+        let default_rx = MockServer::start().await;
+        let burn_rx = MockServer::start().await;
+        for mock in [&default_rx, &burn_rx] {
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(mock)
+                .await;
+        }
+
+        let (sender, _dir) = make_sender();
+        let mut reg = register(&sender, format!("{}/hook", default_rx.uri()));
+        reg.event_urls
+            .insert("secret.burned".into(), format!("{}/burn", burn_rx.uri()));
+        sender.store.put_webhook(&reg).unwrap();
+        sender.fire("secret.burned", "DB_URL", serde_json::json!({}));
+        sender.fire("secret.created", "DB_URL", serde_json::json!({}));
+
+        for _ in 0..50 {
+            if !default_rx.received_requests().await.unwrap().is_empty()
+                && !burn_rx.received_requests().await.unwrap().is_empty()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let event_of = |r: &wiremock::Request| {
+            serde_json::from_slice::<serde_json::Value>(&r.body).unwrap()["event"].clone()
+        };
+        let to_default = default_rx.received_requests().await.unwrap();
+        let to_burn = burn_rx.received_requests().await.unwrap();
+        assert_eq!(to_default.len(), 1);
+        assert_eq!(event_of(&to_default[0]), "secret.created");
+        assert_eq!(to_burn.len(), 1);
+        assert_eq!(to_burn[0].url.path(), "/burn");
+        assert_eq!(event_of(&to_burn[0]), "secret.burned");
+    }
+
+    #[test]
+    fn event_urls_must_name_subscribed_events() {
+        let urls = |event: &str| {
+            BTreeMap::from([(event.to_string(), "https://hooks.example.com/b".to_string())])
+        };
+        let events = vec!["secret.burned".to_string()];
+        assert!(validate_event_urls(&urls("secret.burned"), &events).is_ok());
+        assert!(validate_event_urls(&urls("secret.read"), &events).is_err());
+        assert!(validate_event_urls(&urls("*"), &["*".to_string()]).is_err());
+        let private =
+            BTreeMap::from([("secret.burned".to_string(), "http://10.0.0.1/".to_string())]);
+        assert!(validate_event_urls(&private, &events).is_err());
+    }
+
+    #[tokio::test]
+    async fn persisted_retry_resumes_after_restart() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let open = || {
+            let key = crate::store::crypto::load_key(&[3; 32]).unwrap();
+            let store = Store::open(&path, key).ok()?;
+            Some(
+                WebhookSender::new(store, "test".into(), None, Arc::new(vec![]))
+                    .with_retry_policy(3, Duration::from_millis(10))
+                    .with_persisted_retries(true),
+            )
+        };
+
+        let sender = open().unwrap();
+        register(&sender, format!("{}/hook", mock.uri()));
+        sender.fire("secret.created", "DB_URL", serde_json::json!({}));
+        for _ in 0..50 {
+            if !sender.store.list_pending_retries().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(sender.store.list_pending_retries().unwrap().len(), 1);
+        assert_eq!(mock.received_requests().await.unwrap().len(), 1);
+
+        // Restart: reopen once the delivery task has let go of the store.
+        drop(sender);
+        let mut reopened = None;
+        for _ in 0..50 {
+            reopened = open();
+            if reopened.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let sender = reopened.expect("store reopens");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(sender.retry_due().await, 1);
+        assert_eq!(mock.received_requests().await.unwrap().len(), 2);
+        assert!(sender.store.list_pending_retries().unwrap().is_empty());
+        assert_eq!(sender.store.count_dead_letters().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn preview_matches_the_delivered_body_and_signature() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+
+        let (sender, _dir) = make_sender();
+        let sender = sender.with_event_prefix("prod");
+        let reg = register(&sender, format!("{}/hook", mock.uri()));
+        let detail = serde_json::json!({"reason": "max_reads"});
+        sender.fire("secret.burned", "DB_URL", detail.clone());
+        let (preview, signature) = sender
+            .preview("secret.burned", "DB_URL", detail, None, &reg.secret)
+            .unwrap();
+
+        for _ in 0..50 {
+            if !mock.received_requests().await.unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let received = mock.received_requests().await.unwrap();
+        let delivered = String::from_utf8(received[0].body.clone()).unwrap();
+        // Only the timestamp may differ between the two.
+        let without_timestamp = |body: &str| {
+            let mut v: serde_json::Value = serde_json::from_str(body).unwrap();
+            v.as_object_mut().unwrap().remove("timestamp");
+            v
+        };
+        assert_eq!(without_timestamp(&preview), without_timestamp(&delivered));
+        assert_eq!(
+            signature,
+            format!("sha256={}", compute_signature(&reg.secret, &preview))
+        );
+        assert_eq!(
+            received[0].headers["X-Sirr-Signature"].to_str().unwrap(),
+            format!("sha256={}", compute_signature(&reg.secret, &delivered))
+        );
+    }
 }
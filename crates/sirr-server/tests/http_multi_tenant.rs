@@ -28,8 +28,9 @@ use sirr_server::{
     org_handlers::*,
     store::{
         crypto,
+        model::NewSecret,
         org::{OrgRecord, PrincipalKeyRecord, PrincipalRecord},
-        Store,
+        AuditQuery, MemoryStore, SecretStore, Store,
     },
     AppState,
 };
@@ -47,13 +48,19 @@ async fn inject_connect_info(mut req: Request, next: Next) -> Response {
 /// Build a test app with all routes wired, returning the server, store, and
 /// temp dir (kept alive so the db file is not deleted).
 fn build_test_app() -> (TestServer, Store, TempDir) {
+    build_test_app_with(|_| {})
+}
+
+/// Like `build_test_app`, but lets a test adjust the `AppState` first.
+fn build_test_app_with(configure: impl FnOnce(&mut AppState)) -> (TestServer, Store, TempDir) {
     let dir = tempdir().unwrap();
     let key = crypto::generate_key();
     let store = Store::open(&dir.path().join("test.db"), key).unwrap();
 
-    let state = AppState {
+    let mut state = AppState {
         store: store.clone(),
-        api_key: Some(MASTER_KEY.to_string()),
+        secrets: Arc::new(store.clone()),
+        api_keys: sirr_server::auth::ApiKeys::unlabeled(MASTER_KEY),
         license: LicenseStatus::Free,
         validator: None,
         webhook_sender: None,
@@ -61,15 +68,58 @@ fn build_test_app() -> (TestServer, Store, TempDir) {
         redact_audit_keys: false,
         webhook_allowed_origins: Arc::new(vec![]),
         enable_public_bucket: true,
+        started_at: now_secs(),
+        started: std::time::Instant::now(),
+        create_limiter: None,
+        sweep_lock: Default::default(),
+        public_base_url: None,
+        max_secrets: None,
+        auto_prune_on_full: false,
+        min_ttl_seconds: 0,
+        require_ttl: false,
+        limit_status: axum::http::StatusCode::PAYMENT_REQUIRED,
+        reserved_key_prefixes: Default::default(),
+        generated_key_length: 24,
+        key_generator: Default::default(),
+        audit_cors_rejections: false,
+        audit_failure_mode: Default::default(),
+        audit_max_range_secs: None,
+        create_mode: Default::default(),
+        idempotency_ttl_secs: 24 * 60 * 60,
+        maintenance: Default::default(),
+        self_destruct: None,
+        min_value_bytes: 0,
+        listeners: Default::default(),
+        metrics: Default::default(),
+        metrics_public: false,
     };
+    configure(&mut state);
 
     // Public bucket: read routes (no auth).
-    let secret_read = Router::new().route("/secrets/{key}", get(get_secret).head(head_secret));
+    let secret_read = Router::new()
+        .route("/secrets/{key}", get(get_secret).head(head_secret))
+        .route("/secrets/{key}/describe", get(describe_secret))
+        .route("/secrets/{key}/field/{field}", get(get_secret_field))
+        .route("/secrets/batch-get", post(batch_get_secrets));
 
     // Public bucket: write routes (master key required).
     let protected_public = Router::new()
-        .route("/secrets", get(list_secrets).post(create_secret))
+        .route(
+            "/secrets",
+            get(list_secrets)
+                .post(create_secret)
+                .delete(delete_matching_secrets),
+        )
+        .route("/secrets/batch", post(batch_create_secrets))
         .route("/secrets/{key}", patch(patch_secret).delete(delete_secret))
+        .route("/secrets/{key}/peek", get(peek_secret))
+        .route("/secrets/{key}/touch", post(touch_secret))
+        .route("/secrets/{key}/aliases", post(create_alias))
+        .route("/secrets/{key}/aliases/{alias}", delete(delete_alias))
+        .route("/audit", get(audit_events))
+        .route("/webhooks/export", get(export_webhooks))
+        .route("/webhooks/import", post(import_webhooks))
+        .route("/capacity", get(capacity))
         .route("/prune", post(prune_secrets))
         .layer(middleware::from_fn_with_state(
             state.clone(),
@@ -119,6 +169,7 @@ fn build_test_app() -> (TestServer, Store, TempDir) {
         .merge(protected_public)
         .merge(org_protected)
         .with_state(state)
+        .layer(middleware::from_fn(sirr_server::server::add_retryable_flag))
         .layer(middleware::from_fn(inject_connect_info));
 
     let server = TestServer::new(app);
@@ -177,6 +228,7 @@ fn bootstrap_org_with_key(
             valid_after: now - 60,
             valid_before: now + 3600,
             created_at: now,
+            max_secrets: None,
         })
         .unwrap();
 
@@ -205,6 +257,7 @@ fn add_named_key(
             valid_after: now - 60,
             valid_before: now + 3600,
             created_at: now,
+            max_secrets: None,
         })
         .unwrap();
 
@@ -543,3 +596,2526 @@ async fn wrong_org_key_returns_forbidden() {
         .await;
     resp.assert_status(axum::http::StatusCode::FORBIDDEN);
 }
+
+// ── Test: Per-key secret quota ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn per_key_quota_blocks_only_exhausted_key() {
+    let (server, store, _dir) = build_test_app();
+
+    let (org_id, principal_id, unlimited_key) =
+        bootstrap_org_with_key(&store, "quota", "ci-bot", "writer");
+
+    // Second key for the same principal, limited to two secrets.
+    let now = now_secs();
+    let limited_key = format!("sirr_key_{}", hex::encode(rand::random::<[u8; 16]>()));
+    store
+        .put_principal_key(&PrincipalKeyRecord {
+            id: "pk_limited".into(),
+            principal_id: principal_id.clone(),
+            org_id: org_id.clone(),
+            name: "limited".into(),
+            key_hash: Sha256::digest(limited_key.as_bytes()).to_vec(),
+            valid_after: now - 60,
+            valid_before: now + 3600,
+            created_at: now,
+            max_secrets: Some(2),
+        })
+        .unwrap();
+
+    for key in ["Q1", "Q2"] {
+        let resp = server
+            .post(&format!("/orgs/{org_id}/secrets"))
+            .authorization_bearer(&limited_key)
+            .json(&json!({"key": key, "value": "v"}))
+            .await;
+        resp.assert_status(axum::http::StatusCode::CREATED);
+    }
+
+    // Limited key is at quota → 403.
+    let resp = server
+        .post(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&limited_key)
+        .json(&json!({"key": "Q3", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::FORBIDDEN);
+    let body: Value = resp.json();
+    assert!(body["error"].as_str().unwrap().contains("quota"));
+
+    // Overwriting a secret it already holds is not another one.
+    let resp = server
+        .post(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&limited_key)
+        .json(&json!({"key": "Q2", "value": "v2"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+
+    // The principal's other key is unaffected.
+    let resp = server
+        .post(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&unlimited_key)
+        .json(&json!({"key": "Q3", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+
+    // Deleting one of the limited key's secrets frees a slot.
+    let resp = server
+        .delete(&format!("/orgs/{org_id}/secrets/Q1"))
+        .authorization_bearer(&limited_key)
+        .await;
+    resp.assert_status(axum::http::StatusCode::OK);
+    let resp = server
+        .post(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&limited_key)
+        .json(&json!({"key": "Q4", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+}
+
+// ── Test: Create reports every validation failure at once ───────────────────
+
+#[tokio::test]
+async fn create_returns_all_validation_errors() {
+    let (server, _store, _dir) = build_test_app();
+
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({
+            "key": "bad/key",
+            "value": "v",
+            "ttl_seconds": 999_999_999_999u64,
+            "max_reads": 0,
+        }))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    let body: Value = resp.json();
+    let fields: Vec<&str> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["key", "ttl_seconds", "max_reads"]);
+    assert!(body["errors"][0]["message"]
+        .as_str()
+        .unwrap()
+        .contains("1–256"));
+    assert_eq!(body["error"], body["errors"][0]["message"]);
+
+    // A valid request still succeeds.
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "good", "value": "v", "max_reads": 1}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+}
+
+// ── Test: Annotations round-trip through create, list and patch ─────────────
+
+#[tokio::test]
+async fn annotations_round_trip() {
+    let (server, _store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({
+            "key": "DB_PASS",
+            "value": "hunter2",
+            "delete": false,
+            "annotations": {"runbook": "https://wiki.example.com/db", "owner": "platform"},
+        }))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let resp = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    let body: Value = resp.json();
+    assert_eq!(
+        body["secrets"][0]["annotations"]["runbook"],
+        "https://wiki.example.com/db"
+    );
+    assert_eq!(body["secrets"][0]["annotations"]["owner"], "platform");
+
+    // Patch replaces the whole map.
+    let resp = server
+        .patch("/secrets/DB_PASS")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"annotations": {"description": "primary db"}}))
+        .await;
+    resp.assert_status_ok();
+    let meta: Value = resp.json();
+    assert_eq!(meta["annotations"], json!({"description": "primary db"}));
+
+    // Oversized annotations are rejected on create and patch.
+    let huge = "x".repeat(sirr_server::store::model::MAX_ANNOTATIONS_BYTES + 1);
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "BIG", "value": "v", "annotations": {"notes": huge}}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    let body: Value = resp.json();
+    assert_eq!(body["errors"][0]["field"], "annotations");
+
+    server
+        .patch("/secrets/DB_PASS")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"annotations": {"notes": huge}}))
+        .await
+        .assert_status(axum::http::StatusCode::BAD_REQUEST);
+}
+
+// ── Test: Describe returns metadata without consuming a read ────────────────
+
+#[tokio::test]
+async fn describe_does_not_consume_reads() {
+    let (server, store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "ONCE", "value": "v", "max_reads": 1}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    for _ in 0..2 {
+        let resp = server.get("/secrets/ONCE/describe").await;
+        resp.assert_status_ok();
+        let meta: Value = resp.json();
+        assert_eq!(meta["key"], "ONCE");
+        assert_eq!(meta["read_count"], 0);
+        assert!(meta.get("value").is_none());
+    }
+
+    // The single allowed read is still available.
+    server.get("/secrets/ONCE").await.assert_status_ok();
+    server
+        .get("/secrets/ONCE/describe")
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+
+    let events = store
+        .list_audit(&sirr_server::store::AuditQuery {
+            since: None,
+            until: None,
+            action: Some("secret.describe".into()),
+            limit: 10,
+            org_id: None,
+        })
+        .unwrap();
+    assert_eq!(events.len(), 3);
+}
+
+// ── Test: webhook_events is validated at create time ────────────────────────
+
+#[tokio::test]
+async fn create_validates_webhook_events() {
+    let (server, _store, _dir) = build_test_app();
+
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "K", "value": "v", "webhook_events": ["secret.burned"]}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    let body: Value = resp.json();
+    assert_eq!(body["errors"][0]["field"], "webhook_events");
+
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({
+            "key": "K",
+            "value": "v",
+            "webhook_url": "https://hooks.example.com/sirr",
+            "webhook_events": ["secret.exploded"],
+        }))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    let body: Value = resp.json();
+    let fields: Vec<&str> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["field"].as_str().unwrap())
+        .collect();
+    assert!(fields.contains(&"webhook_events"));
+}
+
+// ── Test: Rapid creates beyond SIRR_CREATE_RATE_LIMIT are throttled ─────────
+
+#[tokio::test]
+async fn create_rate_limit_throttles_rapid_creates() {
+    let (server, store, _dir) = build_test_app_with(|state| {
+        state.create_limiter = Some(sirr_server::rate_limit::CreateLimiter::new(3));
+    });
+
+    for i in 0..3 {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": format!("K{i}"), "value": "v"}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "K3", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("retry-after"));
+    assert_eq!(store.list().unwrap().len(), 3);
+}
+
+// ── Test: Admin peek returns the value without consuming a read ─────────────
+
+#[tokio::test]
+async fn peek_returns_value_without_consuming_reads() {
+    let (server, store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "ONCE", "value": "s3cret", "max_reads": 1}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    // Admin scope only.
+    server
+        .get("/secrets/ONCE/peek")
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+    for _ in 0..2 {
+        let resp = server
+            .get("/secrets/ONCE/peek")
+            .authorization_bearer(MASTER_KEY)
+            .await;
+        resp.assert_status_ok();
+        assert_eq!(resp.json::<Value>()["value"], "s3cret");
+    }
+
+    let meta = server.get("/secrets/ONCE/describe").await.json::<Value>();
+    assert_eq!(meta["read_count"], 0);
+
+    // The one real read still works and burns the secret.
+    server.get("/secrets/ONCE").await.assert_status_ok();
+    server
+        .get("/secrets/ONCE/peek")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+
+    let events = store
+        .list_audit(&sirr_server::store::AuditQuery {
+            since: None,
+            until: None,
+            action: Some("secret.peek".into()),
+            limit: 10,
+            org_id: None,
+        })
+        .unwrap();
+    assert_eq!(events.len(), 3);
+}
+
+// ── Test: SIRR_AUDIT_ACTIONS suppresses unlisted actions ────────────────────
+
+#[tokio::test]
+async fn audit_actions_filter_skips_reads() {
+    let (server, store, _dir) = build_test_app_with(|state| {
+        state.store = state.store.clone().with_audit_actions(vec![
+            "create".into(),
+            "delete".into(),
+            "patch".into(),
+        ]);
+        state.secrets = Arc::new(state.store.clone());
+    });
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "HOT", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server.get("/secrets/HOT").await.assert_status_ok();
+
+    let actions: Vec<String> = store
+        .list_audit(&sirr_server::store::AuditQuery {
+            since: None,
+            until: None,
+            action: None,
+            limit: 10,
+            org_id: None,
+        })
+        .unwrap()
+        .into_iter()
+        .map(|e| e.action)
+        .collect();
+    assert_eq!(actions, vec!["secret.create"]);
+}
+
+// ── Test: Range requests on non-burning secrets ─────────────────────────────
+
+#[tokio::test]
+async fn range_requests_return_partial_content() {
+    let (server, _store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "BLOB", "value": "0123456789"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let resp = server
+        .get("/secrets/BLOB")
+        .add_header("range", "bytes=2-5")
+        .await;
+    resp.assert_status(axum::http::StatusCode::PARTIAL_CONTENT);
+    assert_eq!(resp.header("content-range"), "bytes 2-5/10");
+    assert_eq!(resp.as_bytes().as_ref(), b"2345");
+
+    let resp = server
+        .get("/secrets/BLOB")
+        .add_header("range", "bytes=-3")
+        .await;
+    resp.assert_status(axum::http::StatusCode::PARTIAL_CONTENT);
+    assert_eq!(resp.as_bytes().as_ref(), b"789");
+
+    server
+        .get("/secrets/BLOB")
+        .add_header("range", "bytes=20-30")
+        .await
+        .assert_status(axum::http::StatusCode::RANGE_NOT_SATISFIABLE);
+}
+
+#[tokio::test]
+async fn range_requests_rejected_on_burn_on_read_secrets() {
+    let (server, _store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "ONCE", "value": "0123456789", "max_reads": 1}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    server
+        .get("/secrets/ONCE")
+        .add_header("range", "bytes=0-3")
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+
+    // The rejected range request did not consume the only read.
+    let resp = server.get("/secrets/ONCE").await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["value"], "0123456789");
+}
+
+// ── Test: Structured secrets expose individual fields ───────────────────────
+
+#[tokio::test]
+async fn structured_secret_field_extraction() {
+    let (server, _store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({
+            "key": "DB",
+            "type": "structured",
+            "value": r#"{"username":"app","password":"hunter2","port":5432}"#,
+            "max_reads": 2,
+        }))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let meta = server.get("/secrets/DB/describe").await.json::<Value>();
+    assert_eq!(meta["type"], "structured");
+
+    let resp = server.get("/secrets/DB/field/password").await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["value"], "hunter2");
+
+    let resp = server.get("/secrets/DB/field/port").await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["value"], 5432);
+
+    // Each field read counted: two reads burned the secret.
+    server
+        .get("/secrets/DB")
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn structured_secret_rejects_non_object_values() {
+    let (server, _store, _dir) = build_test_app();
+
+    for value in ["plain text", "[1,2,3]", "\"str\""] {
+        let resp = server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": "BAD", "type": "structured", "value": value}))
+            .await;
+        resp.assert_status(axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(resp.json::<Value>()["errors"][0]["field"], "value");
+    }
+
+    // Field reads are refused on text secrets without consuming a read.
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "TEXT", "value": r#"{"a":1}"#, "max_reads": 1}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server
+        .get("/secrets/TEXT/field/a")
+        .await
+        .assert_status(axum::http::StatusCode::BAD_REQUEST);
+    server.get("/secrets/TEXT").await.assert_status_ok();
+}
+
+// ── Test: Error bodies carry a retryable flag ───────────────────────────────
+
+#[tokio::test]
+async fn error_responses_flag_retryable() {
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.create_limiter = Some(sirr_server::rate_limit::CreateLimiter::new(1));
+    });
+
+    let resp = server.get("/secrets/MISSING").await;
+    resp.assert_status_not_found();
+    let body: Value = resp.json();
+    assert_eq!(body["error"], "not found or expired");
+    assert_eq!(body["retryable"], false);
+
+    for _ in 0..2 {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": "K", "value": "v"}))
+            .await;
+    }
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "K", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.json::<Value>()["retryable"], true);
+}
+
+// ── Test: Handlers run against the in-memory backend ────────────────────────
+
+#[tokio::test]
+async fn handlers_work_against_memory_backend() {
+    let memory = MemoryStore::new();
+    let backend = memory.clone();
+    let (server, store, _dir) = build_test_app_with(move |state| {
+        state.secrets = Arc::new(backend);
+    });
+
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "MEM", "value": "v1", "max_reads": 2, "delete": false}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+
+    // The secret lives only in the memory backend.
+    assert!(memory.head("MEM").unwrap().is_some());
+    assert!(store.head("MEM").unwrap().is_none());
+
+    let resp = server.get("/secrets/MEM").await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["value"], "v1");
+
+    let resp = server
+        .patch("/secrets/MEM")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"value": "v2"}))
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["read_count"], 0);
+
+    server.get("/secrets/MEM").await.assert_status_ok();
+    server.get("/secrets/MEM").await.assert_status_ok();
+    server
+        .get("/secrets/MEM")
+        .await
+        .assert_status(axum::http::StatusCode::GONE);
+
+    let resp = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["secrets"][0]["key"], "MEM");
+
+    server
+        .delete("/secrets/MEM")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .assert_status_ok();
+    server.get("/secrets/MEM").await.assert_status_not_found();
+
+    let actions: Vec<String> = memory
+        .list_audit(&sirr_server::store::AuditQuery {
+            since: None,
+            until: None,
+            action: None,
+            limit: 100,
+            org_id: None,
+        })
+        .unwrap()
+        .into_iter()
+        .map(|e| e.action)
+        .collect();
+    assert!(actions.iter().any(|a| a == "secret.create"));
+    assert!(actions.iter().any(|a| a == "secret.delete"));
+}
+
+// ── Test: Only one sweep runs at a time ─────────────────────────────────────
+
+#[tokio::test]
+async fn concurrent_prune_reports_sweep_in_progress() {
+    let lock = Arc::new(tokio::sync::Mutex::new(()));
+    let shared = lock.clone();
+    let (server, _store, _dir) = build_test_app_with(move |state| {
+        state.sweep_lock = shared;
+    });
+
+    // A sweep is in flight (scheduled or on-demand): the second one backs off.
+    let running = lock.lock().await;
+    let resp = server.post("/prune").authorization_bearer(MASTER_KEY).await;
+    resp.assert_status(axum::http::StatusCode::CONFLICT);
+    assert_eq!(resp.json::<Value>()["error"], "sweep already running");
+
+    drop(running);
+    let resp = server.post("/prune").authorization_bearer(MASTER_KEY).await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["pruned"], 0);
+}
+
+// ── Test: Idempotency-Key replays the original create ───────────────────────
+
+#[tokio::test]
+async fn idempotent_create_replays_original_response() {
+    let (server, store, _dir) = build_test_app();
+    let body = json!({"key": "ONCE", "value": "v", "max_reads": 1});
+
+    let first = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("idempotency-key", "req-1")
+        .json(&body)
+        .await;
+    first.assert_status(axum::http::StatusCode::CREATED);
+
+    // Burn the secret; a re-create would bring it back.
+    server.get("/secrets/ONCE").await.assert_status_ok();
+
+    let retry = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("idempotency-key", "req-1")
+        .json(&body)
+        .await;
+    retry.assert_status(axum::http::StatusCode::CREATED);
+    assert_eq!(retry.text(), first.text());
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    server.get("/secrets/ONCE").await.assert_status_not_found();
+
+    let creates = store
+        .list_audit(&sirr_server::store::AuditQuery {
+            since: None,
+            until: None,
+            action: Some("secret.create".into()),
+            limit: 10,
+            org_id: None,
+        })
+        .unwrap();
+    assert_eq!(creates.len(), 1);
+
+    // A different key is a new request.
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("idempotency-key", "req-2")
+        .json(&body)
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server.get("/secrets/ONCE").await.assert_status_ok();
+}
+
+// ── Test: List reports lifecycle status and filters by it ───────────────────
+
+#[tokio::test]
+async fn list_reports_and_filters_by_status() {
+    let (server, store, _dir) = build_test_app();
+    for body in [
+        json!({"key": "LIVE", "value": "v"}),
+        json!({"key": "SEALED", "value": "v", "max_reads": 1, "delete": false}),
+    ] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    server.get("/secrets/SEALED").await.assert_status_ok();
+    store
+        .put(&NewSecret {
+            key: "STALE".into(),
+            value: "v".into(),
+            ttl_seconds: Some(0),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
+
+    let statuses = |body: Value| -> Vec<(String, String)> {
+        body["secrets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| {
+                (
+                    s["key"].as_str().unwrap().to_owned(),
+                    s["status"].as_str().unwrap().to_owned(),
+                )
+            })
+            .collect()
+    };
+
+    let resp = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(
+        statuses(resp.json()),
+        vec![
+            ("LIVE".to_owned(), "active".to_owned()),
+            ("SEALED".to_owned(), "sealed".to_owned()),
+        ]
+    );
+
+    let resp = server
+        .get("/secrets?status=sealed")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    assert_eq!(
+        statuses(resp.json()),
+        vec![("SEALED".to_owned(), "sealed".to_owned())]
+    );
+
+    let resp = server
+        .get("/secrets?status=expired-pending-sweep")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    assert_eq!(
+        statuses(resp.json()),
+        vec![("STALE".to_owned(), "expired-pending-sweep".to_owned())]
+    );
+
+    server
+        .get("/secrets?status=bogus")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .assert_status_bad_request();
+}
+
+// ── Test: Create response carries the retrieval URL ─────────────────────────
+
+#[tokio::test]
+async fn create_returns_url_when_base_configured() {
+    let (server, _store, _dir) = build_test_app();
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "NOURL", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+    assert!(resp.json::<Value>().get("url").is_none());
+
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.public_base_url = Some("https://sirr.example.com".into());
+    });
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "SHARE_ME", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+    let body: Value = resp.json();
+    assert_eq!(body["key"], "SHARE_ME");
+    assert_eq!(body["url"], "https://sirr.example.com/secrets/SHARE_ME");
+}
+
+// ── Test: Duplicate webhook registrations are rejected ──────────────────────
+
+#[tokio::test]
+async fn duplicate_webhook_url_is_rejected() {
+    let (server, store, _dir) = build_test_app();
+    let (org_id, _principal_id, raw_key) =
+        bootstrap_org_with_key(&store, "hooks", "admin", "admin");
+    let register = |url: &'static str, events: Value| {
+        server
+            .post(&format!("/orgs/{org_id}/webhooks"))
+            .authorization_bearer(&raw_key)
+            .json(&json!({"url": url, "events": events}))
+    };
+
+    let first = register("https://hooks.example.com/sirr", json!(["secret.read"])).await;
+    first.assert_status(axum::http::StatusCode::CREATED);
+    let first_id = first.json::<Value>()["id"].clone();
+
+    let dup = register("https://hooks.example.com/sirr", json!(["*"])).await;
+    dup.assert_status(axum::http::StatusCode::CONFLICT);
+    assert_eq!(dup.json::<Value>()["id"], first_id);
+
+    // Same host, different path.
+    register("https://hooks.example.com/other", json!(["secret.read"]))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    assert_eq!(store.list_webhooks().unwrap().len(), 2);
+}
+
+// ── Test: Value fingerprint tracks changes without consuming reads ──────────
+
+#[tokio::test]
+async fn value_fingerprint_changes_only_when_value_does() {
+    let (server, _store, _dir) = build_test_app();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "FP", "value": "v1", "max_reads": 1, "delete": false}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let fingerprint = |resp: &axum_test::TestResponse| {
+        resp.headers()["x-sirr-value-fingerprint"]
+            .to_str()
+            .unwrap()
+            .to_owned()
+    };
+    let head = server.method(Method::HEAD, "/secrets/FP").await;
+    head.assert_status_ok();
+    let original = fingerprint(&head);
+    assert_eq!(original.len(), 64);
+    assert!(!original.contains("v1"));
+
+    // Stable across describes and metadata-only patches; no read consumed.
+    let describe = server.get("/secrets/FP/describe").await;
+    assert_eq!(fingerprint(&describe), original);
+    assert_eq!(describe.json::<Value>()["value_fingerprint"], original);
+    server
+        .patch("/secrets/FP")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"max_reads": 3}))
+        .await
+        .assert_status_ok();
+    let head = server.method(Method::HEAD, "/secrets/FP").await;
+    assert_eq!(fingerprint(&head), original);
+    assert_eq!(head.headers()["x-sirr-read-count"], "0");
+
+    server
+        .patch("/secrets/FP")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"value": "v2"}))
+        .await
+        .assert_status_ok();
+    let head = server.method(Method::HEAD, "/secrets/FP").await;
+    assert_ne!(fingerprint(&head), original);
+}
+
+// ── Test: Malformed JSON bodies get the JSON error shape ────────────────────
+
+#[tokio::test]
+async fn malformed_json_returns_structured_error() {
+    let (server, store, _dir) = build_test_app();
+
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .content_type("application/json")
+        .bytes("{\"key\": \"K\", \"value\": ".into())
+        .await;
+    resp.assert_status_bad_request();
+    let body: Value = resp.json();
+    assert_eq!(body["code"], "invalid_json");
+    assert!(body["error"].as_str().unwrap().contains("JSON"));
+
+    // Well-formed JSON of the wrong shape uses the same shape.
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "K"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(resp.json::<Value>()["code"], "invalid_json");
+
+    // Org endpoints share the extractor.
+    let (org_id, _principal_id, raw_key) =
+        bootstrap_org_with_key(&store, "json", "writer", "writer");
+    let resp = server
+        .post(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&raw_key)
+        .content_type("application/json")
+        .bytes("not json".into())
+        .await;
+    resp.assert_status_bad_request();
+    assert_eq!(resp.json::<Value>()["code"], "invalid_json");
+}
+
+// ── Test: A full store prunes expired secrets before rejecting creates ──────
+
+#[tokio::test]
+async fn create_at_capacity_auto_prunes_expired_secrets() {
+    let (strict, strict_store, _dir) = build_test_app_with(|state| {
+        state.max_secrets = Some(2);
+    });
+    let (pruning, store, _dir2) = build_test_app_with(|state| {
+        state.max_secrets = Some(2);
+        state.auto_prune_on_full = true;
+    });
+    for s in [&strict_store, &store] {
+        s.put(&NewSecret {
+            key: "OLD".into(),
+            value: "v".into(),
+            ttl_seconds: Some(1),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
+        s.put(&NewSecret {
+            key: "LIVE".into(),
+            value: "v".into(),
+            delete: true,
+            ..NewSecret::default()
+        })
+        .unwrap();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+
+    // Without auto-prune the expired record still occupies its slot.
+    let resp = strict
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "NEW", "value": "v"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::INSUFFICIENT_STORAGE);
+    assert_eq!(
+        resp.json::<Value>()["error"],
+        "secret store is full (2 secrets)"
+    );
+
+    // Overwriting an existing key is not growth.
+    strict
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "LIVE", "value": "v2"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    pruning
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "NEW", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    assert!(store.head("OLD").unwrap().is_none());
+    assert_eq!(store.list_with_status().unwrap().len(), 2);
+
+    // Still full of live secrets: rejected even after pruning.
+    pruning
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "MORE", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::INSUFFICIENT_STORAGE);
+}
+
+// ── Test: TTL policy rejects missing and too-short TTLs ─────────────────────
+
+#[tokio::test]
+async fn create_enforces_configured_ttl_policy() {
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.require_ttl = true;
+        state.min_ttl_seconds = 60;
+    });
+    let create = |body: Value| {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+    };
+
+    let resp = create(json!({"key": "FOREVER", "value": "v"})).await;
+    resp.assert_status_bad_request();
+    let body: Value = resp.json();
+    assert_eq!(body["errors"][0]["field"], "ttl_seconds");
+    assert_eq!(body["error"], "ttl_seconds is required on this server");
+
+    let resp = create(json!({"key": "BRIEF", "value": "v", "ttl_seconds": 59})).await;
+    resp.assert_status_bad_request();
+    assert_eq!(
+        resp.json::<Value>()["error"],
+        "ttl_seconds must be at least 60 on this server"
+    );
+
+    create(json!({"key": "OK", "value": "v", "ttl_seconds": 60}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+}
+
+// ── Test: conditional delete honours If-Match / If-Unmodified-Since ─────────
+
+#[tokio::test]
+async fn delete_honours_preconditions() {
+    let (server, _store, _dir) = build_test_app();
+    let etag_of =
+        |resp: &axum_test::TestResponse| resp.headers()["etag"].to_str().unwrap().to_owned();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "COND", "value": "v1", "delete": false}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let stale = etag_of(
+        &server
+            .method(axum::http::Method::HEAD, "/secrets/COND")
+            .await,
+    );
+
+    server
+        .patch("/secrets/COND")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"value": "v2"}))
+        .await
+        .assert_status_ok();
+    let current = etag_of(
+        &server
+            .method(axum::http::Method::HEAD, "/secrets/COND")
+            .await,
+    );
+    assert_ne!(stale, current);
+
+    // A stale ETag or a date before creation blocks the delete.
+    server
+        .delete("/secrets/COND")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-match", stale.as_str())
+        .await
+        .assert_status(axum::http::StatusCode::PRECONDITION_FAILED);
+    server
+        .delete("/secrets/COND")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-unmodified-since", "Sun, 06 Nov 1994 08:49:37 GMT")
+        .await
+        .assert_status(axum::http::StatusCode::PRECONDITION_FAILED);
+
+    // A metadata-only write moves the ETag too.
+    server
+        .post("/secrets/COND/touch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"ttl_seconds": 3600}))
+        .await
+        .assert_status_ok();
+    server
+        .delete("/secrets/COND")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-match", current.as_str())
+        .await
+        .assert_status(axum::http::StatusCode::PRECONDITION_FAILED);
+    let touched = etag_of(
+        &server
+            .method(axum::http::Method::HEAD, "/secrets/COND")
+            .await,
+    );
+
+    server
+        .delete("/secrets/COND")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-match", touched.as_str())
+        .await
+        .assert_status_ok();
+}
+
+// ── Test: aliases read the canonical secret and share its burn budget ───────
+
+#[tokio::test]
+async fn alias_reads_share_the_canonical_burn_budget() {
+    let (server, _store, _dir) = build_test_app();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "DB_URL_V2", "value": "postgres://x", "max_reads": 2}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server
+        .post("/secrets/DB_URL_V2/aliases")
+        .json(&json!({"alias": "DB_URL"}))
+        .await
+        .assert_status_unauthorized();
+    let resp = server
+        .post("/secrets/DB_URL_V2/aliases")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"alias": "DB_URL"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+    assert_eq!(
+        resp.json::<Value>(),
+        json!({"alias": "DB_URL", "key": "DB_URL_V2"})
+    );
+
+    // Neither name can be reused for the other role.
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "DB_URL", "value": "shadow"}))
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+    server
+        .post("/secrets/DB_URL/aliases")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"alias": "DB_URL_V2"}))
+        .await
+        .assert_status(axum::http::StatusCode::BAD_REQUEST);
+
+    let body: Value = server.get("/secrets/DB_URL").await.json();
+    assert_eq!(body["value"], "postgres://x");
+    let meta: Value = server.get("/secrets/DB_URL/describe").await.json();
+    assert_eq!(meta["key"], "DB_URL_V2");
+    assert_eq!(meta["read_count"], 1);
+
+    // The second read, by either name, burns the secret.
+    server.get("/secrets/DB_URL_V2").await.assert_status_ok();
+    server
+        .get("/secrets/DB_URL")
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+}
+
+// ── Test: batch create is all-or-nothing ────────────────────────────────────
+
+#[tokio::test]
+async fn batch_create_stores_every_secret_or_none() {
+    let (server, store, _dir) = build_test_app_with(|s| s.max_secrets = Some(3));
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "EXISTING", "value": "old"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let resp = server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"secrets": [
+            {"key": "EXISTING", "value": "new", "delete": false},
+            {"key": "A", "value": "a", "ttl_seconds": 60},
+            {"key": "B", "value": "b", "max_reads": 2},
+        ]}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+    let body: Value = resp.json();
+    assert_eq!(body["stored"], 3);
+    assert_eq!(body["created"], 2);
+    assert_eq!(body["replaced"], 1);
+    assert_eq!(
+        body["secrets"][0],
+        json!({"key": "EXISTING", "status": "replaced"})
+    );
+    assert_eq!(body["secrets"][2], json!({"key": "B", "status": "created"}));
+    let read: Value = server.get("/secrets/EXISTING").await.json();
+    assert_eq!(read["value"], "new");
+
+    // One bad entry rejects the whole batch, with every failure listed.
+    let resp = server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"secrets": [
+            {"key": "A", "value": "a2"},
+            {"key": "bad/key", "value": "x"},
+            {"key": "A", "value": "a3", "max_reads": 0},
+        ]}))
+        .await;
+    resp.assert_status_bad_request();
+    let body: Value = resp.json();
+    assert!(body["error"].as_str().unwrap().starts_with("secrets[1]: "));
+    let failed: Vec<_> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["index"].as_u64().unwrap(), e["field"].as_str().unwrap()))
+        .collect();
+    assert_eq!(failed, [(1, "key"), (2, "key"), (2, "max_reads")]);
+    assert_eq!(server.get("/secrets/A").await.json::<Value>()["value"], "a");
+
+    // Two new keys would take the store past max_secrets: neither is stored.
+    server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"secrets": [
+            {"key": "C", "value": "c"},
+            {"key": "B", "value": "b2"},
+        ]}))
+        .await
+        .assert_status(axum::http::StatusCode::INSUFFICIENT_STORAGE);
+    assert!(store.head("C").unwrap().is_none());
+    assert!(store.peek("B").unwrap().is_some_and(|v| v == "b"));
+
+    // Create-only is checked inside the write: B is not overwritten either.
+    let resp = server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-none-match", "*")
+        .json(&json!({"secrets": [
+            {"key": "B", "value": "b2"},
+            {"key": "A", "value": "a2"},
+        ]}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CONFLICT);
+    assert_eq!(resp.json::<Value>()["error"], "secret \"B\" already exists");
+    assert!(store.peek("B").unwrap().is_some_and(|v| v == "b"));
+
+    // The batch routes' own names are not valid keys.
+    server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"secrets": [{"key": "batch", "value": "x"}]}))
+        .await
+        .assert_status_bad_request();
+}
+
+// ── Test: batch create honours Idempotency-Key ──────────────────────────────
+
+#[tokio::test]
+async fn batch_create_replays_for_the_same_idempotency_key() {
+    let (server, store, _dir) = build_test_app();
+    let batch = json!({"secrets": [{"key": "IDEM_A", "value": "a"}]});
+    let first = server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("idempotency-key", "deploy-1")
+        .json(&batch)
+        .await;
+    first.assert_status(axum::http::StatusCode::CREATED);
+
+    // Replayed, not re-run: a re-run would report IDEM_A as replaced.
+    let retry = server
+        .post("/secrets/batch")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("idempotency-key", "deploy-1")
+        .json(&batch)
+        .await;
+    retry.assert_status(axum::http::StatusCode::CREATED);
+    assert_eq!(retry.header("idempotent-replayed"), "true");
+    assert_eq!(retry.json::<Value>(), first.json::<Value>());
+
+    // The same key on POST /secrets is a separate request.
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("idempotency-key", "deploy-1")
+        .json(&json!({"key": "IDEM_C", "value": "c"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    assert!(store.head("IDEM_C").unwrap().is_some());
+}
+
+// ── Test: delete can return the secret's final metadata ─────────────────────
+
+#[tokio::test]
+async fn delete_returns_final_meta_on_request() {
+    let (server, _store, _dir) = build_test_app();
+    for key in ["KEEP_META", "PLAIN"] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": key, "value": "v", "delete": false}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    server.get("/secrets/KEEP_META").await.assert_status_ok();
+    server.get("/secrets/KEEP_META").await.assert_status_ok();
+
+    let resp = server
+        .delete("/secrets/KEEP_META?return_meta=true")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    let body: Value = resp.json();
+    assert_eq!(body["deleted"], true);
+    assert_eq!(body["secret"]["key"], "KEEP_META");
+    assert_eq!(body["secret"]["read_count"], 2);
+    assert!(body["secret"]["created_at"].as_i64().is_some());
+    server
+        .get("/secrets/KEEP_META/describe")
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+
+    let resp = server
+        .delete("/secrets/PLAIN")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>(), json!({"deleted": true}));
+
+    server
+        .delete("/secrets/PLAIN?return_meta=true")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+}
+
+// ── Test: patch bumps updated_at but not created_at ─────────────────────────
+
+#[tokio::test]
+async fn patch_bumps_updated_at_only() {
+    let (server, _store, _dir) = build_test_app();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "MOD", "value": "v1", "delete": false}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let before: Value = server.get("/secrets/MOD/describe").await.json();
+    assert_eq!(before["updated_at"], before["created_at"]);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    server
+        .patch("/secrets/MOD")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"value": "v2"}))
+        .await
+        .assert_status_ok();
+
+    let after: Value = server.get("/secrets/MOD/describe").await.json();
+    assert_eq!(after["created_at"], before["created_at"]);
+    assert!(after["updated_at"].as_i64() > before["updated_at"].as_i64());
+
+    let head = server.method(Method::HEAD, "/secrets/MOD").await;
+    assert_eq!(
+        head.headers()["x-sirr-modified-at"].to_str().unwrap(),
+        after["updated_at"].to_string()
+    );
+    let listed: Value = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .json();
+    assert_eq!(listed["secrets"][0]["updated_at"], after["updated_at"]);
+}
+
+// ── Test: tier limit responses use the configured status ────────────────────
+
+#[tokio::test]
+async fn tier_limit_uses_configured_status() {
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.limit_status = axum::http::StatusCode::TOO_MANY_REQUESTS;
+    });
+    server
+        .post("/orgs")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"name": "first"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let resp = server
+        .post("/orgs")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"name": "second"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+    let body: Value = resp.json();
+    assert!(body["error"].as_str().unwrap().starts_with("tier limit"));
+}
+
+// ── Test: list carries a weak ETag and honours If-None-Match ────────────────
+
+#[tokio::test]
+async fn list_etag_yields_304_until_the_list_changes() {
+    let (server, _store, _dir) = build_test_app();
+    let create = |key: &'static str| {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": key, "value": "v"}))
+    };
+    create("A").await;
+
+    let resp = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    let etag = resp.headers()["etag"].to_str().unwrap().to_owned();
+    assert!(etag.starts_with("W/\""), "{etag}");
+    assert_eq!(resp.json::<Value>()["secrets"][0]["key"], "A");
+
+    let resp = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-none-match", etag.as_str())
+        .await;
+    resp.assert_status(axum::http::StatusCode::NOT_MODIFIED);
+    assert!(resp.as_bytes().is_empty());
+
+    create("B").await;
+    let resp = server
+        .get("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .add_header("if-none-match", etag.as_str())
+        .await;
+    resp.assert_status_ok();
+    assert_ne!(resp.headers()["etag"].to_str().unwrap(), etag);
+}
+
+// ── Test: reserved key prefixes are refused ─────────────────────────────────
+
+#[tokio::test]
+async fn reserved_key_prefix_is_rejected() {
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.reserved_key_prefixes = Arc::new(vec!["__system.".into()]);
+    });
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "__system.token", "value": "v"}))
+        .await;
+    resp.assert_status_forbidden();
+    assert_eq!(
+        resp.json::<Value>()["error"],
+        "key prefix \"__system.\" is reserved"
+    );
+    server
+        .patch("/secrets/__system.token")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"value": "v2"}))
+        .await
+        .assert_status_forbidden();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "__systematic", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+}
+
+// ── Test: /capacity tracks the remaining secret budget ──────────────────────
+
+#[tokio::test]
+async fn capacity_reports_remaining_budget() {
+    let (server, _store, _dir) = build_test_app_with(|state| state.max_secrets = Some(2));
+    let capacity = || async {
+        server
+            .get("/capacity")
+            .authorization_bearer(MASTER_KEY)
+            .await
+            .json::<Value>()
+    };
+
+    let cap = capacity().await;
+    assert_eq!(cap["count"], 0);
+    assert_eq!(cap["limit"], 2);
+    assert_eq!(cap["remaining"], 2);
+    assert_eq!(cap["create_allowed"], true);
+
+    for key in ["A", "B"] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": key, "value": "v"}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    let cap = capacity().await;
+    assert_eq!(cap["count"], 2);
+    assert_eq!(cap["remaining"], 0);
+    assert_eq!(cap["create_allowed"], false);
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "C", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::INSUFFICIENT_STORAGE);
+}
+
+// ── Test: batch-get returns per-key results in one request ──────────────────
+
+#[tokio::test]
+async fn batch_get_reports_each_key() {
+    let (server, _store, _dir) = build_test_app();
+    for body in [
+        json!({"key": "SEALED", "value": "s", "max_reads": 1, "delete": false}),
+        json!({"key": "PLAIN", "value": "p"}),
+        json!({"key": "ONCE", "value": "o", "max_reads": 1}),
+    ] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    server.get("/secrets/SEALED").await.assert_status_ok();
+
+    let resp = server
+        .post("/secrets/batch-get")
+        .json(&json!({"keys": ["PLAIN", "SEALED", "ONCE", "MISSING"]}))
+        .await;
+    resp.assert_status_ok();
+    let body: Value = resp.json();
+    assert_eq!(
+        body["secrets"],
+        json!([
+            {"key": "PLAIN", "status": "ok", "value": "p"},
+            {"key": "SEALED", "status": "sealed"},
+            {"key": "ONCE", "status": "burned", "value": "o"},
+            {"key": "MISSING", "status": "not_found"},
+        ])
+    );
+    server.get("/secrets/ONCE").await.assert_status_not_found();
+
+    server
+        .post("/secrets/batch-get")
+        .json(&json!({"keys": ["PLAIN", "PLAIN"]}))
+        .await
+        .assert_status_bad_request();
+    let too_many: Vec<String> = (0..101).map(|i| format!("K{i}")).collect();
+    server
+        .post("/secrets/batch-get")
+        .json(&json!({"keys": too_many}))
+        .await
+        .assert_status_bad_request();
+}
+
+// ── Test: omitted key is generated server-side ──────────────────────────────
+
+#[tokio::test]
+async fn create_without_key_generates_a_unique_one() {
+    let (server, _store, _dir) = build_test_app();
+    let mut keys = Vec::new();
+    for _ in 0..2 {
+        let resp = server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"value": "shared"}))
+            .await;
+        resp.assert_status(axum::http::StatusCode::CREATED);
+        let key = resp.json::<Value>()["key"].as_str().unwrap().to_owned();
+        assert_eq!(key.len(), 24);
+        assert!(key.bytes().all(|b| b.is_ascii_alphanumeric()), "{key}");
+        keys.push(key);
+    }
+    assert_ne!(keys[0], keys[1]);
+    for key in &keys {
+        let body: Value = server.get(&format!("/secrets/{key}")).await.json();
+        assert_eq!(body["value"], "shared");
+    }
+}
+
+// ── Test: audit write failures follow SIRR_AUDIT_FAILURE_MODE ───────────────
+
+/// In-memory backend whose audit log is unwritable (e.g. a full disk).
+struct UnwritableAudit(MemoryStore);
+
+impl SecretStore for UnwritableAudit {
+    fn ping(&self) -> anyhow::Result<()> {
+        self.0.ping()
+    }
+    fn put_alias(
+        &self,
+        alias: &str,
+        key: &str,
+    ) -> anyhow::Result<Result<String, sirr_server::store::AliasError>> {
+        self.0.put_alias(alias, key)
+    }
+    fn delete_alias(&self, alias: &str, key: &str) -> anyhow::Result<bool> {
+        self.0.delete_alias(alias, key)
+    }
+    fn resolve_alias(&self, alias: &str) -> anyhow::Result<Option<String>> {
+        self.0.resolve_alias(alias)
+    }
+    fn put(&self, secret: &NewSecret) -> anyhow::Result<()> {
+        self.0.put(secret)
+    }
+    fn put_many(
+        &self,
+        secrets: &[sirr_server::store::model::NewSecret],
+        create_only: bool,
+        max_secrets: Option<usize>,
+    ) -> anyhow::Result<Result<sirr_server::store::PutReport, sirr_server::store::PutConflict>>
+    {
+        self.0.put_many(secrets, create_only, max_secrets)
+    }
+    fn get(&self, key: &str) -> anyhow::Result<sirr_server::store::GetResult> {
+        self.0.get(key)
+    }
+    fn head(
+        &self,
+        key: &str,
+    ) -> anyhow::Result<Option<(sirr_server::store::model::SecretMeta, bool)>> {
+        self.0.head(key)
+    }
+    fn peek(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.0.peek(key)
+    }
+    fn patch(
+        &self,
+        key: &str,
+        value: Option<&str>,
+        max_reads: Option<u32>,
+        ttl_seconds: Option<u64>,
+        annotations: sirr_server::store::model::AnnotationsPatch,
+        delete: Option<bool>,
+    ) -> anyhow::Result<Option<sirr_server::store::model::SecretMeta>> {
+        self.0
+            .patch(key, value, max_reads, ttl_seconds, annotations, delete)
+    }
+    fn touch(
+        &self,
+        key: &str,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<Option<sirr_server::store::model::SecretMeta>> {
+        self.0.touch(key, ttl_seconds)
+    }
+    fn delete(&self, key: &str) -> anyhow::Result<bool> {
+        self.0.delete(key)
+    }
+    fn delete_if(
+        &self,
+        key: &str,
+        precondition: &sirr_server::store::Precondition,
+    ) -> anyhow::Result<sirr_server::store::ConditionalDelete> {
+        self.0.delete_if(key, precondition)
+    }
+    fn delete_matching(
+        &self,
+        criteria: &sirr_server::store::model::DeleteCriteria,
+    ) -> anyhow::Result<Vec<String>> {
+        self.0.delete_matching(criteria)
+    }
+    fn wipe_secrets(&self) -> anyhow::Result<usize> {
+        self.0.wipe_secrets()
+    }
+    fn list(&self) -> anyhow::Result<Vec<sirr_server::store::model::SecretMeta>> {
+        self.0.list()
+    }
+    fn list_with_status(
+        &self,
+    ) -> anyhow::Result<
+        Vec<(
+            sirr_server::store::model::SecretMeta,
+            sirr_server::store::model::SecretStatus,
+        )>,
+    > {
+        self.0.list_with_status()
+    }
+    fn count_public(&self) -> anyhow::Result<usize> {
+        self.0.count_public()
+    }
+    fn prune_by(
+        &self,
+        scope: sirr_server::store::model::PruneScope,
+    ) -> anyhow::Result<Vec<(String, sirr_server::store::model::PruneReason)>> {
+        self.0.prune_by(scope)
+    }
+    fn rotate(&self, new_key: &crypto::EncryptionKey, version: u8) -> anyhow::Result<usize> {
+        self.0.rotate(new_key, version)
+    }
+    fn record_audit(&self, _event: sirr_server::store::AuditEvent) -> anyhow::Result<()> {
+        anyhow::bail!("No space left on device")
+    }
+    fn list_audit(
+        &self,
+        query: &sirr_server::store::AuditQuery,
+    ) -> anyhow::Result<Vec<sirr_server::store::AuditEvent>> {
+        self.0.list_audit(query)
+    }
+    fn audit_counts(&self) -> std::collections::BTreeMap<(String, bool), u64> {
+        self.0.audit_counts()
+    }
+    fn put_webhook(&self, reg: &sirr_server::webhooks::WebhookRegistration) -> anyhow::Result<()> {
+        self.0.put_webhook(reg)
+    }
+    fn put_webhooks(
+        &self,
+        regs: &[sirr_server::webhooks::WebhookRegistration],
+        cap: usize,
+    ) -> anyhow::Result<bool> {
+        self.0.put_webhooks(regs, cap)
+    }
+    fn list_webhooks(&self) -> anyhow::Result<Vec<sirr_server::webhooks::WebhookRegistration>> {
+        self.0.list_webhooks()
+    }
+    fn delete_webhook(&self, id: &str) -> anyhow::Result<bool> {
+        self.0.delete_webhook(id)
+    }
+    fn count_webhooks(&self) -> anyhow::Result<usize> {
+        self.0.count_webhooks()
+    }
+    fn get_idempotent(
+        &self,
+        key: &str,
+    ) -> anyhow::Result<Option<sirr_server::store::idempotency::IdempotentResponse>> {
+        self.0.get_idempotent(key)
+    }
+    fn put_idempotent(
+        &self,
+        key: &str,
+        status: u16,
+        body: &str,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()> {
+        self.0.put_idempotent(key, status, body, ttl_seconds)
+    }
+}
+
+#[tokio::test]
+async fn audit_write_failure_follows_configured_mode() {
+    use sirr_server::store::AuditFailureMode;
+
+    for mode in [AuditFailureMode::Ignore, AuditFailureMode::Warn] {
+        let (server, _store, _dir) = build_test_app_with(|state| {
+            state.secrets = Arc::new(UnwritableAudit(MemoryStore::new()));
+            state.audit_failure_mode = mode;
+        });
+        // The create committed before its audit write failed: outside `fail`
+        // mode it is never reported as an error.
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": "K", "value": "v"}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+        server
+            .get("/secrets/K")
+            .await
+            .assert_status(axum::http::StatusCode::OK);
+    }
+}
+
+// ── Test: touch extends TTL without rewriting the value ─────────────────────
+
+#[tokio::test]
+async fn touch_extends_ttl_and_keeps_value_and_reads() {
+    let (server, _store, _dir) = build_test_app();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "KEEP", "value": "v1", "ttl_seconds": 60, "max_reads": 5, "delete": false}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server.get("/secrets/KEEP").await.assert_status_ok();
+    let before: Value = server.get("/secrets/KEEP/describe").await.json();
+
+    let touched = server
+        .post("/secrets/KEEP/touch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"ttl_seconds": 3600}))
+        .await;
+    touched.assert_status_ok();
+    let after: Value = server.get("/secrets/KEEP/describe").await.json();
+    assert!(after["expires_at"].as_i64().unwrap() >= before["expires_at"].as_i64().unwrap() + 3000);
+    assert_eq!(touched.json::<Value>()["expires_at"], after["expires_at"]);
+    assert_eq!(after["read_count"], 1);
+    assert_eq!(after["updated_at"], before["updated_at"]);
+    let peeked: Value = server
+        .get("/secrets/KEEP/peek")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .json();
+    assert_eq!(peeked["value"], "v1");
+
+    server
+        .post("/secrets/KEEP/touch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"ttl_seconds": 400_000_000u64}))
+        .await
+        .assert_status(axum::http::StatusCode::BAD_REQUEST);
+    server
+        .post("/secrets/MISSING/touch")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"ttl_seconds": 60}))
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+    server
+        .post("/secrets/KEEP/touch")
+        .json(&json!({"ttl_seconds": 60}))
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+}
+
+// ── Test: create with an absolute expires_at ────────────────────────────────
+
+#[tokio::test]
+async fn create_accepts_absolute_expires_at() {
+    let (server, _store, _dir) = build_test_app();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let create = |body: Value| {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+    };
+
+    let deadline = now + 7200;
+    create(json!({"key": "DEADLINE", "value": "v", "expires_at": deadline}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let meta: Value = server.get("/secrets/DEADLINE/describe").await.json();
+    assert!(meta["expires_at"].as_i64().unwrap().abs_diff(deadline) <= 1);
+
+    let resp = create(json!({"key": "PAST", "value": "v", "expires_at": now - 10})).await;
+    resp.assert_status_bad_request();
+    let body: Value = resp.json();
+    assert_eq!(body["errors"][0]["field"], "expires_at");
+    assert_eq!(body["error"], "expires_at must be in the future");
+
+    let resp =
+        create(json!({"key": "CLASH", "value": "v", "expires_at": deadline, "ttl_seconds": 60}))
+            .await;
+    resp.assert_status_bad_request();
+    assert_eq!(
+        resp.json::<Value>()["error"],
+        "ttl_seconds and expires_at disagree; send only one"
+    );
+
+    create(json!({"key": "FAR", "value": "v", "expires_at": now + 400_000_000}))
+        .await
+        .assert_status_bad_request();
+    create(json!({"key": "BOTH", "value": "v", "expires_at": deadline, "ttl_seconds": 7200}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+}
+
+// ── Test: PATCH edits individual annotations ────────────────────────────────
+
+#[tokio::test]
+async fn patch_sets_and_removes_single_annotations() {
+    let (server, _store, _dir) = build_test_app();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({
+            "key": "NOTES", "value": "v", "delete": false,
+            "annotations": {"owner": "platform", "runbook": "https://wiki/rb"}
+        }))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let patch = |body: Value| {
+        server
+            .patch("/secrets/NOTES")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+    };
+
+    let meta: Value = patch(json!({"set_annotations": {"team": "infra"}}))
+        .await
+        .json();
+    assert_eq!(
+        meta["annotations"],
+        json!({"owner": "platform", "runbook": "https://wiki/rb", "team": "infra"})
+    );
+
+    let meta: Value = patch(json!({"remove_annotations": ["runbook", "absent"]}))
+        .await
+        .json();
+    assert_eq!(
+        meta["annotations"],
+        json!({"owner": "platform", "team": "infra"})
+    );
+
+    // The size cap applies to the merged result, not just the added entry.
+    let big = "x".repeat(9 * 1024);
+    patch(json!({"set_annotations": {"a": big}}))
+        .await
+        .assert_status_ok();
+    let resp = patch(json!({"set_annotations": {"b": big}})).await;
+    resp.assert_status_bad_request();
+    assert!(resp.json::<Value>()["error"]
+        .as_str()
+        .unwrap()
+        .contains("byte limit"));
+    let meta: Value = server.get("/secrets/NOTES/describe").await.json();
+    assert!(meta["annotations"].get("b").is_none());
+
+    patch(json!({"set_annotations": {"k": "v"}, "remove_annotations": ["k"]}))
+        .await
+        .assert_status_bad_request();
+}
+
+// ── Test: the annotation count cap applies after set_annotations ────────────
+
+#[tokio::test]
+async fn patch_set_annotations_enforces_the_count_cap() {
+    use sirr_server::store::model::MAX_ANNOTATIONS;
+
+    let (server, _store, _dir) = build_test_app();
+    let full: std::collections::BTreeMap<String, String> = (0..MAX_ANNOTATIONS)
+        .map(|i| (format!("a{i}"), "v".to_owned()))
+        .collect();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "NOTES", "value": "v", "delete": false, "annotations": full}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let patch = |body: Value| {
+        server
+            .patch("/secrets/NOTES")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+    };
+
+    let resp = patch(json!({"set_annotations": {"extra": "v"}})).await;
+    resp.assert_status_bad_request();
+    assert!(resp.json::<Value>()["error"]
+        .as_str()
+        .unwrap()
+        .contains("entry limit"));
+
+    // Overwriting an existing entry, or swapping one out, stays within it.
+    patch(json!({"set_annotations": {"a0": "w"}}))
+        .await
+        .assert_status_ok();
+    let meta: Value =
+        patch(json!({"set_annotations": {"extra": "v"}, "remove_annotations": ["a1"]}))
+            .await
+            .json();
+    assert_eq!(
+        meta["annotations"].as_object().unwrap().len(),
+        MAX_ANNOTATIONS
+    );
+    assert_eq!(meta["annotations"]["extra"], "v");
+}
+
+// ── Test: audit queries are bounded by SIRR_AUDIT_MAX_RANGE_DAYS ────────────
+
+#[tokio::test]
+async fn audit_query_range_is_bounded() {
+    let (server, store, _dir) = build_test_app_with(|state| {
+        state.audit_max_range_secs = Some(86_400);
+    });
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let mut old = sirr_server::store::AuditEvent::new(
+        "secret.read",
+        Some("OLD".into()),
+        "127.0.0.1".into(),
+        true,
+        None,
+        None,
+        None,
+    );
+    old.timestamp = now - 3 * 86_400;
+    store.record_audit(old).unwrap();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "NEW", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let audit = |query: String| {
+        server
+            .get(&format!("/audit{query}"))
+            .authorization_bearer(MASTER_KEY)
+    };
+
+    // An open-ended query is clamped to the last day.
+    let body: Value = audit(String::new()).await.json();
+    let keys: Vec<&str> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e["key"].as_str())
+        .collect();
+    assert!(keys.contains(&"NEW"));
+    assert!(!keys.contains(&"OLD"));
+
+    let resp = audit(format!("?since={}", now - 2 * 86_400)).await;
+    resp.assert_status_bad_request();
+    assert!(resp.json::<Value>()["error"]
+        .as_str()
+        .unwrap()
+        .contains("1 days"));
+
+    // A narrow window in the past is fine.
+    let body: Value = audit(format!(
+        "?since={}&until={}",
+        now - 3 * 86_400 - 60,
+        now - 3 * 86_400 + 60
+    ))
+    .await
+    .json();
+    assert_eq!(body["events"][0]["key"], "OLD");
+}
+
+// ── Test: SIRR_CREATE_MODE=reject refuses duplicate creates ─────────────────
+
+#[tokio::test]
+async fn reject_mode_refuses_duplicate_create() {
+    let create = |server: &TestServer, value: &'static str| {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": "ONCE", "value": value}))
+    };
+
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.create_mode = CreateMode::Reject;
+    });
+    create(&server, "first")
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let resp = create(&server, "second").await;
+    resp.assert_status(axum::http::StatusCode::CONFLICT);
+    assert_eq!(
+        resp.json::<Value>()["error"],
+        "secret \"ONCE\" already exists"
+    );
+    let body: Value = server.get("/secrets/ONCE").await.json();
+    assert_eq!(body["value"], "first");
+
+    // A request can opt back into upsert, unless it also sent If-None-Match.
+    create(&server, "second")
+        .add_header("x-sirr-create-mode", "upsert")
+        .add_header("if-none-match", "*")
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+    create(&server, "second")
+        .add_header("x-sirr-create-mode", "upsert")
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    create(&server, "third")
+        .add_header("x-sirr-create-mode", "sometimes")
+        .await
+        .assert_status_bad_request();
+
+    // Upsert mode overwrites unless the request opts out.
+    let (server, _store, _dir) = build_test_app();
+    create(&server, "first")
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    create(&server, "second")
+        .add_header("if-none-match", "*")
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+    create(&server, "second")
+        .add_header("x-sirr-create-mode", "reject")
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+    create(&server, "second")
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+}
+
+// ── Test: webhook registrations round-trip through export/import ────────────
+
+#[tokio::test]
+async fn webhooks_round_trip_through_export_and_import() {
+    let licensed = |state: &mut AppState| {
+        state.license = LicenseStatus::Licensed(sirr_server::license::LicenseTier::Business);
+    };
+    let (source, source_store, _dir_a) = build_test_app_with(licensed);
+    let (target, target_store, _dir_b) = build_test_app_with(licensed);
+    for (id, url) in [
+        ("aaaa", "https://hooks.example.com/a"),
+        ("bbbb", "http://receiver.example.net/sirr"),
+    ] {
+        source_store
+            .put_webhook(&sirr_server::webhooks::WebhookRegistration {
+                id: id.into(),
+                url: url.into(),
+                secret: format!("whsec_{id}"),
+                events: vec!["secret.read".into()],
+                created_at: 1_700_000_000,
+                org_id: None,
+                template: None,
+                event_urls: Default::default(),
+            })
+            .unwrap();
+    }
+
+    let export: Value = source
+        .get("/webhooks/export")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .json();
+    assert_eq!(export["webhooks"][0]["secret"], "whsec_aaaa");
+
+    let resp = target
+        .post("/webhooks/import")
+        .authorization_bearer(MASTER_KEY)
+        .json(&export)
+        .await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["imported"], 2);
+    let imported = target_store.list_webhooks().unwrap();
+    let original = source_store.list_webhooks().unwrap();
+    assert_eq!(
+        serde_json::to_value(&imported).unwrap(),
+        serde_json::to_value(&original).unwrap()
+    );
+
+    // Re-importing overwrites by ID instead of counting against the cap.
+    target
+        .post("/webhooks/import")
+        .authorization_bearer(MASTER_KEY)
+        .json(&export)
+        .await
+        .assert_status_ok();
+    assert_eq!(target_store.count_webhooks().unwrap(), 2);
+
+    // Private targets are refused and nothing from the batch is written.
+    let resp = target
+        .post("/webhooks/import")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"webhooks": [
+            {"id": "cccc", "url": "https://hooks.example.com/c", "secret": "s", "events": ["*"], "created_at": 0},
+            {"id": "dddd", "url": "http://169.254.169.254/latest", "secret": "s", "events": ["*"], "created_at": 0},
+        ]}))
+        .await;
+    resp.assert_status_bad_request();
+    assert_eq!(resp.json::<Value>()["id"], "dddd");
+    assert_eq!(target_store.count_webhooks().unwrap(), 2);
+
+    // MAX_WEBHOOKS applies to the merged set.
+    let many: Vec<Value> = (0..sirr_server::webhooks::MAX_WEBHOOKS - 1)
+        .map(|i| json!({"id": format!("n{i}"), "url": "https://hooks.example.com/n", "secret": "s", "events": ["*"], "created_at": 0}))
+        .collect();
+    target
+        .post("/webhooks/import")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({ "webhooks": many }))
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+}
+
+// ── Test: creates report the remaining quota ────────────────────────────────
+
+#[tokio::test]
+async fn create_reports_remaining_quota() {
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.max_secrets = Some(5);
+    });
+    let mut remaining = Vec::new();
+    for key in ["Q1", "Q2", "Q3"] {
+        let resp = server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": key, "value": "v"}))
+            .await;
+        resp.assert_status(axum::http::StatusCode::CREATED);
+        assert_eq!(resp.headers()["x-sirr-quota-limit"], "5");
+        remaining.push(
+            resp.headers()["x-sirr-quota-remaining"]
+                .to_str()
+                .unwrap()
+                .to_owned(),
+        );
+    }
+    assert_eq!(remaining, ["4", "3", "2"]);
+
+    // Overwriting an existing key uses no extra quota.
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "Q1", "value": "w"}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::CREATED);
+    assert_eq!(resp.headers()["x-sirr-quota-remaining"], "2");
+
+    // Without a limit there is nothing to report.
+    let (server, _store, _dir) = build_test_app();
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "Q", "value": "v"}))
+        .await;
+    assert!(resp.headers().get("x-sirr-quota-remaining").is_none());
+}
+
+// ── Test: bulk delete by expiry threshold ───────────────────────────────────
+
+#[tokio::test]
+async fn bulk_delete_removes_secrets_expiring_before_cutoff() {
+    let (server, _store, _dir) = build_test_app();
+    for body in [
+        json!({"key": "SOON", "value": "v", "ttl_seconds": 60}),
+        json!({"key": "LATER", "value": "v", "ttl_seconds": 7200}),
+        json!({"key": "FOREVER", "value": "v"}),
+    ] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+
+    server
+        .delete("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .await
+        .assert_status(axum::http::StatusCode::BAD_REQUEST);
+    server
+        .delete("/secrets?expires_before=0")
+        .await
+        .assert_status(axum::http::StatusCode::UNAUTHORIZED);
+
+    let cutoff = now_secs() + 3600;
+    let resp = server
+        .delete(&format!("/secrets?expires_before={cutoff}"))
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    let body: Value = resp.json();
+    assert_eq!(body["deleted"], 1);
+    assert_eq!(body["keys"], json!(["SOON"]));
+
+    server
+        .get("/secrets/SOON/describe")
+        .await
+        .assert_status(axum::http::StatusCode::NOT_FOUND);
+    server
+        .get("/secrets/LATER/describe")
+        .await
+        .assert_status_ok();
+    server
+        .get("/secrets/FOREVER/describe")
+        .await
+        .assert_status_ok();
+}
+
+// ── Test: last access time and bulk delete of stale secrets ─────────────────
+
+#[tokio::test]
+async fn reads_stamp_last_access_and_bulk_delete_targets_stale_secrets() {
+    let (server, _store, _dir) = build_test_app();
+    for key in ["USED", "STALE"] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": key, "value": "v", "delete": false}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    let meta: Value = server.get("/secrets/USED/describe").await.json();
+    assert!(meta["last_accessed_at"].is_null());
+
+    // Timestamps have one-second resolution.
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    server.get("/secrets/USED").await.assert_status_ok();
+    let meta: Value = server.get("/secrets/USED/describe").await.json();
+    let accessed = meta["last_accessed_at"].as_i64().expect("stamped by read");
+    assert!(accessed > meta["created_at"].as_i64().unwrap());
+
+    let resp = server
+        .delete(&format!("/secrets?not_accessed_since={accessed}"))
+        .authorization_bearer(MASTER_KEY)
+        .await;
+    resp.assert_status_ok();
+    let body: Value = resp.json();
+    assert_eq!(body["keys"], json!(["STALE"]));
+    server
+        .get("/secrets/USED/describe")
+        .await
+        .assert_status_ok();
+}
+
+// ── Test: patch flips delete=true to seal-on-read ───────────────────────────
+
+#[tokio::test]
+async fn patch_can_flip_unread_burn_secret_to_seal_on_read() {
+    let (server, _store, _dir) = build_test_app();
+    for key in ["FLIP", "READ"] {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .json(&json!({"key": key, "value": "v1", "max_reads": 2, "delete": true}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    let patch = |key: &str, body: Value| {
+        server
+            .patch(&format!("/secrets/{key}"))
+            .authorization_bearer(MASTER_KEY)
+            .json(&body)
+    };
+
+    patch("FLIP", json!({"value": "v2"}))
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+    let resp = patch("FLIP", json!({"value": "v2", "delete": false})).await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["delete"], false);
+    for _ in 0..2 {
+        let read: Value = server.get("/secrets/FLIP").await.json();
+        assert_eq!(read["value"], "v2");
+    }
+    server
+        .get("/secrets/FLIP")
+        .await
+        .assert_status(axum::http::StatusCode::GONE);
+
+    // Once a burn-on-read secret has been read, resetting its counter
+    // would hand out reads it already spent.
+    server.get("/secrets/READ").await.assert_status_ok();
+    patch("READ", json!({"delete": false}))
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+}
+
+// ── Test: Idempotency-Key is only remembered for the configured TTL ─────────
+
+#[tokio::test]
+async fn idempotency_key_expires_after_ttl() {
+    let (server, _store, _dir) = build_test_app_with(|state| state.idempotency_ttl_secs = 1);
+    let create = || {
+        server
+            .post("/secrets")
+            .authorization_bearer(MASTER_KEY)
+            .add_header("idempotency-key", "req-ttl")
+            .json(&json!({"key": "AGAIN", "value": "v", "max_reads": 1}))
+    };
+
+    create()
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server.get("/secrets/AGAIN").await.assert_status_ok();
+
+    let retry = create().await;
+    assert_eq!(retry.headers()["idempotent-replayed"], "true");
+    server.get("/secrets/AGAIN").await.assert_status_not_found();
+
+    tokio::time::sleep(std::time::Duration::from_millis(2100)).await;
+    let fresh = create().await;
+    fresh.assert_status(axum::http::StatusCode::CREATED);
+    assert!(fresh.headers().get("idempotent-replayed").is_none());
+    server.get("/secrets/AGAIN").await.assert_status_ok();
+}
+
+// ── Test: distinct reader IPs are counted per secret ────────────────────────
+
+#[tokio::test]
+async fn distinct_readers_counts_reader_ips() {
+    let (server, _store, _dir) = build_test_app_with(|state| {
+        state.trusted_proxies = Arc::new(vec!["127.0.0.1/32".parse().unwrap()]);
+    });
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "SEEN", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    for ip in ["198.51.100.7", "198.51.100.7", "203.0.113.9"] {
+        server
+            .get("/secrets/SEEN")
+            .add_header("x-forwarded-for", ip)
+            .await
+            .assert_status_ok();
+    }
+
+    let meta: Value = server.get("/secrets/SEEN/describe").await.json();
+    assert_eq!(meta["read_count"], 3);
+    assert_eq!(meta["distinct_readers"], 2);
+    let head = server.method(Method::HEAD, "/secrets/SEEN").await;
+    assert_eq!(head.header("x-sirr-distinct-readers"), "2");
+}
+
+// ── Test: SIRR_MIN_VALUE_BYTES rejects short values ─────────────────────────
+
+#[tokio::test]
+async fn min_value_bytes_rejects_empty_values_only_when_set() {
+    let (server, _store, _dir) = build_test_app();
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "EMPTY", "value": ""}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    let (server, _store, _dir) = build_test_app_with(|state| state.min_value_bytes = 4);
+    let resp = server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "EMPTY", "value": ""}))
+        .await;
+    resp.assert_status(axum::http::StatusCode::BAD_REQUEST);
+    let body: Value = resp.json();
+    assert_eq!(body["errors"][0]["field"], "value");
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "LONG", "value": "abcd"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    server
+        .patch("/secrets/LONG")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"value": "abc"}))
+        .await
+        .assert_status(axum::http::StatusCode::BAD_REQUEST);
+}
+
+// ── Test: only value reads count toward max_reads ───────────────────────────
+
+#[tokio::test]
+async fn metadata_requests_do_not_consume_max_reads() {
+    let (server, _store, _dir) = build_test_app();
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "BUDGET", "value": "v", "max_reads": 1, "delete": false}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+
+    for _ in 0..3 {
+        server
+            .method(Method::HEAD, "/secrets/BUDGET")
+            .await
+            .assert_status_ok();
+        server
+            .get("/secrets/BUDGET/describe")
+            .await
+            .assert_status_ok();
+        server
+            .get("/secrets/BUDGET/peek")
+            .authorization_bearer(MASTER_KEY)
+            .await
+            .assert_status_ok();
+    }
+    let meta: Value = server.get("/secrets/BUDGET/describe").await.json();
+    assert_eq!(meta["read_count"], 0);
+
+    let resp = server.get("/secrets/BUDGET").await;
+    resp.assert_status_ok();
+    assert_eq!(resp.json::<Value>()["value"], "v");
+    let meta: Value = server.get("/secrets/BUDGET/describe").await.json();
+    assert_eq!(meta["read_count"], 1);
+    assert!(!server
+        .get("/secrets/BUDGET")
+        .await
+        .status_code()
+        .is_success());
+}
+
+// ── Test: org secret metadata names the creating key ────────────────────────
+
+#[tokio::test]
+async fn secret_metadata_reports_the_creating_key() {
+    let (server, store, _dir) = build_test_app();
+    let (org_id, principal_id, _raw_key) =
+        bootstrap_org_with_key(&store, "acme", "ci-bot", "writer");
+    let deploy_key = add_named_key(&store, &org_id, &principal_id, "pk_deploy", "deploy-bot");
+
+    server
+        .post(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&deploy_key)
+        .json(&json!({"key": "BY_KEY", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let list: Value = server
+        .get(&format!("/orgs/{org_id}/secrets"))
+        .authorization_bearer(&deploy_key)
+        .await
+        .json();
+    assert_eq!(list["secrets"][0]["key"], "BY_KEY");
+    assert_eq!(list["secrets"][0]["created_by"], "deploy-bot");
+
+    // Master-key writes to the public bucket carry no creator.
+
+    server
+        .post("/secrets")
+        .authorization_bearer(MASTER_KEY)
+        .json(&json!({"key": "PUBLIC", "value": "v"}))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+    let described: Value = server.get("/secrets/PUBLIC/describe").await.json();
+    assert!(described.get("created_by").is_none());
+}
+
+#[tokio::test]
+async fn labeled_master_keys_are_named_in_audit_events() {
+    let (server, store, _dir) = build_test_app_with(|state| {
+        state.api_keys = sirr_server::auth::ApiKeys::parse(
+            Some(MASTER_KEY.into()),
+            Some("ci-a:key-for-pipeline-a, ci-b:key-for-pipeline-b"),
+        )
+        .unwrap();
+    });
+
+    for (token, key) in [
+        ("key-for-pipeline-a", "FROM_A"),
+        (MASTER_KEY, "FROM_MASTER"),
+    ] {
+        server
+            .post("/secrets")
+            .authorization_bearer(token)
+            .json(&json!({"key": key, "value": "v"}))
+            .await
+            .assert_status(axum::http::StatusCode::CREATED);
+    }
+    // The label is kept as the creator; the unlabeled key leaves none.
+    let described: Value = server.get("/secrets/FROM_A/describe").await.json();
+    assert_eq!(described["created_by"], "ci-a");
+    let described: Value = server.get("/secrets/FROM_MASTER/describe").await.json();
+    assert!(described.get("created_by").is_none());
+    server
+        .delete("/secrets/FROM_A")
+        .authorization_bearer("key-for-pipeline-b")
+        .await
+        .assert_status_ok();
+    // A key that is no longer listed is rejected.
+    server
+        .post("/secrets")
+        .authorization_bearer("key-for-pipeline-c")
+        .json(&json!({"key": "X", "value": "v"}))
+        .await
+        .assert_status_unauthorized();
+
+    let label_of = |action: &str, key: &str| {
+        store
+            .list_audit(&AuditQuery {
+                since: None,
+                until: None,
+                action: Some(action.into()),
+                limit: 10,
+                org_id: None,
+            })
+            .unwrap()
+            .into_iter()
+            .find(|e| e.key.as_deref() == Some(key))
+            .unwrap()
+            .key_label
+    };
+    assert_eq!(label_of("secret.create", "FROM_A").as_deref(), Some("ci-a"));
+    assert_eq!(label_of("secret.delete", "FROM_A").as_deref(), Some("ci-b"));
+    assert_eq!(label_of("secret.create", "FROM_MASTER"), None);
+}
@@ -5,8 +5,8 @@
 //! the public (non-org) secret bucket.
 
 use sirr_server::store::{
-    crypto, db::GetResult, org::PrincipalKeyRecord, org::PrincipalRecord, org::RoleRecord,
-    permissions::Permissions, Store,
+    crypto, db::GetResult, model::NewSecret, org::PrincipalKeyRecord, org::PrincipalRecord,
+    org::RoleRecord, permissions::Permissions, Store,
 };
 use std::collections::HashMap;
 use tempfile::tempdir;
@@ -34,7 +34,13 @@ fn public_bucket_backward_compat() {
     let (s, _dir) = make_store();
 
     // Push secret to public bucket (no org).
-    s.put("MY_KEY", "my-value", None, None, true, None).unwrap();
+    s.put(&NewSecret {
+        key: "MY_KEY".into(),
+        value: "my-value".into(),
+        delete: true,
+        ..NewSecret::default()
+    })
+    .unwrap();
 
     // GET returns the value.
     assert_eq!(
@@ -94,6 +100,7 @@ fn org_lifecycle() {
         valid_after: now - 60,
         valid_before: now + 3600,
         created_at: now,
+        max_secrets: None,
     };
     s.put_principal_key(&key_record).unwrap();
 
@@ -115,12 +122,14 @@ fn org_lifecycle() {
         None,
         Some("p_1"),
         None,
+        None,
     )
+    .unwrap()
     .unwrap();
 
     // Read org secret.
     assert_eq!(
-        s.get_org_secret("org_1", "DB_PASSWORD").unwrap(),
+        s.get_org_secret("org_1", "DB_PASSWORD", None).unwrap(),
         GetResult::Value("s3cret".into(), None)
     );
 
@@ -142,12 +151,14 @@ fn org_lifecycle() {
         None,
         None,
         None,
+        None,
     )
+    .unwrap()
     .unwrap();
 
     // Both secrets exist independently.
     assert_eq!(
-        s.get_org_secret("org_2", "DB_PASSWORD").unwrap(),
+        s.get_org_secret("org_2", "DB_PASSWORD", None).unwrap(),
         GetResult::Value("different-secret".into(), None)
     );
 
@@ -177,7 +188,9 @@ fn key_binding_enforcement() {
         None,
         Some("p_1"),
         Some(vec!["deploy_key".to_string()]),
+        None,
     )
+    .unwrap()
     .unwrap();
 
     // Head returns metadata (allowed_keys are stored in the record but not
@@ -202,6 +215,7 @@ fn role_permission_enforcement() {
         org_id: "org_1".into(),
         key_id: "pk_reader".into(),
         key_name: "reader-key".into(),
+        max_secrets: None,
         permissions: reader_perms,
     };
 
@@ -275,6 +289,7 @@ fn principal_key_listing_and_deletion() {
             valid_after: now - 60,
             valid_before: now + 3600,
             created_at: now,
+            max_secrets: None,
         };
         s.put_principal_key(&kr).unwrap();
     }
@@ -296,8 +311,13 @@ fn org_secrets_isolated_from_public() {
     let (s, _dir) = make_store();
 
     // Public secret.
-    s.put("SHARED_NAME", "public-val", None, None, true, None)
-        .unwrap();
+    s.put(&NewSecret {
+        key: "SHARED_NAME".into(),
+        value: "public-val".into(),
+        delete: true,
+        ..NewSecret::default()
+    })
+    .unwrap();
 
     // Org secret with same name.
     s.put_org_secret(
@@ -310,7 +330,9 @@ fn org_secrets_isolated_from_public() {
         None,
         None,
         None,
+        None,
     )
+    .unwrap()
     .unwrap();
 
     // They are completely independent.
@@ -319,7 +341,7 @@ fn org_secrets_isolated_from_public() {
         GetResult::Value("public-val".into(), None)
     );
     assert_eq!(
-        s.get_org_secret("org_1", "SHARED_NAME").unwrap(),
+        s.get_org_secret("org_1", "SHARED_NAME", None).unwrap(),
         GetResult::Value("org-val".into(), None)
     );
 }
@@ -340,7 +362,9 @@ fn org_secret_list_by_owner() {
         None,
         Some("alice"),
         None,
+        None,
     )
+    .unwrap()
     .unwrap();
     s.put_org_secret(
         "org_1",
@@ -352,7 +376,9 @@ fn org_secret_list_by_owner() {
         None,
         Some("bob"),
         None,
+        None,
     )
+    .unwrap()
     .unwrap();
     s.put_org_secret(
         "org_1",
@@ -364,7 +390,9 @@ fn org_secret_list_by_owner() {
         None,
         Some("alice"),
         None,
+        None,
     )
+    .unwrap()
     .unwrap();
 
     // All secrets in org.
@@ -394,6 +422,7 @@ fn can_access_secret_org_wide() {
         org_id: "org_1".into(),
         key_id: "pk_admin".into(),
         key_name: "admin-key".into(),
+        max_secrets: None,
         permissions: admin_perms,
     };
 
@@ -407,6 +436,7 @@ fn can_access_secret_org_wide() {
         org_id: "org_1".into(),
         key_id: "pk_writer".into(),
         key_name: "writer-key".into(),
+        max_secrets: None,
         permissions: writer_perms,
     };
 
@@ -53,7 +53,8 @@ fn build_test_app() -> (TestServer, Store, TempDir) {
 
     let state = AppState {
         store: store.clone(),
-        api_key: Some(MASTER_KEY.to_string()),
+        secrets: std::sync::Arc::new(store.clone()),
+        api_keys: sirr_server::auth::ApiKeys::unlabeled(MASTER_KEY),
         license: LicenseStatus::Free,
         validator: None,
         webhook_sender: None,
@@ -61,9 +62,36 @@ fn build_test_app() -> (TestServer, Store, TempDir) {
         redact_audit_keys: false,
         webhook_allowed_origins: Arc::new(vec![]),
         enable_public_bucket: true,
+        started_at: now_secs(),
+        started: std::time::Instant::now(),
+        create_limiter: None,
+        sweep_lock: Default::default(),
+        public_base_url: None,
+        max_secrets: None,
+        auto_prune_on_full: false,
+        min_ttl_seconds: 0,
+        require_ttl: false,
+        limit_status: axum::http::StatusCode::PAYMENT_REQUIRED,
+        reserved_key_prefixes: Default::default(),
+        generated_key_length: 24,
+        key_generator: Default::default(),
+        audit_cors_rejections: false,
+        audit_failure_mode: Default::default(),
+        audit_max_range_secs: None,
+        create_mode: Default::default(),
+        idempotency_ttl_secs: 24 * 60 * 60,
+        maintenance: Default::default(),
+        self_destruct: None,
+        min_value_bytes: 0,
+        listeners: Default::default(),
+        metrics: Default::default(),
+        metrics_public: false,
     };
 
-    let secret_read = Router::new().route("/secrets/{key}", get(get_secret).head(head_secret));
+    let secret_read = Router::new()
+        .route("/secrets/{key}", get(get_secret).head(head_secret))
+        .route("/secrets/{key}/describe", get(describe_secret))
+        .route("/secrets/{key}/field/{field}", get(get_secret_field));
 
     let protected_public = Router::new()
         .route("/secrets", get(list_secrets).post(create_secret))
@@ -171,6 +199,7 @@ fn bootstrap_org_with_key(
             valid_after: now - 60,
             valid_before: now + 3600,
             created_at: now,
+            max_secrets: None,
         })
         .unwrap();
 
@@ -198,6 +227,7 @@ fn add_named_key(
             valid_after: now - 60,
             valid_before: now + 3600,
             created_at: now,
+            max_secrets: None,
         })
         .unwrap();
 
@@ -225,6 +255,7 @@ fn add_expired_key(
             valid_after: now - 7200,
             valid_before: now - 3600, // expired 1 hour ago
             created_at: now - 7200,
+            max_secrets: None,
         })
         .unwrap();
 
@@ -252,6 +283,7 @@ fn add_not_yet_valid_key(
             valid_after: now + 3600, // valid 1 hour from now
             valid_before: now + 7200,
             created_at: now,
+            max_secrets: None,
         })
         .unwrap();
 
@@ -0,0 +1,99 @@
+//! Graceful shutdown: requests in flight when the server is told to stop
+//! still get their response.
+
+use std::time::Duration;
+
+use sirr_server::ServerConfig;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const MASTER_KEY: &str = "shutdown-test-master-key-0123456789";
+
+/// Boot a server with `shutdown_timeout`, returning its task, the trigger
+/// that stops it and a connection holding a create request whose body is
+/// only half sent.
+async fn start_with_stalled_create(
+    shutdown_timeout: Duration,
+) -> (
+    tokio::task::JoinHandle<anyhow::Result<()>>,
+    tokio::sync::oneshot::Sender<()>,
+    tokio::net::TcpStream,
+    &'static str,
+    tempfile::TempDir,
+) {
+    let dir = tempfile::tempdir().unwrap();
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let cfg = ServerConfig {
+        host: "127.0.0.1".into(),
+        port,
+        api_key: Some(MASTER_KEY.into()),
+        data_dir: Some(dir.path().to_path_buf()),
+        shutdown_timeout,
+        ..ServerConfig::default()
+    };
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(sirr_server::run_until(cfg, async {
+        let _ = stop_rx.await;
+    }));
+
+    let mut conn = None;
+    for _ in 0..50 {
+        if let Ok(stream) = tokio::net::TcpStream::connect(("127.0.0.1", port)).await {
+            conn = Some(stream);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mut conn = conn.expect("server accepts connections");
+
+    let body = r#"{"key": "SLOW", "value": "v"}"#;
+    let (first, rest) = body.split_at(body.len() / 2);
+    let head = format!(
+        "POST /secrets HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {MASTER_KEY}\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    );
+    conn.write_all(head.as_bytes()).await.unwrap();
+    conn.write_all(first.as_bytes()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    (server, stop_tx, conn, rest, dir)
+}
+
+#[tokio::test]
+async fn shutdown_lets_in_flight_create_finish() {
+    let (server, stop_tx, mut conn, rest, _dir) =
+        start_with_stalled_create(Duration::from_secs(10)).await;
+
+    stop_tx.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    conn.write_all(rest.as_bytes()).await.unwrap();
+
+    let mut response = String::new();
+    tokio::time::timeout(Duration::from_secs(5), conn.read_to_string(&mut response))
+        .await
+        .expect("connection closed after the response")
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.1 201"), "{response}");
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server stops once drained")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn shutdown_gives_up_on_stalled_requests_after_timeout() {
+    let (server, stop_tx, _conn, _rest, _dir) =
+        start_with_stalled_create(Duration::from_millis(300)).await;
+
+    stop_tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server stops despite the stalled request")
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,52 @@
+//! Native HTTPS on the main listener (`SIRR_TLS_CERT` / `SIRR_TLS_KEY`).
+#![cfg(feature = "tls")]
+
+use std::time::Duration;
+
+use sirr_server::ServerConfig;
+
+#[tokio::test]
+async fn serve_answers_health_over_tls() {
+    let dir = tempfile::tempdir().unwrap();
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+    let cert_path = dir.path().join("cert.pem");
+    let key_path = dir.path().join("key.pem");
+    std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let cfg = ServerConfig {
+        host: "127.0.0.1".into(),
+        port,
+        data_dir: Some(dir.path().join("data")),
+        tls_cert_path: Some(cert_path),
+        tls_key_path: Some(key_path),
+        ..ServerConfig::default()
+    };
+    tokio::spawn(sirr_server::run(cfg));
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(cert.cert.pem().as_bytes()).unwrap())
+        .resolve("localhost", ([127, 0, 0, 1], port).into())
+        .build()
+        .unwrap();
+    let url = format!("https://localhost:{port}/health");
+    let mut resp = client.get(&url).send().await;
+    for _ in 0..50 {
+        if resp.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        resp = client.get(&url).send().await;
+    }
+    let resp = resp.expect("server answers over TLS");
+    assert_eq!(resp.status(), 200);
+
+    // Plain HTTP on the same port does not get a response.
+    let plain = reqwest::get(format!("http://127.0.0.1:{port}/health")).await;
+    assert!(plain.is_err());
+}
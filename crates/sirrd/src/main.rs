@@ -35,6 +35,9 @@ enum Commands {
     /// Rotate the encryption key (offline). Re-encrypts all records with a new
     /// master key. Requires direct access to the sirr.key and sirr.db files.
     Rotate,
+    /// Verify the store (offline, read-only). Decrypts every record with the
+    /// current key and lists any that fail, without modifying anything.
+    Check,
 }
 
 // ── Entry point ───────────────────────────────────────────────────────────────
@@ -70,6 +73,7 @@ async fn main() -> Result<()> {
         } => cmd_serve(host, port, effective_log_level, init).await,
 
         Commands::Rotate => cmd_rotate().await,
+        Commands::Check => cmd_check(),
     }
 }
 
@@ -84,12 +88,15 @@ async fn cmd_serve(host: String, port: u16, log_level: String, init: bool) -> Re
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
 
-    // If SIRR_API_KEY is not set, generate a random key so the server is
-    // never left open.  The key is shown in the security notice and must be
-    // persisted by the operator if they want it to survive a restart.
+    // If neither SIRR_API_KEY nor SIRR_API_KEYS is set, generate a random key
+    // so the server is never left open.  The key is shown in the security
+    // notice and must be persisted by the operator if they want it to
+    // survive a restart.
     let env_api_key = std::env::var("SIRR_API_KEY").ok();
+    let has_labeled_keys = std::env::var("SIRR_API_KEYS").is_ok_and(|v| !v.trim().is_empty());
     let (api_key, auto_generated_key) = match env_api_key {
         Some(k) => (Some(k), None),
+        None if has_labeled_keys => (None, None),
         None => {
             let key = {
                 let mut bytes = [0u8; 16];
@@ -138,20 +145,48 @@ async fn cmd_rotate() -> Result<()> {
     let db_path = data_dir.join("sirr.db");
     let store = sirr_server::store::Store::open(&db_path, old_key).context("open store")?;
 
-    // Determine new key version (increment from current max).
-    let current_version = store.max_key_version()?;
-    let new_version = current_version
-        .checked_add(1)
-        .context("key version overflow (max 255 rotations)")?;
-
-    // Generate a new random key and re-encrypt all records.
-    let new_key = sirr_server::store::crypto::generate_key();
-    let count = store.rotate(&new_key, new_version)?;
-
-    // Write the new key to sirr.key.
-    std::fs::write(&key_path, new_key.as_bytes()).context("write new sirr.key")?;
+    // Generate a new random key, re-encrypt all records and replace sirr.key
+    // (staged as sirr.key.next until the store has committed).
+    let (new_version, count) = sirr_server::key_rotation::rotate_now(&store, &key_path)?;
 
     println!("rotated {count} secret(s) to key version {new_version}");
     println!("new encryption key written to {}", key_path.display());
     Ok(())
 }
+
+fn cmd_check() -> Result<()> {
+    let data_dir_env = std::env::var("SIRR_DATA_DIR").ok().map(Into::into);
+    let data_dir = sirr_server::resolve_data_dir(data_dir_env.as_ref())?;
+
+    // Same key sources as the server: SIRR_ENCRYPTION_KEY, else sirr.key.
+    let key_bytes = match std::env::var("SIRR_ENCRYPTION_KEY") {
+        Ok(hex_key) => {
+            hex::decode(hex_key.trim()).context("SIRR_ENCRYPTION_KEY is not valid hex")?
+        }
+        Err(_) => std::fs::read(data_dir.join("sirr.key"))
+            .context("read sirr.key — is the server initialized?")?,
+    };
+    let key = sirr_server::store::crypto::load_key(&key_bytes)
+        .ok_or_else(|| anyhow::anyhow!("encryption key must be 32 bytes"))?;
+
+    let db_path = data_dir.join("sirr.db");
+    let store = sirr_server::store::Store::open_existing(&db_path, key).context("open store")?;
+    let report = store.check()?;
+
+    for (key, error) in &report.failed {
+        println!("FAILED {key}: {error}");
+    }
+    println!(
+        "{} ok, {} failed, {} expired (skipped)",
+        report.ok,
+        report.failed.len(),
+        report.expired
+    );
+    if !report.failed.is_empty() {
+        anyhow::bail!(
+            "{} record(s) could not be decrypted (corruption or wrong key)",
+            report.failed.len()
+        );
+    }
+    Ok(())
+}